@@ -0,0 +1,43 @@
+//! Breadth first searches of large boards on the calling thread and on the
+//! threads of `BfsScratch::with_threads`, run with `cargo bench --bench bfs`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pentonimo::{
+    pathfinding::{BfsScratch, Point},
+    rng::Rng,
+    tile_map::TileMap,
+};
+
+/// a square board with a tenth of its cells blocked at random
+fn board(size: u32) -> TileMap {
+    let mut map = TileMap::new((size, size));
+    let mut rng = Rng::new(0);
+    for _ in 0..size * size / 10 {
+        let (x, y) = (rng.below(size as u64), rng.below(size as u64));
+        map.block(x as u32, y as u32);
+    }
+    map
+}
+
+fn distances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distances");
+    group.sample_size(20);
+    for size in [250, 500, 1000] {
+        let map = board(size);
+        // from a corner, so the frontiers grow over most of the search
+        let start = (0..size).map(|x| Point(x, x)).find(|p| !map.get(p.0, p.1));
+        let start = start.unwrap();
+        for threads in [1, 2, 4] {
+            let mut scratch = BfsScratch::new(map.shape).with_threads(threads);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{threads} threads"), size),
+                &map,
+                |b, map| b.iter(|| scratch.distances(map, start)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, distances);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+//! The vector paths of `TileMap::can_place_batch` and `TileMap::cmp_tiles`
+//! against the same loops one tile at a time, run with
+//! `cargo bench --bench tile_map`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pentonimo::{pentonimo::PositionedPentonimo, rng::Rng, spec::BoardSpec, tile_map::TileMap};
+
+/// a square board with a tenth of its cells blocked at random
+fn board(size: u32) -> TileMap {
+    let mut map = TileMap::new((size, size));
+    let mut rng = Rng::new(0);
+    for _ in 0..size * size / 10 {
+        let (x, y) = (rng.below(size as u64), rng.below(size as u64));
+        map.block(x as u32, y as u32);
+    }
+    map
+}
+
+/// `TileMap::can_place` with a branch per tile
+fn can_place_tile_by_tile(map: &TileMap, piece: PositionedPentonimo) -> bool {
+    let (x, y) = piece.position();
+    let parts = piece.pentonimo().shift_split((x % 8) as i8, (y % 8) as i8);
+    parts.iter().enumerate().all(|(i, part)| {
+        let (tx, ty) = (x / 8 + (i % 2) as u32, y / 8 + (i / 2) as u32);
+        part.is_empty() || (map.get_tile(tx, ty) & *part).is_empty()
+    })
+}
+
+fn can_place(c: &mut Criterion) {
+    let mut group = c.benchmark_group("can_place");
+    for size in [16, 64] {
+        let map = board(size);
+        let pieces = format!("{size}x{size}")
+            .parse::<BoardSpec>()
+            .unwrap()
+            .placements();
+        let mut fits = Vec::new();
+        group.bench_with_input(BenchmarkId::new("batch", size), &pieces, |b, pieces| {
+            b.iter(|| {
+                map.can_place_batch(pieces, &mut fits);
+                black_box(&fits);
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("tile by tile", size),
+            &pieces,
+            |b, pieces| {
+                b.iter(|| {
+                    fits.clear();
+                    fits.extend(
+                        pieces
+                            .iter()
+                            .map(|&piece| can_place_tile_by_tile(&map, piece)),
+                    );
+                    black_box(&fits);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn cmp_tiles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cmp_tiles");
+    for size in [64, 256] {
+        // equal up to the last cell, so every tile has to be compared
+        let map = board(size);
+        let mut other = map.clone();
+        other.block(size - 1, size - 1);
+        group.bench_with_input(BenchmarkId::new("vector", size), &other, |b, other| {
+            b.iter(|| black_box(map.cmp_tiles(other)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("tile by tile", size),
+            &other,
+            |b, other| {
+                let words =
+                    |map: &TileMap| map.tiles().iter().map(|tile| tile.0).collect::<Vec<_>>();
+                let (a, b_) = (words(&map), words(other));
+                b.iter(|| black_box(a.iter().cmp(b_.iter())))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, can_place, cmp_tiles);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pentonimo::spec::{parse_specs, BoardSpec};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(specs) = parse_specs(input) else {
+        return;
+    };
+
+    for spec in specs {
+        // every accepted spec has to survive a roundtrip through its text form
+        let reparsed = spec.to_string().parse::<BoardSpec>().unwrap();
+        assert_eq!(reparsed, spec);
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pentonimo::solution::Solution;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(solution) = input.parse::<Solution>() else {
+        return;
+    };
+
+    let text = solution.to_string();
+    let reparsed = text.parse::<Solution>().unwrap();
+    assert_eq!(reparsed.to_string(), text);
+});
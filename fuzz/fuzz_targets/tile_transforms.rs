@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pentonimo::tile::{Rotate, Tile};
+
+fuzz_target!(|data: (u64, Vec<u8>)| {
+    let (bits, ops) = data;
+    let mut tile = Tile(bits);
+
+    for op in ops {
+        // shifts are only defined for distances within a tile
+        let d = ((op >> 3) % 15) as i8 - 7;
+        tile = match op & 0b111 {
+            0 => tile.shift_x(d),
+            1 => tile.shift_y(d),
+            2 => tile.rotate(Rotate::Left),
+            3 => tile.rotate(Rotate::Right),
+            4 => tile.rotate(Rotate::Full),
+            5 => tile.flip_x(),
+            6 => tile.flip_y(),
+            _ => tile.transpose(),
+        };
+
+        assert_eq!(tile.transpose().transpose(), tile);
+        assert_eq!(tile.rotate(Rotate::Left).rotate(Rotate::Right), tile);
+        assert!(tile.shift_x(d).0.count_ones() <= tile.0.count_ones());
+        assert!(tile.shift_y(d).0.count_ones() <= tile.0.count_ones());
+    }
+});
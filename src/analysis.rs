@@ -0,0 +1,611 @@
+//! Graph properties of a board without placing any pieces.
+
+use std::fmt::Display;
+
+use strum::VariantArray;
+
+use crate::{
+    backend::default_num_threads,
+    brute_force::placements,
+    candidates::Candidates,
+    pathfinding::{neighbours, BfsScratch, Path, Point},
+    pentonimo::{PentonimoKind, PositionedPentonimo, KINDS},
+    solution::Solution,
+    spec::BoardSpec,
+    tile_map::TileMap,
+};
+
+pub struct Analysis {
+    pub free_cells: usize,
+    pub components: usize,
+    /// free cells with exactly one free neighbour
+    pub dead_ends: Vec<Point>,
+    /// number of cells on the longest shortest path, as in `graph_diameter`
+    pub diameter: u32,
+    pub diameter_path: Path,
+    /// every pair of cells with the diameter between them, as in [`diameter_pairs`]
+    ///
+    /// [`diameter_pairs`]: crate::pathfinding::diameter_pairs
+    pub diameter_pairs: Vec<Path>,
+    /// smallest eccentricity of a free cell, in cells like the diameter
+    pub radius: u32,
+    pub center: Point,
+    /// number of pairs of connected cells by their distance in steps
+    pub histogram: Vec<u64>,
+}
+
+impl Analysis {
+    /// Runs a breadth first search from every free cell, so this is quadratic
+    /// in the number of cells. The large frontiers of large boards are
+    /// expanded on all threads, see [`BfsScratch::with_threads`].
+    pub fn new(map: &TileMap) -> Self {
+        let (w, h) = map.shape;
+        let free = (0..h)
+            .flat_map(|y| (0..w).map(move |x| Point(x, y)))
+            .filter(|p| !map.get(p.0, p.1))
+            .collect::<Vec<_>>();
+
+        let mut analysis = Self {
+            free_cells: free.len(),
+            components: 0,
+            dead_ends: Vec::new(),
+            diameter: 0,
+            diameter_path: Path(Point(0, 0), Point(0, 0)),
+            diameter_pairs: Vec::new(),
+            radius: 0,
+            center: Point(0, 0),
+            histogram: Vec::new(),
+        };
+
+        let mut scratch = BfsScratch::new(map.shape).with_threads(default_num_threads());
+        let mut component = vec![None; w as usize * h as usize];
+        // the diameter of the pairs seen so far
+        let mut pair_cells = 0;
+        for &start in &free {
+            if neighbours(map, start).count() == 1 {
+                analysis.dead_ends.push(start);
+            }
+
+            let distances = scratch.distances(map, start);
+            if component[(start.1 * w + start.0) as usize].is_none() {
+                for (i, distance) in distances.iter().enumerate() {
+                    if distance.is_some() {
+                        component[i] = Some(analysis.components);
+                    }
+                }
+                analysis.components += 1;
+            }
+
+            let mut eccentricity = 0;
+            let mut farthest = start;
+            for (i, &distance) in distances.iter().enumerate() {
+                let Some(distance) = distance else {
+                    continue;
+                };
+                let cell = Point(i as u32 % w, i as u32 / w);
+                if distance > eccentricity {
+                    eccentricity = distance;
+                    farthest = cell;
+                }
+                if (start.1, start.0) <= (cell.1, cell.0) {
+                    let cells = distance + 1;
+                    if cells > pair_cells {
+                        pair_cells = cells;
+                        analysis.diameter_pairs.clear();
+                    }
+                    if cells == pair_cells {
+                        analysis.diameter_pairs.push(Path(start, cell));
+                    }
+                }
+                // every pair is seen from both ends, only count it from the smaller one
+                if (start.1, start.0) < (cell.1, cell.0) {
+                    let distance = distance as usize;
+                    if analysis.histogram.len() <= distance {
+                        analysis.histogram.resize(distance + 1, 0);
+                    }
+                    analysis.histogram[distance] += 1;
+                }
+            }
+
+            let cells = eccentricity + 1;
+            if cells > analysis.diameter {
+                analysis.diameter = cells;
+                analysis.diameter_path = Path(start, farthest);
+            }
+            if analysis.radius == 0 || cells < analysis.radius {
+                analysis.radius = cells;
+                analysis.center = start;
+            }
+        }
+
+        analysis
+    }
+
+    pub fn pairs(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    /// mean number of steps between two connected cells
+    pub fn mean_distance(&self) -> f64 {
+        let total = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(distance, &count)| distance as u64 * count)
+            .sum::<u64>();
+        total as f64 / self.pairs().max(1) as f64
+    }
+
+    /// smallest distance with at least half of the pairs at most as far apart
+    pub fn median_distance(&self) -> usize {
+        let half = self.pairs().div_ceil(2);
+        let mut seen = 0;
+        for (distance, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= half {
+                return distance;
+            }
+        }
+        0
+    }
+}
+
+/// Something about a board that makes searching it pointless or its result
+/// surprising
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardIssue {
+    /// every cell is a hole
+    NoFreeCells,
+    /// none of the pieces fits anywhere, so the search can't place any
+    NothingFits,
+    /// some of the fixed ends of the board aren't connected
+    EndsDisconnected,
+    /// the free cells fall apart into this many components, and the diameter
+    /// is the one of the largest
+    Disconnected(usize),
+    /// the pieces cover more cells than are free and may be covered, so not
+    /// all of them can be placed
+    PiecesExceedArea { cells: usize, free: usize },
+}
+
+impl BoardIssue {
+    /// whether searching the board is pointless
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Self::NoFreeCells | Self::NothingFits | Self::EndsDisconnected
+        )
+    }
+}
+
+impl Display for BoardIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoFreeCells => write!(f, "the board has no free cells"),
+            Self::NothingFits => write!(f, "none of the pieces fits on the board"),
+            Self::EndsDisconnected => write!(f, "the ends aren't connected"),
+            Self::Disconnected(components) => {
+                write!(f, "the free cells form {components} separate regions")
+            }
+            Self::PiecesExceedArea { cells, free } => write!(
+                f,
+                "the pieces cover {cells} cells, but only {free} free cells may be covered"
+            ),
+        }
+    }
+}
+
+/// Checks a board before it is searched
+pub fn board_issues(spec: &BoardSpec) -> Vec<BoardIssue> {
+    let map = spec.initial_map();
+    let free = map.free_cells() as usize;
+    if free == 0 {
+        return vec![BoardIssue::NoFreeCells];
+    }
+
+    let mut issues = Vec::new();
+    if spec.placements().is_empty() {
+        issues.push(BoardIssue::NothingFits);
+    }
+    if !spec.ends.is_empty() && BfsScratch::new(spec.shape).objective(&map, &spec.ends).0 == 0 {
+        issues.push(BoardIssue::EndsDisconnected);
+    }
+    let components = Analysis::new(&map).components;
+    if components > 1 {
+        issues.push(BoardIssue::Disconnected(components));
+    }
+    let cells = spec.piece_cells();
+    let mut covered = map.clone();
+    covered |= &spec.free_map();
+    let coverable = covered.free_cells() as usize;
+    if cells > coverable {
+        issues.push(BoardIssue::PiecesExceedArea {
+            cells,
+            free: coverable,
+        });
+    }
+    issues
+}
+
+/// How the diameter of a solution changes when single pieces are taken away or
+/// added, which shows the pieces that matter
+pub struct WhatIf {
+    pub diameter: u32,
+    /// every placed piece with the diameter of the board without it
+    pub removed: Vec<(PositionedPentonimo, u32)>,
+    /// every kind with pieces left, with the placement of one more of them that
+    /// leads to the largest diameter, if any fits
+    pub added: Vec<(PentonimoKind, Option<(PositionedPentonimo, u32)>)>,
+}
+
+impl WhatIf {
+    pub fn new(solution: &Solution) -> Self {
+        let mut scratch = BfsScratch::new(solution.spec.shape);
+        let mut map = solution.map();
+        let ends = &solution.spec.ends;
+        let (diameter, _) = scratch.objective(&map, ends);
+
+        let mut removed = Vec::with_capacity(solution.pieces.len());
+        let mut used = [0; KINDS];
+        for &piece in &solution.pieces {
+            used[piece.pentonimo().kind() as usize] += 1;
+            map.remove(piece);
+            removed.push((piece, scratch.objective(&map, ends).0));
+            map |= piece;
+        }
+
+        let mut added = Vec::new();
+        for &kind in PentonimoKind::VARIANTS {
+            if used[kind as usize] >= solution.spec.pieces[kind as usize] {
+                continue;
+            }
+            let mut single = [0; KINDS];
+            single[kind as usize] = 1;
+
+            let best = placements(&map, Candidates::new(single))
+                .into_iter()
+                .filter(|&piece| solution.spec.allows(piece))
+                .map(|piece| (piece, scratch.objective(&(map.clone() | piece), ends).0))
+                .max_by_key(|&(_, diameter)| diameter);
+            added.push((kind, best));
+        }
+
+        Self {
+            diameter,
+            removed,
+            added,
+        }
+    }
+}
+
+/// The narrowest places of the connection between two cells, the corridors
+/// that a piece would have to block to separate them. By Menger's theorem the
+/// fewest cells separating them are as many as the paths between them that
+/// share no cells, which a maximum flow through the cells finds.
+pub struct Bottlenecks {
+    pub ends: Path,
+    /// the fewest cells that separate the ends
+    pub width: u32,
+    /// every cell of some smallest set of cells that separates the ends, by
+    /// their distance from the start
+    pub cells: Vec<Point>,
+}
+
+impl Bottlenecks {
+    /// Runs a flow for every free cell, but the flows are at most 4 paths.
+    pub fn new(map: &TileMap, ends: Path) -> Self {
+        let Path(start, goal) = ends;
+        let width = disjoint_paths(map, ends, None);
+        let distances = BfsScratch::new(map.shape).distances(map, start);
+        let mut cells = (0..map.shape.1)
+            .flat_map(|y| (0..map.shape.0).map(move |x| Point(x, y)))
+            .filter(|&p| p != start && p != goal && !map.get(p.0, p.1))
+            .filter(|&p| disjoint_paths(map, ends, Some(p)) < width)
+            .collect::<Vec<_>>();
+        cells.sort_by_key(|p| distances[(p.1 * map.shape.0 + p.0) as usize]);
+        Self { ends, width, cells }
+    }
+}
+
+/// The number of paths between the ends that share no cells, without
+/// `blocked`. Every cell is split into an entry and an exit joined by an edge
+/// of capacity 1, so a cell carries one path at most.
+fn disjoint_paths(map: &TileMap, Path(start, goal): Path, blocked: Option<Point>) -> u32 {
+    let (w, h) = map.shape;
+    let index = |p: Point| (p.1 * w + p.0) as usize;
+    if start == goal || map.get(start.0, start.1) || map.get(goal.0, goal.1) {
+        return 0;
+    }
+
+    // edges with their reverse edge at `e ^ 1`
+    let mut to = Vec::new();
+    let mut capacity = Vec::new();
+    let mut edges = vec![Vec::new(); 2 * (w * h) as usize];
+    let mut add = |from: usize, next: usize| {
+        edges[from].push(to.len());
+        to.push(next);
+        capacity.push(1);
+        edges[next].push(to.len());
+        to.push(from);
+        capacity.push(0);
+    };
+    for y in 0..h {
+        for x in 0..w {
+            let p = Point(x, y);
+            if map.get(x, y) || Some(p) == blocked {
+                continue;
+            }
+            add(2 * index(p), 2 * index(p) + 1);
+            for next in neighbours(map, p).filter(|&next| Some(next) != blocked) {
+                add(2 * index(p) + 1, 2 * index(next));
+            }
+        }
+    }
+
+    let (source, sink) = (2 * index(start) + 1, 2 * index(goal));
+    let mut paths = 0;
+    loop {
+        let mut via = vec![None; edges.len()];
+        let mut queue = std::collections::VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for &e in &edges[node] {
+                let next = to[e];
+                if capacity[e] > 0 && next != source && via[next].is_none() {
+                    via[next] = Some(e);
+                    queue.push_back(next);
+                }
+            }
+        }
+        if via[sink].is_none() {
+            return paths;
+        }
+        let mut node = sink;
+        while let Some(e) = via[node] {
+            capacity[e] -= 1;
+            capacity[e ^ 1] += 1;
+            node = to[e ^ 1];
+        }
+        paths += 1;
+    }
+}
+
+/// The dead ends of a board and the corridors leading to them, which make a
+/// board look like a maze
+pub struct DeadEnds {
+    /// free cells with exactly one free neighbour
+    pub cells: Vec<Point>,
+    /// The cells of the corridor of every dead end, from it to the first cell
+    /// with more than two free neighbours. A corridor between two dead ends is
+    /// counted from both.
+    pub corridors: Vec<u32>,
+}
+
+impl DeadEnds {
+    pub fn new(map: &TileMap) -> Self {
+        let mut dead_ends = Self {
+            cells: Vec::new(),
+            corridors: Vec::new(),
+        };
+        for y in 0..map.shape.1 {
+            for x in 0..map.shape.0 {
+                let start = Point(x, y);
+                if map.get(x, y) || neighbours(map, start).count() != 1 {
+                    continue;
+                }
+
+                let (mut previous, mut cell) = (start, neighbours(map, start).next().unwrap());
+                let mut length = 1;
+                loop {
+                    let mut next = neighbours(map, cell).filter(|&next| next != previous);
+                    match (next.next(), next.next()) {
+                        (Some(next), None) => (previous, cell) = (cell, next),
+                        // the other end of a corridor on its own
+                        (None, _) => {
+                            length += 1;
+                            break;
+                        }
+                        _ => break,
+                    }
+                    length += 1;
+                }
+                dead_ends.cells.push(start);
+                dead_ends.corridors.push(length);
+            }
+        }
+        dead_ends
+    }
+
+    pub fn longest(&self) -> u32 {
+        self.corridors.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.corridors.iter().sum::<u32>() as f64 / self.corridors.len().max(1) as f64
+    }
+}
+
+/// How often pieces appear in a set of solutions
+#[derive(Debug, Default)]
+pub struct PieceUsage {
+    pub solutions: usize,
+    /// number of placed pieces of every kind
+    pub kinds: [u64; KINDS],
+    /// number of placed pieces of every variant of every kind
+    pub variants: [[u64; 8]; KINDS],
+}
+
+impl PieceUsage {
+    pub fn new(solutions: &[Vec<PositionedPentonimo>]) -> Self {
+        let mut usage = Self {
+            solutions: solutions.len(),
+            ..Default::default()
+        };
+        for piece in solutions.iter().flatten() {
+            let kind = piece.pentonimo().kind() as usize;
+            usage.kinds[kind] += 1;
+            usage.variants[kind][piece.variant()] += 1;
+        }
+        usage
+    }
+
+    pub fn pieces(&self) -> u64 {
+        self.kinds.iter().sum()
+    }
+
+    pub fn mean_pieces(&self) -> f64 {
+        self.pieces() as f64 / self.solutions.max(1) as f64
+    }
+}
+
+#[test]
+fn ring() {
+    // a 3x3 ring around a hole
+    let mut map = TileMap::new((3, 3));
+    map.block(1, 1);
+    let analysis = Analysis::new(&map);
+
+    assert_eq!(analysis.free_cells, 8);
+    assert_eq!(analysis.components, 1);
+    assert!(analysis.dead_ends.is_empty());
+    assert_eq!((analysis.diameter, analysis.radius), (5, 5));
+    // 8 cells at distances 1, 1, 2, 2, 3, 3, 4 from each other
+    assert_eq!(analysis.histogram, [0, 8, 8, 8, 4]);
+    assert_eq!(analysis.pairs(), 28);
+    // the opposite cells
+    let pairs = [
+        ((0, 0), (2, 2)),
+        ((1, 0), (1, 2)),
+        ((2, 0), (0, 2)),
+        ((0, 1), (2, 1)),
+    ]
+    .map(|((sx, sy), (ex, ey))| Path(Point(sx, sy), Point(ex, ey)));
+    assert_eq!(analysis.diameter_pairs, pairs);
+    assert_eq!(
+        crate::pathfinding::diameter_pairs(&map),
+        (5, pairs.to_vec())
+    );
+}
+
+#[test]
+fn components_and_dead_ends() {
+    let mut map = TileMap::new((5, 1));
+    map.block(2, 0);
+    let analysis = Analysis::new(&map);
+
+    assert_eq!(analysis.components, 2);
+    assert_eq!(analysis.dead_ends.len(), 4);
+    assert_eq!((analysis.diameter, analysis.radius), (2, 2));
+}
+
+#[test]
+fn bottlenecks() {
+    let ends = |(sx, sy), (ex, ey)| Path(Point(sx, sy), Point(ex, ey));
+
+    // two rooms joined by a door, which is only reached through the cells
+    // next to it
+    let mut map = TileMap::new((5, 3));
+    map.block(2, 0);
+    map.block(2, 2);
+    let bottlenecks = Bottlenecks::new(&map, ends((0, 0), (4, 2)));
+    assert_eq!(bottlenecks.width, 1);
+    assert_eq!(bottlenecks.cells, [Point(1, 1), Point(2, 1), Point(3, 1)]);
+
+    // every cell of a corridor separates its ends, from the start on
+    let bottlenecks = Bottlenecks::new(&TileMap::new((4, 1)), ends((3, 0), (0, 0)));
+    assert_eq!(bottlenecks.width, 1);
+    assert_eq!(bottlenecks.cells, [Point(2, 0), Point(1, 0)]);
+
+    // the two halves of a ring around a hole
+    let mut map = TileMap::new((3, 3));
+    map.block(1, 1);
+    let bottlenecks = Bottlenecks::new(&map, ends((0, 0), (2, 2)));
+    assert_eq!(bottlenecks.width, 2);
+    assert_eq!(bottlenecks.cells.len(), 6);
+
+    // an open board is 2 wide at its corners, but not in the middle
+    let bottlenecks = Bottlenecks::new(&TileMap::new((3, 3)), ends((0, 0), (2, 2)));
+    assert_eq!(bottlenecks.width, 2);
+    let corners = [Point(1, 0), Point(0, 1), Point(2, 1), Point(1, 2)];
+    assert_eq!(bottlenecks.cells, corners);
+}
+
+#[test]
+fn dead_end_corridors() {
+    // a comb of three teeth on a corridor along the bottom, the outer ones
+    // continue along it to the middle one
+    let mut map = TileMap::new((5, 3));
+    for (x, y) in [(1, 0), (1, 1), (3, 0), (3, 1)] {
+        map.block(x, y);
+    }
+    let dead_ends = DeadEnds::new(&map);
+    let cells = [(0, 0), (2, 0), (4, 0)].map(|(x, y)| Point(x, y));
+    assert_eq!(dead_ends.cells, cells);
+    assert_eq!(dead_ends.corridors, [4, 2, 4]);
+    assert_eq!(dead_ends.longest(), 4);
+
+    // a corridor on its own is counted from both ends
+    let dead_ends = DeadEnds::new(&TileMap::new((4, 1)));
+    assert_eq!(dead_ends.corridors, [4, 4]);
+    assert!(DeadEnds::new(&TileMap::new((3, 3))).cells.is_empty());
+}
+
+#[test]
+fn what_if() {
+    // an I along the top of a 5x2 board leaves a path of 5 cells
+    let piece = crate::pentonimo::Pentonimo::variant(PentonimoKind::I, 1)
+        .unwrap()
+        .position(0, 0);
+    let mut spec = crate::spec::BoardSpec::new((5, 2));
+    spec.pieces = [0; KINDS];
+    spec.pieces[PentonimoKind::I as usize] = 1;
+    spec.pieces[PentonimoKind::L as usize] = 1;
+    let solution = Solution {
+        spec,
+        diameter: 5,
+        pieces: vec![piece],
+        proved_optimal: false,
+    };
+
+    let what_if = WhatIf::new(&solution);
+    assert_eq!(what_if.diameter, 5);
+    assert_eq!(what_if.removed, [(piece, 6)]);
+    // no L fits next to the I
+    assert_eq!(what_if.added, [(PentonimoKind::L, None)]);
+}
+
+#[test]
+fn board_issues_of_bad_boards() {
+    // only an I
+    let only_i = |board: &str| {
+        format!("{board} pieces=F=0,I=1,L=0,N=0,P=0,T=0,U=0,V=0,W=0,X=0,Y=0,Z=0")
+            .parse::<BoardSpec>()
+            .unwrap()
+    };
+
+    assert_eq!(board_issues(&only_i("5x1")), []);
+    assert_eq!(
+        board_issues(&only_i("1x1 holes=0,0")),
+        [BoardIssue::NoFreeCells]
+    );
+    assert_eq!(
+        board_issues(&only_i("5x1 holes=2,0")),
+        [
+            BoardIssue::NothingFits,
+            BoardIssue::Disconnected(2),
+            BoardIssue::PiecesExceedArea { cells: 5, free: 4 }
+        ]
+    );
+    assert_eq!(
+        board_issues(&only_i("5x1 holes=2,0 ends=0,0;4,0"))[1],
+        BoardIssue::EndsDisconnected
+    );
+    let issues = board_issues(&BoardSpec::new((5, 5)));
+    assert_eq!(
+        issues,
+        [BoardIssue::PiecesExceedArea {
+            cells: 60,
+            free: 25
+        }]
+    );
+    assert!(!issues[0].is_error());
+}
@@ -0,0 +1,118 @@
+//! Property testing on top of `proptest`: strategies for the core types and a
+//! runner that shrinks the first failing input and reports it with its seed.
+
+use std::fmt::Debug;
+
+use proptest::{
+    bool::weighted,
+    collection::vec,
+    prelude::*,
+    sample::select,
+    test_runner::{Config, RngSeed, TestCaseError, TestError, TestRunner},
+};
+use strum::VariantArray;
+
+use crate::{
+    pentonimo::{Pentonimo, PentonimoKind, PositionedPentonimo},
+    tile::Tile,
+    tile_map::TileMap,
+};
+
+pub trait Arbitrary: Debug + Sized {
+    fn strategy() -> BoxedStrategy<Self>;
+}
+
+impl Arbitrary for Tile {
+    fn strategy() -> BoxedStrategy<Self> {
+        // mix in sparse tiles, fully random ones are almost always half full
+        prop_oneof![
+            any::<u64>().prop_map(Tile),
+            any::<[u64; 3]>().prop_map(|[a, b, c]| Tile(a & b & c)),
+            Pentonimo::strategy().prop_map(|pentonimo| pentonimo.tile()),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Pentonimo {
+    fn strategy() -> BoxedStrategy<Self> {
+        let kinds = PentonimoKind::VARIANTS
+            .iter()
+            .copied()
+            .filter(|kind| kind.is_defined())
+            .collect::<Vec<_>>();
+        select(kinds)
+            .prop_flat_map(|kind| select(Pentonimo::new(kind).variants().collect::<Vec<_>>()))
+            .boxed()
+    }
+}
+
+/// boards of up to 20x20 that are at least `min` large, with up to 3/8 of
+/// their cells blocked
+fn tile_maps(min: (u32, u32)) -> BoxedStrategy<TileMap> {
+    (min.0.max(1)..=20, min.1.max(1)..=20, 0..4u32)
+        .prop_flat_map(|(w, h, density)| {
+            vec(weighted(density as f64 / 8.), (w * h) as usize).prop_map(move |blocked| {
+                let mut map = TileMap::new((w, h));
+                for (i, _) in blocked.iter().enumerate().filter(|(_, &b)| b) {
+                    map.block(i as u32 % w, i as u32 / w);
+                }
+                map
+            })
+        })
+        .boxed()
+}
+
+impl Arbitrary for TileMap {
+    fn strategy() -> BoxedStrategy<Self> {
+        tile_maps((1, 1))
+    }
+}
+
+/// a board together with a pentonimo that fits within its bounds
+impl Arbitrary for (TileMap, PositionedPentonimo) {
+    fn strategy() -> BoxedStrategy<Self> {
+        Pentonimo::strategy()
+            .prop_flat_map(|pentonimo| {
+                let (w, h) = pentonimo.shape();
+                (tile_maps((w as u32, h as u32)), Just(pentonimo))
+            })
+            .prop_flat_map(|(map, pentonimo)| {
+                let (w, h) = pentonimo.shape();
+                let (px, py) = (map.shape.0 - w as u32, map.shape.1 - h as u32);
+                (0..=px, 0..=py).prop_map(move |(x, y)| (map.clone(), pentonimo.position(x, y)))
+            })
+            .boxed()
+    }
+}
+
+/// checks `property` for `cases` random inputs and panics with the smallest
+/// failing input proptest can shrink to. The inputs only depend on the seed,
+/// which is 0 unless `PROPTEST_RNG_SEED` sets it.
+pub fn check<T: Arbitrary>(cases: u32, property: impl Fn(&T) -> bool) {
+    let seed = match Config::default().rng_seed {
+        RngSeed::Fixed(seed) => seed,
+        RngSeed::Random => 0,
+    };
+    let config = Config {
+        cases,
+        rng_seed: RngSeed::Fixed(seed),
+        failure_persistence: None,
+        ..Config::default()
+    };
+    let result = TestRunner::new(config).run(&T::strategy(), |input| match property(&input) {
+        true => Ok(()),
+        false => Err(TestCaseError::fail("property returned false")),
+    });
+    match result {
+        Ok(()) => {}
+        Err(TestError::Fail(reason, input)) => {
+            panic!(
+                "property failed with PROPTEST_RNG_SEED={seed}: {reason}\nminimal input: {input:?}"
+            )
+        }
+        Err(TestError::Abort(reason)) => {
+            panic!("property aborted with PROPTEST_RNG_SEED={seed}: {reason}")
+        }
+    }
+}
@@ -0,0 +1,522 @@
+//! The concurrency primitives the solver is built on.
+//!
+//! With the default `parallel` feature root placements are searched on a thread
+//! pool and share a `DashMap` of visited states. Without it every job runs on the
+//! calling thread and states live in a plain hash map, which keeps the solver
+//! core free of threads for `wasm32` and embedded targets.
+
+use std::hash::Hash;
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+/// The clock of the time limits and statistics of searches. The one of std
+/// panics on `wasm32` in browsers, where `web-time` asks the page instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Runs the independent jobs of a search
+pub trait Executor {
+    fn execute(&self, job: Box<dyn FnOnce() + Send + 'static>);
+    /// waits until all jobs have finished
+    fn join(&self);
+}
+
+/// Runs every job immediately on the calling thread
+pub struct SingleThreaded;
+
+impl Executor for SingleThreaded {
+    fn execute(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        job()
+    }
+    fn join(&self) {}
+}
+
+#[cfg(feature = "parallel")]
+pub struct ThreadPoolExecutor {
+    pool: threadpool::ThreadPool,
+    /// the cores the threads are pinned to, in turn
+    cores: Option<Arc<PinnedCores>>,
+    /// keeps the calling thread on its reserved core until the pool is dropped
+    _reserved: Option<affinity::Restore>,
+}
+
+#[cfg(feature = "parallel")]
+struct PinnedCores {
+    cores: Vec<usize>,
+    next: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(feature = "parallel")]
+thread_local! {
+    /// whether the current pool thread was pinned already
+    static PINNED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+#[cfg(feature = "parallel")]
+impl ThreadPoolExecutor {
+    pub fn new(num_threads: usize, pinning: Pinning) -> Self {
+        let mut cores = if pinning.workers || pinning.reserve_core {
+            affinity::allowed_cores()
+        } else {
+            Vec::new()
+        };
+        if (pinning.workers || pinning.reserve_core) && cores.is_empty() {
+            eprintln!("warning: threads can't be pinned to cores on this system");
+        }
+
+        let mut reserved = None;
+        if pinning.reserve_core && !cores.is_empty() {
+            if cores.len() < 2 {
+                eprintln!("warning: a single core can't be reserved for the coordinator");
+            } else {
+                let core = cores.remove(0);
+                reserved = affinity::pin_current_thread(core);
+            }
+        }
+
+        Self {
+            pool: threadpool::ThreadPool::new(num_threads),
+            cores: (pinning.workers && !cores.is_empty()).then(|| {
+                Arc::new(PinnedCores {
+                    cores,
+                    next: Default::default(),
+                })
+            }),
+            _reserved: reserved,
+        }
+    }
+
+    /// A pool whose threads are pinned to `cores` in turn, e.g. those of a
+    /// NUMA node
+    pub fn on_cores(num_threads: usize, cores: Vec<usize>) -> Self {
+        Self {
+            pool: threadpool::ThreadPool::new(num_threads),
+            cores: (!cores.is_empty()).then(|| {
+                Arc::new(PinnedCores {
+                    cores,
+                    next: Default::default(),
+                })
+            }),
+            _reserved: None,
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Executor for ThreadPoolExecutor {
+    fn execute(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        let Some(cores) = self.cores.clone() else {
+            return self.pool.execute(job);
+        };
+        self.pool.execute(move || {
+            if !PINNED.get() {
+                // the threads of the pool are new, and pinned by their first job
+                let next = cores
+                    .next
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                // dropping the guard would unpin the thread right away, pool
+                // threads stay pinned until they exit
+                std::mem::forget(affinity::pin_current_thread(
+                    cores.cores[next % cores.cores.len()],
+                ));
+                PINNED.set(true);
+            }
+            job()
+        })
+    }
+    fn join(&self) {
+        self.pool.join()
+    }
+}
+
+/// Which threads of a search are pinned to a core of their own
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Pinning {
+    /// pin every thread of the pool to a core, taking turns if there are more
+    /// threads than cores
+    pub workers: bool,
+    /// keep a core for the thread that started the search, which waits for the
+    /// pool, reports progress and writes checkpoints. The workers use the others.
+    pub reserve_core: bool,
+}
+
+/// Pinning threads with `sched_setaffinity`, which only exists on linux
+#[cfg(all(feature = "parallel", target_os = "linux"))]
+mod affinity {
+    use std::mem::{size_of, zeroed};
+
+    /// The affinity of a thread before it was pinned, which is restored when
+    /// this is dropped
+    pub struct Restore(libc::cpu_set_t);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            // SAFETY: the set is a valid cpu_set_t of the given size
+            unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &self.0) };
+        }
+    }
+
+    fn current() -> Option<libc::cpu_set_t> {
+        // SAFETY: cpu_set_t is a plain bit set, which is valid when zeroed
+        let mut set = unsafe { zeroed::<libc::cpu_set_t>() };
+        // SAFETY: the set is a valid cpu_set_t of the given size
+        let result = unsafe { libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) };
+        (result == 0).then_some(set)
+    }
+
+    /// the cores the calling thread may run on
+    pub fn allowed_cores() -> Vec<usize> {
+        let Some(set) = current() else {
+            return Vec::new();
+        };
+        (0..libc::CPU_SETSIZE as usize)
+            // SAFETY: every core is below CPU_SETSIZE
+            .filter(|&core| unsafe { libc::CPU_ISSET(core, &set) })
+            .collect()
+    }
+
+    /// Pins the calling thread to `core`. None if that failed.
+    pub fn pin_current_thread(core: usize) -> Option<Restore> {
+        let previous = current()?;
+        // SAFETY: cpu_set_t is a plain bit set, which is valid when zeroed
+        let mut set = unsafe { zeroed::<libc::cpu_set_t>() };
+        // SAFETY: `allowed_cores` only returns cores below CPU_SETSIZE
+        unsafe { libc::CPU_SET(core, &mut set) };
+        // SAFETY: the set is a valid cpu_set_t of the given size
+        let result = unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) };
+        (result == 0).then_some(Restore(previous))
+    }
+}
+
+#[cfg(all(feature = "parallel", not(target_os = "linux")))]
+mod affinity {
+    pub struct Restore;
+
+    pub fn allowed_cores() -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub fn pin_current_thread(_core: usize) -> Option<Restore> {
+        None
+    }
+}
+
+/// `PENTONIMO_NUM_THREADS` or the number of cpus
+#[cfg(feature = "parallel")]
+pub fn default_num_threads() -> usize {
+    (|| std::env::var("PENTONIMO_NUM_THREADS").ok()?.parse().ok())().unwrap_or_else(num_cpus::get)
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn default_num_threads() -> usize {
+    1
+}
+
+/// The executor best suited for `num_threads` on this build. Threads are only
+/// pinned on a thread pool.
+pub fn executor(num_threads: usize, pinning: Pinning) -> Box<dyn Executor> {
+    #[cfg(feature = "parallel")]
+    if num_threads > 1 {
+        return Box::new(ThreadPoolExecutor::new(num_threads, pinning));
+    }
+
+    _ = (num_threads, pinning);
+    Box::new(SingleThreaded)
+}
+
+/// An executor of `num_threads` threads pinned to `cores`, which is single
+/// threaded without the `parallel` feature
+pub fn executor_on_cores(num_threads: usize, cores: Vec<usize>) -> Box<dyn Executor> {
+    #[cfg(feature = "parallel")]
+    return Box::new(ThreadPoolExecutor::on_cores(num_threads, cores));
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        _ = (num_threads, cores);
+        Box::new(SingleThreaded)
+    }
+}
+
+/// The cores of every NUMA node with cores, read from
+/// `/sys/devices/system/node`. Empty without the `numa` feature or where that
+/// doesn't exist.
+#[cfg(all(feature = "numa", target_os = "linux"))]
+pub fn numa_nodes() -> Vec<Vec<usize>> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return Vec::new();
+    };
+    let mut nodes = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let node = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse()
+                .ok()?;
+            let cpus = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((node, parse_cpu_list(cpus.trim())?))
+        })
+        .filter(|(_, cores): &(usize, Vec<usize>)| !cores.is_empty())
+        .collect::<Vec<_>>();
+    nodes.sort_unstable();
+
+    // only the cores this process may run on
+    let allowed = affinity::allowed_cores();
+    nodes
+        .into_iter()
+        .map(|(_, cores)| {
+            cores
+                .into_iter()
+                .filter(|core| allowed.contains(core))
+                .collect()
+        })
+        .filter(|cores: &Vec<usize>| !cores.is_empty())
+        .collect()
+}
+
+#[cfg(not(all(feature = "numa", target_os = "linux")))]
+pub fn numa_nodes() -> Vec<Vec<usize>> {
+    Vec::new()
+}
+
+/// parses lists of cpus like `0-3,8,10-11`
+#[cfg(any(test, all(feature = "numa", target_os = "linux")))]
+fn parse_cpu_list(s: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in s.split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => cpus.extend(start.parse::<usize>().ok()?..=end.parse().ok()?),
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+/// A map that can be shared between the jobs of an [`Executor`]
+pub struct SharedMap<K, V> {
+    #[cfg(feature = "parallel")]
+    inner: dashmap::DashMap<K, V, rustc_hash::FxBuildHasher>,
+    #[cfg(not(feature = "parallel"))]
+    inner: std::sync::Mutex<rustc_hash::FxHashMap<K, V>>,
+}
+
+impl<K: Hash + Eq, V> SharedMap<K, V> {
+    /// A map split into `shards` separately locked parts, a power of two of
+    /// at least 2, with room for `capacity` entries
+    #[cfg(feature = "parallel")]
+    pub fn new(shards: usize, capacity: usize) -> Self {
+        Self {
+            inner: dashmap::DashMap::with_capacity_and_hasher_and_shard_amount(
+                capacity,
+                rustc_hash::FxBuildHasher,
+                shards,
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn new(_shards: usize, capacity: usize) -> Self {
+        Self {
+            inner: std::sync::Mutex::new(rustc_hash::FxHashMap::with_capacity_and_hasher(
+                capacity,
+                Default::default(),
+            )),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.lock().unwrap().contains_key(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Copy,
+    {
+        self.get_with(key, |value| *value)
+    }
+
+    /// Calls `f` with the value of `key` while the map is locked
+    #[cfg(feature = "parallel")]
+    pub fn get_with<R>(&self, key: &K, f: impl FnOnce(&V) -> R) -> Option<R> {
+        self.inner.get(key).map(|value| f(&value))
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn get_with<R>(&self, key: &K, f: impl FnOnce(&V) -> R) -> Option<R> {
+        self.inner.lock().unwrap().get(key).map(f)
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn insert(&self, key: K, value: V) {
+        self.inner.insert(key, value);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn insert(&self, key: K, value: V) {
+        self.inner.lock().unwrap().insert(key, value);
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity()
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn clear(&self) {
+        self.inner.clear();
+        self.inner.shrink_to_fit();
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = Default::default();
+    }
+
+    /// Rough estimate of the memory used by the map, given how many bytes every
+    /// entry owns on the heap
+    pub fn estimated_bytes(&self, heap_per_entry: usize) -> u64 {
+        // hashbrown stores one control byte per bucket
+        let bucket = std::mem::size_of::<(K, V)>() + 1;
+        (self.capacity() * bucket + self.len() * heap_per_entry) as u64
+    }
+}
+
+#[cfg(all(feature = "parallel", target_os = "linux"))]
+#[test]
+fn pinning_is_restored() {
+    let cores = affinity::allowed_cores();
+    assert!(!cores.is_empty());
+    let restore = affinity::pin_current_thread(cores[cores.len() - 1]).unwrap();
+    assert_eq!(affinity::allowed_cores(), [cores[cores.len() - 1]]);
+    drop(restore);
+    assert_eq!(affinity::allowed_cores(), cores);
+}
+
+#[cfg(all(feature = "parallel", target_os = "linux"))]
+#[test]
+fn pool_threads_stay_pinned() {
+    let cores = affinity::allowed_cores();
+    let core = cores[cores.len() - 1];
+    let pool = ThreadPoolExecutor::on_cores(1, vec![core]);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for _ in 0..3 {
+        let sender = sender.clone();
+        pool.execute(Box::new(move || {
+            sender.send(affinity::allowed_cores()).unwrap()
+        }));
+    }
+    pool.join();
+    // the first job pins the thread, the others run on the same core
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [[core]; 3]);
+    assert_eq!(affinity::allowed_cores(), cores);
+}
+
+#[cfg(all(feature = "parallel", target_os = "linux"))]
+#[test]
+fn memory_is_local_to_the_cores_of_a_pool() {
+    use std::ptr::null_mut;
+
+    // the core and node the calling thread runs on
+    fn location() -> (usize, usize) {
+        let (mut core, mut node) = (0u32, 0u32);
+        // SAFETY: getcpu writes both numbers and ignores its cache argument
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_getcpu,
+                &mut core as *mut u32,
+                &mut node as *mut u32,
+                null_mut::<libc::c_void>(),
+            )
+        };
+        assert_eq!(result, 0);
+        (core as usize, node as usize)
+    }
+    // the node of the page of `address`, None without NUMA support
+    fn page_node(address: *const u8) -> Option<usize> {
+        const MPOL_F_NODE: libc::c_ulong = 1;
+        const MPOL_F_ADDR: libc::c_ulong = 2;
+        let mut node = 0;
+        // SAFETY: with these flags get_mempolicy only writes the node of the
+        // mapped page at `address` to `node`
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_get_mempolicy,
+                &mut node as *mut libc::c_int,
+                null_mut::<libc::c_ulong>(),
+                0 as libc::c_ulong,
+                address,
+                MPOL_F_NODE | MPOL_F_ADDR,
+            )
+        };
+        (result == 0).then_some(node as usize)
+    }
+
+    // every core is a node of its own on machines with a single one
+    let mut nodes = numa_nodes();
+    if nodes.len() < 2 {
+        nodes = affinity::allowed_cores()
+            .into_iter()
+            .map(|core| vec![core])
+            .collect();
+    }
+    for cores in nodes {
+        let pool = executor_on_cores(2, cores.clone());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // more jobs than threads, so threads run jobs after their first one
+        for _ in 0..6 {
+            let sender = sender.clone();
+            pool.execute(Box::new(move || {
+                // like the memo tables, the memory is touched first by the pool
+                let memory = vec![1u8; 1 << 20];
+                let (core, node) = location();
+                let page = page_node(std::hint::black_box(&memory[memory.len() / 2]));
+                sender.send((core, node, page)).unwrap();
+            }));
+        }
+        pool.join();
+        for (core, node, page) in receiver.try_iter() {
+            assert!(cores.contains(&core), "{core} isn't one of {cores:?}");
+            if let Some(page) = page {
+                assert_eq!(page, node);
+            }
+        }
+    }
+}
+
+#[test]
+fn cpu_lists() {
+    assert_eq!(
+        parse_cpu_list("0-3,8,10-11"),
+        Some(vec![0, 1, 2, 3, 8, 10, 11])
+    );
+    assert_eq!(parse_cpu_list(""), Some(vec![]));
+    assert_eq!(parse_cpu_list("1-x"), None);
+}
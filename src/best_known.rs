@@ -0,0 +1,131 @@
+//! The best diameters earlier runs found for boards without holes, free cells
+//! or ends. Searches start from them to prune more, and `solve` warns when a
+//! run falls short of them.
+//!
+//! New results are added to [`BEST_KNOWN`]: `solve` prints the entry of every
+//! board it solved better than the table. Only entries a `--prove` run backs
+//! are optimal, searches with monotone pruning can miss longer placements.
+
+use crate::{
+    pentonimo::{KINDS, PENTOMINOES},
+    spec::BoardSpec,
+};
+
+/// the best diameter found for a board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bound {
+    pub diameter: u32,
+    /// proved by an exhaustive (`--prove`) search, so no placement is longer
+    pub optimal: bool,
+}
+
+pub struct Known {
+    /// (width, height) with width >= height
+    pub shape: (u32, u32),
+    pub pieces: [u8; KINDS],
+    pub bound: Bound,
+}
+
+/// one of each pentomino
+const ALL: [u8; KINDS] = PENTOMINOES;
+
+/// a diameter of all pentominoes proved optimal by `solve --prove`
+const fn optimal(shape: (u32, u32), diameter: u32) -> Known {
+    Known {
+        shape,
+        pieces: ALL,
+        bound: Bound {
+            diameter,
+            optimal: true,
+        },
+    }
+}
+
+pub const BEST_KNOWN: &[Known] = &[
+    optimal((3, 3), 4),
+    optimal((4, 3), 7),
+    optimal((5, 3), 10),
+    optimal((6, 3), 13),
+    optimal((7, 3), 14),
+    optimal((8, 3), 15),
+    optimal((9, 3), 17),
+    optimal((10, 3), 20),
+    optimal((11, 3), 23),
+    optimal((4, 4), 11),
+    optimal((5, 4), 13),
+    optimal((6, 4), 14),
+    optimal((7, 4), 18),
+    optimal((8, 4), 20),
+    optimal((5, 5), 15),
+    optimal((6, 5), 20),
+    optimal((7, 5), 22),
+    optimal((8, 5), 25),
+    optimal((6, 6), 22),
+    optimal((7, 6), 27),
+];
+
+/// The best known diameter of a `shape` board with `pieces`, in either orientation
+pub fn best_known(shape: (u32, u32), pieces: [u8; KINDS]) -> Option<Bound> {
+    let shape = (shape.0.max(shape.1), shape.0.min(shape.1));
+    BEST_KNOWN
+        .iter()
+        .find(|known| known.shape == shape && known.pieces == pieces)
+        .map(|known| known.bound)
+}
+
+/// [`best_known`] of `spec`, unless it has holes, free cells, ends, regions or
+/// one-sided pieces
+pub fn best_known_for(spec: &BoardSpec) -> Option<Bound> {
+    if !spec.holes.is_empty()
+        || !spec.free.is_empty()
+        || !spec.ends.is_empty()
+        || !spec.regions.is_empty()
+        || spec.one_sided
+    {
+        return None;
+    }
+    best_known(spec.shape, spec.pieces)
+}
+
+/// The entry of [`BEST_KNOWN`] for `bound` on `spec`
+pub fn entry(spec: &BoardSpec, bound: Bound) -> String {
+    let (w, h) = spec.shape;
+    let shape = (w.max(h), w.min(h));
+    if spec.pieces == ALL && bound.optimal {
+        return format!("optimal({shape:?}, {}),", bound.diameter);
+    }
+    format!(
+        "Known {{ shape: {shape:?}, pieces: {:?}, bound: Bound {{ diameter: {}, optimal: {} }} }},",
+        spec.pieces, bound.diameter, bound.optimal
+    )
+}
+
+#[test]
+fn entries_are_unique_and_canonical() {
+    for (i, known) in BEST_KNOWN.iter().enumerate() {
+        assert!(known.shape.0 >= known.shape.1, "{:?}", known.shape);
+        assert!(
+            BEST_KNOWN[..i]
+                .iter()
+                .all(|other| (other.shape, other.pieces) != (known.shape, known.pieces)),
+            "{:?} is listed twice",
+            known.shape
+        );
+    }
+
+    let spec = "4x7".parse::<BoardSpec>().unwrap();
+    assert_eq!(best_known_for(&spec).map(|bound| bound.diameter), Some(18));
+    let spec = "7x4 holes=0,0".parse::<BoardSpec>().unwrap();
+    assert_eq!(best_known_for(&spec), None);
+    let spec = "7x4 pieces=X=0".parse::<BoardSpec>().unwrap();
+    assert_eq!(best_known_for(&spec), None);
+    assert_eq!(
+        entry(&"4x7".parse().unwrap(), best_known((7, 4), ALL).unwrap()),
+        "optimal((7, 4), 18),"
+    );
+    let unproved = Bound {
+        diameter: 18,
+        optimal: false,
+    };
+    assert!(entry(&"4x7".parse().unwrap(), unproved).starts_with("Known {"));
+}
@@ -1,17 +1,46 @@
+//! The exact search: a depth first search over the placements of the pieces
+//! that prunes states which can't beat the best diameter found so far and
+//! remembers the states it has searched.
+//!
+//! Unless [`SearchConfig::exhaustive`] is set it also discards every placement
+//! that makes the diameter smaller. This is a heuristic: a set of pieces is
+//! only found if it can be placed in an order that never makes the diameter
+//! smaller, and a piece cutting off the far end of the path may be needed
+//! before another one adds a longer detour. It rarely loses anything and makes
+//! the search much smaller, but only exhaustive searches prove their result
+//! optimal.
+
 use std::{
-    ops::Deref,
-    sync::{Arc, Mutex},
+    borrow::Cow,
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Duration,
 };
 
-use rustc_hash::FxBuildHasher;
 use strum::VariantArray;
-use threadpool::ThreadPool;
 
 use crate::{
+    backend::{self, Instant, Pinning, SharedMap},
+    best_known::best_known_for,
     candidates::Candidates,
-    pathfinding::BfsScratch,
+    certificate::Certificate,
+    checkpoint::{write_atomic, Checkpoint, Checkpointer},
+    greedy::{self, Greedy},
+    monitor::{MemoUsage, MemoryPolicy, Monitor},
+    pathfinding::{BfsScratch, Point},
     pentonimo::{Pentonimo, PentonimoKind, PositionedPentonimo},
+    profile::{Profile, Stopwatch},
+    solution::Solution,
+    spec::BoardSpec,
+    strategy::SearchStrategy,
+    tile::Tile,
     tile_map::TileMap,
+    trace::{End, Trace, TraceNode},
 };
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -21,6 +50,18 @@ pub struct StateKey {
 }
 
 impl StateKey {
+    /// Two independent 64 bit hashes. Two different states end up with the same
+    /// hash with a chance of about 2^-128.
+    fn hash128(&self) -> u128 {
+        let hash = |seed: u64| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            self.hash(&mut hasher);
+            hasher.finish()
+        };
+        (hash(0) as u128) << 64 | hash(1) as u128
+    }
+
     fn avaiable_variants(&self) -> impl Iterator<Item = Pentonimo> + '_ {
         PentonimoKind::VARIANTS
             .iter()
@@ -29,54 +70,599 @@ impl StateKey {
     }
 }
 
+/// The searched states and their diameters
+enum Memo {
+    Exact(SharedMap<StateKey, u32>),
+    /// only stores hashes of the states, which are smaller than the states
+    /// themselves and never own heap memory
+    Hashed {
+        states: SharedMap<u128, u32>,
+        check: Option<HashCheck>,
+    },
+}
+
+/// one in this many hashes keeps its full state for `HashCheck`
+const HASH_CHECK_SAMPLE: u128 = 64;
+
+/// Keeps the full states of a sample of the hashes to find out how often
+/// different states share a hash. Sampling by the hash means two states with
+/// the same hash are either both sampled or both not.
+struct HashCheck {
+    states: SharedMap<u128, StateKey>,
+    checked: AtomicU64,
+    collisions: AtomicU64,
+}
+
+/// How often the hashes of states matched during a search
+#[derive(Debug, Clone, Copy)]
+pub struct HashCheckReport {
+    /// sampled lookups of a hash that was already stored
+    pub checked: u64,
+    /// lookups among them that found a different state
+    pub collisions: u64,
+}
+
+impl HashCheck {
+    fn check(&self, hash: u128, key: &StateKey) {
+        if !hash.is_multiple_of(HASH_CHECK_SAMPLE) {
+            return;
+        }
+        match self.states.get_with(&hash, |stored| stored == key) {
+            Some(same) => {
+                self.checked.fetch_add(1, Ordering::Relaxed);
+                if !same {
+                    self.collisions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            None => self.states.insert(hash, key.clone()),
+        }
+    }
+
+    fn report(&self) -> HashCheckReport {
+        HashCheckReport {
+            checked: self.checked.load(Ordering::Relaxed),
+            collisions: self.collisions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Memo {
+    fn contains_key(&self, key: &StateKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn get(&self, key: &StateKey) -> Option<u32> {
+        match self {
+            Memo::Exact(states) => states.get(key),
+            Memo::Hashed { states, check } => {
+                let hash = key.hash128();
+                let diameter = states.get(&hash);
+                if let (Some(_), Some(check)) = (diameter, check) {
+                    check.check(hash, key);
+                }
+                diameter
+            }
+        }
+    }
+
+    fn insert(&self, key: StateKey, diameter: u32) {
+        match self {
+            Memo::Exact(states) => states.insert(key, diameter),
+            Memo::Hashed { states, check } => {
+                let hash = key.hash128();
+                if let Some(check) = check {
+                    check.check(hash, &key);
+                }
+                states.insert(hash, diameter)
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Memo::Exact(states) => states.len(),
+            Memo::Hashed { states, .. } => states.len(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match self {
+            Memo::Exact(states) => states.capacity(),
+            Memo::Hashed { states, .. } => states.capacity(),
+        }
+    }
+
+    fn clear(&self) {
+        match self {
+            Memo::Exact(states) => states.clear(),
+            Memo::Hashed { states, check } => {
+                states.clear();
+                if let Some(check) = check {
+                    check.states.clear();
+                }
+            }
+        }
+    }
+
+    fn estimated_bytes(&self, heap_per_entry: usize) -> u64 {
+        match self {
+            Memo::Exact(states) => states.estimated_bytes(heap_per_entry),
+            Memo::Hashed { states, check } => {
+                let samples = check
+                    .as_ref()
+                    .map_or(0, |check| check.states.estimated_bytes(heap_per_entry));
+                states.estimated_bytes(0) + samples
+            }
+        }
+    }
+
+    fn hash_check(&self) -> Option<HashCheckReport> {
+        match self {
+            Memo::Hashed {
+                check: Some(check), ..
+            } => Some(check.report()),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for a single call to `find_best`
+#[derive(Debug, Default, Clone)]
+pub struct SearchConfig {
+    /// measure where the search spends its time
+    pub profile: bool,
+    /// print progress to stderr in this interval
+    pub progress: Option<Duration>,
+    /// print the size and load of the table of searched states to stderr in
+    /// this interval
+    pub memo_stats: Option<Duration>,
+    /// limit for the estimated memory used by stored states and buffers
+    pub memory_cap: Option<u64>,
+    pub memory_policy: MemoryPolicy,
+    /// stop the search after this time and report the best result found so far
+    pub time_limit: Option<Duration>,
+    /// stop the search like `time_limit` once another thread sets this
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// save the finished roots and the best result to this file in this interval
+    pub checkpoint: Option<(PathBuf, Duration)>,
+    /// continue the search saved in this checkpoint
+    pub resume: Option<Checkpoint>,
+    /// Write the result of every root to `root-<index>.checkpoint` in this
+    /// directory as soon as it is done, a checkpoint with only this root done.
+    /// They show the progress of long searches and can be merged into one
+    /// checkpoint to resume from, also after a crash.
+    pub root_files: Option<PathBuf>,
+    /// collect every distinct optimal placement instead of a single one
+    pub all_optima: bool,
+    /// Search every state, also those reached by making the diameter smaller
+    /// by more than `max_decrease`, so a complete search proves the result optimal. Roots that are a
+    /// rotation or reflection of an earlier root are skipped.
+    pub exhaustive: bool,
+    /// Skip states dominated by a searched state with one piece less and at
+    /// least the same diameter. Much faster, but the searched state may not
+    /// reach what the skipped one would have, so optimal placements can be missed.
+    pub dominance: bool,
+    /// Store complete states instead of 128 bit hashes of them. Needs several
+    /// times the memory, but rules out hash collisions.
+    pub exact_states: bool,
+    /// keep the full states of a sample of the hashes and count collisions
+    pub check_hashes: bool,
+    /// print every improvement of the best diameter and every finished root
+    pub debug: bool,
+    /// threads of the search instead of [`backend::default_num_threads`]
+    pub threads: Option<usize>,
+    /// which threads are pinned to cores
+    pub pinning: Pinning,
+    /// With the `numa` feature on machines with several NUMA nodes, split the
+    /// roots and threads between the nodes and give every node a table of
+    /// searched states in its own memory. Nodes don't see the states of the
+    /// others, but never wait for remote memory. Replaces `pinning`.
+    pub numa: bool,
+    /// separately locked parts of the table of searched states instead of
+    /// [`memo_shards`], a power of two of at least 2
+    pub memo_shards: Option<usize>,
+    /// states the table makes room for up front instead of [`memo_capacity`]
+    pub memo_capacity: Option<usize>,
+    /// place at most this many pieces. Searches with a limit don't prove
+    /// results optimal for the board
+    pub max_pieces: Option<usize>,
+    /// Only look for placements reaching the diameter of
+    /// [`best_known_for`] the board, which prunes much more. If the search
+    /// finds none, it runs again without it.
+    pub best_known: bool,
+    /// record the nodes of the search down to a depth in this trace
+    pub trace: Option<Arc<Trace>>,
+    /// Run [`Greedy`] first and only look for placements with a larger
+    /// diameter than its construction, which is the result if there are none.
+    /// Ignored with `max_pieces`.
+    pub greedy_seed: bool,
+    /// Search the children of states with at most this many pieces in the
+    /// order of the diameters a [`greedy::construct`] below them reaches,
+    /// largest first, instead of the order of the placements. Large diameters
+    /// are found earlier and prune more, but these states run a construction
+    /// for every child, so deep ones cost much more than they save.
+    pub rollout_depth: usize,
+    /// Also search states whose diameter is at most this much smaller than
+    /// that of their parent. 0 discards every branch making the diameter
+    /// smaller, which can miss placements that only reach a larger diameter
+    /// after a detour is cut. Larger values search more states.
+    pub max_decrease: u32,
+}
+
+/// What [`find_best`] found
+pub struct SearchResult {
+    pub diameter: u32,
+    /// the pieces in the order the search placed them
+    pub placed: Vec<PositionedPentonimo>,
+    /// With `all_optima` every distinct set of covered cells found with the
+    /// optimal diameter, each with one placement of pieces leading to it.
+    /// Placements only reachable through a smaller diameter and roots skipped
+    /// by resuming a checkpoint are missing.
+    pub optima: Vec<Vec<PositionedPentonimo>>,
+    pub profile: Option<Profile>,
+    /// false if the search was stopped before exploring all states
+    pub complete: bool,
+    /// the largest diameter a stopped search could still have found
+    pub bound: u32,
+    /// Set if an exhaustive search finished, so no placement has a larger
+    /// diameter. Assumes a resumed checkpoint was written by an exhaustive
+    /// search, too.
+    pub certificate: Option<Certificate>,
+    /// set if `check_hashes` was set
+    pub hash_check: Option<HashCheckReport>,
+    /// nodes visited by all jobs
+    pub nodes: u64,
+    /// how long it took to find the best diameter
+    pub time_to_best: Duration,
+}
+
+/// The placements with the largest diameter seen so far
+#[derive(Default)]
+struct Optima {
+    /// lets jobs skip the lock for states that can't be optimal
+    best: AtomicU32,
+    solutions: Mutex<(u32, Vec<Vec<PositionedPentonimo>>)>,
+}
+
+impl Optima {
+    fn record(&self, diameter: u32, placed: &[PositionedPentonimo]) {
+        if diameter < self.best.load(Ordering::Relaxed) {
+            return;
+        }
+        self.best.fetch_max(diameter, Ordering::Relaxed);
+
+        let mut solutions = self.solutions.lock().unwrap();
+        if diameter > solutions.0 {
+            *solutions = (diameter, Vec::new());
+        }
+        if diameter == solutions.0 {
+            solutions.1.push(placed.to_vec());
+        }
+    }
+}
+
+/// how many nodes a job visits or prunes between checks of the monitor
+const CHECK_STEPS: u64 = 1024;
+
 struct DfsState {
     scratch: BfsScratch,
-    states: Arc<dashmap::DashMap<StateKey, u16, FxBuildHasher>>,
+    profiling: bool,
+    profile: Profile,
+    monitor: Arc<Monitor>,
+    /// the searched states of every NUMA node, a single table without `numa`
+    memos: Arc<[Memo]>,
+    /// the node of this job, whose table it uses
+    node: usize,
+    /// heap memory owned by every stored state
+    state_heap_bytes: usize,
     buffer_capacity: usize,
     buffer_pool: Vec<Vec<PositionedPentonimo>>,
+    optima: Option<Arc<Optima>>,
+    /// the pieces placed to reach the current state
+    path: Vec<PositionedPentonimo>,
+    exhaustive: bool,
+    dominance: bool,
+    rollout_depth: usize,
+    max_decrease: u32,
+    debug: bool,
+    max_pieces: Option<usize>,
+    /// the fixed ends of the path, see [`BoardSpec::ends`]
+    ends: Arc<[Point]>,
+    /// the index of the root this job searches
+    root: usize,
+    /// the largest diameter this job has reported to the monitor
+    reported: u32,
+    /// nodes visited and subtrees pruned, to check the monitor regularly
+    steps: u64,
+    /// nodes already added to the count of the monitor
+    reported_nodes: u64,
+    /// the symmetries of the board except the identity, empty if the states
+    /// are stored as they are
+    symmetries: Arc<[Symmetry]>,
+    trace: Option<Arc<Trace>>,
+    /// the ids of the traced nodes on the path, all of them or a prefix
+    traced: Vec<usize>,
+    /// the diameter of the last expanded node and why its search ended
+    ended: (u32, End),
+    /// children skipped because their states were searched before
+    known: u64,
+}
+
+impl Drop for DfsState {
+    fn drop(&mut self) {
+        let bytes = self.buffer_pool.len() * self.buffer_bytes();
+        self.monitor
+            .buffer_bytes
+            .fetch_sub(bytes as u64, Ordering::Relaxed);
+    }
 }
 
 impl DfsState {
+    fn buffer_bytes(&self) -> usize {
+        self.buffer_capacity * std::mem::size_of::<PositionedPentonimo>()
+    }
     fn get_buffer(&mut self) -> Vec<PositionedPentonimo> {
         if let Some(mut buffer) = self.buffer_pool.pop() {
             buffer.clear();
             buffer
         } else {
+            self.monitor
+                .buffer_bytes
+                .fetch_add(self.buffer_bytes() as u64, Ordering::Relaxed);
             Vec::with_capacity(self.buffer_capacity)
         }
     }
     fn return_buffer(&mut self, buffer: Vec<PositionedPentonimo>) {
         self.buffer_pool.push(buffer)
     }
+    fn step(&mut self) {
+        self.steps += 1;
+        if self.steps.is_multiple_of(CHECK_STEPS) {
+            self.check_monitor();
+        }
+    }
+    /// Reports the visited nodes and evicts the stored states if the monitor asks to
+    fn check_monitor(&mut self) {
+        let nodes = self.profile.nodes - self.reported_nodes;
+        self.monitor.nodes.fetch_add(nodes, Ordering::Relaxed);
+        self.reported_nodes = self.profile.nodes;
+
+        let usage = MemoUsage {
+            states: self.memos.iter().map(Memo::len).sum(),
+            capacity: self.memos.iter().map(Memo::capacity).sum(),
+            bytes: self
+                .memos
+                .iter()
+                .map(|memo| memo.estimated_bytes(self.state_heap_bytes))
+                .sum(),
+        };
+        if self.monitor.check(usage) {
+            self.memos.iter().for_each(Memo::clear);
+        }
+    }
+    fn states(&self) -> &Memo {
+        &self.memos[self.node]
+    }
+    /// The smallest of the symmetric images of `key`. Symmetric states have the
+    /// same diameter and mirrored subtrees, so they share a memo entry.
+    fn canonical<'a>(&self, key: &'a StateKey) -> Cow<'a, StateKey> {
+        match key.map.canonical(&self.symmetries) {
+            Cow::Borrowed(_) => Cow::Borrowed(key),
+            Cow::Owned(map) => Cow::Owned(StateKey {
+                map,
+                available: key.available,
+            }),
+        }
+    }
+    /// Whether no state with at most `bound` cells on its longest shortest path
+    /// can improve the result
+    fn is_hopeless(&self, bound: u32) -> bool {
+        let best = self.monitor.best.load(Ordering::Relaxed);
+        // all optima also need the states that only tie
+        bound < best || (bound == best && self.optima.is_none())
+    }
+    /// Whether no state reachable from `map` can improve the result. A shortest
+    /// path visits every free cell at most once and can't leave its component,
+    /// so neither bound is ever too small.
+    fn can_prune(&mut self, map: &TileMap) -> bool {
+        self.step();
+        let stopwatch = Stopwatch::start(self.profiling);
+        let hopeless = self.is_hopeless(map.free_cells()) || {
+            let component = self.scratch.largest_component(map);
+            self.is_hopeless(component)
+        };
+        stopwatch.stop(&mut self.profile.bound);
+        self.profile.pruned += hopeless as u64;
+        hopeless
+    }
+    /// The indices of the children of `key` in `available`, ordered by the
+    /// largest diameter on the way of a greedy construction below them
+    fn rollout_order(&mut self, key: &StateKey, available: &[PositionedPentonimo]) -> Vec<usize> {
+        let stopwatch = Stopwatch::start(self.profiling);
+        let mut scored = Vec::with_capacity(available.len());
+        for (i, &positioned) in available.iter().enumerate() {
+            if !key.map.can_place(positioned) {
+                continue;
+            }
+            let map = key.map.clone() | positioned;
+            let mut pieces = key.available;
+            pieces.decrement(positioned.pentonimo().kind() as u8);
+            let (reached, _) = self.scratch.objective(&map, &self.ends);
+            let (steps, _) =
+                greedy::construct(&mut self.scratch, map, pieces, available, &self.ends);
+            let score = steps
+                .iter()
+                .map(|&(diameter, _)| diameter)
+                .fold(reached, u32::max);
+            scored.push((score, i));
+        }
+        // stable, so children with the same score keep the order of the placements
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        stopwatch.stop(&mut self.profile.diameter);
+        scored.into_iter().map(|(_, i)| i).collect()
+    }
+    /// Whether a searched state has fewer blocked cells, more pieces left and
+    /// at least the same diameter. Such states differ from `key` by one of the
+    /// pieces placed before the last one.
+    fn is_dominated(&mut self, key: &StateKey, diameter: u32) -> bool {
+        let stopwatch = Stopwatch::start(self.profiling);
+        let earlier = &self.path[..self.path.len().saturating_sub(1)];
+        let dominated = earlier.iter().any(|&piece| {
+            let mut map = key.map.clone();
+            map.remove(piece);
+            let mut available = key.available;
+            available.increment(piece.pentonimo().kind() as u8);
+            let key = StateKey { map, available };
+            let stored = self.states().get(&self.canonical(&key));
+            stored.is_some_and(|stored| stored >= diameter)
+        });
+        stopwatch.stop(&mut self.profile.memo);
+        self.profile.dominated += dominated as u64;
+        dominated
+    }
+    /// Searches the state `key` and records it in the trace, if it's traced.
+    /// Returns the best diameter reachable from it and the pieces reaching it.
     fn dfs(
         &mut self,
         key: StateKey,
-        prev_diameter: u16,
+        prev_diameter: u32,
         available: &[PositionedPentonimo],
-    ) -> (u16, Vec<PositionedPentonimo>) {
-        let (diameter, _) = self.scratch.graph_diameter(&key.map);
+    ) -> (u32, Vec<PositionedPentonimo>) {
+        let depth = self.path.len();
+        let id = (self.trace.as_ref())
+            .filter(|_| self.traced.len() + 1 == depth)
+            .and_then(|trace| trace.reserve(depth));
+        let Some(id) = id else {
+            return self.expand(key, prev_diameter, available);
+        };
+
+        let before = (
+            self.profile.nodes,
+            self.profile.pruned,
+            self.known,
+            self.profile.dominated,
+        );
+        self.traced.push(id);
+        let result = self.expand(key, prev_diameter, available);
+        self.traced.pop();
+
+        let (diameter, end) = self.ended;
+        self.trace.as_ref().unwrap().record(TraceNode {
+            id,
+            parent: self.traced.last().copied(),
+            root: self.root,
+            depth,
+            piece: self.path[depth - 1],
+            diameter,
+            nodes: self.profile.nodes - before.0,
+            pruned: self.profile.pruned - before.1,
+            known: self.known - before.2,
+            dominated: self.profile.dominated - before.3,
+            end,
+        });
+        result
+    }
+    fn expand(
+        &mut self,
+        key: StateKey,
+        prev_diameter: u32,
+        available: &[PositionedPentonimo],
+    ) -> (u32, Vec<PositionedPentonimo>) {
+        self.profile.nodes += 1;
+        self.step();
 
-        if diameter < prev_diameter {
-            // diameter decreased. Discard this branch
+        let stopwatch = Stopwatch::start(self.profiling);
+        let (diameter, _) = self.scratch.objective(&key.map, &self.ends);
+        stopwatch.stop(&mut self.profile.diameter);
+
+        if diameter == 0 && !self.ends.is_empty() {
+            // the ends were disconnected, which no search may do
+            self.ended = (diameter, End::Disconnected);
             return (diameter, vec![]);
         }
 
+        if diameter + self.max_decrease < prev_diameter && !self.exhaustive {
+            // diameter decreased by too much. Discard this branch
+            self.ended = (diameter, End::Decreased);
+            return (diameter, vec![]);
+        }
+
+        if diameter > self.reported {
+            self.reported = diameter;
+            let best = self.monitor.improve(diameter);
+            if self.debug && diameter > best {
+                self.monitor.log(format_args!(
+                    "root {} node {}: diameter {diameter} with {} pieces",
+                    self.root,
+                    self.profile.nodes,
+                    self.path.len()
+                ));
+            }
+        }
+
+        if self.dominance && self.is_dominated(&key, diameter) {
+            // not searched, so it must not be stored either
+            self.ended = (diameter, End::Dominated);
+            return (diameter, vec![]);
+        }
+
+        if let Some(optima) = &self.optima {
+            optima.record(diameter, &self.path);
+        }
+
         let mut current_max = diameter;
         let mut placed = vec![];
+        let available = match self.max_pieces {
+            Some(max) if self.path.len() >= max => &[],
+            _ => available,
+        };
+
+        let order = if self.path.len() <= self.rollout_depth {
+            self.rollout_order(&key, available)
+        } else {
+            (0..available.len()).collect()
+        };
+
+        // Below the root the bound of the root is refined to the bounds of the
+        // children that are left. suffix_bounds[i] bounds children i and later.
+        let mut suffix_bounds = Vec::new();
+        if self.path.len() == 1 {
+            suffix_bounds = vec![0; available.len() + 1];
+            for (i, &positioned) in order.iter().map(|&j| &available[j]).enumerate().rev() {
+                let mut bound = 0;
+                if key.map.can_place(positioned) {
+                    let mut map = key.map.clone();
+                    map |= positioned;
+                    bound = self.scratch.largest_component(&map);
+                }
+                suffix_bounds[i] = suffix_bounds[i + 1].max(bound);
+            }
+        }
+
+        for (i, &positioned) in order.iter().map(|&j| &available[j]).enumerate() {
+            if self.monitor.should_stop() {
+                // the state is not fully explored, so it must not be stored
+                self.ended = (diameter, End::Stopped);
+                return (current_max, placed);
+            }
 
-        for &positioned in available {
             if key.map.can_place(positioned) {
                 let mut map = key.map.clone();
                 map |= positioned;
                 let mut available_pieces = key.available;
                 available_pieces.decrement(positioned.pentonimo().kind() as u8);
 
-                // if mx == my also check for key.mirror_xy()
                 let key = StateKey {
                     map,
                     available: available_pieces,
                 };
-                if !self.states.contains_key(&key) {
+                let stopwatch = Stopwatch::start(self.profiling);
+                let known = self.states().contains_key(&self.canonical(&key));
+                stopwatch.stop(&mut self.profile.memo);
+                self.known += known as u64;
+
+                if !known && !self.can_prune(&key.map) {
+                    let stopwatch = Stopwatch::start(self.profiling);
                     let mut new_available = self.get_buffer();
                     for &positioned in available {
                         if available_pieces.get(positioned.pentonimo().kind() as u8) > 0
@@ -85,8 +671,11 @@ impl DfsState {
                             new_available.push(positioned);
                         }
                     }
+                    stopwatch.stop(&mut self.profile.placements);
 
+                    self.path.push(positioned);
                     let (max_diameter, mut new_placed) = self.dfs(key, diameter, &new_available);
+                    self.path.pop();
 
                     self.return_buffer(new_available);
 
@@ -97,80 +686,344 @@ impl DfsState {
                     }
                 }
             }
+
+            if let Some(&bound) = suffix_bounds.get(i + 1) {
+                self.monitor.root_bounds[self.root].store(bound, Ordering::Relaxed);
+            }
         }
 
-        self.states.insert(key, diameter);
+        let stopwatch = Stopwatch::start(self.profiling);
+        let key = self.canonical(&key).into_owned();
+        self.states().insert(key, diameter);
+        stopwatch.stop(&mut self.profile.memo);
 
+        self.ended = (diameter, End::Searched);
         (current_max, placed)
     }
 }
 
-pub fn find_best(shape: (u16, u16)) -> (u16, Vec<PositionedPentonimo>) {
-    let mut scratch = BfsScratch::new(shape);
-
-    let map = TileMap::new(shape);
-    let (diameter, _) = scratch.graph_diameter(&map);
-
-    let mut available = Vec::new();
+/// Every position of every variant of the `available` pieces that fits on `map`
+pub fn placements(map: &TileMap, available: Candidates) -> Vec<PositionedPentonimo> {
     let key = StateKey {
-        map,
-        available: Candidates::new([1; 12]),
+        map: map.clone(),
+        available,
     };
+    let shape = map.shape;
+
+    let mut placements = Vec::new();
     for variant in key.avaiable_variants() {
         let (dx, dy) = variant.shape();
 
-        let Some(px) = shape.0.checked_sub(dx as u16) else {
+        let Some(px) = shape.0.checked_sub(dx as u32) else {
             continue;
         };
-        let Some(py) = shape.1.checked_sub(dy as u16) else {
+        let Some(py) = shape.1.checked_sub(dy as u32) else {
             continue;
         };
 
-        for x in 0..px {
-            for y in 0..py {
+        for x in 0..=px {
+            for y in 0..=py {
                 let positioned = variant.position(x, y);
-                debug_assert!(key.map.can_place(positioned));
-                available.push(variant.position(x, y));
+                if map.can_place(positioned) {
+                    placements.push(positioned);
+                }
             }
         }
     }
-    available.shrink_to_fit();
+    placements.shrink_to_fit();
+    placements
+}
+
+/// The placement of the pieces of `spec` with the largest diameter, or the
+/// best one found before a limit of `config` stopped the search. With the
+/// `parallel` feature the roots are searched on a thread pool. Exhaustive
+/// searches find the same diameter with any number of threads, but only
+/// searches on a single thread always report the same placement.
+pub fn find_best(spec: &BoardSpec, config: &SearchConfig) -> SearchResult {
+    let known = best_known_for(spec)
+        .filter(|_| config.best_known && !config.all_optima && config.max_pieces.is_none());
+    if let Some(known) = known {
+        let result = search(spec, config, Some(known.diameter));
+        if result.diameter >= known.diameter || !result.complete {
+            return result;
+        }
+        if config.debug {
+            eprintln!(
+                "{spec}: no placement reaches the best known diameter {}, searching again",
+                known.diameter
+            );
+        }
+    }
+    search(spec, config, None)
+}
+
+/// [`find_best`], pruning every state that can't reach `goal` if it is set
+fn search(spec: &BoardSpec, config: &SearchConfig, goal: Option<u32>) -> SearchResult {
+    let start = Instant::now();
+    let profiling = config.profile;
+    let mut profile = Profile::default();
 
-    let num_threads = (|| std::env::var("PENTONIMO_NUM_THREADS").ok()?.parse().ok())()
-        .unwrap_or_else(num_cpus::get);
-    let pool = ThreadPool::new(num_threads);
+    let shape = spec.shape;
+    let mut scratch = BfsScratch::new(shape);
+
+    let map = spec.initial_map();
+    let (diameter, _) = scratch.objective(&map, &spec.ends);
+
+    let stopwatch = Stopwatch::start(profiling);
+    let key = StateKey {
+        map,
+        available: spec.candidates(),
+    };
+    let mut available = spec.placements();
+    if config.max_pieces == Some(0) {
+        available.clear();
+    }
+    stopwatch.stop(&mut profile.placements);
+
+    let num_threads = config.threads.unwrap_or_else(backend::default_num_threads);
+    let nodes = if config.numa {
+        backend::numa_nodes()
+    } else {
+        Vec::new()
+    };
+    // every NUMA node searches its share of the roots on its own cores
+    let pools = if nodes.len() > 1 {
+        let threads = num_threads.div_ceil(nodes.len());
+        nodes
+            .into_iter()
+            .map(|cores| backend::executor_on_cores(threads, cores))
+            .collect::<Vec<_>>()
+    } else {
+        vec![backend::executor(num_threads, config.pinning)]
+    };
 
     let available = Arc::new(available);
-    // dashmap ?
-    let states = Arc::new(dashmap::DashMap::with_hasher_and_shard_amount(
-        FxBuildHasher,
-        num_threads.max(2),
+    let shards = config
+        .memo_shards
+        .unwrap_or_else(|| memo_shards(num_threads));
+    let capacity = config.memo_capacity.unwrap_or_else(|| {
+        let entry = if config.exact_states {
+            std::mem::size_of::<(StateKey, u32)>()
+        } else {
+            std::mem::size_of::<(u128, u32)>()
+        };
+        memo_capacity(&key.map, entry, config.memory_cap)
+    });
+    if config.debug {
+        eprintln!(
+            "{spec}: {} memo table(s) of {shards} shards with room for {capacity} states",
+            pools.len()
+        );
+    }
+    let (exact_states, check_hashes) = (config.exact_states, config.check_hashes);
+    let new_memo = move || {
+        if exact_states {
+            Memo::Exact(SharedMap::new(shards, capacity))
+        } else {
+            Memo::Hashed {
+                states: SharedMap::new(shards, capacity),
+                check: check_hashes.then(|| HashCheck {
+                    states: SharedMap::new(shards, capacity / HASH_CHECK_SAMPLE as usize),
+                    checked: AtomicU64::new(0),
+                    collisions: AtomicU64::new(0),
+                }),
+            }
+        }
+    };
+    let memos: Arc<[Memo]> = if pools.len() > 1 {
+        // memory belongs to the node of the thread that touches it first
+        pools
+            .iter()
+            .map(|pool| {
+                let memo = Arc::new(OnceLock::new());
+                let slot = memo.clone();
+                pool.execute(Box::new(move || _ = slot.set(new_memo())));
+                pool.join();
+                Arc::into_inner(memo).unwrap().into_inner().unwrap()
+            })
+            .collect()
+    } else {
+        Arc::new([new_memo()])
+    };
+    let monitor = Arc::new(
+        Monitor::new(
+            spec.to_string(),
+            available.len(),
+            config.progress,
+            config.memo_stats,
+            config.memory_cap,
+            config.memory_policy,
+            config.time_limit,
+        )
+        .with_cancel(config.cancel.clone())
+        .with_threads(num_threads),
+    );
+    let state_heap_bytes = if key.map.tiles().spilled() {
+        key.map.tiles().len() * std::mem::size_of::<Tile>()
+    } else {
+        0
+    };
+    let mut results = RootResults::new(available.len());
+    let mut done = vec![false; available.len()];
+    if let Some(checkpoint) = &config.resume {
+        assert_eq!(
+            (&checkpoint.best.spec, checkpoint.roots),
+            (spec, available.len()),
+            "checkpoint belongs to a different search"
+        );
+        done.clone_from(&checkpoint.done);
+        results.resumed = Some((checkpoint.best.diameter, checkpoint.best.pieces.clone()));
+    }
+    if config.greedy_seed && config.max_pieces.is_none() {
+        let seed = Greedy.search(spec);
+        if results
+            .resumed
+            .as_ref()
+            .is_none_or(|(best, _)| seed.diameter > *best)
+        {
+            results.resumed = Some((seed.diameter, seed.pieces));
+        }
+    }
+    let mut searched_roots = available.len();
+    if config.exhaustive {
+        for (done, symmetric) in done.iter_mut().zip(symmetric_roots(spec, &available)) {
+            *done |= symmetric;
+            searched_roots -= symmetric as usize;
+        }
+    }
+    let finished = done.iter().filter(|&&done| done).count();
+    monitor.roots_done.store(finished, Ordering::Relaxed);
+    // the empty board doesn't count, so only a resumed or greedy result is known to be reached
+    if let Some((best, _)) = &results.resumed {
+        monitor.best.store(*best, Ordering::Relaxed);
+    }
+    if let Some(goal) = goal {
+        // states that can reach the goal aren't pruned
+        monitor
+            .best
+            .fetch_max(goal.saturating_sub(1), Ordering::Relaxed);
+    }
+    for (index, &positioned) in available.iter().enumerate() {
+        if !done[index] {
+            let mut map = key.map.clone();
+            map |= positioned;
+            monitor.root_bounds[index].store(scratch.largest_component(&map), Ordering::Relaxed);
+        }
+    }
+    let results = Arc::new(results);
+    let done = Arc::new(Mutex::new(done));
+    let checkpointer = Arc::new(Mutex::new(
+        config
+            .checkpoint
+            .clone()
+            .map(|(path, interval)| Checkpointer::new(path, interval)),
     ));
-    let results = Arc::new(Mutex::new(Vec::<(u16, Vec<PositionedPentonimo>)>::new()));
+    let profiles = Arc::new(Mutex::new(profile));
+    let optima = config.all_optima.then(|| Arc::new(Optima::default()));
+    let exhaustive = config.exhaustive;
+    let dominance = config.dominance;
+    let rollout_depth = config.rollout_depth;
+    let max_decrease = config.max_decrease;
+    let debug = config.debug;
+    let max_pieces = config.max_pieces;
+    let ends: Arc<[Point]> = spec.ends.clone().into();
+    // all optima need every state, also those mirroring a stored one
+    let symmetries: Arc<[Symmetry]> = if config.all_optima {
+        Arc::new([])
+    } else {
+        board_symmetries(spec)
+            .into_iter()
+            .filter(|&symmetry| symmetry != (false, false, false))
+            .collect()
+    };
 
-    available.clone().deref().iter().for_each(|&positioned| {
+    let snapshot = {
+        let spec = spec.clone();
+        let results = results.clone();
+        let done = done.clone();
+        move || {
+            let (diameter, pieces) = results.best(diameter);
+            let done = done.lock().unwrap().clone();
+            Checkpoint {
+                roots: done.len(),
+                done,
+                best: Solution {
+                    spec: spec.clone(),
+                    diameter,
+                    pieces,
+                    proved_optimal: false,
+                },
+            }
+        }
+    };
+    let snapshot = Arc::new(snapshot);
+    let root_files = config
+        .root_files
+        .clone()
+        .map(|dir| Arc::new((dir, spec.clone())));
+
+    for (index, &positioned) in available.iter().enumerate() {
+        if done.lock().unwrap()[index] {
+            continue;
+        }
+
+        let initial = key.clone();
         let available = available.clone();
-        let states = states.clone();
+        let memos = memos.clone();
+        let node = index % pools.len();
         let results = results.clone();
-        pool.execute(move || {
+        let done = done.clone();
+        let checkpointer = checkpointer.clone();
+        let snapshot = snapshot.clone();
+        let profiles = profiles.clone();
+        let monitor = monitor.clone();
+        let optima = optima.clone();
+        let symmetries = symmetries.clone();
+        let ends = ends.clone();
+        let trace = config.trace.clone();
+        let root_files = root_files.clone();
+        pools[node].execute(Box::new(move || {
+            if monitor.should_stop() {
+                return;
+            }
+            let started = Instant::now();
+
             let mut state = DfsState {
                 scratch: BfsScratch::new(shape),
-                states,
+                profiling,
+                profile: Profile::default(),
+                monitor,
+                memos,
+                node,
+                state_heap_bytes,
                 buffer_capacity: available.len(),
                 buffer_pool: Vec::new(),
+                optima,
+                path: vec![positioned],
+                exhaustive,
+                dominance,
+                rollout_depth,
+                max_decrease,
+                debug,
+                max_pieces,
+                ends,
+                root: index,
+                reported: 0,
+                steps: 0,
+                reported_nodes: 0,
+                symmetries,
+                trace,
+                traced: Vec::new(),
+                ended: (0, End::Searched),
+                known: 0,
             };
 
-            let mut map = TileMap::new(shape);
-
-            map |= positioned;
-
-            let mut key = StateKey {
-                map,
-                available: Candidates::new([1; 12]),
-            };
+            let mut key = initial;
+            key.map |= positioned;
 
             key.available.decrement(positioned.pentonimo().kind() as u8);
 
+            let stopwatch = Stopwatch::start(profiling);
             let mut new_available = state.get_buffer();
 
             for &positioned in &*available {
@@ -181,9 +1034,14 @@ pub fn find_best(shape: (u16, u16)) -> (u16, Vec<PositionedPentonimo>) {
                 }
             }
 
+            stopwatch.stop(&mut state.profile.placements);
+
             let (max, mut placed) = state.dfs(key, diameter, &new_available);
+            state.return_buffer(new_available);
 
+            // the pieces of a branch come back deepest first
             placed.push(positioned);
+            placed.reverse();
 
             // let mut dest = states.lock().unwrap();
 
@@ -192,17 +1050,722 @@ pub fn find_best(shape: (u16, u16)) -> (u16, Vec<PositionedPentonimo>) {
             //     debug_assert_eq!(old_value, value);
             // }
 
-            results.lock().unwrap().push((max, placed));
-        });
-    });
+            let root_result = root_files.as_ref().map(|_| placed.clone());
+            let stopwatch = Stopwatch::start(profiling);
+            results.set(index, (max, placed));
+            stopwatch.stop(&mut state.profile.results);
+            state.monitor.improve(max);
+            state.check_monitor();
+
+            *profiles.lock().unwrap() += state.profile;
+
+            if state.monitor.should_stop() {
+                // the root was not searched completely
+                return;
+            }
+            done.lock().unwrap()[index] = true;
+            state.monitor.finish_root(index, started.elapsed());
+            if state.debug {
+                state.monitor.log(format_args!(
+                    "root {index} done: diameter {max} after {} nodes",
+                    state.profile.nodes
+                ));
+            }
+
+            if let (Some(root_files), Some(pieces)) = (root_files, root_result) {
+                let (dir, spec) = &*root_files;
+                let roots = available.len();
+                let checkpoint = Checkpoint {
+                    roots,
+                    done: (0..roots).map(|root| root == index).collect(),
+                    best: Solution {
+                        spec: spec.clone(),
+                        diameter: max,
+                        pieces,
+                        proved_optimal: false,
+                    },
+                };
+                let path = dir.join(format!("root-{index}.checkpoint"));
+                if let Err(err) = write_atomic(&path, checkpoint.to_string().as_bytes()) {
+                    eprintln!("warning: failed to write {}: {err}", path.display());
+                }
+            }
+
+            if let Some(checkpointer) = checkpointer.lock().unwrap().as_mut() {
+                if checkpointer.is_due() {
+                    write_checkpoint(checkpointer, &snapshot());
+                }
+            }
+        }));
+    }
+
+    pools.iter().for_each(|pool| pool.join());
+
+    if let Some(checkpointer) = checkpointer.lock().unwrap().as_mut() {
+        write_checkpoint(checkpointer, &snapshot());
+    }
+
+    let mut profile = Arc::into_inner(profiles).unwrap().into_inner().unwrap();
+
+    let stopwatch = Stopwatch::start(profiling);
+    let (diameter, placed) = results.best(diameter);
+    stopwatch.stop(&mut profile.results);
+    profile.wall = start.elapsed();
+
+    let optima = match optima {
+        Some(optima) => {
+            let (best, solutions) = Arc::into_inner(optima)
+                .unwrap()
+                .solutions
+                .into_inner()
+                .unwrap();
+            if best < diameter {
+                // every piece makes the diameter smaller, so only the result was recorded
+                vec![placed.clone()]
+            } else {
+                // jobs running at the same time can reach the same state
+                let mut seen = HashSet::new();
+                solutions
+                    .into_iter()
+                    .filter(|placed| {
+                        let mut map = spec.initial_map();
+                        for &piece in placed {
+                            map |= piece;
+                        }
+                        seen.insert(map)
+                    })
+                    .collect()
+            }
+        }
+        None => Vec::new(),
+    };
+
+    let complete = !monitor.should_stop();
+    let bound = monitor.bounds().1.max(diameter);
+    let certificate =
+        (config.exhaustive && !config.dominance && config.max_pieces.is_none() && complete).then(
+            || Certificate {
+                solution: Solution {
+                    spec: spec.clone(),
+                    diameter,
+                    pieces: placed.clone(),
+                    proved_optimal: true,
+                },
+                symmetries: board_symmetries(spec).len(),
+                roots: available.len(),
+                searched: searched_roots,
+                nodes: profile.nodes,
+                bound,
+            },
+        );
+    SearchResult {
+        diameter,
+        placed,
+        optima,
+        profile: profiling.then_some(profile),
+        complete,
+        bound,
+        certificate,
+        hash_check: memos
+            .iter()
+            .filter_map(Memo::hash_check)
+            .reduce(|a, b| HashCheckReport {
+                checked: a.checked + b.checked,
+                collisions: a.collisions + b.collisions,
+            }),
+        nodes: profile.nodes,
+        time_to_best: monitor.time_to_best(),
+    }
+}
 
-    pool.join();
+/// Larger tables only grow as they fill up, so big boards don't reserve
+/// gigabytes for states they may never reach
+pub const MAX_MEMO_CAPACITY: usize = 1 << 20;
+
+/// Shards of the table of searched states for a search on `num_threads`
+/// threads. With a few shards per thread, threads rarely wait for the same
+/// lock.
+pub fn memo_shards(num_threads: usize) -> usize {
+    (4 * num_threads).next_power_of_two().max(2)
+}
+
+/// States the table of searched states makes room for before a search of
+/// `map`, so it doesn't have to grow while every thread waits for it. The
+/// states of small boards grow roughly with the cube of their free cells. At
+/// most [`MAX_MEMO_CAPACITY`] states and a quarter of `memory_cap` are taken
+/// up front, given the bytes of every `entry`.
+pub fn memo_capacity(map: &TileMap, entry: usize, memory_cap: Option<u64>) -> usize {
+    let capacity = (map.free_cells() as usize).pow(3).min(MAX_MEMO_CAPACITY);
+    match memory_cap {
+        // hashbrown keeps up to half of its buckets empty
+        Some(cap) => capacity.min((cap / 4) as usize / (2 * (entry + 1))),
+        None => capacity,
+    }
+}
 
-    Arc::into_inner(results)
-        .unwrap()
-        .into_inner()
-        .unwrap()
-        .into_iter()
-        .max_by_key(|x| x.0)
-        .unwrap_or((diameter, Vec::new()))
+/// A rotation or reflection of a board: (transpose, flip x, flip y), applied in this order
+pub type Symmetry = (bool, bool, bool);
+
+/// The rotations and reflections mapping the board, its holes and the cells
+/// that have to stay free onto themselves, including the identity. One-sided
+/// boards only have the rotations, their mirror images would need mirrored
+/// pieces.
+pub fn board_symmetries(spec: &BoardSpec) -> Vec<Symmetry> {
+    let (w, h) = spec.shape;
+    let map = spec.initial_map();
+    // not the free map, which can't tell the ends from the free cells
+    let mut free = TileMap::new(spec.shape);
+    for &(x, y) in &spec.free {
+        free.block(x, y);
+    }
+    let mut ends = spec
+        .ends
+        .iter()
+        .map(|&Point(x, y)| (x, y))
+        .collect::<Vec<_>>();
+    ends.sort_unstable();
+    (0..8)
+        .map(|i| (i & 1 != 0, i & 2 != 0, i & 4 != 0))
+        .filter(|&(transpose, _, _)| !transpose || w == h)
+        // an odd number of reflections mirrors the board
+        .filter(|&(transpose, flip_x, flip_y)| !spec.one_sided || !(transpose ^ flip_x ^ flip_y))
+        .filter(|&symmetry| {
+            (0..h).all(|y| {
+                (0..w).all(|x| {
+                    let (tx, ty) = transform(symmetry, (w, h), (x, y));
+                    map.get(x, y) == map.get(tx, ty) && free.get(x, y) == free.get(tx, ty)
+                })
+            })
+        })
+        .filter(|&symmetry| {
+            let mut image = (ends.iter())
+                .map(|&end| transform(symmetry, (w, h), end))
+                .collect::<Vec<_>>();
+            image.sort_unstable();
+            image == ends
+        })
+        .filter(|&symmetry| {
+            spec.regions.iter().all(|(_, cells)| {
+                (cells.iter()).all(|&cell| cells.contains(&transform(symmetry, (w, h), cell)))
+            })
+        })
+        .collect()
+}
+
+/// Marks every root that a symmetry of the board turns into an earlier root.
+/// Their searches are mirror images, so only the first one of each class has
+/// to be searched.
+pub fn symmetric_roots(spec: &BoardSpec, roots: &[PositionedPentonimo]) -> Vec<bool> {
+    let (w, h) = spec.shape;
+    let symmetries = board_symmetries(spec);
+    let cells = (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    roots
+        .iter()
+        .map(|root| {
+            let kind = root.pentonimo().kind();
+            let covered = cells
+                .iter()
+                .copied()
+                .filter(|&(x, y)| root.get(x, y))
+                .collect::<Vec<_>>();
+            let images = symmetries
+                .iter()
+                .map(|&symmetry| {
+                    let mut image = covered
+                        .iter()
+                        .map(|&cell| transform(symmetry, (w, h), cell))
+                        .collect::<Vec<_>>();
+                    image.sort_unstable();
+                    (kind, image)
+                })
+                .collect::<Vec<_>>();
+            let symmetric = images.iter().any(|image| seen.contains(image));
+            seen.extend(images);
+            symmetric
+        })
+        .collect()
+}
+
+fn transform(
+    (transpose, flip_x, flip_y): Symmetry,
+    (w, h): (u32, u32),
+    (mut x, mut y): (u32, u32),
+) -> (u32, u32) {
+    if transpose {
+        (x, y) = (y, x);
+    }
+    if flip_x {
+        x = w - 1 - x;
+    }
+    if flip_y {
+        y = h - 1 - y;
+    }
+    (x, y)
+}
+
+/// The best result of every root placement in a slot of its own, so which of
+/// them is reported doesn't depend on the order the jobs finish in.
+///
+/// The result of a root itself still depends on the other roots: they share
+/// the best diameter, which prunes subtrees that can only tie it, and the
+/// table of searched states, whose subtrees aren't searched again. With several
+/// threads a search can report another placement of the same diameter on every
+/// run, and a heuristic one even another diameter. Searching the roots apart
+/// from each other would make that reproducible, but visits several times as
+/// many nodes. Searches on a single thread take the roots in order and are
+/// reproducible.
+struct RootResults {
+    slots: Vec<OnceLock<(u32, Vec<PositionedPentonimo>)>>,
+    /// the best result of a resumed checkpoint or of the greedy seed
+    resumed: Option<(u32, Vec<PositionedPentonimo>)>,
+}
+
+impl RootResults {
+    fn new(roots: usize) -> Self {
+        Self {
+            slots: (0..roots).map(|_| OnceLock::new()).collect(),
+            resumed: None,
+        }
+    }
+
+    /// every root is searched at most once per search
+    fn set(&self, root: usize, result: (u32, Vec<PositionedPentonimo>)) {
+        if self.slots[root].set(result).is_err() {
+            unreachable!("root {root} was searched twice");
+        }
+    }
+
+    /// The result with the largest diameter or an empty board of `diameter`.
+    /// Of results with the same diameter, the one whose pieces come first in
+    /// [`canonical_order`] wins.
+    fn best(&self, diameter: u32) -> (u32, Vec<PositionedPentonimo>) {
+        self.resumed
+            .iter()
+            .chain(self.slots.iter().filter_map(OnceLock::get))
+            .map(|(diameter, placed)| (*diameter, canonical_order(placed), placed))
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)))
+            .map_or((diameter, Vec::new()), |(diameter, _, placed)| {
+                (diameter, placed.clone())
+            })
+    }
+}
+
+/// The pieces sorted by their position, then their kind and variant, which
+/// orders placements independently of the order their pieces were placed in
+fn canonical_order(placed: &[PositionedPentonimo]) -> Vec<(u32, u32, u8, usize)> {
+    let mut order = placed
+        .iter()
+        .map(|piece| {
+            let (x, y) = piece.position();
+            (y, x, piece.pentonimo().kind() as u8, piece.variant())
+        })
+        .collect::<Vec<_>>();
+    order.sort_unstable();
+    order
+}
+
+fn write_checkpoint(checkpointer: &mut Checkpointer, checkpoint: &Checkpoint) {
+    if let Err(err) = checkpointer.write(checkpoint) {
+        eprintln!(
+            "warning: failed to write checkpoint {}: {err}",
+            checkpointer.path.display()
+        );
+    }
+}
+
+#[test]
+fn best_result_ignores_the_order_of_roots() {
+    let piece = |kind, x, y| Pentonimo::new(kind).position(x, y);
+    let results = [
+        (8, vec![piece(PentonimoKind::I, 0, 0)]),
+        (9, vec![piece(PentonimoKind::L, 1, 0)]),
+        (9, vec![piece(PentonimoKind::X, 0, 1)]),
+        (9, vec![piece(PentonimoKind::F, 0, 1)]),
+    ];
+    for rotation in 0..results.len() {
+        let roots = RootResults::new(results.len());
+        for (root, result) in results
+            .iter()
+            .cycle()
+            .skip(rotation)
+            .take(results.len())
+            .enumerate()
+        {
+            roots.set(root, result.clone());
+        }
+        assert_eq!(roots.best(0), results[1]);
+    }
+    assert_eq!(RootResults::new(3).best(4), (4, Vec::new()));
+}
+
+#[test]
+fn families_are_mixed_on_one_board() {
+    use crate::{pentonimo::Family, solution::Solution};
+
+    let spec = "5x4 pieces=*=0,L=1,O4=1,L3=1,I2=2"
+        .parse::<BoardSpec>()
+        .unwrap();
+    let config = SearchConfig {
+        threads: Some(1),
+        ..Default::default()
+    };
+    let result = find_best(&spec, &config);
+    let exhaustive = find_best(
+        &spec,
+        &SearchConfig {
+            exhaustive: true,
+            ..config.clone()
+        },
+    );
+    assert!(result.diameter <= exhaustive.diameter);
+    assert!(result
+        .placed
+        .iter()
+        .any(|piece| piece.pentonimo().kind().family() != Family::Pentomino));
+    Solution {
+        spec,
+        diameter: result.diameter,
+        pieces: result.placed,
+        proved_optimal: false,
+    }
+    .verify()
+    .unwrap();
+}
+
+#[test]
+fn searches_start_from_the_best_known_diameter() {
+    let spec = BoardSpec::new((6, 4));
+    // a single thread visits the same nodes every time
+    let config = SearchConfig {
+        threads: Some(1),
+        ..Default::default()
+    };
+    let plain = find_best(&spec, &config);
+    let seeded = find_best(
+        &spec,
+        &SearchConfig {
+            best_known: true,
+            ..config.clone()
+        },
+    );
+    assert_eq!(seeded.diameter, plain.diameter);
+    assert!(seeded.nodes < plain.nodes);
+    Solution {
+        spec: spec.clone(),
+        diameter: seeded.diameter,
+        pieces: seeded.placed,
+        proved_optimal: false,
+    }
+    .verify()
+    .unwrap();
+
+    // nothing reaches a goal above the optimum, which find_best notices
+    let missed = search(&spec, &config, Some(plain.diameter + 1));
+    assert!(missed.complete);
+    assert!(missed.diameter <= plain.diameter);
+}
+
+#[test]
+fn rollouts_keep_the_optimum() {
+    let config = SearchConfig {
+        threads: Some(1),
+        ..Default::default()
+    };
+    for shape in [(5, 4), (6, 4)] {
+        let spec = BoardSpec::new(shape);
+        let plain = find_best(&spec, &config);
+        for rollout_depth in [1, 2] {
+            let ordered = find_best(
+                &spec,
+                &SearchConfig {
+                    rollout_depth,
+                    ..config.clone()
+                },
+            );
+            assert_eq!(ordered.diameter, plain.diameter);
+            assert!(ordered.complete);
+        }
+    }
+}
+
+#[test]
+fn allowed_decreases_search_between_plain_and_exhaustive() {
+    let spec = BoardSpec::new((5, 4));
+    let config = SearchConfig {
+        threads: Some(1),
+        best_known: false,
+        ..Default::default()
+    };
+    let plain = find_best(&spec, &config);
+    let exhaustive = find_best(
+        &spec,
+        &SearchConfig {
+            exhaustive: true,
+            ..config.clone()
+        },
+    );
+    for max_decrease in [1, 2, u32::MAX / 2] {
+        let relaxed = find_best(
+            &spec,
+            &SearchConfig {
+                max_decrease,
+                ..config.clone()
+            },
+        );
+        assert!(relaxed.diameter >= plain.diameter);
+        assert!(relaxed.diameter <= exhaustive.diameter);
+    }
+}
+
+#[test]
+fn pieces_come_in_the_order_they_were_placed() {
+    let config = SearchConfig {
+        best_known: false,
+        ..Default::default()
+    };
+    for shape in [(5, 4), (6, 5)] {
+        let spec = BoardSpec::new(shape);
+        let result = find_best(&spec, &config);
+        let mut scratch = BfsScratch::new(shape);
+        let mut map = spec.initial_map();
+        let mut diameter = scratch.objective(&map, &spec.ends).0;
+        // without allowed decreases no piece makes the diameter smaller
+        for &piece in &result.placed {
+            map |= piece;
+            let next = scratch.objective(&map, &spec.ends).0;
+            assert!(next >= diameter, "{shape:?}: {next} after {diameter}");
+            diameter = next;
+        }
+        assert_eq!(diameter, result.diameter);
+    }
+}
+
+#[test]
+fn root_files_merge_into_the_result() {
+    let dir = std::env::temp_dir().join(format!("pentonimo-roots-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let spec = BoardSpec::new((5, 4)).canonical().0;
+    let result = find_best(
+        &spec,
+        &SearchConfig {
+            threads: Some(2),
+            best_known: false,
+            root_files: Some(dir.clone()),
+            ..Default::default()
+        },
+    );
+
+    let mut merged: Option<Checkpoint> = None;
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let text = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        let checkpoint = text.parse::<Checkpoint>().unwrap();
+        assert_eq!(checkpoint.done.iter().filter(|&&done| done).count(), 1);
+        checkpoint.best.verify().unwrap();
+        match &mut merged {
+            Some(merged) => merged.merge(&checkpoint).unwrap(),
+            None => merged = Some(checkpoint),
+        }
+    }
+    _ = std::fs::remove_dir_all(&dir);
+
+    let merged = merged.unwrap();
+    assert!(merged.done.iter().all(|&done| done));
+    assert_eq!(merged.best.diameter, result.diameter);
+}
+
+#[test]
+fn greedy_seeds_prune_the_search() {
+    let spec = BoardSpec::new((6, 4));
+    let config = SearchConfig {
+        threads: Some(1),
+        ..Default::default()
+    };
+    let plain = find_best(&spec, &config);
+    let seeded = find_best(
+        &spec,
+        &SearchConfig {
+            greedy_seed: true,
+            ..config.clone()
+        },
+    );
+    assert_eq!(seeded.diameter, plain.diameter);
+    assert!(seeded.nodes < plain.nodes);
+
+    // greedy finds the optimum of 5x4, so the search finds nothing larger
+    let spec = BoardSpec::new((5, 4));
+    let greedy = Greedy.search(&spec);
+    let seeded = find_best(
+        &spec,
+        &SearchConfig {
+            greedy_seed: true,
+            ..config
+        },
+    );
+    assert_eq!(seeded.diameter, greedy.diameter);
+    assert!(seeded.complete);
+    Solution {
+        spec,
+        diameter: seeded.diameter,
+        pieces: seeded.placed,
+        proved_optimal: false,
+    }
+    .verify()
+    .unwrap();
+}
+
+#[test]
+fn traces_account_for_every_node() {
+    // on smaller boards single pieces are optimal, so every child is pruned
+    let spec = BoardSpec::new((6, 4));
+    let trace = Arc::new(Trace::new(2, usize::MAX));
+    let config = SearchConfig {
+        trace: Some(trace.clone()),
+        ..Default::default()
+    };
+    let result = find_best(&spec, &config);
+    let nodes = trace.nodes();
+
+    let roots = nodes.iter().filter(|node| node.parent.is_none());
+    assert_eq!(roots.clone().count(), spec.placements().len());
+    assert_eq!(roots.map(|node| node.nodes).sum::<u64>(), result.nodes);
+    for node in &nodes {
+        let children = nodes
+            .iter()
+            .filter(|child| child.parent == Some(node.id))
+            .inspect(|child| assert_eq!(child.depth, node.depth + 1))
+            .map(|child| child.nodes)
+            .sum::<u64>();
+        assert!(children < node.nodes, "{node:?}");
+    }
+    assert!(nodes.iter().any(|node| node.depth == 2));
+
+    let trace = Arc::new(Trace::new(3, 10));
+    find_best(
+        &spec,
+        &SearchConfig {
+            trace: Some(trace.clone()),
+            ..config
+        },
+    );
+    assert_eq!(trace.nodes().len(), 10);
+}
+
+#[test]
+fn thread_counts_agree_on_the_optimum() {
+    use crate::solution::Solution;
+
+    let spec = BoardSpec::new((6, 4));
+    let config = |threads| SearchConfig {
+        exhaustive: true,
+        threads: Some(threads),
+        ..Default::default()
+    };
+    let single = find_best(&spec, &config(1));
+    // a single thread takes the roots in order
+    assert_eq!(find_best(&spec, &config(1)).placed, single.placed);
+    for threads in [2, 4] {
+        let result = find_best(&spec, &config(threads));
+        // the placement can differ, see RootResults
+        assert_eq!(result.diameter, single.diameter, "{threads} threads");
+        Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        }
+        .verify()
+        .unwrap();
+    }
+}
+
+#[test]
+fn symmetries_keep_the_ends_apart_from_free_cells() {
+    // mirroring left to right or top to bottom swaps the ends with the free
+    // cells, only the rotation by 180 degrees and the diagonals keep them
+    let spec = "5x5 ends=0,0;4,4 free=4,0;0,4"
+        .parse::<BoardSpec>()
+        .unwrap();
+    assert_eq!(board_symmetries(&spec).len(), 4);
+
+    let spec = "6x5 ends=0,0;5,4 free=5,0;0,4"
+        .parse::<BoardSpec>()
+        .unwrap();
+    let config = SearchConfig {
+        exhaustive: true,
+        ..Default::default()
+    };
+    let reduced = find_best(&spec, &config);
+    // all_optima searches every root and state without symmetries
+    let unreduced = find_best(
+        &spec,
+        &SearchConfig {
+            all_optima: true,
+            ..config.clone()
+        },
+    );
+    assert_eq!(reduced.diameter, unreduced.diameter);
+    assert!(reduced.certificate.is_some());
+}
+
+#[test]
+fn pieces_fit_against_the_last_column_and_row() {
+    // the only placement of these boards ends in their last column or row
+    for board in ["5x1", "1x5"] {
+        let result = find_best(&board.parse().unwrap(), &SearchConfig::default());
+        assert_eq!(result.placed.len(), 1, "{board}");
+    }
+}
+
+#[test]
+fn one_sided_boards_only_have_rotations() {
+    let spec = "5x5 pieces=one-sided-pentominoes"
+        .parse::<BoardSpec>()
+        .unwrap();
+    assert_eq!(board_symmetries(&spec).len(), 4);
+    assert_eq!(board_symmetries(&BoardSpec::new((5, 5))).len(), 8);
+
+    // the T is its own mirror image, so both boards have the same roots, but
+    // only the one-sided board has to search the mirror images of them
+    let searched = |spec: &str| {
+        let spec = spec.parse::<BoardSpec>().unwrap();
+        let roots = spec.placements();
+        symmetric_roots(&spec, &roots)
+            .iter()
+            .filter(|&&symmetric| !symmetric)
+            .count()
+    };
+    assert_eq!(searched("5x5 pieces=*=0,T=1"), 6);
+    assert_eq!(searched("5x5 pieces=one-sided,*=0,T=1"), 9);
+
+    // the F and the N, which mirrored roots would place mirrored
+    let spec = "6x4 pieces=one-sided,*=0,F=1,N=1"
+        .parse::<BoardSpec>()
+        .unwrap();
+    let config = SearchConfig {
+        exhaustive: true,
+        ..Default::default()
+    };
+    let reduced = find_best(&spec, &config);
+    let unreduced = find_best(
+        &spec,
+        &SearchConfig {
+            all_optima: true,
+            ..config.clone()
+        },
+    );
+    assert_eq!(reduced.diameter, unreduced.diameter);
+    Solution {
+        spec,
+        diameter: reduced.diameter,
+        pieces: reduced.placed,
+        proved_optimal: false,
+    }
+    .verify()
+    .unwrap();
 }
@@ -1,7 +1,9 @@
 use std::fmt::Debug;
 
+use crate::pentonimo::KINDS;
+
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
-pub struct Candidates(u64);
+pub struct Candidates(u128);
 
 impl IntoIterator for Candidates {
     type Item = u8;
@@ -25,7 +27,7 @@ impl Iterator for CandidateIterator {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i < 12 {
+        if self.i < KINDS as u8 {
             self.i += 1;
             Some(self.candidates.get(self.i - 1))
         } else {
@@ -43,7 +45,7 @@ impl Debug for Candidates {
 }
 
 impl Candidates {
-    pub fn mask(i: u8) -> u64 {
+    pub fn mask(i: u8) -> u128 {
         0b11111 << (5 * i)
     }
     pub fn get(self, i: u8) -> u8 {
@@ -52,19 +54,23 @@ impl Candidates {
     pub fn set(&mut self, i: u8, v: u8) {
         debug_assert!(v < 0b100000);
 
-        self.0 = (self.0 & !Self::mask(i)) | ((v as u64) << (5 * i));
+        self.0 = (self.0 & !Self::mask(i)) | ((v as u128) << (5 * i));
     }
 
     pub fn decrement(&mut self, i: u8) {
         self.set(i, self.get(i).checked_sub(1).unwrap())
     }
 
-    pub fn new(candidates: [u8; 12]) -> Self {
-        let mut res: u64 = 0;
+    pub fn increment(&mut self, i: u8) {
+        self.set(i, self.get(i) + 1)
+    }
+
+    pub fn new(candidates: [u8; KINDS]) -> Self {
+        let mut res: u128 = 0;
 
         for (i, &v) in candidates.iter().enumerate() {
             debug_assert!(v < 0b100000);
-            res |= ((v & 0b11111) as u64) << (5 * i);
+            res |= ((v & 0b11111) as u128) << (5 * i);
         }
 
         Self(res)
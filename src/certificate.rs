@@ -0,0 +1,174 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    brute_force::{board_symmetries, symmetric_roots},
+    solution::Solution,
+};
+
+/// The record of an exhaustive search proving a solution optimal.
+///
+/// Stored as the text of the solution preceded by
+///
+/// ```text
+/// certificate
+/// symmetries 4
+/// roots 571
+/// searched 148
+/// nodes 1826344
+/// bound 20
+/// ```
+///
+/// `roots` is the number of placements of the first piece, of which `searched`
+/// remain after skipping those mirroring an earlier one under one of the
+/// `symmetries` of the board. `bound` is the largest diameter any searched
+/// state can reach, so it has to match the diameter of the solution.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pub solution: Solution,
+    pub symmetries: usize,
+    pub roots: usize,
+    pub searched: usize,
+    /// states visited by the search that finished, without resumed runs
+    pub nodes: u64,
+    pub bound: u32,
+}
+
+impl Certificate {
+    /// Checks that the solution is valid and that the recorded search matches
+    /// the one this version would run for the board. The search itself is not
+    /// repeated.
+    pub fn verify(&self) -> Result<(), String> {
+        let solution = &self.solution;
+        solution.verify()?;
+        if !solution.proved_optimal {
+            return Err("the solution is not marked as proved optimal".to_owned());
+        }
+        if self.bound != solution.diameter {
+            return Err(format!(
+                "bound {} doesn't match the diameter {}",
+                self.bound, solution.diameter
+            ));
+        }
+
+        let spec = &solution.spec;
+        let symmetries = board_symmetries(spec).len();
+        if self.symmetries != symmetries {
+            return Err(format!(
+                "claims {} symmetries, but the board has {symmetries}",
+                self.symmetries
+            ));
+        }
+        let roots = spec.placements();
+        if self.roots != roots.len() {
+            return Err(format!(
+                "claims {} roots, but the board has {}",
+                self.roots,
+                roots.len()
+            ));
+        }
+        let searched = symmetric_roots(spec, &roots)
+            .into_iter()
+            .filter(|&symmetric| !symmetric)
+            .count();
+        if self.searched != searched {
+            return Err(format!(
+                "claims to have searched {} roots, but {searched} are distinct",
+                self.searched
+            ));
+        }
+        if self.nodes < self.searched as u64 {
+            return Err(format!(
+                "{} nodes can't cover {} roots",
+                self.nodes, self.searched
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// the same certificate for the board mirrored along its diagonal
+    pub fn transposed(&self) -> Self {
+        Self {
+            solution: self.solution.transposed(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for Certificate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "certificate")?;
+        writeln!(f, "symmetries {}", self.symmetries)?;
+        writeln!(f, "roots {}", self.roots)?;
+        writeln!(f, "searched {}", self.searched)?;
+        writeln!(f, "nodes {}", self.nodes)?;
+        writeln!(f, "bound {}", self.bound)?;
+        write!(f, "{}", self.solution)
+    }
+}
+
+impl FromStr for Certificate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        if lines.next().map(str::trim) != Some("certificate") {
+            return Err("not a certificate".to_owned());
+        }
+
+        let mut values = [None; 5];
+        let mut solution = String::new();
+        for line in lines {
+            let (key, value) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let index = match key {
+                "symmetries" => 0,
+                "roots" => 1,
+                "searched" => 2,
+                "nodes" => 3,
+                "bound" => 4,
+                _ => {
+                    solution += line;
+                    solution.push('\n');
+                    continue;
+                }
+            };
+            values[index] = Some(
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid {key} {value:?}"))?,
+            );
+        }
+
+        let [symmetries, roots, searched, nodes, bound] = values;
+        let missing = |name: &str| format!("certificate is missing its {name}");
+        Ok(Self {
+            symmetries: symmetries.ok_or_else(|| missing("symmetries"))? as usize,
+            roots: roots.ok_or_else(|| missing("roots"))? as usize,
+            searched: searched.ok_or_else(|| missing("searched roots"))? as usize,
+            nodes: nodes.ok_or_else(|| missing("nodes"))?,
+            bound: bound.ok_or_else(|| missing("bound"))? as u32,
+            solution: solution.parse()?,
+        })
+    }
+}
+
+#[test]
+fn roundtrip_and_verify() {
+    use crate::brute_force::{find_best, SearchConfig};
+
+    let spec = crate::spec::BoardSpec::new((4, 4));
+    let config = SearchConfig {
+        exhaustive: true,
+        ..Default::default()
+    };
+    let certificate = find_best(&spec, &config).certificate.unwrap();
+    certificate.verify().unwrap();
+
+    let parsed = certificate.to_string().parse::<Certificate>().unwrap();
+    assert_eq!(parsed.to_string(), certificate.to_string());
+    parsed.transposed().verify().unwrap();
+
+    let mut forged = parsed;
+    forged.searched -= 1;
+    assert!(forged.verify().is_err());
+}
@@ -0,0 +1,167 @@
+//! Small SVG line charts of how the diameter scales, e.g. with the number of
+//! pieces or the size of the board.
+
+use std::fmt::Write;
+
+/// colors of the series, repeated if there are more
+const COLORS: [&str; 6] = [
+    "#1d99f3", "#ed1515", "#11d116", "#f67400", "#9b59b6", "#1abc9c",
+];
+
+pub struct Series {
+    pub name: String,
+    /// points in the order they are connected
+    pub points: Vec<(f64, f64)>,
+}
+
+pub struct Chart {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub series: Vec<Series>,
+}
+
+impl Chart {
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            x_label: x_label.to_owned(),
+            y_label: y_label.to_owned(),
+            series: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, points: Vec<(f64, f64)>) {
+        self.series.push(Series {
+            name: name.to_owned(),
+            points,
+        });
+    }
+
+    /// Both axes start at 0 and end at the largest value rounded up to a tick
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (640., 400.);
+        let (left, right, top, bottom) = (60., 140., 40., 50.);
+        let (plot_w, plot_h) = (width - left - right, height - top - bottom);
+
+        let points = || self.series.iter().flat_map(|series| &series.points);
+        let (x_step, x_max) = axis(points().map(|p| p.0).fold(0., f64::max));
+        let (y_step, y_max) = axis(points().map(|p| p.1).fold(0., f64::max));
+        let px = |x: f64| left + x / x_max * plot_w;
+        let py = |y: f64| top + plot_h - y / y_max * plot_h;
+
+        let mut svg = String::new();
+        _ = writeln!(
+            svg,
+            r#"<svg viewBox="0 0 {width} {height}" xmlns="http://www.w3.org/2000/svg" font-family="sans-serif" font-size="12">"#
+        );
+        _ = writeln!(
+            svg,
+            r#"<text x="{}" y="24" font-size="16" text-anchor="middle">{}</text>"#,
+            left + plot_w / 2.,
+            escape(&self.title)
+        );
+
+        for i in 0..=(x_max / x_step).round() as u32 {
+            let x = i as f64 * x_step;
+            _ = writeln!(
+                svg,
+                r##"<line x1="{0}" y1="{top}" x2="{0}" y2="{1}" stroke="#ddd" /><text x="{0}" y="{2}" text-anchor="middle">{x}</text>"##,
+                px(x),
+                top + plot_h,
+                top + plot_h + 16.
+            );
+        }
+        for i in 0..=(y_max / y_step).round() as u32 {
+            let y = i as f64 * y_step;
+            _ = writeln!(
+                svg,
+                r##"<line x1="{left}" y1="{0}" x2="{1}" y2="{0}" stroke="#ddd" /><text x="{2}" y="{3}" text-anchor="end">{y}</text>"##,
+                py(y),
+                left + plot_w,
+                left - 6.,
+                py(y) + 4.
+            );
+        }
+        _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            left + plot_w / 2.,
+            height - 10.,
+            escape(&self.x_label)
+        );
+        _ = writeln!(
+            svg,
+            r#"<text x="16" y="{0}" text-anchor="middle" transform="rotate(-90 16 {0})">{1}</text>"#,
+            top + plot_h / 2.,
+            escape(&self.y_label)
+        );
+
+        for (i, series) in self.series.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            let line = series
+                .points
+                .iter()
+                .map(|&(x, y)| format!("{},{}", px(x), py(y)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            _ = writeln!(
+                svg,
+                r#"<polyline points="{line}" fill="none" stroke="{color}" stroke-width="2" />"#
+            );
+            for &(x, y) in &series.points {
+                _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="3" fill="{color}"><title>{x}: {y}</title></circle>"#,
+                    px(x),
+                    py(y)
+                );
+            }
+            let legend_y = top + 10. + 18. * i as f64;
+            _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="12" height="12" fill="{color}" /><text x="{}" y="{}">{}</text>"#,
+                left + plot_w + 16.,
+                legend_y - 10.,
+                left + plot_w + 34.,
+                legend_y,
+                escape(&series.name)
+            );
+        }
+
+        _ = writeln!(svg, "</svg>");
+        svg
+    }
+}
+
+/// the distance between ticks and the end of an axis up to `max`, with at
+/// most 10 steps between round numbers
+fn axis(max: f64) -> (f64, f64) {
+    if max <= 0. {
+        return (1., 1.);
+    }
+    let magnitude = 10f64.powf((max / 10.).log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|mantissa| mantissa * magnitude)
+        .find(|step| max / step <= 10.)
+        .unwrap_or(10. * magnitude)
+        .max(1.);
+    (step, (max / step).ceil() * step)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[test]
+fn axes_end_at_round_ticks() {
+    assert_eq!(axis(0.), (1., 1.));
+    assert_eq!(axis(7.), (1., 7.));
+    assert_eq!(axis(12.), (2., 12.));
+    assert_eq!(axis(34.), (5., 35.));
+    assert_eq!(axis(49.), (5., 50.));
+    assert_eq!(axis(230.), (50., 250.));
+}
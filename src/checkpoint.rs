@@ -0,0 +1,240 @@
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{backend::Instant, solution::Solution};
+
+/// The state of an unfinished search: which root placements have been searched
+/// completely and the best solution found so far.
+///
+/// Stored as the text of the best solution preceded by
+///
+/// ```text
+/// roots 571
+/// done 0-12,14
+/// ```
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub roots: usize,
+    pub done: Vec<bool>,
+    pub best: Solution,
+}
+
+impl Checkpoint {
+    pub fn is_complete(&self) -> bool {
+        self.done.iter().all(|&done| done)
+    }
+
+    /// Combines the finished roots and best solutions of two runs of the same
+    /// search
+    pub fn merge(&mut self, other: &Checkpoint) -> Result<(), String> {
+        if (&self.best.spec, self.roots) != (&other.best.spec, other.roots) {
+            return Err(format!(
+                "can't merge checkpoints of different searches ({} with {} roots and {} with {} roots)",
+                self.best.spec, self.roots, other.best.spec, other.roots
+            ));
+        }
+        for (done, &other) in self.done.iter_mut().zip(&other.done) {
+            *done |= other;
+        }
+        self.merge_solution(&other.best)
+    }
+
+    /// Keeps `solution` if it is better than the best one so far
+    pub fn merge_solution(&mut self, solution: &Solution) -> Result<(), String> {
+        let (spec, transposed) = solution.spec.canonical();
+        if spec != self.best.spec {
+            return Err(format!(
+                "can't merge a solution of {} into a checkpoint of {}",
+                solution.spec, self.best.spec
+            ));
+        }
+        if solution.diameter > self.best.diameter {
+            self.best = if transposed {
+                solution.transposed()
+            } else {
+                solution.clone()
+            };
+            self.best.spec = spec;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "roots {}", self.roots)?;
+        writeln!(f, "done {}", format_roots(&self.done))?;
+        write!(f, "{}", self.best)
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut roots = None;
+        let mut done_ranges = None;
+        let mut solution = String::new();
+
+        for line in s.lines() {
+            if let Some(value) = line.strip_prefix("roots ") {
+                roots = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid number of roots {value:?}"))?,
+                );
+            } else if let Some(value) = line.strip_prefix("done") {
+                done_ranges = Some(value.trim().to_owned());
+            } else {
+                solution += line;
+                solution.push('\n');
+            }
+        }
+
+        let roots = roots.ok_or("checkpoint is missing its number of roots")?;
+        let done = parse_roots(
+            &done_ranges.ok_or("checkpoint is missing its finished roots")?,
+            roots,
+        )?;
+
+        Ok(Self {
+            roots,
+            done,
+            best: solution.parse()?,
+        })
+    }
+}
+
+/// Writes a checkpoint every `interval` to `path`
+pub struct Checkpointer {
+    pub path: PathBuf,
+    pub interval: Duration,
+    last: Instant,
+}
+
+impl Checkpointer {
+    pub fn new(path: PathBuf, interval: Duration) -> Self {
+        Self {
+            path,
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last.elapsed() >= self.interval
+    }
+
+    pub fn write(&mut self, checkpoint: &Checkpoint) -> std::io::Result<()> {
+        self.last = Instant::now();
+        write_atomic(&self.path, checkpoint.to_string().as_bytes())
+    }
+}
+
+/// The set roots of `roots` as comma separated ranges like `0-3,5`
+pub fn format_roots(roots: &[bool]) -> String {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < roots.len() {
+        if !roots[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < roots.len() && roots[i] {
+            i += 1;
+        }
+        ranges.push(if i - start == 1 {
+            start.to_string()
+        } else {
+            format!("{start}-{}", i - 1)
+        });
+    }
+    ranges.join(",")
+}
+
+/// Parses the ranges of [`format_roots`] into a set of `roots` roots
+pub fn parse_roots(ranges: &str, roots: usize) -> Result<Vec<bool>, String> {
+    let mut set = vec![false; roots];
+    for range in ranges.split(',').filter(|range| !range.is_empty()) {
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .ok()
+                .filter(|&n| n < roots)
+                .ok_or_else(|| format!("invalid root {n:?}"))
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (number(start)?, number(end)?),
+            None => (number(range)?, number(range)?),
+        };
+        for root in set.iter_mut().take(end + 1).skip(start) {
+            *root = true;
+        }
+    }
+    Ok(set)
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it, so
+/// `path` always contains either the old or the new contents.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp, path)
+}
+
+#[test]
+fn roundtrip() {
+    let checkpoint = Checkpoint {
+        roots: 7,
+        done: vec![true, true, false, true, false, true, true],
+        best: Solution {
+            spec: crate::spec::BoardSpec::new((4, 3)),
+            diameter: 3,
+            pieces: vec![],
+            proved_optimal: false,
+        },
+    };
+    let text = checkpoint.to_string();
+    assert!(text.starts_with("roots 7\ndone 0-1,3,5-6\n"));
+
+    let parsed = text.parse::<Checkpoint>().unwrap();
+    assert_eq!(parsed.done, checkpoint.done);
+    assert_eq!(parsed.best.to_string(), checkpoint.best.to_string());
+}
+
+#[test]
+fn merge() {
+    let spec = crate::spec::BoardSpec::new((4, 3));
+    let checkpoint = |done: Vec<bool>, diameter| Checkpoint {
+        roots: done.len(),
+        done,
+        best: Solution {
+            spec: spec.clone(),
+            diameter,
+            pieces: vec![],
+            proved_optimal: false,
+        },
+    };
+
+    let mut merged = checkpoint(vec![true, false, false], 4);
+    merged
+        .merge(&checkpoint(vec![false, false, true], 6))
+        .unwrap();
+    assert_eq!(merged.done, [true, false, true]);
+    assert_eq!(merged.best.diameter, 6);
+
+    assert!(merged.merge(&checkpoint(vec![true, true], 7)).is_err());
+}
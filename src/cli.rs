@@ -0,0 +1,1457 @@
+use std::{fmt::Display, ops::RangeInclusive, path::PathBuf, time::Duration};
+
+use pentonimo::{
+    monitor::{parse_bytes, MemoryPolicy},
+    pathfinding::PathStyle,
+    pentonimo::{KINDS, PENTOMINOES},
+    shared_budget::Goal,
+    spec::{parse_pieces, BoardSpec},
+    strategy::Objective,
+};
+
+#[derive(Debug, Default)]
+pub enum Command {
+    /// solve the default sweep of board sizes or a batch file
+    #[default]
+    Solve,
+    /// check saved solutions
+    Verify(Vec<PathBuf>),
+    /// combine the finished roots and solutions of the same board from several runs
+    Merge {
+        files: Vec<PathBuf>,
+        output: Option<PathBuf>,
+    },
+    /// tile classic boards with all pentonimos
+    Tile { presets: Vec<String>, count: bool },
+    /// graph properties of a board without solving it
+    Analyze { file: PathBuf, svg: Option<PathBuf> },
+    /// how the diameter of a solution changes without or with single pieces
+    WhatIf(PathBuf),
+    /// export a solution as a maze
+    Maze {
+        file: PathBuf,
+        format: MazeFormat,
+        output: Option<PathBuf>,
+    },
+    /// render a saved solution again
+    Show {
+        file: PathBuf,
+        format: ShowFormat,
+        output: Option<PathBuf>,
+        /// size of a cell, in pixels for PNG
+        scale: Option<u32>,
+        theme: Theme,
+        /// highlight the cells that separate the ends of the path
+        bottlenecks: bool,
+    },
+    /// fold a search trace of `solve --trace` for flamegraphs
+    Trace {
+        file: PathBuf,
+        output: Option<PathBuf>,
+    },
+    /// tables of the solutions in a results directory
+    Stats {
+        /// `results` by default
+        dir: Option<PathBuf>,
+        recompute: bool,
+    },
+    /// browse the solutions in a results directory in the terminal
+    View {
+        /// `results` by default
+        dir: Option<PathBuf>,
+    },
+    /// run every strategy on a board with the same budget and compare them
+    BenchStrategies {
+        board: Option<BoardSpec>,
+        budget: Duration,
+        seed: Option<u64>,
+    },
+    /// estimate the largest diameter of a board from random greedy constructions
+    Sample {
+        board: Option<BoardSpec>,
+        samples: u32,
+        seed: Option<u64>,
+    },
+    /// solve a board with at most 1, 2, ... pieces
+    PieceSweep {
+        board: Option<BoardSpec>,
+        timeout: Option<Duration>,
+        svg: Option<PathBuf>,
+    },
+    /// distribute one set of pieces over several boards
+    Share {
+        boards: Vec<BoardSpec>,
+        pieces: [u8; KINDS],
+        one_sided: bool,
+        goal: Goal,
+    },
+    /// solve boards submitted over TCP, one queued job after the other
+    Serve {
+        listen: String,
+        jobs: PathBuf,
+        workers: usize,
+    },
+    /// lease the roots of a board to workers on other machines
+    Coordinate {
+        board: Option<BoardSpec>,
+        listen: String,
+        shard_size: usize,
+        heartbeat_timeout: Duration,
+        /// finished shards, `<out dir>/<board>.shards` by default
+        state: Option<PathBuf>,
+    },
+    /// search roots leased by a coordinator
+    Work {
+        coordinator: Option<String>,
+        name: Option<String>,
+        threads: Option<usize>,
+    },
+    /// play Golomb's pentomino game against the computer
+    Play {
+        board: BoardSpec,
+        ai_first: bool,
+        playouts: u32,
+    },
+}
+
+/// the result files `solve` writes for every board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultFormat {
+    Svg,
+    Png,
+    Txt,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MazeFormat {
+    Text,
+    Json,
+    Svg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowFormat {
+    Console,
+    Svg,
+    Png,
+    /// pieces appearing in the order they were placed, then the path
+    AnimatedSvg,
+    Json,
+}
+
+/// colors of SVG and PNG pictures
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// which cells of the pieces of SVG pictures show their kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Letters {
+    #[default]
+    None,
+    /// a cell in the middle of every piece
+    One,
+    All,
+}
+
+/// How much `solve` prints
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// `-q`: a line with the diameter of every board, warnings and errors
+    Quiet,
+    /// the grid of every board
+    #[default]
+    Normal,
+    /// `-v`: also the progress of running searches
+    Verbose,
+    /// `-vv`: also every improvement found and every finished root
+    Debug,
+}
+
+/// How boards are solved
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// brute-force for boards with an estimated search of at most
+    /// [`AUTO_NODE_LIMIT`] nodes, lns for larger ones
+    #[default]
+    Auto,
+    BruteForce,
+    /// large neighbourhood search within a time budget
+    Lns,
+    /// tabu search over complete placements within a time budget
+    Tabu,
+    /// a single greedy construction, as a baseline
+    Greedy,
+}
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Auto => "auto",
+            Strategy::BruteForce => "brute-force",
+            Strategy::Lns => "lns",
+            Strategy::Tabu => "tabu",
+            Strategy::Greedy => "greedy",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub command: Command,
+    /// file of board specs to solve instead of the default sweep over board sizes
+    pub batch: Option<PathBuf>,
+    /// preset boards of the tile command to solve as well
+    pub presets: Vec<String>,
+    /// widths of the boards to solve as well, [`DEFAULT_SIZES`] if only
+    /// `heights` are given
+    pub widths: Option<RangeInclusive<u32>>,
+    /// heights of the boards to solve as well, [`DEFAULT_SIZES`] if only
+    /// `widths` are given
+    pub heights: Option<RangeInclusive<u32>>,
+    /// result files of every board, [`DEFAULT_FORMATS`] by default
+    pub formats: Option<Vec<ResultFormat>>,
+    /// print where the search spent its time for every board
+    pub profile: bool,
+    /// print the progress of running searches, also set by `-v`
+    pub progress: bool,
+    /// print the size and load of the table of searched states regularly
+    pub memo_stats: bool,
+    pub verbosity: Verbosity,
+    pub memory_cap: Option<u64>,
+    pub memory_policy: MemoryPolicy,
+    /// estimate the size of every search instead of running it
+    pub dry_run: bool,
+    /// enumerate all optimal placements and report how often pieces are used
+    pub all_optima: bool,
+    /// search without pruning to prove the results optimal
+    pub prove: bool,
+    /// like `prove`, and report whether searches that discard placements
+    /// making the diameter smaller find the same diameter
+    pub exact_no_monotone_prune: bool,
+    /// skip dominated states at the risk of missing optimal placements
+    pub aggressive: bool,
+    /// store complete states instead of their hashes
+    pub exact_states: bool,
+    /// compare the full states of a sample of the hashes
+    pub check_hashes: bool,
+    pub strategy: Strategy,
+    /// what the tabu strategy maximizes
+    pub objective: Objective,
+    /// time for every board of the heuristic strategies
+    pub budget: Option<Duration>,
+    /// seed of the heuristic strategies instead of the current time
+    pub seed: Option<u64>,
+    /// cover every free cell instead of maximizing the diameter
+    pub exact_fill: bool,
+    /// stop the brute-force search of every board after this time
+    pub timeout: Option<Duration>,
+    /// save and resume the state of every search in this interval
+    pub checkpoint_every: Option<Duration>,
+    /// write the result of every finished root to `<stem>.roots/`
+    pub root_files: bool,
+    /// directory of the result files, `results` by default
+    pub out_dir: Option<PathBuf>,
+    /// name of the result files of every board, see [`expand_name`]
+    pub name: Option<String>,
+    /// replace existing result files
+    pub force: bool,
+    /// only print the results, without writing any files
+    pub no_files: bool,
+    /// solve the boards of `--batch` and `--preset` in the given order instead
+    /// of the quick ones first
+    pub in_order: bool,
+    /// search boards even if they are in the result cache
+    pub no_cache: bool,
+    /// don't start searches from the best known diameters
+    pub no_best_known: bool,
+    /// the pieces of every board of `solve` instead of those of their specs,
+    /// and whether they are one-sided
+    pub pieces: Option<([u8; KINDS], bool)>,
+    /// don't start brute-force searches from a greedy construction
+    pub no_greedy_seed: bool,
+    /// order the children of brute-force states with at most this many pieces
+    /// by greedy rollouts below them, see
+    /// [`SearchConfig::rollout_depth`](pentonimo::brute_force::SearchConfig::rollout_depth)
+    pub rollout_depth: usize,
+    /// search brute-force states whose diameter is at most this much smaller
+    /// than that of their parent
+    pub max_decrease: u32,
+    /// which of the longest shortest paths `solve` and `analyze` show
+    pub path_style: PathStyle,
+    /// only show the pieces, without the path
+    pub no_path: bool,
+    /// report and highlight the dead ends of `solve` and `show`
+    pub dead_ends: bool,
+    /// letters on the pieces of the SVG pictures of `solve` and `show`
+    pub letters: Letters,
+    /// colors of `solve` and `show`
+    pub palette: Option<PathBuf>,
+    /// the shapes of the custom pieces of every command
+    pub piece_file: Option<PathBuf>,
+    /// write a trace of every brute-force search
+    pub trace: bool,
+    /// pieces of the deepest traced nodes, [`DEFAULT_TRACE_DEPTH`] by default
+    pub trace_depth: Option<usize>,
+    /// most traced nodes of every search, [`DEFAULT_TRACE_NODES`] by default
+    pub trace_nodes: Option<usize>,
+    /// threads of every search, see [`SETTINGS`]
+    pub threads: Option<usize>,
+    /// pin the threads of every search to cores
+    pub pin_threads: bool,
+    /// keep a core for the thread that coordinates the search
+    pub reserve_core: bool,
+    /// split every search between the NUMA nodes
+    pub numa: bool,
+    /// shards of the table of searched states, see [`SETTINGS`]
+    pub memo_shards: Option<usize>,
+    /// states the table of searched states makes room for up front
+    pub memo_capacity: Option<usize>,
+    /// file of settings instead of [`DEFAULT_CONFIG`]
+    pub config: Option<PathBuf>,
+    /// the value and origin of every setting in [`SETTINGS`] that isn't the default
+    pub settings: Vec<(&'static str, String, Source)>,
+}
+
+impl Options {
+    pub fn formats(&self) -> &[ResultFormat] {
+        self.formats.as_deref().unwrap_or(&DEFAULT_FORMATS)
+    }
+
+    /// every setting of [`SETTINGS`] with its value and where that comes from,
+    /// None for defaults
+    pub fn effective_settings(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &str, Option<&Source>)> {
+        SETTINGS.into_iter().map(|(key, _, default)| {
+            match self.settings.iter().find(|(k, ..)| *k == key) {
+                Some((_, value, source)) => (key, value.as_str(), Some(source)),
+                None => (key, default, None),
+            }
+        })
+    }
+}
+
+/// Where the value of a setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// a line of the config file
+    File(PathBuf, usize),
+    Env(&'static str),
+    Cli,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path, line) => write!(f, "{}:{line}", path.display()),
+            Source::Env(var) => write!(f, "{var}"),
+            Source::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// Settings of `solve` that can also be set by an environment variable or in
+/// the config file, with the variable and the default. Flags override
+/// environment variables, which override the config file.
+pub const SETTINGS: [(&str, &str, &str); 11] = [
+    ("threads", "PENTONIMO_NUM_THREADS", "number of cpus"),
+    ("max-memory", "PENTONIMO_MAX_MEMORY", "none"),
+    ("on-memory-cap", "PENTONIMO_ON_MEMORY_CAP", "evict"),
+    ("timeout", "PENTONIMO_TIMEOUT", "none"),
+    ("checkpoint-every", "PENTONIMO_CHECKPOINT_EVERY", "none"),
+    ("strategy", "PENTONIMO_STRATEGY", "auto"),
+    ("budget", "PENTONIMO_BUDGET", "60s"),
+    ("seed", "PENTONIMO_SEED", "current time"),
+    ("out-dir", "PENTONIMO_OUT_DIR", DEFAULT_OUT_DIR),
+    ("memo-shards", "PENTONIMO_MEMO_SHARDS", "auto"),
+    ("memo-capacity", "PENTONIMO_MEMO_CAPACITY", "auto"),
+];
+
+/// widths and heights of the boards `solve` sweeps over without `--batch`,
+/// `--preset`, `--width` and `--height`
+pub const DEFAULT_SIZES: RangeInclusive<u32> = 3..=7;
+
+/// the result files of every board without `--format`
+pub const DEFAULT_FORMATS: [ResultFormat; 4] = [
+    ResultFormat::Svg,
+    ResultFormat::Png,
+    ResultFormat::Txt,
+    ResultFormat::Json,
+];
+
+/// Config file in the current directory that is read if it exists and neither
+/// `--config` nor `PENTONIMO_CONFIG` name another one. Every line is a
+/// `<setting> = <value>` of [`SETTINGS`], lines starting with `#` are comments.
+pub const DEFAULT_CONFIG: &str = "pentonimo.conf";
+
+/// random probes of the search tree per board for `--dry-run`
+pub const DRY_RUN_PROBES: u32 = 2000;
+
+/// Largest search the auto strategy runs brute-force for, in estimated nodes
+/// of `--dry-run`. The estimate doesn't merge states reached on different
+/// paths, so the real searches are much smaller: a 6x6 board is estimated at
+/// ~6e8 nodes and solved in seconds.
+pub const AUTO_NODE_LIMIT: f64 = 1e10;
+
+/// default time for every board of the heuristic strategies
+pub const DEFAULT_BUDGET: Duration = Duration::from_secs(60);
+
+/// exit code of `solve` if a search was stopped by `--timeout` or
+/// `--on-memory-cap abort` before it finished, its board may have a better
+/// solution
+pub const EXIT_STOPPED: i32 = 2;
+
+/// exit code of `solve` if a board was solved by a heuristic strategy, which
+/// may miss better solutions, and no search was stopped
+pub const EXIT_HEURISTIC: i32 = 3;
+
+/// exit code of invalid arguments, unreadable inputs and unwritable results
+pub const EXIT_ERROR: i32 = 1;
+
+/// default time of every strategy for `bench-strategies`
+pub const DEFAULT_BENCH_BUDGET: Duration = Duration::from_secs(10);
+
+/// where the result files go without `--out-dir`
+pub const DEFAULT_OUT_DIR: &str = "results";
+
+/// name of the result files without `--name`
+pub const DEFAULT_NAME: &str = "{stem}";
+
+/// the fields `--name` templates can refer to
+pub const NAME_FIELDS: [&str; 6] = ["stem", "w", "h", "holes", "diameter", "pieces"];
+
+/// pieces of the deepest nodes `--trace` records
+pub const DEFAULT_TRACE_DEPTH: usize = 4;
+
+/// most nodes `--trace` records of every search
+pub const DEFAULT_TRACE_NODES: usize = 100_000;
+
+/// interval of the progress output
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+pub const USAGE: &str = "\
+usage: pentonimo [solve] [-q|-v|-vv] [--batch <spec file>] [--preset <name>]...
+                 [--width <n>|<n>..<m>] [--height <n>|<n>..<m>] [--profile]
+                 [--progress] [--max-memory <size>] [--on-memory-cap evict|abort]
+                 [--checkpoint-every <duration>] [--root-files] [--timeout <duration>]
+                 [--exact-fill] [--all-optima]
+                 [--prove] [--exact-no-monotone-prune] [--aggressive]
+                 [--exact-states] [--check-hashes]
+                 [--strategy auto|brute-force|lns|tabu|greedy] [--budget <duration>] [--seed <n>]
+                 [--objective diameter|dead-ends] [--dead-ends]
+                 [--out-dir <dir>] [--name <template>] [--format <formats>]
+                 [--pieces <counts>] [--force] [--no-files]
+                 [--no-cache] [--memo-shards <n>] [--memo-capacity <states>]
+                 [--memo-stats] [--pin-threads] [--reserve-core] [--numa]
+                 [--threads <n>] [--config <file>] [--path <style>] [--no-path]
+                 [--dry-run] [--in-order] [--no-best-known] [--no-greedy-seed]
+                 [--rollout-depth <n>] [--max-decrease <n>] [--letters none|one|all]
+                 [--palette <file>] [--trace] [--trace-depth <n>] [--trace-nodes <n>]
+       pentonimo verify <solution, certificate or solution SVG file>...
+       pentonimo merge [--output <file>] <checkpoint or solution file or directory>...
+       pentonimo tile [--count] [6x10|5x12|4x15|3x20|8x8-center]...
+       pentonimo analyze [--svg <file>] [--path <style>] [--no-path]
+                         <board file, spec file or PBM/PGM image>
+       pentonimo what-if <solution file>
+       pentonimo maze [--format text|json|svg] [--output <file>] <solution file>
+       pentonimo show [--format console|svg|png|animated-svg|json] [--output <file>] [--scale <n>]
+                      [--theme light|dark] [--palette <file>] [--letters none|one|all]
+                      [--path <style>] [--no-path] [--bottlenecks] [--dead-ends]
+                      <solution file or solution SVG>
+       pentonimo stats [--recompute] [<results dir>]
+       pentonimo view [--path <style>] [--no-path] [<results dir>]
+       pentonimo trace [--output <file>] <trace file>
+       pentonimo play [--board <spec>] [--ai-first] [--playouts <n>]
+       pentonimo bench-strategies [--budget <duration>] [--seed <n>] <spec>
+       pentonimo sample [--samples <n>] [--seed <n>] <spec>
+       pentonimo piece-sweep [--timeout <duration>] [--svg <file>] <spec>
+       pentonimo share [--goal sum|min] [--pieces <counts>] <spec>...
+       pentonimo serve [--listen <address>] [--jobs <dir>] [--workers <n>]
+       pentonimo coordinate [--listen <address>] [--shard-size <roots>]
+                            [--heartbeat-timeout <duration>] [--state <file>] <spec>
+       pentonimo work [--name <name>] [--threads <n>] <coordinator address>
+
+solve, the default command, solves every board of --batch, --preset and of
+--width and --height, which are a size or an inclusive range of sizes like
+3..7. Without --height the heights are 3..7 as well, and the other way around.
+Without any boards it solves every board from 3x3 to 7x7 like --width 3..7
+--height 3..7, but doesn't check them, sort them or write a summary. --format
+picks the files written for every board from svg, png, txt and json, separated
+by commas (default all of them). The PNG pictures are the ones of show --format
+png, for places without an SVG viewer. The JSON files also have the cells of the
+path shown by --path, from one end to the other.
+
+--pieces replaces the pieces of every board with counts like F=2,X=0 for two Fs,
+no X and one of every other pentomino. *=2 gives every board two of every
+pentomino, *=0,F=1,N=1 only an F and an N. Boards have none of the smaller
+pieces unless they are given: the tetrominoes I4, L4, O4, S4 and T4, the
+triominoes I3 and L3 and the domino I2, so *=1,O4=2,I2=1 adds two squares and a
+domino to the pentominoes. Entries can also be named piece sets, which replace
+all counts: pentominoes, pentomino-double (two of each), tetrominoes, triominoes
+and polyominoes (one of every piece from the domino to the pentominoes), so
+tetrominoes,I2=1 has the tetrominoes and a domino. one-sided never places
+mirrored pieces, and so do the sets after one-sided-, like
+one-sided-pentominoes. That is still a single piece of every kind, the twelve
+pentominoes only rotated, not the 18 with separate mirror images. Mirrored and
+transposed boards are then solved on their own. The pieces of a single board
+of a batch file are set the same way by pieces= in its spec.
+
+Specs in a batch file can keep a kind of piece inside a region of the board,
+like region-X=2..4,1..3 for Xs only in the 3x3 cells from 2,1 to 4,3 or
+region-I=border for Is only along the edges. Regions are lists of cells, ranges
+of cells and border, separated by ; like in free=, and pieces that would cover
+a cell outside of their region are never placed.
+
+Every command takes --piece-file <file> with the shapes of up to five custom
+pieces C1 to C5, each a line with its name followed by its rows of x and . like
+.x. and xxx, at most 5 by 5 cells. Boards get them with --pieces like C1=2, and
+saved solutions with custom pieces need the same file to be read again. Results
+with a piece file are never cached.
+
+solve exits with 0 if every search finished, 2 if a search was stopped by
+--timeout or --on-memory-cap abort, 3 if a board was solved by lns, tabu or
+greedy, which can miss better solutions, and 1 on errors. Boards of --batch and
+--preset are checked first: boards without free cells or without room for any
+piece are skipped as errors, others get warnings if their free cells are split
+or the pieces cover more cells than are free. They are solved in the order of
+their estimated search sizes, so quick boards are done first, unless --in-order
+is given.
+
+--threads, --max-memory, --on-memory-cap, --timeout, --checkpoint-every,
+--strategy, --budget, --seed, --out-dir, --memo-shards and --memo-capacity can
+also be set by PENTONIMO_NUM_THREADS and PENTONIMO_<SETTING> variables like
+PENTONIMO_OUT_DIR, or as <setting> = <value> lines of a config file (--config,
+PENTONIMO_CONFIG or ./pentonimo.conf).
+Flags override variables, which override the config file.
+
+--memo-shards splits the table of searched states into this many separately
+locked parts, a power of two (default: 4 per thread). --memo-capacity makes room
+for this many states up front (default: derived from the board size and
+--max-memory). --memo-stats regularly prints the stored states, their memory,
+how full the table is and how often --max-memory evicted it.
+
+--progress (also -v) prints the bounds of the diameter, the finished roots, the
+nodes and the stored states of every search every 2 seconds, with how much of
+it is done and the time left if the open roots take as long as the finished
+ones did on average, which gets more accurate as more roots are done.
+
+--pin-threads pins every thread of a search to a core, which keeps its caches
+warm. --reserve-core keeps the first core for the thread that reports progress
+and writes checkpoints and pins the search threads to the others. Both only
+work on linux and with more than one thread.
+
+--numa splits the roots and threads of every search between the NUMA nodes,
+pins the threads of every node to its cores and gives every node a table of
+searched states in its own memory. It needs a build with the numa feature and
+replaces --pin-threads and --reserve-core on machines with several nodes.
+
+Complete brute-force results are cached in cache.txt of the result directory
+and reused by later runs, also for boards that are the same up to mirroring
+and rotation, with the same pieces and ends. --no-cache searches them again.
+--prove, --exact-no-monotone-prune, --all-optima, --profile, --check-hashes,
+--aggressive, --trace, --exact-fill, --max-decrease and the other strategies
+never use the cache.
+
+--all-optima enumerates every optimal placement, prints how often every kind of
+piece is used in them and writes <name>.heatmap.svg, the share of the optimal
+placements covering every cell. Cells covered by all of them have a thick
+border, they are forced by the optimum, lighter cells show where the optimal
+placements differ.
+
+Brute-force searches of boards without holes, free cells or ends only look for
+placements reaching the best diameter found before, if it is known, and search
+again without it if there is none. --no-best-known searches without it. Boards
+solved worse than before get a warning, boards solved better print their new
+entry of the table.
+
+Brute-force searches start by placing the piece that makes the diameter largest
+until every piece would make it smaller and only look for placements with a
+larger diameter than this greedy construction. --no-greedy-seed searches
+without it. --strategy greedy only runs the construction, a quick baseline for
+the other strategies. --rollout-depth <n> searches the children of states with
+at most n pieces in the order of the diameters greedy constructions below them
+reach, which finds large diameters earlier but costs a construction for every
+child (default 0, in the order of the placements).
+
+Brute-force searches discard every placement that makes the diameter smaller.
+--max-decrease <n> also searches placements making it smaller by up to n, which
+can find placements that only reach a larger diameter after cutting a detour,
+but searches more states. --prove searches all of them.
+--exact-no-monotone-prune searches like --prove and then searches every board
+again with the usual pruning, and reports whether it finds the same diameter.
+
+--path picks the shortest path that is shown: fewest-turns (default), any,
+most-turns or hug-walls. --no-path leaves it out and only shows the pieces. In
+SVG pictures the path is a layer of its own that viewers can hide. In the
+console its ends are highlighted and a legend under the board names the kinds
+of the colors and the ends of the path. --letters writes the kind of every
+piece on one cell in its middle or on all of its cells of SVG pictures, which
+helps where colors can't be told apart, like on grayscale prints.
+
+--dead-ends prints the number of dead ends, free cells with a single free
+neighbour, and the mean and longest lengths of the corridors leading to them
+and highlights them. --objective dead-ends makes the tabu strategy maximize the
+dead ends instead of the diameter, and the diameter only among boards with as
+many, for boards that look like mazes. It is tabu by default and implies
+--dead-ends.
+
+--palette reads the colors of pictures and of the console from a JSON file like
+{\"kinds\": {\"F\": \"#ed1515\"}, \"path\": \"#bbb\"}, with the fields kinds,
+background, hole, path, center, dead_end, bottleneck and lines. Colors are #rgb
+or #rrggbb, the background can also be none. Missing colors are those of
+--theme, which is light for solve. In the console the colors of the kinds and
+the path need a terminal with 24 bit colors.
+
+show renders a saved solution without solving it again, to the console
+(default) or to the SVG or PNG --output. --scale is the size of a cell (default
+100 units in SVG, 24 pixels in PNG), --theme picks light (default) or dark
+colors. PNG pictures show the cells of the path without their numbers. --format
+animated-svg fades the pieces in one after the other in the order the search
+placed them, then the path from one end to the other. --format json writes the
+solution like the JSON result files to --output or the console, for other
+programs.
+--bottlenecks finds the narrowest places between the ends of the path, the
+fewest cells that would separate them if a piece covered them, and highlights
+every cell of such a smallest cut. Corridors of width 1 are where the maze can't
+be shortcut, wider ones show where another piece could make it longer.
+
+stats reads the solutions of a results directory (default results), also those
+of several runs, and prints the number of boards, solutions and proved optima
+and the best and mean diameters of every board shape and of every number of
+placed pieces. Every board counts once with its best solution. --recompute
+checks every solution and uses the diameters of its pieces instead of the
+recorded ones.
+
+view browses the same solutions in the terminal and needs a build with the tui
+feature. The up and down keys pick a board, left and right a piece, whose kind,
+position and cells are shown with the diameter of the board without it. p shows
+or hides the path and q leaves.
+
+--root-files writes the best placement of every root that a brute-force search
+has finished to <stem>.roots/root-<n>.checkpoint right away, so long searches
+can be inspected while they run. merge combines them, also after a crash, into
+a checkpoint to resume from, and takes a directory for all files in it. They are
+removed once the search is complete. Checkpoints only keep the finished roots
+and the best solution, not the searched states, so merge can't combine those
+and a resumed search starts with an empty table.
+
+--trace writes the nodes of every brute-force search with at most --trace-depth
+(default 4) pieces to <stem>.trace.jsonl, up to --trace-nodes (default 100000)
+of them. Every node has its parent, diameter, the nodes, pruned, known and
+dominated children of its subtree and why its search ended. trace folds such a
+file into lines of the pieces from a root to a node and the nodes of its subtree
+that weren't traced, which flamegraph.pl, inferno and speedscope draw as
+flamegraphs, and prints them or writes them to --output.
+
+sample runs --samples (default 1000) random greedy constructions of a board,
+each placing the best of a few random pieces until none fits, and prints the
+mean diameter they reach, how often they reach the best one and an estimate of
+the largest diameter they can reach, each with a 95% confidence interval. It is
+a quick check of big boards before or instead of an exact search, but not a
+bound: the optimum may need placements that greedy constructions never make.
+
+share places every piece of --pieces (default one of each) on at most one of
+the boards and maximizes the sum or the smallest of their diameters. It searches
+every board exhaustively, so it is meant for small boards.
+
+serve, coordinate and work need a build with the server feature.
+
+serve listens on --listen (default 127.0.0.1:4512) for lines like
+`submit <spec>`, `list`, `cancel <id>` and `result <id>` and answers every line
+with a line of JSON. Boards that solve would skip are rejected with an error.
+Queued boards with the smallest estimated searches are
+solved first, --workers (default 1) at the same time, each with its share of
+the cores. The queue and the results are kept in --jobs
+(default jobs), so they survive restarts.
+
+coordinate splits the roots of a board into shards of --shard-size (default 16)
+roots and leases them to work processes connecting to --listen (default
+127.0.0.1:4513). It prints the best solution once every shard is done. Workers
+that send no heartbeat for --heartbeat-timeout (default 30s) lose their shard
+to the next worker. Finished shards are kept in --state (default
+results/<board>.shards), and a coordinator started again with it only searches
+the roots that aren't finished, also with another --shard-size.";
+
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.peekable();
+
+    if let Some(command) = args.next_if(|arg| !arg.starts_with('-')) {
+        options.command = match command.as_str() {
+            "solve" => Command::Solve,
+            "verify" => Command::Verify(Vec::new()),
+            "merge" => Command::Merge {
+                files: Vec::new(),
+                output: None,
+            },
+            "analyze" => Command::Analyze {
+                file: PathBuf::new(),
+                svg: None,
+            },
+            "what-if" => Command::WhatIf(PathBuf::new()),
+            "maze" => Command::Maze {
+                file: PathBuf::new(),
+                format: MazeFormat::Text,
+                output: None,
+            },
+            "show" => Command::Show {
+                file: PathBuf::new(),
+                format: ShowFormat::Console,
+                output: None,
+                scale: None,
+                theme: Theme::Light,
+                bottlenecks: false,
+            },
+            "trace" => Command::Trace {
+                file: PathBuf::new(),
+                output: None,
+            },
+            "stats" => Command::Stats {
+                dir: None,
+                recompute: false,
+            },
+            "view" => Command::View { dir: None },
+            "play" => Command::Play {
+                board: BoardSpec::new((8, 8)),
+                ai_first: false,
+                playouts: 20_000,
+            },
+            "bench-strategies" => Command::BenchStrategies {
+                board: None,
+                budget: DEFAULT_BENCH_BUDGET,
+                seed: None,
+            },
+            "sample" => Command::Sample {
+                board: None,
+                samples: 1000,
+                seed: None,
+            },
+            "piece-sweep" => Command::PieceSweep {
+                board: None,
+                timeout: None,
+                svg: None,
+            },
+            "share" => Command::Share {
+                boards: Vec::new(),
+                pieces: PENTOMINOES,
+                one_sided: false,
+                goal: Goal::Sum,
+            },
+            "serve" => Command::Serve {
+                listen: "127.0.0.1:4512".to_owned(),
+                jobs: "jobs".into(),
+                workers: 1,
+            },
+            "coordinate" => Command::Coordinate {
+                board: None,
+                listen: "127.0.0.1:4513".to_owned(),
+                shard_size: 16,
+                heartbeat_timeout: Duration::from_secs(30),
+                state: None,
+            },
+            "work" => Command::Work {
+                coordinator: None,
+                name: None,
+                threads: None,
+            },
+            "tile" => Command::Tile {
+                presets: Vec::new(),
+                count: false,
+            },
+            _ => return Err(format!("unknown command {command:?}\n{USAGE}")),
+        };
+    }
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{name} requires a value"))
+        };
+
+        match (&mut options.command, arg.as_str()) {
+            (_, "--piece-file") => options.piece_file = Some(value("--piece-file")?.into()),
+            (Command::Solve, "--batch") => options.batch = Some(value("--batch")?.into()),
+            (Command::Solve, "--preset") => options.presets.push(value("--preset")?),
+            (Command::Solve, "--width") => options.widths = Some(parse_sizes(&value("--width")?)?),
+            (Command::Solve, "--height") => {
+                options.heights = Some(parse_sizes(&value("--height")?)?)
+            }
+            (Command::Solve, "--format") => {
+                options.formats = Some(
+                    value("--format")?
+                        .split(',')
+                        .map(|format| match format.trim() {
+                            "svg" => Ok(ResultFormat::Svg),
+                            "png" => Ok(ResultFormat::Png),
+                            "txt" => Ok(ResultFormat::Txt),
+                            "json" => Ok(ResultFormat::Json),
+                            format => Err(format!("unknown result format {format:?}")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            (Command::Solve, "--profile") => options.profile = true,
+            (Command::Solve, "--progress") => options.progress = true,
+            (Command::Solve, "--memo-stats") => options.memo_stats = true,
+            (Command::Solve, "--pin-threads") => options.pin_threads = true,
+            (Command::Solve, "--reserve-core") => options.reserve_core = true,
+            (Command::Solve, "--numa") => options.numa = true,
+            (Command::Solve, "-q" | "--quiet") => options.verbosity = Verbosity::Quiet,
+            (Command::Solve, "-v") => options.verbosity = Verbosity::Verbose,
+            (Command::Solve, "-vv") => options.verbosity = Verbosity::Debug,
+            (Command::Solve, "--exact-fill") => options.exact_fill = true,
+            (Command::Solve, "--all-optima") => options.all_optima = true,
+            (Command::Solve, "--prove") => options.prove = true,
+            (Command::Solve, "--exact-no-monotone-prune") => options.exact_no_monotone_prune = true,
+            (Command::Solve, "--aggressive") => options.aggressive = true,
+            (Command::Solve, "--exact-states") => options.exact_states = true,
+            (Command::Solve, "--check-hashes") => options.check_hashes = true,
+            (Command::Solve, "--dry-run") => options.dry_run = true,
+            (Command::Solve, "--config") => options.config = Some(value("--config")?.into()),
+            (Command::Solve, flag) if setting(flag).is_some() => {
+                let key = setting(flag).unwrap();
+                set(&mut options, key, value(flag)?, Source::Cli)?
+            }
+            (Command::Solve, "--force") => options.force = true,
+            (Command::Solve, "--no-files") => options.no_files = true,
+            (Command::Solve, "--root-files") => options.root_files = true,
+            (Command::Solve, "--no-cache") => options.no_cache = true,
+            (Command::Solve, "--in-order") => options.in_order = true,
+            (Command::Solve, "--no-best-known") => options.no_best_known = true,
+            (Command::Solve, "--pieces") => {
+                options.pieces = Some(parse_pieces(&value("--pieces")?)?)
+            }
+            (Command::Solve, "--no-greedy-seed") => options.no_greedy_seed = true,
+            (Command::Solve, "--max-decrease") => {
+                let n = value("--max-decrease")?;
+                options.max_decrease = n
+                    .parse()
+                    .map_err(|_| format!("invalid diameter decrease {n:?}"))?
+            }
+            (Command::Solve, "--rollout-depth") => {
+                let n = value("--rollout-depth")?;
+                options.rollout_depth = n
+                    .parse()
+                    .map_err(|_| format!("invalid rollout depth {n:?}"))?
+            }
+            (Command::Solve, "--trace") => options.trace = true,
+            (Command::Solve, "--trace-depth") => {
+                let n = value("--trace-depth")?;
+                options.trace_depth = Some(
+                    n.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("invalid trace depth {n:?}"))?,
+                )
+            }
+            (Command::Solve, "--trace-nodes") => {
+                let n = value("--trace-nodes")?;
+                options.trace_nodes = Some(
+                    n.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("invalid number of trace nodes {n:?}"))?,
+                )
+            }
+            (Command::Solve, "--name") => {
+                let name = value("--name")?;
+                if name.contains(['/', '\\']) {
+                    return Err(format!(
+                        "--name {name:?} can't contain directories, use --out-dir instead"
+                    ));
+                }
+                expand_name(&name, |field| {
+                    NAME_FIELDS.contains(&field).then(String::new)
+                })?;
+                options.name = Some(name);
+            }
+            (
+                Command::Solve
+                | Command::Analyze { .. }
+                | Command::Show { .. }
+                | Command::View { .. },
+                "--path",
+            ) => {
+                options.path_style = match value("--path")?.as_str() {
+                    "any" => PathStyle::Any,
+                    "fewest-turns" => PathStyle::FewestTurns,
+                    "most-turns" => PathStyle::MostTurns,
+                    "hug-walls" => PathStyle::HugWalls,
+                    style => return Err(format!("unknown path style {style:?}")),
+                }
+            }
+            (Command::Solve | Command::Show { .. }, "--palette") => {
+                options.palette = Some(value("--palette")?.into())
+            }
+            (Command::Solve | Command::Show { .. }, "--letters") => {
+                options.letters = match value("--letters")?.as_str() {
+                    "none" => Letters::None,
+                    "one" => Letters::One,
+                    "all" => Letters::All,
+                    letters => return Err(format!("unknown letters {letters:?}")),
+                }
+            }
+            (Command::Solve, "--objective") => {
+                options.objective = match value("--objective")?.as_str() {
+                    "diameter" => Objective::Diameter,
+                    "dead-ends" => Objective::DeadEnds,
+                    objective => return Err(format!("unknown objective {objective:?}")),
+                }
+            }
+            (Command::Solve | Command::Show { .. }, "--dead-ends") => options.dead_ends = true,
+            (
+                Command::Solve
+                | Command::Analyze { .. }
+                | Command::Show { .. }
+                | Command::View { .. },
+                "--no-path",
+            ) => options.no_path = true,
+            (Command::Tile { count, .. }, "--count") => *count = true,
+            (Command::Tile { presets, .. }, preset) if !preset.starts_with('-') => {
+                presets.push(preset.to_owned())
+            }
+            (Command::Play { board, .. }, "--board") => *board = value("--board")?.parse()?,
+            (Command::Play { ai_first, .. }, "--ai-first") => *ai_first = true,
+            (Command::Play { playouts, .. }, "--playouts") => {
+                let n = value("--playouts")?;
+                *playouts = n
+                    .parse()
+                    .map_err(|_| format!("invalid number of playouts {n:?}"))?
+            }
+            (Command::BenchStrategies { budget, .. }, "--budget") => {
+                *budget = parse_duration(&value("--budget")?)?
+            }
+            (Command::BenchStrategies { seed, .. }, "--seed") => {
+                let n = value("--seed")?;
+                *seed = Some(n.parse().map_err(|_| format!("invalid seed {n:?}"))?)
+            }
+            (Command::BenchStrategies { board, .. }, spec)
+                if !spec.starts_with('-') && board.is_none() =>
+            {
+                *board = Some(spec.parse()?)
+            }
+            (Command::Sample { samples, .. }, "--samples") => {
+                let n = value("--samples")?;
+                *samples = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("invalid number of samples {n:?}"))?
+            }
+            (Command::Sample { seed, .. }, "--seed") => {
+                let n = value("--seed")?;
+                *seed = Some(n.parse().map_err(|_| format!("invalid seed {n:?}"))?)
+            }
+            (Command::Sample { board, .. }, spec) if !spec.starts_with('-') && board.is_none() => {
+                *board = Some(spec.parse()?)
+            }
+            (Command::Share { goal, .. }, "--goal") => {
+                *goal = match value("--goal")?.as_str() {
+                    "sum" => Goal::Sum,
+                    "min" => Goal::Min,
+                    goal => return Err(format!("unknown goal {goal:?}")),
+                }
+            }
+            (
+                Command::Share {
+                    pieces, one_sided, ..
+                },
+                "--pieces",
+            ) => (*pieces, *one_sided) = parse_pieces(&value("--pieces")?)?,
+            (Command::Share { boards, .. }, spec) if !spec.starts_with('-') => {
+                boards.push(spec.parse()?)
+            }
+            (Command::Serve { listen, .. }, "--listen") => *listen = value("--listen")?,
+            (Command::Serve { jobs, .. }, "--jobs") => *jobs = value("--jobs")?.into(),
+            (Command::Serve { workers, .. }, "--workers") => {
+                let n = value("--workers")?;
+                *workers = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("invalid number of workers {n:?}"))?
+            }
+            (Command::Coordinate { listen, .. }, "--listen") => *listen = value("--listen")?,
+            (Command::Coordinate { shard_size, .. }, "--shard-size") => {
+                let n = value("--shard-size")?;
+                *shard_size = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("invalid shard size {n:?}"))?
+            }
+            (
+                Command::Coordinate {
+                    heartbeat_timeout, ..
+                },
+                "--heartbeat-timeout",
+            ) => *heartbeat_timeout = parse_duration(&value("--heartbeat-timeout")?)?,
+            (Command::Coordinate { state, .. }, "--state") => {
+                *state = Some(value("--state")?.into())
+            }
+            (Command::Coordinate { board, .. }, spec)
+                if !spec.starts_with('-') && board.is_none() =>
+            {
+                *board = Some(spec.parse()?)
+            }
+            (Command::Work { name, .. }, "--name") => *name = Some(value("--name")?),
+            (Command::Work { threads, .. }, "--threads") => {
+                let n = value("--threads")?;
+                *threads = Some(
+                    n.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("invalid number of threads {n:?}"))?,
+                )
+            }
+            (Command::Work { coordinator, .. }, address)
+                if !address.starts_with('-') && coordinator.is_none() =>
+            {
+                *coordinator = Some(address.to_owned())
+            }
+            (Command::PieceSweep { timeout, .. }, "--timeout") => {
+                *timeout = Some(parse_duration(&value("--timeout")?)?)
+            }
+            (Command::PieceSweep { svg, .. }, "--svg") => *svg = Some(value("--svg")?.into()),
+            (Command::PieceSweep { board, .. }, spec)
+                if !spec.starts_with('-') && board.is_none() =>
+            {
+                *board = Some(spec.parse()?)
+            }
+            (Command::Analyze { svg, .. }, "--svg") => *svg = Some(value("--svg")?.into()),
+            (Command::Analyze { file, .. }, path)
+                if !path.starts_with('-') && file.as_os_str().is_empty() =>
+            {
+                *file = path.into()
+            }
+            (Command::WhatIf(file), path)
+                if !path.starts_with('-') && file.as_os_str().is_empty() =>
+            {
+                *file = path.into()
+            }
+            (Command::Maze { format, .. }, "--format") => {
+                *format = match value("--format")?.as_str() {
+                    "text" => MazeFormat::Text,
+                    "json" => MazeFormat::Json,
+                    "svg" => MazeFormat::Svg,
+                    format => return Err(format!("unknown maze format {format:?}")),
+                }
+            }
+            (Command::Maze { output, .. }, "-o" | "--output") => {
+                *output = Some(value("--output")?.into())
+            }
+            (Command::Maze { file, .. }, path)
+                if !path.starts_with('-') && file.as_os_str().is_empty() =>
+            {
+                *file = path.into()
+            }
+            (Command::Show { format, .. }, "--format") => {
+                *format = match value("--format")?.as_str() {
+                    "console" => ShowFormat::Console,
+                    "svg" => ShowFormat::Svg,
+                    "png" => ShowFormat::Png,
+                    "animated-svg" => ShowFormat::AnimatedSvg,
+                    "json" => ShowFormat::Json,
+                    format => return Err(format!("unknown show format {format:?}")),
+                }
+            }
+            (Command::Show { output, .. }, "-o" | "--output") => {
+                *output = Some(value("--output")?.into())
+            }
+            (Command::Show { scale, .. }, "--scale") => {
+                let n = value("--scale")?;
+                *scale = Some(
+                    n.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("invalid scale {n:?}"))?,
+                );
+            }
+            (Command::Show { theme, .. }, "--theme") => {
+                *theme = match value("--theme")?.as_str() {
+                    "light" => Theme::Light,
+                    "dark" => Theme::Dark,
+                    theme => return Err(format!("unknown theme {theme:?}")),
+                }
+            }
+            (Command::Show { bottlenecks, .. }, "--bottlenecks") => *bottlenecks = true,
+            (Command::Show { file, .. }, path)
+                if !path.starts_with('-') && file.as_os_str().is_empty() =>
+            {
+                *file = path.into()
+            }
+            (Command::Trace { output, .. }, "-o" | "--output") => {
+                *output = Some(value("--output")?.into())
+            }
+            (Command::Trace { file, .. }, path)
+                if !path.starts_with('-') && file.as_os_str().is_empty() =>
+            {
+                *file = path.into()
+            }
+            (Command::Stats { recompute, .. }, "--recompute") => *recompute = true,
+            (Command::Stats { dir, .. } | Command::View { dir }, path)
+                if !path.starts_with('-') && dir.is_none() =>
+            {
+                *dir = Some(path.into())
+            }
+            (_, "-h" | "--help") => return Err(USAGE.to_owned()),
+            (Command::Verify(files), file) if !file.starts_with('-') => files.push(file.into()),
+            (Command::Merge { output, .. }, "-o" | "--output") => {
+                *output = Some(value("--output")?.into())
+            }
+            (Command::Merge { files, .. }, file) if !file.starts_with('-') => {
+                files.push(file.into())
+            }
+            _ => return Err(format!("unexpected argument {arg:?}\n{USAGE}")),
+        }
+    }
+
+    if let Command::Solve = options.command {
+        for (key, var, _) in SETTINGS {
+            if let Ok(value) = std::env::var(var) {
+                if !options.settings.iter().any(|(k, ..)| *k == key) {
+                    set(&mut options, key, value, Source::Env(var))
+                        .map_err(|err| format!("{var}: {err}"))?;
+                }
+            }
+        }
+        read_config(&mut options)?;
+    }
+
+    match &options.command {
+        Command::Verify(files) if files.is_empty() => {
+            return Err(format!("verify requires at least one file\n{USAGE}"));
+        }
+        Command::Merge { files, .. } if files.is_empty() => {
+            return Err(format!("merge requires at least one file\n{USAGE}"));
+        }
+        Command::Maze { file, .. } if file.as_os_str().is_empty() => {
+            return Err(format!("maze requires a solution file\n{USAGE}"));
+        }
+        Command::Show { file, .. } if file.as_os_str().is_empty() => {
+            return Err(format!("show requires a solution file\n{USAGE}"));
+        }
+        Command::Show {
+            format: ShowFormat::Console,
+            output: Some(_),
+            ..
+        } => {
+            return Err("--output requires --format svg, png, animated-svg or json".to_owned());
+        }
+        Command::Show {
+            format: ShowFormat::Svg | ShowFormat::Png | ShowFormat::AnimatedSvg,
+            output: None,
+            ..
+        } => {
+            return Err(format!(
+                "show --format svg, png and animated-svg require --output\n{USAGE}"
+            ));
+        }
+        Command::Trace { file, .. } if file.as_os_str().is_empty() => {
+            return Err(format!("trace requires a trace file\n{USAGE}"));
+        }
+        Command::BenchStrategies { board: None, .. } => {
+            return Err(format!("bench-strategies requires a board spec\n{USAGE}"));
+        }
+        Command::Sample { board: None, .. } => {
+            return Err(format!("sample requires a board spec\n{USAGE}"));
+        }
+        Command::PieceSweep { board: None, .. } => {
+            return Err(format!("piece-sweep requires a board spec\n{USAGE}"));
+        }
+        Command::Serve { .. } | Command::Coordinate { .. } | Command::Work { .. }
+            if !cfg!(feature = "server") =>
+        {
+            return Err(
+                "serve, coordinate and work need a build with the server feature".to_owned(),
+            );
+        }
+        Command::Coordinate { board: None, .. } => {
+            return Err(format!("coordinate requires a board spec\n{USAGE}"));
+        }
+        Command::Work {
+            coordinator: None, ..
+        } => {
+            return Err(format!(
+                "work requires the address of a coordinator\n{USAGE}"
+            ));
+        }
+        Command::Share { boards, .. } if boards.is_empty() => {
+            return Err(format!("share requires at least one board spec\n{USAGE}"));
+        }
+        Command::WhatIf(file) if file.as_os_str().is_empty() => {
+            return Err(format!("what-if requires a solution file\n{USAGE}"));
+        }
+        Command::Analyze { file, .. } if file.as_os_str().is_empty() => {
+            return Err(format!("analyze requires a board file\n{USAGE}"));
+        }
+        Command::Solve if options.exact_states && options.check_hashes => {
+            return Err(
+                "--check-hashes requires hashed states, so it can't be used with --exact-states"
+                    .to_owned(),
+            );
+        }
+        Command::Solve
+            if matches!(
+                options.strategy,
+                Strategy::Lns | Strategy::Tabu | Strategy::Greedy
+            ) && (options.prove
+                || options.exact_no_monotone_prune
+                || options.all_optima
+                || options.checkpoint_every.is_some()
+                || options.root_files
+                || options.timeout.is_some()
+                || options.trace) =>
+        {
+            return Err(
+                "--prove, --exact-no-monotone-prune, --all-optima, --checkpoint-every, \
+                 --root-files, --timeout and --trace require the brute-force strategy"
+                    .to_owned(),
+            );
+        }
+        Command::Solve
+            if !options.trace
+                && (options.trace_depth.is_some() || options.trace_nodes.is_some()) =>
+        {
+            return Err("--trace-depth and --trace-nodes require --trace".to_owned());
+        }
+        Command::Solve if options.no_files && options.trace => {
+            return Err("--trace writes files, so it can't be used with --no-files".to_owned());
+        }
+        Command::Solve
+            if options.objective != Objective::Diameter
+                && (matches!(
+                    options.strategy,
+                    Strategy::BruteForce | Strategy::Lns | Strategy::Greedy
+                ) || options.exact_fill
+                    || options.prove
+                    || options.all_optima
+                    || options.checkpoint_every.is_some()
+                    || options.timeout.is_some()
+                    || options.trace) =>
+        {
+            return Err(format!(
+                "--objective {} requires the tabu strategy and can't be used with --exact-fill, \
+                 --prove, --all-optima, --checkpoint-every, --timeout or --trace",
+                options.objective.name()
+            ));
+        }
+        Command::Solve if options.no_files && options.checkpoint_every.is_some() => {
+            return Err(
+                "--checkpoint-every writes files, so it can't be used with --no-files".to_owned(),
+            );
+        }
+        Command::Solve if options.no_files && options.root_files => {
+            return Err(
+                "--root-files writes files, so it can't be used with --no-files".to_owned(),
+            );
+        }
+        Command::Solve if options.numa && !cfg!(feature = "numa") => {
+            return Err("--numa needs a build with the numa feature".to_owned());
+        }
+        Command::View { .. } if !cfg!(feature = "tui") => {
+            return Err("view needs a build with the tui feature".to_owned());
+        }
+        Command::Solve if options.prove && options.aggressive => {
+            return Err(
+                "--aggressive can't prove results optimal, so it can't be used with --prove"
+                    .to_owned(),
+            );
+        }
+        _ => {}
+    }
+
+    if options.verbosity >= Verbosity::Verbose {
+        options.progress = true;
+    }
+    if options.objective == Objective::DeadEnds {
+        options.dead_ends = true;
+    }
+
+    Ok(options)
+}
+
+/// the setting of a flag like `--timeout`
+fn setting(flag: &str) -> Option<&'static str> {
+    let key = flag.strip_prefix("--")?;
+    SETTINGS.iter().map(|(k, ..)| *k).find(|k| *k == key)
+}
+
+/// Sets a setting unless a source with higher precedence already set it
+fn set(
+    options: &mut Options,
+    key: &'static str,
+    value: String,
+    source: Source,
+) -> Result<(), String> {
+    if let Some(i) = options.settings.iter().position(|(k, ..)| *k == key) {
+        // flags that are given twice override the earlier one
+        if !(source == Source::Cli && options.settings[i].2 == Source::Cli) {
+            return Ok(());
+        }
+        options.settings.remove(i);
+    }
+
+    match key {
+        "threads" => {
+            let threads = value.parse().ok().filter(|&n: &usize| n > 0);
+            options.threads =
+                Some(threads.ok_or_else(|| format!("invalid number of threads {value:?}"))?)
+        }
+        "max-memory" => options.memory_cap = Some(parse_bytes(&value)?),
+        "on-memory-cap" => {
+            options.memory_policy = match value.as_str() {
+                "evict" => MemoryPolicy::Evict,
+                "abort" => MemoryPolicy::Abort,
+                policy => return Err(format!("unknown memory cap policy {policy:?}")),
+            }
+        }
+        "timeout" => options.timeout = Some(parse_duration(&value)?),
+        "checkpoint-every" => options.checkpoint_every = Some(parse_duration(&value)?),
+        "strategy" => {
+            options.strategy = match value.as_str() {
+                "auto" => Strategy::Auto,
+                "brute-force" => Strategy::BruteForce,
+                "lns" => Strategy::Lns,
+                "tabu" => Strategy::Tabu,
+                "greedy" => Strategy::Greedy,
+                strategy => return Err(format!("unknown strategy {strategy:?}")),
+            }
+        }
+        "budget" => options.budget = Some(parse_duration(&value)?),
+        "seed" => {
+            options.seed = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid seed {value:?}"))?,
+            )
+        }
+        "out-dir" => options.out_dir = Some(value.as_str().into()),
+        "memo-shards" => {
+            let shards = value
+                .parse()
+                .ok()
+                .filter(|&n: &usize| n > 1 && n.is_power_of_two());
+            options.memo_shards = Some(shards.ok_or_else(|| {
+                format!("invalid number of memo shards {value:?}, expected a power of two of at least 2")
+            })?)
+        }
+        "memo-capacity" => {
+            options.memo_capacity = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid memo capacity {value:?}"))?,
+            )
+        }
+        _ => unreachable!("setting {key} isn't in SETTINGS"),
+    }
+    options.settings.push((key, value, source));
+    Ok(())
+}
+
+/// Fills the settings that neither flags nor environment variables set from
+/// the config file
+fn read_config(options: &mut Options) -> Result<(), String> {
+    let path = match (&options.config, std::env::var_os("PENTONIMO_CONFIG")) {
+        (Some(path), _) => path.clone(),
+        (None, Some(path)) => path.into(),
+        (None, None) if std::path::Path::new(DEFAULT_CONFIG).exists() => DEFAULT_CONFIG.into(),
+        (None, None) => return Ok(()),
+    };
+    let input = std::fs::read_to_string(&path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at = || format!("{}:{}", path.display(), i + 1);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}: expected <setting> = <value>", at()))?;
+        let key = setting(&format!("--{}", key.trim()))
+            .ok_or_else(|| format!("{}: unknown setting {:?}", at(), key.trim()))?;
+        set(
+            options,
+            key,
+            value.trim().to_owned(),
+            Source::File(path.clone(), i + 1),
+        )
+        .map_err(|err| format!("{}: {err}", at()))?;
+    }
+    Ok(())
+}
+
+/// Replaces every `{field}` of a name template like `{w}x{h}_d{diameter}` by
+/// its value. Every kind of result file adds its own extension to the name.
+pub fn expand_name(
+    template: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name += &rest[..start];
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed {{ in name {template:?}"))?;
+        let field = &rest[start + 1..start + end];
+        name += &value(field).ok_or_else(|| {
+            format!(
+                "unknown field {{{field}}} in name {template:?}, expected one of {}",
+                NAME_FIELDS.map(|field| format!("{{{field}}}")).join(" ")
+            )
+        })?;
+        rest = &rest[start + end + 1..];
+    }
+    name += rest;
+    Ok(name)
+}
+
+/// parses a board size like `9` or an inclusive range of them like `3..7`
+pub fn parse_sizes(s: &str) -> Result<RangeInclusive<u32>, String> {
+    let size = |n: &str| {
+        n.trim()
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("invalid board size {s:?}"))
+    };
+    let sizes = match s.split_once("..") {
+        Some((start, end)) => size(start)?..=size(end.strip_prefix('=').unwrap_or(end))?,
+        None => size(s)?..=size(s)?,
+    };
+    if sizes.is_empty() {
+        return Err(format!("empty range of board sizes {s:?}"));
+    }
+    Ok(sizes)
+}
+
+/// parses durations like `90s`, `5m` or `2h`
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("invalid duration {s:?}"))?;
+    let factor = match unit {
+        "ms" => return Ok(Duration::from_millis(number)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("invalid duration unit in {s:?}")),
+    };
+
+    number
+        .checked_mul(factor)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration {s:?} is too large"))
+}
@@ -0,0 +1,137 @@
+//! Custom pieces from a file, drawn like the pieces in `pentonimo.rs`:
+//!
+//! ```text
+//! # a plus sign with a longer arm
+//! C1
+//! .x.
+//! xxx
+//! .x.
+//! .x.
+//!
+//! C2
+//! xx
+//! ```
+//!
+//! Every piece starts with the name of a custom kind, `C1` to `C5`, followed
+//! by its rows of `x` for covered and `.` for uncovered cells, at most 5 by 5.
+//! Lines starting with `#` are comments. The pieces are searched in every
+//! rotation and mirror image that differs from the others.
+
+use std::{path::Path, str::FromStr};
+
+use crate::{
+    pentonimo::{define_custom, Family, PentonimoKind},
+    tile::Tile,
+};
+
+/// Parses the pieces of a custom piece file, see the module documentation
+pub fn parse_custom_pieces(text: &str) -> Result<Vec<(PentonimoKind, Tile)>, String> {
+    let mut pieces = Vec::new();
+    let mut current: Option<(PentonimoKind, Tile, u8)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| format!("line {}: {message}", i + 1);
+        if line.is_empty() {
+            pieces.extend(current.take().map(|(kind, tile, _)| (kind, tile)));
+            continue;
+        }
+        match &mut current {
+            Some((kind, tile, rows)) if line.chars().all(|c| matches!(c, 'x' | '.')) => {
+                if *rows == 5 || line.len() > 5 {
+                    return Err(error(format!("{kind} is larger than 5x5 cells")));
+                }
+                for (x, c) in line.chars().enumerate() {
+                    if c == 'x' {
+                        tile.set(x as u8, *rows);
+                    }
+                }
+                *rows += 1;
+            }
+            _ => {
+                pieces.extend(current.take().map(|(kind, tile, _)| (kind, tile)));
+                let kind = PentonimoKind::from_str(line)
+                    .ok()
+                    .filter(|kind| kind.family() == Family::Custom)
+                    .ok_or_else(|| error(format!("expected C1 to C5, got {line:?}")))?;
+                current = Some((kind, Tile::empty(), 0));
+            }
+        }
+    }
+    pieces.extend(current.map(|(kind, tile, _)| (kind, tile)));
+    Ok(pieces)
+}
+
+/// Reads the custom pieces of a file and gives their kinds these shapes
+pub fn load_custom_pieces(path: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    let pieces = parse_custom_pieces(&text).map_err(|err| format!("{}: {err}", path.display()))?;
+    define_custom(&pieces).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+#[test]
+fn custom_pieces_are_searched_in_every_orientation() {
+    use crate::{
+        brute_force::{find_best, SearchConfig},
+        pentonimo::Pentonimo,
+        solution::Solution,
+        spec::BoardSpec,
+    };
+
+    let pieces = parse_custom_pieces(
+        "# one of every kind of symmetry\n\
+         C1\n\
+         .x.\n\
+         xxx\n\
+         .x.\n\
+         .x.\n\
+         \n\
+         C2\n\
+         xx\n\
+         C3\n\
+         xx.\n\
+         .xx\n\
+         \n\
+         C4\n\
+         xxx\n\
+         x.x\n\
+         C5\n\
+         x.\n\
+         xx\n\
+         .x\n\
+         .x\n",
+    )
+    .unwrap();
+    assert_eq!(pieces.len(), 5);
+    assert!(parse_custom_pieces("C6\nx\n").is_err());
+    assert!(parse_custom_pieces("C1\nxxxxxx\n").is_err());
+    assert!(parse_custom_pieces("C1\nx\nx\nx\nx\nx\nx\n").is_err());
+    assert!(define_custom(&[(PentonimoKind::F, Tile(1))]).is_err());
+
+    define_custom(&pieces).unwrap();
+    for (kind, variants, cells) in [
+        (PentonimoKind::C1, 4, 6),
+        (PentonimoKind::C2, 2, 2),
+        (PentonimoKind::C3, 4, 4),
+        (PentonimoKind::C4, 4, 5),
+        (PentonimoKind::C5, 8, 5),
+    ] {
+        assert_eq!(Pentonimo::new(kind).variants().count(), variants, "{kind}");
+        assert_eq!(kind.cells(), cells, "{kind}");
+    }
+
+    let spec = "5x4 pieces=*=0,C1=1,C2=2".parse::<BoardSpec>().unwrap();
+    let result = find_best(&spec, &SearchConfig::default());
+    assert!(!result.placed.is_empty());
+    Solution {
+        spec,
+        diameter: result.diameter,
+        pieces: result.placed,
+        proved_optimal: false,
+    }
+    .verify()
+    .unwrap();
+}
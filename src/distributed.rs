@@ -0,0 +1,776 @@
+//! Search of a single board on several machines. `pentonimo coordinate`
+//! splits the roots of the board, without those a symmetry turns into an
+//! earlier one, into shards and leases them to `pentonimo work` processes,
+//! which search their shard and send back the best solution of it.
+//!
+//! Workers send a heartbeat for their shard regularly. A shard whose worker
+//! missed its heartbeats for the timeout of the coordinator is leased to the
+//! next worker asking, and the late worker learns from its next heartbeat that
+//! it lost the shard. Results are idempotent: a shard is done with the first
+//! result for it, later ones can only improve the best solution.
+//!
+//! Every request is a line of JSON with a connection of its own and gets a
+//! line of JSON back:
+//!
+//! ```text
+//! {"request": "lease", "worker": "a"}
+//!     {"shard": 3, "spec": "7x5", "roots": 147, "shard_roots": [48, ...], "heartbeat_ms": 10000, "best": "spec 7x5\n..."}
+//!     {"wait_ms": 10000} while every shard left is leased, {"done": true} once all are done
+//! {"request": "heartbeat", "worker": "a", "shard": 3}
+//!     {"leased": true}, or false if the shard went to another worker
+//! {"request": "finish", "worker": "a", "shard": 3, "shard_roots": [48, ...], "solution": "spec 7x5\n..."}
+//!     {"new": true}, or false if the shard was done already
+//! ```
+//!
+//! Failed requests get `{"error": "..."}`.
+//!
+//! The coordinator keeps the finished roots, who searched them and the best
+//! solution in a state file:
+//!
+//! ```text
+//! roots 147
+//! shard 0-5,8 by a
+//! shard 9-20 by b
+//! spec 7x5
+//! diameter 22
+//! piece ...
+//! ```
+//!
+//! A coordinator started with an existing state file only splits the roots
+//! that aren't finished into shards, so a run can be stopped and resumed
+//! later, also with another shard size or more workers.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    brute_force::{find_best, symmetric_roots, SearchConfig},
+    checkpoint::{format_roots, parse_roots, write_atomic, Checkpoint},
+    json::Json,
+    pathfinding::BfsScratch,
+    solution::Solution,
+    spec::BoardSpec,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShardState {
+    Pending,
+    Leased { worker: String, heartbeat: Instant },
+    Done { worker: String },
+}
+
+#[derive(Debug)]
+struct Shard {
+    roots: Vec<usize>,
+    state: ShardState,
+}
+
+/// What a worker asking for work gets
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lease {
+    /// the index of a shard and its roots
+    Shard(usize, Vec<usize>),
+    /// every shard left is leased, ask again later
+    Wait,
+    /// every shard is done
+    Done,
+}
+
+/// The shards of a distributed search and who works on them
+#[derive(Debug)]
+pub struct Coordinator {
+    /// the canonical board
+    spec: BoardSpec,
+    roots: usize,
+    shards: Vec<Shard>,
+    best: Checkpoint,
+    /// how long a lease lasts without heartbeats
+    timeout: Duration,
+}
+
+impl Coordinator {
+    /// Splits the roots of `spec` into shards of `shard_size` roots
+    pub fn new(spec: &BoardSpec, shard_size: usize, timeout: Duration) -> Self {
+        let spec = spec.canonical().0;
+        let roots = spec.placements().len();
+        let (diameter, _) = BfsScratch::new(spec.shape).objective(&spec.initial_map(), &spec.ends);
+        let best = Solution {
+            spec: spec.clone(),
+            diameter,
+            pieces: Vec::new(),
+            proved_optimal: false,
+        };
+        Self::with_finished(spec, shard_size, timeout, Vec::new(), best, roots)
+    }
+
+    /// Continues the search of a state file written by [`Display`], with only
+    /// the roots that aren't finished in new shards
+    pub fn resume(
+        spec: &BoardSpec,
+        shard_size: usize,
+        timeout: Duration,
+        state: &str,
+    ) -> Result<Self, String> {
+        let spec = spec.canonical().0;
+        let roots = spec.placements().len();
+        let mut finished = Vec::new();
+        let mut solution = String::new();
+        for line in state.lines() {
+            if let Some(value) = line.strip_prefix("roots ") {
+                if value.trim().parse() != Ok(roots) {
+                    return Err(format!(
+                        "the state has {value:?} roots, but {spec} has {roots}"
+                    ));
+                }
+            } else if let Some(value) = line.strip_prefix("shard ") {
+                let (ranges, worker) = value
+                    .split_once(" by ")
+                    .ok_or_else(|| format!("expected `shard <roots> by <worker>`, got {line:?}"))?;
+                let roots = parse_roots(ranges, roots)?;
+                finished.push(Shard {
+                    roots: (0..roots.len()).filter(|&root| roots[root]).collect(),
+                    state: ShardState::Done {
+                        worker: worker.to_owned(),
+                    },
+                });
+            } else {
+                solution += line;
+                solution.push('\n');
+            }
+        }
+
+        let best = solution.parse::<Solution>()?;
+        if best.spec != spec {
+            return Err(format!("the state belongs to {}, not {spec}", best.spec));
+        }
+        best.verify()?;
+        Ok(Self::with_finished(
+            spec, shard_size, timeout, finished, best, roots,
+        ))
+    }
+
+    /// `finished` shards followed by new ones for the other roots of `spec`
+    fn with_finished(
+        spec: BoardSpec,
+        shard_size: usize,
+        timeout: Duration,
+        mut shards: Vec<Shard>,
+        best: Solution,
+        roots: usize,
+    ) -> Self {
+        let mut done = vec![false; roots];
+        for shard in &shards {
+            for &root in &shard.roots {
+                done[root] = true;
+            }
+        }
+        let left = symmetric_roots(&spec, &spec.placements())
+            .iter()
+            .enumerate()
+            .filter(|&(root, &symmetric)| !symmetric && !done[root])
+            .map(|(root, _)| root)
+            .collect::<Vec<_>>();
+        shards.extend(left.chunks(shard_size.max(1)).map(|roots| Shard {
+            roots: roots.to_vec(),
+            state: ShardState::Pending,
+        }));
+        Self {
+            spec,
+            roots,
+            shards,
+            best: Checkpoint { roots, done, best },
+            timeout,
+        }
+    }
+
+    pub fn spec(&self) -> &BoardSpec {
+        &self.spec
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// shards that are done
+    pub fn finished(&self) -> usize {
+        self.shards
+            .iter()
+            .filter(|shard| matches!(shard.state, ShardState::Done { .. }))
+            .count()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// the best solution reported so far
+    pub fn best(&self) -> &Solution {
+        &self.best.best
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| matches!(shard.state, ShardState::Done { .. }))
+    }
+
+    /// Takes back the leases without a heartbeat since the timeout and
+    /// returns their shards and workers
+    pub fn expire(&mut self, now: Instant) -> Vec<(usize, String)> {
+        let mut expired = Vec::new();
+        for (index, shard) in self.shards.iter_mut().enumerate() {
+            if let ShardState::Leased { worker, heartbeat } = &shard.state {
+                if now.duration_since(*heartbeat) > self.timeout {
+                    expired.push((index, worker.clone()));
+                    shard.state = ShardState::Pending;
+                }
+            }
+        }
+        expired
+    }
+
+    /// Leases the first pending shard to `worker`
+    pub fn lease(&mut self, worker: &str, now: Instant) -> Lease {
+        let Some((index, shard)) = self
+            .shards
+            .iter_mut()
+            .enumerate()
+            .find(|(_, shard)| shard.state == ShardState::Pending)
+        else {
+            return if self.is_done() {
+                Lease::Done
+            } else {
+                Lease::Wait
+            };
+        };
+        shard.state = ShardState::Leased {
+            worker: worker.to_owned(),
+            heartbeat: now,
+        };
+        Lease::Shard(index, shard.roots.clone())
+    }
+
+    /// Extends the lease of `worker` on `shard`. False if it doesn't hold it
+    /// anymore, because it timed out or the shard is done.
+    pub fn heartbeat(&mut self, worker: &str, shard: usize, now: Instant) -> Result<bool, String> {
+        let shard = self.shard(shard)?;
+        match &mut shard.state {
+            ShardState::Leased {
+                worker: holder,
+                heartbeat,
+            } if holder == worker => {
+                *heartbeat = now;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Records the best `solution` of the `roots` of `shard`, also from a
+    /// worker whose lease timed out. Returns whether the shard wasn't done yet.
+    /// The roots tell apart the shards of coordinators before and after a
+    /// restart.
+    pub fn finish(
+        &mut self,
+        worker: &str,
+        shard: usize,
+        roots: &[usize],
+        solution: &Solution,
+    ) -> Result<bool, String> {
+        if solution.spec != self.spec {
+            return Err(format!(
+                "solution of {} for a search of {}",
+                solution.spec, self.spec
+            ));
+        }
+        solution.verify()?;
+        let index = shard;
+        let shard = self.shard(index)?;
+        if shard.roots != roots {
+            return Err(format!(
+                "shard {index} has other roots, the coordinator was restarted"
+            ));
+        }
+        let new = !matches!(shard.state, ShardState::Done { .. });
+        if new {
+            shard.state = ShardState::Done {
+                worker: worker.to_owned(),
+            };
+            for &root in &self.shards[index].roots {
+                self.best.done[root] = true;
+            }
+        }
+        self.best.merge_solution(solution)?;
+        Ok(new)
+    }
+
+    fn shard(&mut self, shard: usize) -> Result<&mut Shard, String> {
+        self.shards
+            .get_mut(shard)
+            .ok_or_else(|| format!("unknown shard {shard}"))
+    }
+
+    /// The answer to a request of a worker
+    pub fn handle(&mut self, request: &Json, now: Instant) -> Json {
+        let error = |err: String| Json::Object(vec![("error".to_owned(), err.into())]);
+        let answer = |key: &str, value: Json| Json::Object(vec![(key.to_owned(), value)]);
+        let Some(Json::String(worker)) = request.get("worker") else {
+            return error("request without a worker".to_owned());
+        };
+        if worker.is_empty() || worker.contains(char::is_control) {
+            return error(format!("invalid worker name {worker:?}"));
+        }
+        let shard = match request.get("shard") {
+            Some(&Json::Number(shard)) => Ok(shard as usize),
+            _ => Err("request without a shard".to_owned()),
+        };
+
+        let result = match request.get("request") {
+            Some(Json::String(kind)) if kind == "lease" => Ok(match self.lease(worker, now) {
+                Lease::Shard(index, roots) => Json::Object(vec![
+                    ("shard".to_owned(), index.into()),
+                    ("spec".to_owned(), self.spec.to_string().into()),
+                    ("roots".to_owned(), self.roots.into()),
+                    ("shard_roots".to_owned(), roots.into()),
+                    (
+                        "heartbeat_ms".to_owned(),
+                        (self.timeout.as_millis() as u64 / 3).into(),
+                    ),
+                    ("best".to_owned(), self.best().to_string().into()),
+                ]),
+                Lease::Wait => answer("wait_ms", (self.timeout.as_millis() as u64 / 3).into()),
+                Lease::Done => answer("done", true.into()),
+            }),
+            Some(Json::String(kind)) if kind == "heartbeat" => shard
+                .and_then(|shard| self.heartbeat(worker, shard, now))
+                .map(|leased| answer("leased", leased.into())),
+            Some(Json::String(kind)) if kind == "finish" => {
+                let solution = match string_field(request, "solution") {
+                    Some(solution) => solution.parse::<Solution>(),
+                    None => Err("finish without a solution".to_owned()),
+                };
+                let roots = match request.get("shard_roots") {
+                    Some(Json::Array(roots)) => roots
+                        .iter()
+                        .map(|root| match root {
+                            &Json::Number(root) => Ok(root as usize),
+                            root => Err(format!("invalid root {root}")),
+                        })
+                        .collect::<Result<Vec<_>, _>>(),
+                    _ => Err("finish without roots".to_owned()),
+                };
+                shard
+                    .and_then(|shard| Ok((shard, roots?, solution?)))
+                    .and_then(|(shard, roots, solution)| {
+                        self.finish(worker, shard, &roots, &solution)
+                    })
+                    .map(|new| answer("new", new.into()))
+            }
+            _ => Err("unknown request, expected lease, heartbeat or finish".to_owned()),
+        };
+        result.unwrap_or_else(error)
+    }
+}
+
+impl Display for Coordinator {
+    /// the state file, see the [module docs](self)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "roots {}", self.roots)?;
+        for shard in &self.shards {
+            if let ShardState::Done { worker } = &shard.state {
+                let mut roots = vec![false; self.roots];
+                for &root in &shard.roots {
+                    roots[root] = true;
+                }
+                writeln!(f, "shard {} by {worker}", format_roots(&roots))?;
+            }
+        }
+        write!(f, "{}", self.best())
+    }
+}
+
+/// the string `key` of an object
+fn string_field<'a>(request: &'a Json, key: &str) -> Option<&'a str> {
+    match request.get(key) {
+        Some(Json::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Answers the requests of workers on `listener` until every shard of
+/// `coordinator` is done, then returns the best solution. The listener keeps
+/// answering afterwards, until the process ends. The state is written to
+/// `state` whenever a shard is done.
+pub fn coordinate(
+    listener: TcpListener,
+    coordinator: Coordinator,
+    state: Option<PathBuf>,
+) -> io::Result<Solution> {
+    let coordinator = Arc::new(Mutex::new(coordinator));
+    let (finished, done) = mpsc::channel();
+    {
+        let coordinator = coordinator.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        _ = finished.send(Err(err));
+                        return;
+                    }
+                };
+                let coordinator = coordinator.clone();
+                let finished = finished.clone();
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = answer(&coordinator, stream, state.as_deref()) {
+                        eprintln!("worker connection failed: {err}");
+                    }
+                    if coordinator.lock().unwrap().is_done() {
+                        _ = finished.send(Ok(()));
+                    }
+                });
+            }
+        });
+    }
+
+    let mut coordinator_done = coordinator.lock().unwrap().is_done();
+    while !coordinator_done {
+        let timeout = coordinator.lock().unwrap().timeout();
+        match done.recv_timeout(timeout) {
+            Ok(result) => result?,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => unreachable!("the listener never stops"),
+        }
+        let mut coordinator = coordinator.lock().unwrap();
+        for (shard, worker) in coordinator.expire(Instant::now()) {
+            eprintln!("{worker} missed its heartbeats, shard {shard} is leased again");
+        }
+        coordinator_done = coordinator.is_done();
+    }
+    let (best, timeout) = {
+        let coordinator = coordinator.lock().unwrap();
+        (coordinator.best().clone(), coordinator.timeout())
+    };
+    // waiting workers ask again within a third of the timeout and learn that they are done
+    std::thread::sleep(timeout / 2);
+    Ok(best)
+}
+
+/// answers a request line of a worker
+fn answer(
+    coordinator: &Mutex<Coordinator>,
+    stream: TcpStream,
+    state: Option<&Path>,
+) -> io::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response = match line.parse::<Json>() {
+        Ok(request) => {
+            let mut coordinator = coordinator.lock().unwrap();
+            let now = Instant::now();
+            for (shard, worker) in coordinator.expire(now) {
+                eprintln!("{worker} missed its heartbeats, shard {shard} is leased again");
+            }
+            let response = coordinator.handle(&request, now);
+            if let (Some(Json::Bool(true)), Some(&Json::Number(shard))) =
+                (response.get("new"), request.get("shard"))
+            {
+                eprintln!(
+                    "shard {shard} done ({} of {}), best diameter so far {}",
+                    coordinator.finished(),
+                    coordinator.shards(),
+                    coordinator.best().diameter
+                );
+                if let Some(path) = state {
+                    if let Err(err) = write_atomic(path, coordinator.to_string().as_bytes()) {
+                        eprintln!("failed to write {}: {err}", path.display());
+                    }
+                }
+            }
+            response
+        }
+        Err(err) => Json::Object(vec![("error".to_owned(), err.into())]),
+    };
+    writeln!(&stream, "{response}")
+}
+
+/// sends `request` to the coordinator at `address` and returns its answer
+fn request(address: impl ToSocketAddrs, request: &Json) -> Result<Json, String> {
+    let stream = TcpStream::connect(address)
+        .map_err(|err| format!("failed to reach the coordinator: {err}"))?;
+    writeln!(&stream, "{request}").map_err(|err| format!("failed to send a request: {err}"))?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|err| format!("failed to read an answer: {err}"))?;
+    let answer = line.parse::<Json>()?;
+    match answer.get("error") {
+        Some(Json::String(err)) => Err(format!("coordinator: {err}")),
+        _ => Ok(answer),
+    }
+}
+
+/// Searches the shards the coordinator at `address` leases to `worker` with
+/// `config` until every shard is done. Returns the number of shards searched.
+pub fn work(
+    address: impl ToSocketAddrs + Copy + Send + 'static,
+    worker: &str,
+    config: &SearchConfig,
+) -> Result<usize, String> {
+    let message = |request: &str, fields: Vec<(&str, Json)>| {
+        let mut object = vec![
+            ("request".to_owned(), request.into()),
+            ("worker".to_owned(), worker.into()),
+        ];
+        object.extend(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+        Json::Object(object)
+    };
+    let number = |answer: &Json, key: &str| match answer.get(key) {
+        Some(&Json::Number(n)) => Ok(n as u64),
+        _ => Err(format!("answer without {key}")),
+    };
+
+    let mut searched = 0;
+    loop {
+        let lease = request(address, &message("lease", Vec::new()))?;
+        if lease.get("done").is_some() {
+            return Ok(searched);
+        }
+        if let Ok(wait) = number(&lease, "wait_ms") {
+            std::thread::sleep(Duration::from_millis(wait));
+            continue;
+        }
+
+        let shard = number(&lease, "shard")? as usize;
+        let spec = string_field(&lease, "spec")
+            .ok_or("lease without a spec")?
+            .parse::<BoardSpec>()?;
+        let best = string_field(&lease, "best")
+            .ok_or("lease without a best solution")?
+            .parse::<Solution>()?;
+        let roots = spec.placements().len();
+        if number(&lease, "roots")? != roots as u64 {
+            return Err(format!(
+                "{spec} has {roots} roots here, but {} at the coordinator",
+                number(&lease, "roots")?
+            ));
+        }
+        // every root outside of the shard counts as done
+        let mut done = vec![true; roots];
+        let Some(shard_roots) = lease.get("shard_roots") else {
+            return Err("lease without roots".to_owned());
+        };
+        let Json::Array(shard_roots) = shard_roots.clone() else {
+            return Err("lease without roots".to_owned());
+        };
+        for root in &shard_roots {
+            match root {
+                &Json::Number(root) if (root as usize) < roots => done[root as usize] = false,
+                root => return Err(format!("invalid root {root}")),
+            }
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (searching, stopped) = mpsc::channel::<()>();
+        let heartbeats = {
+            let interval = Duration::from_millis(number(&lease, "heartbeat_ms")?);
+            let heartbeat = message("heartbeat", vec![("shard", shard.into())]);
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                // until the search drops its end of the channel
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    match request(address, &heartbeat) {
+                        Ok(answer) if answer.get("leased") == Some(&Json::Bool(false)) => {
+                            cancel.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                        Ok(_) => {}
+                        // the coordinator may be back before the lease times out
+                        Err(err) => eprintln!("heartbeat for shard {shard} failed: {err}"),
+                    }
+                }
+            })
+        };
+
+        let config = SearchConfig {
+            resume: Some(Checkpoint { roots, done, best }),
+            cancel: Some(cancel.clone()),
+            ..config.clone()
+        };
+        let result = find_best(&spec, &config);
+        drop(searching);
+        _ = heartbeats.join();
+        if cancel.load(Ordering::Relaxed) {
+            eprintln!("shard {shard} went to another worker");
+            continue;
+        }
+        if !result.complete {
+            return Err(format!("the search of shard {shard} was stopped"));
+        }
+
+        let solution = Solution {
+            spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        let finish = message(
+            "finish",
+            vec![
+                ("shard", shard.into()),
+                ("shard_roots", Json::Array(shard_roots)),
+                ("solution", solution.to_string().into()),
+            ],
+        );
+        match request(address, &finish) {
+            Ok(_) => searched += 1,
+            // the coordinator restarted with other shards
+            Err(err) => eprintln!("result of shard {shard} was rejected: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use super::{coordinate, work, Coordinator, Lease};
+    use crate::{
+        brute_force::{find_best, SearchConfig},
+        spec::BoardSpec,
+    };
+
+    #[test]
+    fn lost_workers_lose_their_shards() {
+        let spec = "5x4".parse::<BoardSpec>().unwrap();
+        let timeout = Duration::from_secs(10);
+        let mut coordinator = Coordinator::new(&spec, 1000, timeout);
+        assert_eq!(coordinator.shards(), 1);
+        let start = Instant::now();
+
+        let Lease::Shard(shard, roots) = coordinator.lease("a", start) else {
+            panic!("the shard is pending");
+        };
+        assert_eq!(coordinator.lease("b", start), Lease::Wait);
+        assert_eq!(coordinator.heartbeat("a", shard, start + timeout), Ok(true));
+        assert!(coordinator.expire(start + timeout).is_empty());
+
+        // a hangs, so b gets its shard
+        let late = start + 3 * timeout;
+        assert_eq!(coordinator.expire(late), [(shard, "a".to_owned())]);
+        assert_eq!(
+            coordinator.lease("b", late),
+            Lease::Shard(shard, roots.clone())
+        );
+        assert_eq!(coordinator.heartbeat("a", shard, late), Ok(false));
+
+        // a finishes anyway, b's result for the same shard changes nothing
+        let solution = {
+            let result = find_best(&spec, &SearchConfig::default());
+            crate::solution::Solution {
+                spec: coordinator.spec().clone(),
+                diameter: result.diameter,
+                pieces: result.placed,
+                proved_optimal: false,
+            }
+        };
+        assert_eq!(coordinator.finish("a", shard, &roots, &solution), Ok(true));
+        assert!(coordinator.is_done());
+        assert_eq!(coordinator.finish("b", shard, &roots, &solution), Ok(false));
+        assert_eq!(coordinator.heartbeat("b", shard, late), Ok(false));
+        assert_eq!(coordinator.lease("b", late), Lease::Done);
+        assert_eq!(coordinator.best().diameter, solution.diameter);
+
+        let mut wrong = solution;
+        wrong.diameter += 1;
+        assert!(coordinator.finish("b", shard, &roots, &wrong).is_err());
+    }
+
+    #[test]
+    fn resumed_runs_skip_finished_roots() {
+        let spec = "4x5".parse::<BoardSpec>().unwrap();
+        let timeout = Duration::from_secs(10);
+        let mut coordinator = Coordinator::new(&spec, 7, timeout);
+        let shards = coordinator.shards();
+        let result = find_best(coordinator.spec(), &SearchConfig::default());
+        let solution = crate::solution::Solution {
+            spec: coordinator.spec().clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        let mut finished = Vec::new();
+        for worker in ["a", "b"] {
+            let Lease::Shard(shard, roots) = coordinator.lease(worker, Instant::now()) else {
+                panic!("shards are pending");
+            };
+            coordinator
+                .finish(worker, shard, &roots, &solution)
+                .unwrap();
+            finished.extend(roots);
+        }
+        // a worker of the last run can't finish a shard of the next one
+        let Lease::Shard(shard, roots) = coordinator.lease("c", Instant::now()) else {
+            panic!("shards are pending");
+        };
+
+        let state = coordinator.to_string();
+        assert!(state.contains(" by a\n") && state.contains(" by b\n"));
+        let mut resumed = Coordinator::resume(&spec, 3, timeout, &state).unwrap();
+        assert_eq!(resumed.to_string(), state);
+        assert_eq!(resumed.finished(), 2);
+        assert!(resumed.shards() > shards);
+        assert_eq!(resumed.best().diameter, solution.diameter);
+        assert!(resumed.finish("c", shard, &roots, &solution).is_err());
+        while let Lease::Shard(shard, roots) = resumed.lease("d", Instant::now()) {
+            assert!(roots.len() <= 3);
+            assert!(roots.iter().all(|root| !finished.contains(root)));
+            resumed.finish("d", shard, &roots, &solution).unwrap();
+        }
+        assert!(resumed.is_done());
+
+        let other = "5x5".parse::<BoardSpec>().unwrap();
+        assert!(Coordinator::resume(&other, 3, timeout, &state).is_err());
+    }
+
+    #[test]
+    fn workers_find_the_optimum() {
+        for spec in ["5x4", "6x4 holes=2,1"] {
+            let spec = spec.parse::<BoardSpec>().unwrap();
+            let coordinator = Coordinator::new(&spec, 7, Duration::from_secs(1));
+            assert!(coordinator.shards() > 1);
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            let workers = ["a", "b"].map(|name| {
+                std::thread::spawn(move || work(address, name, &SearchConfig::default()))
+            });
+
+            let best = coordinate(listener, coordinator, None).unwrap();
+            let searched = workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap().unwrap())
+                .sum::<usize>();
+            assert!(searched > 1);
+            best.verify().unwrap();
+            let expected = find_best(&spec.canonical().0, &SearchConfig::default());
+            assert_eq!(best.diameter, expected.diameter);
+        }
+    }
+}
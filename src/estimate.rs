@@ -0,0 +1,137 @@
+//! Estimates the size of a search before running it, with Knuth's random
+//! probing: a probe walks down a random path of the search tree and the
+//! product of the branching factors along it is an unbiased estimate of the
+//! number of nodes at every depth.
+
+use crate::{brute_force::StateKey, pathfinding::BfsScratch, rng::Rng, spec::BoardSpec};
+
+/// random probes of [`priority`], enough to tell quick boards from slow ones
+pub const PRIORITY_PROBES: u32 = 200;
+
+pub struct Estimate {
+    /// legal placements of the first piece
+    pub roots: usize,
+    pub probes: u32,
+    /// mean number of legal placements at every depth, over the probes that
+    /// reached it
+    pub branching: Vec<f64>,
+    /// estimated number of nodes of the search tree without merging states
+    /// that are reached on different paths
+    pub nodes: f64,
+}
+
+impl Estimate {
+    pub fn new(spec: &BoardSpec, probes: u32, rng: &mut Rng) -> Self {
+        let map = spec.initial_map();
+        let all = spec.placements();
+        let mut scratch = BfsScratch::new(spec.shape);
+        let (initial_diameter, _) = scratch.objective(&map, &spec.ends);
+
+        let mut branching_sum = Vec::<f64>::new();
+        let mut branching_probes = Vec::<u32>::new();
+        let mut fits = Vec::new();
+        let mut nodes = 0.;
+
+        for _ in 0..probes {
+            let mut map = map.clone();
+            let mut available = spec.candidates();
+            let mut diameter = initial_diameter;
+            let mut weight = 1.;
+            let mut total = 0.;
+
+            for depth in 0.. {
+                map.can_place_batch(&all, &mut fits);
+                let children = all
+                    .iter()
+                    .zip(&fits)
+                    .filter(|&(piece, &fits)| {
+                        fits && available.get(piece.pentonimo().kind() as u8) > 0
+                    })
+                    .map(|(piece, _)| piece)
+                    .collect::<Vec<_>>();
+                if children.is_empty() {
+                    break;
+                }
+
+                if branching_sum.len() <= depth {
+                    branching_sum.push(0.);
+                    branching_probes.push(0);
+                }
+                branching_sum[depth] += children.len() as f64;
+                branching_probes[depth] += 1;
+
+                weight *= children.len() as f64;
+                total += weight;
+
+                let piece = *children[rng.below(children.len() as u64) as usize];
+                map |= piece;
+                available.decrement(piece.pentonimo().kind() as u8);
+
+                let (next, _) = scratch.objective(&map, &spec.ends);
+                if next < diameter {
+                    // the search discards this branch without expanding it
+                    break;
+                }
+                diameter = next;
+            }
+
+            nodes += total;
+        }
+
+        Self {
+            roots: all.len(),
+            probes,
+            branching: branching_sum
+                .iter()
+                .zip(&branching_probes)
+                .map(|(&sum, &probes)| sum / probes as f64)
+                .collect(),
+            nodes: nodes / probes.max(1) as f64,
+        }
+    }
+
+    /// Memory needed to store every node as a visited state, which is an upper
+    /// bound as states reached on different paths are only stored once.
+    pub fn memory_bound(&self, spec: &BoardSpec, exact_states: bool) -> f64 {
+        if !exact_states {
+            // one control byte per bucket and buckets at most half empty
+            return self.nodes * (2 * (std::mem::size_of::<(u128, u32)>() + 1)) as f64;
+        }
+        let map = spec.initial_map();
+        let heap = if map.tiles().spilled() {
+            map.tiles().len() * std::mem::size_of_val(&map.tiles()[0])
+        } else {
+            0
+        };
+        let entry = 2 * (std::mem::size_of::<(StateKey, u32)>() + 1) + heap;
+        self.nodes * entry as f64
+    }
+}
+
+/// Estimated nodes of the search of `spec`, to run the quick ones of a queue
+/// first. The probes are seeded, so equal boards get equal priorities.
+pub fn priority(spec: &BoardSpec) -> f64 {
+    Estimate::new(&spec.canonical().0, PRIORITY_PROBES, &mut Rng::new(0)).nodes
+}
+
+#[test]
+fn exact_for_a_single_level() {
+    // only one I fits on a 5x1 board
+    let mut spec = BoardSpec::new((5, 1));
+    spec.pieces = [0; crate::pentonimo::KINDS];
+    spec.pieces[crate::pentonimo::PentonimoKind::I as usize] = 1;
+
+    let estimate = Estimate::new(&spec, 10, &mut Rng::new(0));
+    assert_eq!(estimate.roots, 1);
+    assert_eq!(estimate.nodes, 1.);
+}
+
+#[test]
+fn larger_boards_come_later() {
+    let specs = ["6x6", "3x4", "5x4 holes=0,0", "5x5"].map(|spec| spec.parse().unwrap());
+    let priorities = specs.each_ref().map(priority);
+    assert!(priorities[1] < priorities[2]);
+    assert!(priorities[2] < priorities[3]);
+    assert!(priorities[3] < priorities[0]);
+    assert_eq!(priority(&specs[2].transposed()), priorities[2]);
+}
@@ -0,0 +1,283 @@
+//! Knuth's Algorithm X with dancing links.
+//!
+//! Primary columns have to be covered exactly once, secondary columns at most
+//! once. Rows can additionally be put into groups of which at most a given
+//! number of rows may be chosen.
+
+const ROOT: usize = 0;
+
+pub struct ExactCover {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// header node of the column of every node
+    column: Vec<usize>,
+    /// row of every node, unused for headers
+    row: Vec<usize>,
+    /// number of nodes in every column, indexed by header node
+    size: Vec<usize>,
+    rows: usize,
+    /// the group of every row
+    row_group: Vec<Option<usize>>,
+    /// how many more rows of every group may be chosen
+    remaining: Vec<usize>,
+}
+
+impl ExactCover {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let headers = 1 + primary + secondary;
+        let mut cover = Self {
+            left: Vec::with_capacity(headers),
+            right: Vec::with_capacity(headers),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+            rows: 0,
+            row_group: Vec::new(),
+            remaining: Vec::new(),
+        };
+
+        for header in 0..headers {
+            if header <= primary {
+                // root and primary columns form a circular list
+                cover
+                    .left
+                    .push(if header == ROOT { primary } else { header - 1 });
+                cover
+                    .right
+                    .push(if header == primary { ROOT } else { header + 1 });
+            } else {
+                // secondary columns are never chosen, so they link to themselves
+                cover.left.push(header);
+                cover.right.push(header);
+            }
+        }
+
+        cover
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Adds a group of which at most `capacity` rows may be chosen
+    pub fn add_group(&mut self, capacity: usize) -> usize {
+        self.remaining.push(capacity);
+        self.remaining.len() - 1
+    }
+
+    /// Adds a row covering `columns` and returns its index
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        self.add_row_in_group(columns, None)
+    }
+
+    pub fn add_row_in_group(&mut self, columns: &[usize], group: Option<usize>) -> usize {
+        assert!(
+            !columns.is_empty(),
+            "rows have to cover at least one column"
+        );
+        let row = self.rows;
+        self.rows += 1;
+        self.row_group.push(group);
+
+        let first = self.left.len();
+        for (i, &column) in columns.iter().enumerate() {
+            let header = column + 1;
+            assert!(header < self.size.len(), "column {column} out of range");
+            let node = first + i;
+
+            self.left.push(if i == 0 {
+                first + columns.len() - 1
+            } else {
+                node - 1
+            });
+            self.right.push(if i == columns.len() - 1 {
+                first
+            } else {
+                node + 1
+            });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+
+        row
+    }
+
+    /// Calls `visit` with the rows of every solution until it returns false
+    pub fn solve(&mut self, mut visit: impl FnMut(&[usize]) -> bool) {
+        let mut solution = Vec::new();
+        self.search(&mut solution, &mut visit);
+    }
+
+    pub fn count(&mut self) -> u64 {
+        let mut count = 0;
+        self.solve(|_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    pub fn first(&mut self) -> Option<Vec<usize>> {
+        let mut first = None;
+        self.solve(|solution| {
+            first = Some(solution.to_vec());
+            false
+        });
+        first
+    }
+
+    /// returns false if `visit` asked to stop
+    fn search(
+        &mut self,
+        solution: &mut Vec<usize>,
+        visit: &mut impl FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if self.right[ROOT] == ROOT {
+            return visit(solution);
+        }
+
+        // the column with the fewest candidates
+        let mut column = self.right[ROOT];
+        let mut header = self.right[column];
+        while header != ROOT {
+            if self.size[header] < self.size[column] {
+                column = header;
+            }
+            header = self.right[header];
+        }
+        if self.size[column] == 0 {
+            return true;
+        }
+
+        self.cover(column);
+        let mut keep_going = true;
+
+        let mut node = self.down[column];
+        while node != column && keep_going {
+            let group = self.row_group[self.row[node]];
+            if let Some(group) = group {
+                if self.remaining[group] == 0 {
+                    node = self.down[node];
+                    continue;
+                }
+                self.remaining[group] -= 1;
+            }
+
+            solution.push(self.row[node]);
+            let mut other = self.right[node];
+            while other != node {
+                self.cover(self.column[other]);
+                other = self.right[other];
+            }
+
+            keep_going = self.search(solution, visit);
+
+            let mut other = self.left[node];
+            while other != node {
+                self.uncover(self.column[other]);
+                other = self.left[other];
+            }
+            solution.pop();
+            if let Some(group) = group {
+                self.remaining[group] += 1;
+            }
+            node = self.down[node];
+        }
+
+        self.uncover(column);
+        keep_going
+    }
+
+    fn cover(&mut self, column: usize) {
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut node = self.down[column];
+        while node != column {
+            let mut other = self.right[node];
+            while other != node {
+                let (up, down) = (self.up[other], self.down[other]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[other]] -= 1;
+                other = self.right[other];
+            }
+            node = self.down[node];
+        }
+    }
+
+    fn uncover(&mut self, column: usize) {
+        let mut node = self.up[column];
+        while node != column {
+            let mut other = self.left[node];
+            while other != node {
+                self.size[self.column[other]] += 1;
+                let (up, down) = (self.up[other], self.down[other]);
+                self.down[up] = other;
+                self.up[down] = other;
+                other = self.left[other];
+            }
+            node = self.up[node];
+        }
+
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = column;
+        self.left[right] = column;
+    }
+}
+
+#[test]
+fn knuth_example() {
+    // the example from "Dancing Links"
+    let mut cover = ExactCover::new(7, 0);
+    for row in [
+        &[2, 4, 5][..],
+        &[0, 3, 6],
+        &[1, 2, 5],
+        &[0, 3],
+        &[1, 6],
+        &[3, 4, 6],
+    ] {
+        cover.add_row(row);
+    }
+
+    let mut solution = cover.first().unwrap();
+    solution.sort_unstable();
+    assert_eq!(solution, [0, 3, 4]);
+    assert_eq!(cover.count(), 1);
+}
+
+#[test]
+fn secondary_columns() {
+    let mut cover = ExactCover::new(2, 1);
+    cover.add_row(&[0, 2]);
+    cover.add_row(&[1, 2]);
+    cover.add_row(&[0]);
+    cover.add_row(&[1]);
+    // column 2 may be covered at most once
+    assert_eq!(cover.count(), 3);
+}
+
+#[test]
+fn groups() {
+    let mut cover = ExactCover::new(3, 0);
+    let group = cover.add_group(1);
+    cover.add_row_in_group(&[0], Some(group));
+    cover.add_row_in_group(&[1], Some(group));
+    cover.add_row(&[2]);
+    cover.add_row(&[0, 1]);
+    // [0] and [1] can't be chosen together
+    assert_eq!(cover.count(), 1);
+}
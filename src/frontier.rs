@@ -0,0 +1,251 @@
+//! The threads [`BfsScratch::with_threads`] expands large frontiers on.
+//!
+//! Every visited cell has a tag with the layer it was found in and its position
+//! in the frontier of that layer. A thread expanding a part of the frontier
+//! only keeps a neighbour if no cell before its own in the frontier is next to
+//! it, too, so every cell is found once, from the cell a sequential search
+//! finds it from. Put together in thread order, the parts the threads found
+//! are the next frontier of the sequential search.
+//!
+//! [`BfsScratch::with_threads`]: crate::pathfinding::BfsScratch::with_threads
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, RwLock,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    pathfinding::{neighbours, Point},
+    tile_map::TileMap,
+};
+
+/// the tag of cells that were visited before the pool took over a search
+const OLD: u64 = 1 << 32;
+
+/// Rounds of waiting for the next layer before a thread sleeps. Layers of one
+/// search follow each other within microseconds, so only threads between
+/// searches sleep.
+const SPINS: u32 = 1 << 14;
+
+/// Threads that stay around for all searches of a [`BfsScratch`], so each
+/// layer only costs a wake up instead of starting threads.
+///
+/// [`BfsScratch`]: crate::pathfinding::BfsScratch
+pub struct FrontierPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    /// the layer of the frontier, as in the tags
+    layer: u64,
+}
+
+struct Shared {
+    /// 0 for cells that weren't visited, otherwise the layer they were found
+    /// in << 32 | their position in its frontier
+    tags: Vec<AtomicU64>,
+    width: usize,
+    map: RwLock<TileMap>,
+    frontier: RwLock<Vec<Point>>,
+    /// the cells of the next frontier every thread found, the calling thread first
+    found: Vec<Mutex<Vec<Point>>>,
+    /// threads with a part of the frontier in this layer
+    parts: AtomicUsize,
+    /// counts the layers, threads wait for it to change
+    epoch: AtomicU64,
+    /// workers done with the current layer
+    finished: AtomicUsize,
+    sleepers: AtomicUsize,
+    sleep: Mutex<()>,
+    wake: Condvar,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn tag(&self, p: Point) -> &AtomicU64 {
+        &self.tags[p.0 as usize + p.1 as usize * self.width]
+    }
+
+    /// expands part `part` of `parts` of the frontier of `layer` into `found[part]`
+    fn expand(&self, part: usize, parts: usize, layer: u64) {
+        let frontier = self.frontier.read().unwrap();
+        let map = self.map.read().unwrap();
+        let mut found = self.found[part].lock().unwrap();
+        found.clear();
+
+        let len = frontier.len();
+        let range = part * len / parts..(part + 1) * len / parts;
+        for (position, &cell) in frontier[range.clone()].iter().enumerate() {
+            let position = (range.start + position) as u64;
+            for next in neighbours(&map, cell) {
+                if self.tag(next).load(Ordering::Relaxed) != 0 {
+                    continue;
+                }
+                // the first cell of the frontier next to `next` finds it
+                let first = neighbours(&map, next)
+                    .map(|p| self.tag(p).load(Ordering::Relaxed))
+                    .filter(|tag| tag >> 32 == layer)
+                    .min();
+                if first == Some(layer << 32 | position) {
+                    found.push(next);
+                }
+            }
+        }
+    }
+
+    fn work(&self, part: usize) {
+        let mut seen = 0;
+        loop {
+            seen = self.wait(seen);
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            let parts = self.parts.load(Ordering::Relaxed);
+            if part < parts {
+                self.expand(part, parts, seen >> 32);
+            }
+            self.finished.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// waits until the epoch isn't `seen` anymore and returns it
+    fn wait(&self, seen: u64) -> u64 {
+        for round in 0..SPINS {
+            let epoch = self.epoch.load(Ordering::Acquire);
+            if epoch != seen {
+                return epoch;
+            }
+            pause(round);
+        }
+
+        let mut guard = self.sleep.lock().unwrap();
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        let epoch = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            if epoch != seen {
+                break epoch;
+            }
+            guard = self.wake.wait(guard).unwrap();
+        };
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+        epoch
+    }
+
+    /// starts the next round of the workers, whose epoch has `layer` in its
+    /// upper half so they know which layer to expand
+    fn start_round(&self, layer: u64) {
+        self.finished.store(0, Ordering::Relaxed);
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        let count = (epoch as u32).wrapping_add(1) as u64;
+        self.epoch.store(layer << 32 | count, Ordering::SeqCst);
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            let _guard = self.sleep.lock().unwrap();
+            self.wake.notify_all();
+        }
+    }
+}
+
+/// spins, but lets other threads run now and then in case there are more
+/// threads than cores
+fn pause(round: u32) {
+    if round % 64 == 63 {
+        std::thread::yield_now();
+    } else {
+        std::hint::spin_loop();
+    }
+}
+
+impl FrontierPool {
+    /// `threads` threads including the calling one for boards of `shape`
+    pub fn new(shape: (u32, u32), threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            tags: (0..shape.0 as usize * shape.1 as usize)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            width: shape.0 as usize,
+            map: RwLock::new(TileMap::new(shape)),
+            frontier: RwLock::new(Vec::new()),
+            found: (0..threads).map(|_| Mutex::new(Vec::new())).collect(),
+            parts: AtomicUsize::new(0),
+            epoch: AtomicU64::new(0),
+            finished: AtomicUsize::new(0),
+            sleepers: AtomicUsize::new(0),
+            sleep: Mutex::new(()),
+            wake: Condvar::new(),
+            stop: AtomicBool::new(false),
+        });
+        let workers = (1..threads)
+            .map(|part| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.work(part))
+            })
+            .collect();
+        Self {
+            shared,
+            workers,
+            layer: 0,
+        }
+    }
+
+    /// Takes over a search on `map` with the `visited` cells by row major index
+    /// and `frontier`
+    pub fn take_over(&mut self, map: &TileMap, visited: &[bool], frontier: &[Point]) {
+        let shared = &*self.shared;
+        for (tag, &visited) in shared.tags.iter().zip(visited) {
+            tag.store(if visited { OLD } else { 0 }, Ordering::Relaxed);
+        }
+        self.layer = 2;
+        for (position, &cell) in frontier.iter().enumerate() {
+            shared
+                .tag(cell)
+                .store(self.layer << 32 | position as u64, Ordering::Relaxed);
+        }
+        shared.map.write().unwrap().clone_from(map);
+    }
+
+    /// Replaces `frontier` with the next one in `next`, expanding it on up to
+    /// `parts` threads
+    pub fn grow(&mut self, frontier: &mut Vec<Point>, next: &mut Vec<Point>, parts: usize) {
+        let shared = &*self.shared;
+        let parts = parts.clamp(1, shared.found.len());
+        std::mem::swap(&mut *shared.frontier.write().unwrap(), frontier);
+
+        if parts > 1 {
+            shared.parts.store(parts, Ordering::Relaxed);
+            shared.start_round(self.layer);
+        }
+        shared.expand(0, parts, self.layer);
+        if parts > 1 {
+            for round in 0u32.. {
+                if shared.finished.load(Ordering::Acquire) == self.workers.len() {
+                    break;
+                }
+                pause(round);
+            }
+        }
+
+        std::mem::swap(&mut *shared.frontier.write().unwrap(), frontier);
+        self.layer += 1;
+        next.clear();
+        for found in &shared.found[..parts] {
+            for &cell in found.lock().unwrap().iter() {
+                shared
+                    .tag(cell)
+                    .store(self.layer << 32 | next.len() as u64, Ordering::Relaxed);
+                next.push(cell);
+            }
+        }
+        std::mem::swap(frontier, next);
+    }
+}
+
+impl Drop for FrontierPool {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.shared.start_round(0);
+        for worker in self.workers.drain(..) {
+            _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Golomb's pentomino game: two players take turns placing pieces from a shared
+//! set on a board. The first player who can't place a piece loses.
+
+use crate::{
+    candidates::Candidates, pentonimo::PositionedPentonimo, rng::Rng, spec::BoardSpec,
+    tile_map::TileMap,
+};
+
+#[derive(Clone)]
+pub struct Game {
+    pub spec: BoardSpec,
+    map: TileMap,
+    available: Candidates,
+    /// every placement that is still legal
+    moves: Vec<PositionedPentonimo>,
+    pub history: Vec<PositionedPentonimo>,
+}
+
+impl Game {
+    pub fn new(spec: &BoardSpec) -> Self {
+        let map = spec.initial_map();
+        let available = spec.candidates();
+        Self {
+            spec: spec.clone(),
+            moves: spec.placements(),
+            map,
+            available,
+            history: Vec::new(),
+        }
+    }
+
+    /// the classic game on a chess board
+    pub fn classic() -> Self {
+        Self::new(&BoardSpec::new((8, 8)))
+    }
+
+    pub fn moves(&self) -> &[PositionedPentonimo] {
+        &self.moves
+    }
+
+    /// 0 for the player who moved first, 1 for the other one
+    pub fn player(&self) -> usize {
+        self.history.len() % 2
+    }
+
+    /// the player to move has lost
+    pub fn is_over(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn play(&mut self, piece: PositionedPentonimo) -> Result<(), String> {
+        if !self.moves.contains(&piece) {
+            let (x, y) = piece.position();
+            return Err(format!(
+                "{} at {x},{y} is not a legal move",
+                piece.pentonimo().kind()
+            ));
+        }
+        self.play_unchecked(piece);
+        Ok(())
+    }
+
+    fn play_unchecked(&mut self, piece: PositionedPentonimo) {
+        self.map |= piece;
+        self.available.decrement(piece.pentonimo().kind() as u8);
+        let (map, available) = (&self.map, self.available);
+        self.moves
+            .retain(|&m| available.get(m.pentonimo().kind() as u8) > 0 && map.can_place(m));
+        self.history.push(piece);
+    }
+
+    /// Whether the player to move can force a win. Only feasible with few moves left.
+    pub fn is_won(&self) -> bool {
+        self.winning_move().is_some()
+    }
+
+    fn winning_move(&self) -> Option<PositionedPentonimo> {
+        self.moves.iter().copied().find(|&piece| {
+            let mut next = self.clone();
+            next.play_unchecked(piece);
+            !next.is_won()
+        })
+    }
+}
+
+/// Positions with at most this many legal moves are solved exactly
+const ENDGAME_MOVES: usize = 12;
+
+/// Plays by solving endgames exactly and otherwise picking the move that wins
+/// the most random playouts, spending more playouts on the more promising moves.
+pub struct Ai {
+    pub playouts: u32,
+    rng: Rng,
+}
+
+impl Ai {
+    pub fn new(playouts: u32, rng: Rng) -> Self {
+        Self { playouts, rng }
+    }
+
+    pub fn choose(&mut self, game: &Game) -> Option<PositionedPentonimo> {
+        if game.moves.len() <= ENDGAME_MOVES {
+            if let Some(piece) = game.winning_move() {
+                return Some(piece);
+            }
+            // every move loses against perfect play, hope the opponent isn't perfect
+        }
+
+        let moves = game.moves();
+        if moves.len() <= 1 {
+            return moves.first().copied();
+        }
+
+        let mut wins = vec![0u32; moves.len()];
+        let mut visits = vec![0u32; moves.len()];
+        for t in 1..=self.playouts.max(moves.len() as u32) {
+            // UCB1
+            let log = (t as f64).ln();
+            let i = (0..moves.len())
+                .max_by(|&a, &b| {
+                    let score = |i: usize| {
+                        if visits[i] == 0 {
+                            f64::INFINITY
+                        } else {
+                            let n = visits[i] as f64;
+                            wins[i] as f64 / n + (2. * log / n).sqrt()
+                        }
+                    };
+                    score(a).total_cmp(&score(b))
+                })
+                .unwrap();
+
+            let mut playout = game.clone();
+            playout.play_unchecked(moves[i]);
+            visits[i] += 1;
+            if self.random_playout(playout) {
+                wins[i] += 1;
+            }
+        }
+
+        let best = (0..moves.len()).max_by_key(|&i| visits[i]).unwrap();
+        Some(moves[best])
+    }
+
+    /// Plays random moves until the game is over. Returns true if the player who
+    /// is not to move in `game` wins.
+    fn random_playout(&mut self, mut game: Game) -> bool {
+        let mut moves = 0;
+        while !game.is_over() {
+            let i = self.rng.below(game.moves.len() as u64) as usize;
+            game.play_unchecked(game.moves[i]);
+            moves += 1;
+        }
+        moves % 2 == 0
+    }
+}
+
+#[test]
+fn single_move() {
+    // only one I fits, so the first player wins
+    let mut spec = BoardSpec::new((5, 1));
+    spec.pieces = [0; crate::pentonimo::KINDS];
+    spec.pieces[crate::pentonimo::PentonimoKind::I as usize] = 3;
+
+    let mut game = Game::new(&spec);
+    assert!(game.is_won());
+    let piece = Ai::new(10, Rng::new(0)).choose(&game).unwrap();
+    game.play(piece).unwrap();
+    assert!(game.is_over());
+    assert_eq!(game.player(), 1);
+    assert!(game.play(piece).is_err());
+}
+
+#[test]
+fn second_player_takes_the_last_piece() {
+    // two I fit on a 5x5 board in any order, so the second player places the last one
+    let mut spec = BoardSpec::new((5, 5));
+    spec.pieces = [0; crate::pentonimo::KINDS];
+    spec.pieces[crate::pentonimo::PentonimoKind::I as usize] = 2;
+
+    assert!(!Game::new(&spec).is_won());
+}
+
+#[test]
+fn self_play() {
+    let mut game = Game::new(&BoardSpec::new((6, 6)));
+    let mut ai = Ai::new(50, Rng::new(1));
+    while let Some(piece) = ai.choose(&game) {
+        game.play(piece).unwrap();
+    }
+    assert!(game.is_over());
+    assert!(!game.history.is_empty());
+}
@@ -0,0 +1,120 @@
+//! The simplest strategy and a baseline for the others: places the piece that
+//! makes the diameter largest until every piece would make it smaller. Like
+//! the brute-force search it places at least one piece. It is fast enough to
+//! run before every brute-force search, whose pruning starts from its diameter,
+//! and below the children of its states to order them, see
+//! [`SearchConfig::rollout_depth`](crate::brute_force::SearchConfig::rollout_depth).
+
+use std::sync::{atomic::AtomicBool, Arc};
+
+use crate::{
+    backend::Instant,
+    candidates::Candidates,
+    pathfinding::{BfsScratch, Point},
+    pentonimo::PositionedPentonimo,
+    solution::Solution,
+    spec::BoardSpec,
+    strategy::{SearchStats, SearchStrategy},
+    tile_map::TileMap,
+};
+
+/// Greedy construction without randomness, so a board always gets the same
+/// placement
+pub struct Greedy;
+
+impl SearchStrategy for Greedy {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn search_with_stats(&mut self, spec: &BoardSpec) -> (Solution, SearchStats) {
+        let start = Instant::now();
+        let mut scratch = BfsScratch::new(spec.shape);
+        let (steps, nodes) = construct(
+            &mut scratch,
+            spec.initial_map(),
+            spec.candidates(),
+            &spec.placements(),
+            &spec.ends,
+        );
+
+        let mut best = Solution {
+            spec: spec.clone(),
+            diameter: scratch.objective(&spec.initial_map(), &spec.ends).0,
+            pieces: Vec::new(),
+            proved_optimal: false,
+        };
+        for (i, &(diameter, _)) in steps.iter().enumerate() {
+            if diameter > best.diameter || i == 0 {
+                best.diameter = diameter;
+                best.pieces = steps[..=i].iter().map(|&(_, piece)| piece).collect();
+            }
+        }
+        let stats = SearchStats {
+            // a single construction finds its best placement when it is done
+            time_to_best: start.elapsed(),
+            nodes,
+        };
+        (best, stats)
+    }
+
+    // a single construction takes about as long as checking the flag would
+    fn set_cancel(&mut self, _: Arc<AtomicBool>) {}
+}
+
+/// Places the pieces of `available` from `placements` on `map`, always the one
+/// with the largest diameter, as long as that is at least the diameter before.
+/// The first piece is placed in any case. Returns every placed piece with the
+/// diameter after it and the number of evaluated placements.
+pub fn construct(
+    scratch: &mut BfsScratch,
+    mut map: TileMap,
+    mut available: Candidates,
+    placements: &[PositionedPentonimo],
+    ends: &[Point],
+) -> (Vec<(u32, PositionedPentonimo)>, u64) {
+    let mut diameter = scratch.objective(&map, ends).0;
+    let mut steps = Vec::new();
+    let mut nodes = 0;
+    loop {
+        // pieces that keep the diameter are placed too, as they often make
+        // room for a longer detour with the next one
+        let mut chosen = None;
+        for &piece in placements {
+            if available.get(piece.pentonimo().kind() as u8) == 0 || !map.can_place(piece) {
+                continue;
+            }
+            nodes += 1;
+            let (reached, _) = scratch.objective(&(map.clone() | piece), ends);
+            if (reached >= diameter || steps.is_empty())
+                && chosen.is_none_or(|(score, _)| reached > score)
+            {
+                chosen = Some((reached, piece));
+            }
+        }
+        let Some((reached, piece)) = chosen else {
+            return (steps, nodes);
+        };
+
+        map |= piece;
+        available.decrement(piece.pentonimo().kind() as u8);
+        diameter = reached;
+        steps.push((diameter, piece));
+    }
+}
+
+#[test]
+fn constructs_a_valid_lower_bound() {
+    use crate::brute_force::{find_best, SearchConfig};
+
+    // every piece makes the diameter of 3x3 smaller
+    for shape in [(3, 3), (5, 4), (6, 5), (3, 9)] {
+        let spec = BoardSpec::new(shape);
+        let solution = Greedy.search(&spec);
+        solution.verify().unwrap();
+        assert!(!solution.pieces.is_empty());
+        let optimum = find_best(&spec, &SearchConfig::default()).diameter;
+        assert!(solution.diameter <= optimum, "{spec}");
+    }
+    assert_eq!(Greedy.search(&BoardSpec::new((5, 4))).diameter, 13);
+}
@@ -0,0 +1,161 @@
+//! How often every cell of a board is covered by a piece across a set of
+//! solutions, like the optimal placements of `--all-optima`. Cells covered by
+//! every solution are forced by the optimum, cells covered by some of them
+//! show where the solutions differ.
+
+use std::fmt::Write;
+
+use crate::{pentonimo::PositionedPentonimo, spec::BoardSpec};
+
+/// color of the cells that no solution covers
+const EMPTY: [u8; 3] = [255, 255, 255];
+/// color of the cells that every solution covers
+const FULL: [u8; 3] = [29, 153, 243];
+
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    pub spec: BoardSpec,
+    pub solutions: u64,
+    /// how many solutions cover every cell, row major
+    pub covered: Vec<u64>,
+}
+
+impl Heatmap {
+    pub fn new(spec: &BoardSpec, solutions: &[Vec<PositionedPentonimo>]) -> Self {
+        let (w, h) = spec.shape;
+        let mut covered = vec![0; w as usize * h as usize];
+        for piece in solutions.iter().flatten() {
+            for y in 0..h {
+                for x in 0..w {
+                    if piece.get(x, y) {
+                        covered[(y * w + x) as usize] += 1;
+                    }
+                }
+            }
+        }
+        Self {
+            spec: spec.clone(),
+            solutions: solutions.len() as u64,
+            covered,
+        }
+    }
+
+    /// the heatmap of the transposed board
+    pub fn transposed(&self) -> Self {
+        let (w, h) = self.spec.shape;
+        let mut covered = vec![0; self.covered.len()];
+        for y in 0..h {
+            for x in 0..w {
+                covered[(x * h + y) as usize] = self.covered[(y * w + x) as usize];
+            }
+        }
+        Self {
+            spec: self.spec.transposed(),
+            solutions: self.solutions,
+            covered,
+        }
+    }
+
+    /// free cells covered by every solution
+    pub fn forced(&self) -> usize {
+        self.free_cells()
+            .filter(|&i| self.solutions > 0 && self.covered[i] == self.solutions)
+            .count()
+    }
+
+    /// free cells covered by no solution
+    pub fn never(&self) -> usize {
+        self.free_cells().filter(|&i| self.covered[i] == 0).count()
+    }
+
+    fn free_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        let map = self.spec.initial_map();
+        let w = self.spec.shape.0;
+        (0..self.covered.len()).filter(move |&i| !map.get(i as u32 % w, i as u32 / w))
+    }
+
+    /// Cells shaded from white to blue by the share of the solutions covering
+    /// them, with the share in percent. Forced cells have a thick border.
+    pub fn to_svg(&self) -> String {
+        let (w, h) = self.spec.shape;
+        let scale = 100;
+        let holes = self.spec.initial_map();
+
+        let mut svg = String::new();
+        _ = writeln!(
+            svg,
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg" font-family="sans-serif">"#,
+            w * scale,
+            h * scale
+        );
+        _ = writeln!(
+            svg,
+            "<desc>{} of {} solutions cover every cell</desc>",
+            self.spec, self.solutions
+        );
+        for y in 0..h {
+            for x in 0..w {
+                let (px, py) = (x * scale, y * scale);
+                if holes.get(x, y) {
+                    _ = writeln!(
+                        svg,
+                        r##"<rect x="{px}" y="{py}" width="{scale}" height="{scale}" fill="#333" />"##
+                    );
+                    continue;
+                }
+
+                let covered = self.covered[(y * w + x) as usize];
+                let share = covered as f64 / self.solutions.max(1) as f64;
+                let [r, g, b] = [0, 1, 2]
+                    .map(|i| (EMPTY[i] as f64 + (FULL[i] as f64 - EMPTY[i] as f64) * share) as u8);
+                let stroke = if covered == self.solutions && covered > 0 {
+                    8.
+                } else {
+                    0.5
+                };
+                _ = writeln!(
+                    svg,
+                    r##"<rect x="{px}" y="{py}" width="{scale}" height="{scale}" fill="#{r:02x}{g:02x}{b:02x}" stroke="#000" stroke-width="{stroke}" />"##
+                );
+                _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-size="30" text-anchor="middle">{:.0}%</text>"#,
+                    px + scale / 2,
+                    py + scale * 3 / 5,
+                    100. * share
+                );
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[test]
+fn counts_the_solutions_covering_every_cell() {
+    use crate::pentonimo::{Pentonimo, PentonimoKind};
+
+    let i = |variant, x, y| {
+        Pentonimo::variant(PentonimoKind::I, variant)
+            .unwrap()
+            .position(x, y)
+    };
+    // a vertical I in the first or the last column of a 5x5 board with a hole
+    let mut spec = BoardSpec::new((5, 5));
+    spec.holes.push((2, 2));
+    let heatmap = Heatmap::new(&spec, &[vec![i(0, 0, 0)], vec![i(0, 4, 0)]]);
+    assert_eq!(heatmap.solutions, 2);
+    assert_eq!(&heatmap.covered[..5], [1, 0, 0, 0, 1]);
+    assert_eq!(heatmap.forced(), 0);
+    assert_eq!(heatmap.never(), 14);
+    assert!(heatmap.to_svg().contains(">50%<"));
+
+    // the transposed board has the Is in its first and last rows
+    let transposed = heatmap.transposed();
+    assert_eq!(transposed.spec.holes, [(2, 2)]);
+    assert_eq!(&transposed.covered[..5], [1; 5]);
+    assert_eq!(&transposed.covered[5..10], [0; 5]);
+
+    let single = Heatmap::new(&spec, &[vec![i(0, 0, 0)]]);
+    assert_eq!((single.forced(), single.never()), (5, 19));
+}
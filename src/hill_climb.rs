@@ -0,0 +1,64 @@
+//! A cheap polishing pass for the results of the heuristic strategies: moves
+//! single pieces as long as that makes the diameter larger.
+
+use crate::{pathfinding::BfsScratch, solution::Solution, strategy::placed_map};
+
+/// First improvement hill climbing. The neighbours of a placement are all
+/// placements with one piece moved to another legal position or orientation.
+/// Returns the number of improving moves.
+pub fn polish(solution: &mut Solution) -> usize {
+    let spec = &solution.spec;
+    let all = spec.placements();
+    let mut scratch = BfsScratch::new(spec.shape);
+
+    let mut moves = 0;
+    'climb: loop {
+        for i in 0..solution.pieces.len() {
+            let piece = solution.pieces[i];
+            let mut others = solution.pieces.clone();
+            others.swap_remove(i);
+            let Some(map) = placed_map(spec, &others) else {
+                return moves;
+            };
+
+            let kind = piece.pentonimo().kind();
+            for &moved in &all {
+                if moved.pentonimo().kind() != kind || moved == piece || !map.can_place(moved) {
+                    continue;
+                }
+                let mut map = map.clone();
+                map |= moved;
+                let (diameter, _) = scratch.objective(&map, &spec.ends);
+                if diameter > solution.diameter {
+                    solution.diameter = diameter;
+                    solution.pieces[i] = moved;
+                    moves += 1;
+                    continue 'climb;
+                }
+            }
+        }
+        return moves;
+    }
+}
+
+#[test]
+fn improves_a_bad_placement() {
+    use crate::spec::BoardSpec;
+
+    let spec = BoardSpec::new((5, 4));
+    let all = spec.placements();
+    let mut solution = Solution {
+        diameter: 0,
+        pieces: vec![all[0]],
+        spec,
+        proved_optimal: false,
+    };
+    solution.diameter = BfsScratch::new(solution.spec.shape)
+        .graph_diameter(&solution.map())
+        .0;
+    let before = solution.diameter;
+
+    assert!(polish(&mut solution) > 0);
+    solution.verify().unwrap();
+    assert!(solution.diameter > before);
+}
@@ -0,0 +1,386 @@
+//! A small JSON value for the machine readable outputs, with a parser and a
+//! validator for the subset of JSON Schema used by the schemas in `schema/`.
+
+use std::{fmt::Display, str::FromStr};
+
+/// Version of the JSON outputs. Every output has it in its `version` field
+/// and the schemas of a version are in `schema/v{version}/`. Changes that can
+/// break consumers increase it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// fields in the order they are written
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// an object with the `version` field followed by `fields`
+    pub fn versioned(fields: impl IntoIterator<Item = (&'static str, Json)>) -> Self {
+        let mut object = vec![("version".to_owned(), SCHEMA_VERSION.into())];
+        object.extend(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+        Json::Object(object)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "boolean",
+            Json::Number(n) if n.fract() == 0. => "integer",
+            Json::Number(_) => "number",
+            Json::String(_) => "string",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object",
+        }
+    }
+
+    /// Checks the value against a schema using `type`, `enum`, `const`,
+    /// `minimum`, `properties`, `required`, `additionalProperties` and
+    /// `items`. Other keywords are ignored.
+    pub fn validate(&self, schema: &Json) -> Result<(), String> {
+        self.validate_at(schema, "$")
+    }
+
+    fn validate_at(&self, schema: &Json, at: &str) -> Result<(), String> {
+        if let Some(expected) = schema.get("type") {
+            let actual = self.type_name();
+            let matches = |name: &Json| {
+                name == &Json::from(actual)
+                    || (name == &Json::from("number") && actual == "integer")
+            };
+            let ok = match expected {
+                Json::Array(names) => names.iter().any(matches),
+                name => matches(name),
+            };
+            if !ok {
+                return Err(format!("{at}: expected {expected}, got {actual}"));
+            }
+        }
+        if let Some(Json::Array(values)) = schema.get("enum") {
+            if !values.contains(self) {
+                return Err(format!(
+                    "{at}: {self} is not one of {}",
+                    Json::Array(values.clone())
+                ));
+            }
+        }
+        if let Some(value) = schema.get("const") {
+            if value != self {
+                return Err(format!("{at}: expected {value}, got {self}"));
+            }
+        }
+        if let (Some(Json::Number(minimum)), Json::Number(n)) = (schema.get("minimum"), self) {
+            if n < minimum {
+                return Err(format!("{at}: {n} is less than {minimum}"));
+            }
+        }
+
+        if let Json::Object(fields) = self {
+            if let Some(Json::Array(required)) = schema.get("required") {
+                for key in required {
+                    let Json::String(key) = key else { continue };
+                    if self.get(key).is_none() {
+                        return Err(format!("{at}: missing field {key:?}"));
+                    }
+                }
+            }
+            let properties = schema.get("properties");
+            for (key, value) in fields {
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property) => value.validate_at(property, &format!("{at}.{key}"))?,
+                    None if schema.get("additionalProperties") == Some(&Json::Bool(false)) => {
+                        return Err(format!("{at}: unexpected field {key:?}"));
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Json::Array(values), Some(items)) = (self, schema.get("items")) {
+            for (i, value) in values.iter().enumerate() {
+                value.validate_at(items, &format!("{at}[{i}]"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+macro_rules! from_number {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Json {
+            fn from(value: $t) -> Self {
+                Json::Number(value as f64)
+            }
+        }
+    )*};
+}
+from_number!(u8, u32, u64, usize, f64);
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {value}", Json::from(key.as_str()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl FromStr for Json {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, at: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.at < s.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{message} at byte {}", self.at)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.s[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.at..].chars().next()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.s[self.at..].starts_with(token) {
+            self.at += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {token:?}")))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some(']') => {
+                            self.at += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(":")?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some('}') => {
+                            self.at += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let rest = &self.s[self.at..];
+                let end = rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(rest.len());
+                let number = rest[..end]
+                    .parse()
+                    .map_err(|_| self.error("invalid number"))?;
+                self.at += end;
+                Ok(Json::Number(number))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.at += 1;
+        let mut string = String::new();
+        let mut chars = self.s[self.at..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += i + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'u')) => {
+                            let hex = chars.by_ref().take(4).map(|(_, c)| c).collect::<String>();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        Some((_, c)) => c,
+                        None => break,
+                    };
+                    string.push(escaped);
+                }
+                c => string.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[test]
+fn roundtrip_and_validate() {
+    let value = Json::versioned([
+        ("name", "a \"b\"\n".into()),
+        ("sizes", vec![1u32, 2, 3].into()),
+        ("ok", true.into()),
+        ("ratio", 0.5.into()),
+    ]);
+    let parsed = value.to_string().parse::<Json>().unwrap();
+    assert_eq!(parsed, value);
+
+    let schema = r#"{
+        "type": "object",
+        "required": ["version", "sizes"],
+        "additionalProperties": false,
+        "properties": {
+            "version": {"const": 1},
+            "name": {"type": "string"},
+            "sizes": {"type": "array", "items": {"type": "integer", "minimum": 1}},
+            "ok": {"type": "boolean"},
+            "ratio": {"type": "number"}
+        }
+    }"#
+    .parse::<Json>()
+    .unwrap();
+    value.validate(&schema).unwrap();
+
+    let Json::Object(mut fields) = value else {
+        unreachable!()
+    };
+    fields.push(("extra".to_owned(), Json::Null));
+    assert!(Json::Object(fields).validate(&schema).is_err());
+    assert!(Json::versioned([("sizes", vec![0u32].into())])
+        .validate(&schema)
+        .is_err());
+}
@@ -0,0 +1,97 @@
+//! Places pentominoes on a board so the longest shortest path between two
+//! free cells, the diameter of the board, becomes as long as possible.
+//!
+//! The `pentonimo` binary is a front-end for this library. The building
+//! blocks are [`tile::Tile`] and [`tile_map::TileMap`] for boards,
+//! [`pentonimo::Pentonimo`] for the pieces and [`pathfinding::BfsScratch`] for
+//! diameters. [`brute_force::find_best`] searches a [`spec::BoardSpec`] for the
+//! best placement, the [`strategy::SearchStrategy`]s find good ones of larger
+//! boards within a time budget. Other threads can stop both early with
+//! [`brute_force::SearchConfig::cancel`] and
+//! [`strategy::SearchStrategy::set_cancel`]. With the `wasm` feature the
+//! solver can also be used from JavaScript, see `src/wasm.rs`.
+//!
+//! ```
+//! use pentonimo::{
+//!     brute_force::{find_best, SearchConfig},
+//!     pathfinding::BfsScratch,
+//!     pentonimo::{Pentonimo, PentonimoKind},
+//!     solution::Solution,
+//!     spec::BoardSpec,
+//! };
+//!
+//! let spec: BoardSpec = "5x4".parse().unwrap();
+//! let mut map = spec.initial_map();
+//! let mut scratch = BfsScratch::new(spec.shape);
+//! assert_eq!(scratch.graph_diameter(&map).0, 8);
+//!
+//! // the I only fits lying down, where it cuts the board in two
+//! let standing = Pentonimo::variant(PentonimoKind::I, 0).unwrap();
+//! let lying = Pentonimo::variant(PentonimoKind::I, 1).unwrap();
+//! assert!(!map.can_place(standing.position(0, 0)));
+//! map |= lying.position(0, 1);
+//! assert_eq!(scratch.graph_diameter(&map).0, 6);
+//!
+//! let result = find_best(&spec, &SearchConfig::default());
+//! let solution = Solution {
+//!     spec,
+//!     diameter: result.diameter,
+//!     pieces: result.placed,
+//!     proved_optimal: false,
+//! };
+//! solution.verify().unwrap();
+//! assert_eq!(solution.diameter, 13);
+//! ```
+
+pub mod analysis;
+#[cfg(test)]
+mod arbitrary;
+pub mod backend;
+pub mod best_known;
+pub mod brute_force;
+pub mod candidates;
+pub mod certificate;
+pub mod chart;
+pub mod checkpoint;
+pub mod custom_pieces;
+#[cfg(feature = "server")]
+pub mod distributed;
+pub mod estimate;
+pub mod exact_cover;
+#[cfg(feature = "parallel")]
+mod frontier;
+pub mod game;
+pub mod greedy;
+pub mod heatmap;
+pub mod hill_climb;
+pub mod json;
+pub mod lns;
+pub mod maze;
+pub mod monitor;
+pub mod netpbm;
+pub mod pathfinding;
+pub mod pentonimo;
+pub mod png;
+pub mod profile;
+pub mod result_cache;
+pub mod rng;
+#[cfg(feature = "row-major")]
+pub mod row_map;
+pub mod run_log;
+pub mod sampling;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shared_budget;
+mod simd;
+pub mod solution;
+pub mod spec;
+pub mod stats;
+pub mod strategy;
+pub mod summary;
+pub mod tabu;
+pub mod tile;
+pub mod tile_map;
+pub mod tiling;
+pub mod trace;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,133 @@
+//! Large neighbourhood search: repeatedly removes some pieces of the best
+//! solution and searches all ways to place pieces on the freed cells again.
+
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{
+    backend::Instant,
+    brute_force::{find_best, SearchConfig},
+    hill_climb::polish,
+    monitor::is_cancelled,
+    rng::Rng,
+    solution::Solution,
+    spec::BoardSpec,
+    strategy::{reduced_spec, SearchStats, SearchStrategy},
+};
+
+pub struct Lns {
+    /// time for the whole search
+    pub budget: Duration,
+    /// time limit of the first search and of every neighbourhood
+    pub iteration_limit: Duration,
+    /// pieces removed in every iteration
+    pub destroy: usize,
+    /// settings of the searches of the neighbourhoods, whose `cancel` also
+    /// stops the whole search
+    pub config: SearchConfig,
+    /// print every improvement to stderr
+    pub verbose: bool,
+    pub rng: Rng,
+}
+
+impl Lns {
+    pub fn new(budget: Duration, rng: Rng) -> Self {
+        Self {
+            budget,
+            iteration_limit: Duration::from_secs(1),
+            destroy: 3,
+            config: SearchConfig::default(),
+            verbose: false,
+            rng,
+        }
+    }
+}
+
+impl SearchStrategy for Lns {
+    fn name(&self) -> &'static str {
+        "lns"
+    }
+
+    fn search_with_stats(&mut self, spec: &BoardSpec) -> (Solution, SearchStats) {
+        let start = Instant::now();
+        let time_limit = |start: Instant| {
+            let left = self.budget.saturating_sub(start.elapsed());
+            Some(left.min(self.iteration_limit))
+        };
+
+        // an incomplete depth first search of the whole board as the start
+        let config = SearchConfig {
+            time_limit: time_limit(start),
+            ..self.config.clone()
+        };
+        let result = find_best(spec, &config);
+        let mut best = Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        let mut stats = SearchStats {
+            time_to_best: result.time_to_best,
+            nodes: result.nodes,
+        };
+
+        let mut iterations = 0;
+        while start.elapsed() < self.budget && !is_cancelled(self.config.cancel.as_ref()) {
+            iterations += 1;
+            let mut fixed = best.pieces.clone();
+            for _ in 0..self.destroy.min(fixed.len()) {
+                let i = self.rng.below(fixed.len() as u64) as usize;
+                fixed.swap_remove(i);
+            }
+
+            let config = SearchConfig {
+                time_limit: time_limit(start),
+                ..self.config.clone()
+            };
+            let result = find_best(&reduced_spec(spec, &fixed), &config);
+            stats.nodes += result.nodes;
+            // equally good solutions are accepted to move across plateaus
+            if result.diameter >= best.diameter {
+                if self.verbose && result.diameter > best.diameter {
+                    eprintln!(
+                        "[{spec}] lns iteration {iterations}: diameter {}",
+                        result.diameter
+                    );
+                }
+                let improved = result.diameter > best.diameter;
+                fixed.extend(result.placed);
+                best.diameter = result.diameter;
+                best.pieces = fixed;
+                let polished = polish(&mut best) > 0;
+                if polished && self.verbose {
+                    eprintln!(
+                        "[{spec}] lns iteration {iterations}: polished to diameter {}",
+                        best.diameter
+                    );
+                }
+                if improved || polished {
+                    stats.time_to_best = start.elapsed();
+                }
+            }
+        }
+
+        (best, stats)
+    }
+
+    fn set_cancel(&mut self, cancel: Arc<AtomicBool>) {
+        self.config.cancel = Some(cancel);
+    }
+}
+
+#[test]
+fn finds_valid_solutions() {
+    let spec = BoardSpec::new((5, 4));
+    let mut lns = Lns::new(Duration::from_millis(200), Rng::new(0));
+    lns.iteration_limit = Duration::from_millis(50);
+    let solution = lns.search(&spec);
+    solution.verify().unwrap();
+    assert!(solution.diameter >= 12);
+}
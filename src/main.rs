@@ -1,88 +1,1789 @@
-use std::{fmt::Display, fs::File, io::Write, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use brute_force::find_best;
-use pathfinding::{dijkstra, BfsScratch};
-use pentonimo::{PentonimoKind, PositionedPentonimo};
-use tile_map::TileMap;
+use pentonimo::{
+    analysis::{board_issues, Analysis, BoardIssue, Bottlenecks, DeadEnds, PieceUsage, WhatIf},
+    backend::Pinning,
+    best_known::{self, best_known, Bound},
+    brute_force::{find_best, SearchConfig},
+    certificate::Certificate,
+    chart::Chart,
+    checkpoint::Checkpoint,
+    custom_pieces::load_custom_pieces,
+    estimate::{self, Estimate},
+    game::{Ai, Game},
+    greedy::Greedy,
+    heatmap::Heatmap,
+    hill_climb::polish,
+    json::Json,
+    lns::Lns,
+    maze::Maze,
+    monitor::format_bytes,
+    netpbm::parse_netpbm,
+    pathfinding::{shortest_path, BfsScratch, Path as GridPath, PathStyle, Point},
+    pentonimo::{Pentonimo, PentonimoKind, PositionedPentonimo, KINDS},
+    png,
+    profile::Profile,
+    result_cache::{self, Provenance, ResultCache},
+    rng::Rng,
+    run_log::{self, Run},
+    sampling::Sampling,
+    shared_budget::{self, Goal},
+    solution::{parse_piece, Solution},
+    spec::{self, BoardSpec},
+    stats::Stats,
+    strategy::{BruteForce, Objective, SearchStrategy},
+    summary::Summary,
+    tabu::Tabu,
+    tiling::{Preset, Tiling, PRESETS},
+    trace::{self, Trace},
+};
 
-mod brute_force;
-mod candidates;
-mod pathfinding;
-mod pentonimo;
-mod tile;
-mod tile_map;
+#[cfg(feature = "server")]
+use pentonimo::{
+    backend,
+    distributed::{self, Coordinator},
+    server,
+};
+#[cfg(feature = "server")]
+use std::net::{TcpListener, ToSocketAddrs};
+use strum::VariantArray;
+
+mod cli;
+#[cfg(feature = "tui")]
+mod view;
+
+use cli::{Command, Letters, MazeFormat, ResultFormat, ShowFormat, Strategy, Theme, Verbosity};
 
 fn main() {
-    _ = std::fs::create_dir("results");
+    let options = cli::parse(std::env::args().skip(1)).unwrap_or_else(|err| exit_with(&err));
+    if let Some(file) = &options.piece_file {
+        load_custom_pieces(file).unwrap_or_else(|err| exit_with(&err));
+    }
+
+    match &options.command {
+        Command::Solve => {}
+        Command::Verify(files) => return verify(files),
+        Command::Merge { files, output } => return merge(files, output.as_deref()),
+        Command::Tile { presets, count } => return tile(presets, *count),
+        Command::WhatIf(file) => return what_if(file),
+        Command::Analyze { file, svg } => {
+            let style = (!options.no_path).then_some(options.path_style);
+            return analyze(file, svg.as_deref(), style);
+        }
+        Command::Maze {
+            file,
+            format,
+            output,
+        } => return maze(file, *format, output.as_deref()),
+        Command::Show {
+            file,
+            format,
+            output,
+            scale,
+            theme,
+            bottlenecks,
+        } => {
+            let palette = load_palette(options.palette.as_deref(), *theme);
+            return show(
+                file,
+                *format,
+                output.as_deref(),
+                *scale,
+                *bottlenecks,
+                palette,
+                &options,
+            );
+        }
+        Command::Trace { file, output } => return fold_trace(file, output.as_deref()),
+        Command::Stats { dir, recompute } => {
+            let dir = dir.as_deref().unwrap_or(Path::new(cli::DEFAULT_OUT_DIR));
+            return stats(dir, *recompute);
+        }
+        #[cfg(feature = "tui")]
+        Command::View { dir } => {
+            let dir = dir.as_deref().unwrap_or(Path::new(cli::DEFAULT_OUT_DIR));
+            let style = (!options.no_path).then_some(options.path_style);
+            return view::view(dir, style);
+        }
+        #[cfg(not(feature = "tui"))]
+        Command::View { .. } => unreachable!("rejected by cli::parse"),
+        Command::Play {
+            board,
+            ai_first,
+            playouts,
+        } => return play(board, *ai_first, *playouts),
+        Command::BenchStrategies {
+            board,
+            budget,
+            seed,
+        } => return bench_strategies(board.as_ref().unwrap(), *budget, *seed),
+        Command::Sample {
+            board,
+            samples,
+            seed,
+        } => return sample(board.as_ref().unwrap(), *samples, *seed),
+        Command::PieceSweep {
+            board,
+            timeout,
+            svg,
+        } => return piece_sweep(board.as_ref().unwrap(), *timeout, svg.as_deref()),
+        Command::Share {
+            boards,
+            pieces,
+            one_sided,
+            goal,
+        } => return share(boards, *pieces, *one_sided, *goal),
+        #[cfg(feature = "server")]
+        Command::Serve {
+            listen,
+            jobs,
+            workers,
+        } => return serve(listen, jobs, *workers),
+        #[cfg(feature = "server")]
+        Command::Coordinate {
+            board,
+            listen,
+            shard_size,
+            heartbeat_timeout,
+            state,
+        } => {
+            let board = board.as_ref().unwrap();
+            let state = state.clone().unwrap_or_else(|| {
+                Path::new(cli::DEFAULT_OUT_DIR).join(format!("{}.shards", board.file_stem()))
+            });
+            return coordinate(board, listen, *shard_size, *heartbeat_timeout, &state);
+        }
+        #[cfg(feature = "server")]
+        Command::Work {
+            coordinator,
+            name,
+            threads,
+        } => return work(coordinator.as_ref().unwrap(), name.clone(), *threads),
+        #[cfg(not(feature = "server"))]
+        Command::Serve { .. } | Command::Coordinate { .. } | Command::Work { .. } => {
+            unreachable!("rejected by cli::parse")
+        }
+    }
+
+    let mut specs = match &options.batch {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))
+            .and_then(|input| spec::parse_specs(&input))
+            .unwrap_or_else(|err| exit_with(&err)),
+        None => Vec::new(),
+    };
+    for name in &options.presets {
+        let preset =
+            Preset::find(name).unwrap_or_else(|| exit_with(&format!("unknown preset {name:?}")));
+        specs.push(preset.spec());
+    }
+    if options.widths.is_some() || options.heights.is_some() {
+        let widths = options.widths.clone().unwrap_or(cli::DEFAULT_SIZES);
+        let heights = options.heights.clone().unwrap_or(cli::DEFAULT_SIZES);
+        specs.extend(widths.flat_map(|x| heights.clone().map(move |y| BoardSpec::new((x, y)))));
+    }
+    // whether a board couldn't be searched or a result file couldn't be written
+    let mut failed = false;
+    let sweep = specs.is_empty();
+    if sweep {
+        specs = cli::DEFAULT_SIZES
+            .flat_map(|x| cli::DEFAULT_SIZES.map(move |y| BoardSpec::new((x, y))))
+            .collect();
+    }
+    if let Some((pieces, one_sided)) = options.pieces {
+        for spec in &mut specs {
+            (spec.pieces, spec.one_sided) = (pieces, one_sided);
+        }
+    }
+    if let Some((spec, kind)) =
+        (specs.iter()).find_map(|spec| Some((spec, spec.undefined_piece()?)))
+    {
+        exit_with(&format!(
+            "{spec}: custom piece {kind} isn't defined, pass its shape with --piece-file"
+        ));
+    }
+    if !sweep {
+        specs.retain(|spec| {
+            let issues = board_issues(spec);
+            let valid = !issues.iter().any(BoardIssue::is_error);
+            for issue in &issues {
+                if !valid && issue.is_error() {
+                    eprintln!("error: {spec}: {issue}, skipping it");
+                } else if valid && options.verbosity >= Verbosity::Normal {
+                    eprintln!("warning: {spec}: {issue}");
+                }
+            }
+            failed |= !valid;
+            valid
+        });
+        if specs.is_empty() {
+            std::process::exit(cli::EXIT_ERROR);
+        }
+        if !options.in_order {
+            let mut prioritized = specs
+                .into_iter()
+                .map(|spec| (estimate::priority(&spec), spec))
+                .collect::<Vec<_>>();
+            prioritized.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            specs = prioritized.into_iter().map(|(_, spec)| spec).collect();
+        }
+    }
+
+    if options.dry_run {
+        let mut rng = Rng::from_time();
+        for spec in &specs {
+            let estimate = Estimate::new(&spec.canonical().0, cli::DRY_RUN_PROBES, &mut rng);
+            let branching = estimate
+                .branching
+                .iter()
+                .map(|b| format!("{b:.1}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "{spec}: {} roots, ~{:.1e} nodes, stored states up to ~{}\n  branching by depth: {branching}",
+                estimate.roots,
+                estimate.nodes,
+                format_bytes(estimate.memory_bound(spec, options.exact_states) as u64),
+            );
+        }
+        return;
+    }
+
+    let config = SearchConfig {
+        profile: options.profile,
+        progress: options.progress.then_some(cli::PROGRESS_INTERVAL),
+        memo_stats: options.memo_stats.then_some(cli::PROGRESS_INTERVAL),
+        memory_cap: options.memory_cap,
+        memory_policy: options.memory_policy,
+        all_optima: options.all_optima,
+        exhaustive: options.prove || options.exact_no_monotone_prune,
+        dominance: options.aggressive,
+        exact_states: options.exact_states,
+        check_hashes: options.check_hashes,
+        debug: options.verbosity >= Verbosity::Debug,
+        time_limit: options.timeout,
+        threads: options.threads,
+        memo_shards: options.memo_shards,
+        pinning: Pinning {
+            workers: options.pin_threads || options.reserve_core,
+            reserve_core: options.reserve_core,
+        },
+        numa: options.numa,
+        memo_capacity: options.memo_capacity,
+        best_known: !options.no_best_known,
+        greedy_seed: !options.no_greedy_seed,
+        rollout_depth: options.rollout_depth,
+        max_decrease: options.max_decrease,
+        ..Default::default()
+    };
+
+    if options.verbosity >= Verbosity::Verbose {
+        eprintln!("effective configuration:");
+        for (key, value, source) in options.effective_settings() {
+            match source {
+                Some(source) => eprintln!("  {key} = {value} ({source})"),
+                None => eprintln!("  {key} = {value} (default)"),
+            }
+        }
+    }
+
+    let out_dir = options
+        .out_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(cli::DEFAULT_OUT_DIR));
+    if !options.no_files {
+        std::fs::create_dir_all(&out_dir).unwrap_or_else(|err| {
+            exit_with(&format!("failed to create {}: {err}", out_dir.display()))
+        });
+    }
+
+    let cache_path = out_dir.join(result_cache::FILE_NAME);
+    let mut cache = if options.no_files {
+        ResultCache::default()
+    } else {
+        ResultCache::load(&cache_path).unwrap_or_else(|err| {
+            exit_with(&format!(
+                "invalid result cache {}: {err}",
+                cache_path.display()
+            ))
+        })
+    };
+    // searches whose results are all in a cached solution
+    let use_cache = !options.no_cache
+        && !options.prove
+        && !options.exact_no_monotone_prune
+        && !options.all_optima
+        && !options.profile
+        && !options.check_hashes
+        && !options.aggressive
+        && !options.exact_fill
+        && !options.trace
+        && options.max_decrease == 0
+        && options.piece_file.is_none();
+
+    // (a, b) and (b, a) are the same problem, so only the canonical one of them is solved
+    let mut solved = HashMap::<BoardSpec, Solved>::new();
+    let mut stems = HashSet::new();
+    let mut names = HashSet::new();
+    let mut summary = Summary::new(options.all_optima);
+    let run_config = run_config(&options);
+    let palette = (options.palette.as_deref()).map(|file| load_palette(Some(file), Theme::Light));
+
+    for spec in &specs {
+        let mut stem = spec.file_stem();
+        for i in 2.. {
+            if stems.insert(stem.clone()) {
+                break;
+            }
+            stem = format!("{}_{i}", spec.file_stem());
+        }
+
+        let (canonical, transposed) = spec.canonical();
+        let start = Instant::now();
+        let entry = solved.entry(canonical).or_insert_with_key(|canonical| {
+            if options.exact_fill {
+                return Solved::new(exact_fill(canonical));
+            }
+            let strategy = board_strategy(canonical, &options);
+            if strategy != Strategy::BruteForce {
+                let mut strategy = heuristic_strategy(strategy, &options, &config);
+                let mut solution = strategy.search(canonical);
+                if options.objective == Objective::Diameter {
+                    polish(&mut solution);
+                }
+                return Solved {
+                    heuristic: true,
+                    ..Solved::new(solution)
+                };
+            }
+            if let Some((solution, source)) = cache.get(canonical).filter(|_| use_cache) {
+                return Solved {
+                    source,
+                    ..Solved::new(solution)
+                };
+            }
+
+            let mut config = config.clone();
+            if let Some(interval) = options.checkpoint_every {
+                // exhaustive searches skip other roots, so they can't share checkpoints
+                let exhaustive = options.prove || options.exact_no_monotone_prune;
+                let mode = if exhaustive { ".prove" } else { "" };
+                let path = out_dir.join(format!("{stem}{mode}.checkpoint"));
+                config.resume = load_checkpoint(&path, canonical);
+                config.checkpoint = Some((path, interval));
+            }
+            if options.root_files {
+                let mode = if options.prove || options.exact_no_monotone_prune { ".prove" } else { "" };
+                let dir = out_dir.join(format!("{stem}{mode}.roots"));
+                if let Err(err) = std::fs::create_dir_all(&dir) {
+                    exit_with(&format!("failed to create {}: {err}", dir.display()));
+                }
+                config.root_files = Some(dir);
+            }
+
+            if options.trace {
+                config.trace = Some(Arc::new(Trace::new(
+                    options.trace_depth.unwrap_or(cli::DEFAULT_TRACE_DEPTH),
+                    options.trace_nodes.unwrap_or(cli::DEFAULT_TRACE_NODES),
+                )));
+            }
+
+            let result = find_best(canonical, &config);
+            if let (true, Some((path, _))) = (result.complete, &config.checkpoint) {
+                // the result files replace the checkpoint
+                _ = std::fs::remove_file(path);
+            }
+            if let (true, Some(dir)) = (result.complete, &config.root_files) {
+                _ = std::fs::remove_dir_all(dir);
+            }
+            if let Some(trace) = &config.trace {
+                let path = out_dir.join(format!("{stem}.trace.jsonl"));
+                failed |= !write_result(&path, &options, |path| {
+                    std::fs::write(path, trace.to_jsonl())
+                });
+            }
+            if let Some(profile) = &result.profile {
+                println!("profile for {canonical}:\n{profile}");
+            }
+            if !result.complete {
+                eprintln!(
+                    "warning: search for {canonical} was stopped early, the result may not be optimal \
+                     (the full search finds at most {})",
+                    result.bound
+                );
+            }
+            if let Some(check) = result.hash_check {
+                println!(
+                    "{canonical}: {} of {} sampled hash matches were collisions",
+                    check.collisions, check.checked
+                );
+            }
+            if result.certificate.is_some() {
+                println!("{canonical}: diameter {} is optimal", result.diameter);
+            }
+            if options.exact_no_monotone_prune && result.complete {
+                let monotone = find_best(
+                    canonical,
+                    &SearchConfig {
+                        exhaustive: false,
+                        checkpoint: None,
+                        resume: None,
+                        trace: None,
+                        ..config.clone()
+                    },
+                );
+                let outcome = if !monotone.complete {
+                    format!("was stopped at diameter {}", monotone.diameter)
+                } else if monotone.diameter == result.diameter {
+                    "finds the same diameter".to_owned()
+                } else {
+                    format!("only finds diameter {}", monotone.diameter)
+                };
+                println!("{canonical}: the search with monotone pruning {outcome}");
+            }
+            let usage = options
+                .all_optima
+                .then(|| PieceUsage::new(&result.optima));
+            let heatmap = options
+                .all_optima
+                .then(|| Heatmap::new(canonical, &result.optima));
+            let solution = Solution {
+                spec: canonical.clone(),
+                diameter: result.diameter,
+                pieces: result.placed,
+                proved_optimal: result.certificate.is_some(),
+            };
+            Solved {
+                solution,
+                usage,
+                heatmap,
+                certificate: result.certificate,
+                profile: result.profile,
+                complete: result.complete,
+                heuristic: false,
+                time: None,
+                source: Provenance::Search,
+            }
+        });
+        let time = *entry.time.get_or_insert_with(|| start.elapsed());
+        let Solved {
+            solution,
+            usage,
+            heatmap,
+            certificate,
+            profile,
+            complete,
+            source,
+            ..
+        } = entry;
+        let (mut solution, source) = if transposed {
+            (solution.transposed(), source.transposed())
+        } else {
+            (solution.clone(), *source)
+        };
+        solution.spec = spec.clone();
+        let max = solution.diameter;
+        // complete brute-force searches. Monotone pruning can miss longer
+        // placements, so only `--prove` searches, which skip it, are optimal
+        let settled = *complete
+            && !options.exact_fill
+            && !options.aggressive
+            && board_strategy(&spec.canonical().0, &options) == Strategy::BruteForce;
+        let optimal = solution.proved_optimal;
+        // the shapes of custom pieces can change between runs
+        let cacheable = source == Provenance::Search && settled && options.piece_file.is_none();
+        if cacheable && !options.no_files {
+            if let Err(err) = cache.append(&cache_path, &solution) {
+                eprintln!("failed to write {}: {err}", cache_path.display());
+                failed = true;
+            }
+        }
+
+        let plain = spec.holes.is_empty()
+            && spec.free.is_empty()
+            && spec.ends.is_empty()
+            && spec.regions.is_empty()
+            && options.piece_file.is_none();
+        if plain && !options.exact_fill && options.objective == Objective::Diameter {
+            let known = best_known(spec.shape, spec.pieces);
+            match known {
+                Some(known) if max < known.diameter => eprintln!(
+                    "warning: {spec}: diameter {max} is below the best known {}",
+                    known.diameter
+                ),
+                Some(known) if max == known.diameter && (known.optimal || !optimal) => {}
+                _ => println!(
+                    "{spec}: diameter {max} is new, its entry of BEST_KNOWN in src/best_known.rs is {}",
+                    best_known::entry(
+                        spec,
+                        Bound {
+                            diameter: max,
+                            optimal,
+                        }
+                    )
+                ),
+            }
+        }
+
+        let mut grid = if options.exact_fill {
+            piece_grid(&solution.spec, &solution.pieces)
+        } else {
+            build_print_map(&solution, (!options.no_path).then_some(options.path_style))
+        };
+        let dead_ends = options
+            .dead_ends
+            .then(|| mark_dead_ends(&solution, &mut grid));
+        if options.verbosity >= Verbosity::Normal {
+            _ = ConsolePrinter(palette.as_ref()).print(spec.shape, max, &grid);
+            if source != Provenance::Search {
+                println!("{spec}: from the {source}");
+            }
+            if let Some(dead_ends) = &dead_ends {
+                print_dead_ends(dead_ends);
+            }
+        } else {
+            println!("{spec}: {max}");
+        }
+
+        let template = options.name.as_deref().unwrap_or(cli::DEFAULT_NAME);
+        let base = cli::expand_name(template, |field| {
+            Some(match field {
+                "stem" => stem.clone(),
+                "w" => spec.shape.0.to_string(),
+                "h" => spec.shape.1.to_string(),
+                "holes" => spec.holes.len().to_string(),
+                "diameter" => max.to_string(),
+                "pieces" => solution.pieces.len().to_string(),
+                _ => return None,
+            })
+        })
+        .unwrap_or_else(|err| exit_with(&err));
+        let mut name = base.clone();
+        for i in 2.. {
+            if names.insert(name.clone()) {
+                break;
+            }
+            name = format!("{base}_{i}");
+        }
+
+        for &format in options.formats() {
+            let path = match format {
+                ResultFormat::Svg => out_dir.join(format!("{name}.svg")),
+                ResultFormat::Png => out_dir.join(format!("{name}.png")),
+                ResultFormat::Txt => out_dir.join(format!("{name}.txt")),
+                ResultFormat::Json => out_dir.join(format!("{name}.json")),
+            };
+            failed |= !write_result(&path, &options, |path| match format {
+                ResultFormat::Svg => SvgPrinter {
+                    letters: options.letters,
+                    palette: palette
+                        .clone()
+                        .unwrap_or_else(|| Palette::new(Theme::Light)),
+                    ..SvgPrinter::new(path.to_owned(), Some(&solution))
+                }
+                .print(spec.shape, max, &grid),
+                ResultFormat::Png => PngPrinter {
+                    path: path.to_owned(),
+                    scale: PngPrinter::DEFAULT_SCALE,
+                    palette: palette
+                        .clone()
+                        .unwrap_or_else(|| Palette::new(Theme::Light)),
+                }
+                .print(spec.shape, max, &grid),
+                ResultFormat::Txt => std::fs::write(path, solution.to_string()),
+                ResultFormat::Json => JsonPrinter {
+                    path: Some(path.to_owned()),
+                    solution: &solution,
+                    style: (!options.no_path).then_some(options.path_style),
+                }
+                .print(spec.shape, max, &grid),
+            });
+        }
+        if let Some(certificate) = certificate {
+            let mut certificate = if transposed {
+                certificate.transposed()
+            } else {
+                certificate.clone()
+            };
+            certificate.solution.spec = spec.clone();
+            failed |= !write_result(
+                &out_dir.join(format!("{name}.certificate")),
+                &options,
+                |path| std::fs::write(path, certificate.to_string()),
+            );
+        }
+
+        if let Some(profile) = profile {
+            failed |= !write_result(
+                &out_dir.join(format!("{name}.stats.json")),
+                &options,
+                |path| std::fs::write(path, format!("{}\n", profile.to_json())),
+            );
+        }
+
+        if let Some(usage) = usage {
+            print_usage(usage);
+        }
+        if let Some(heatmap) = heatmap {
+            let mut heatmap = if transposed {
+                heatmap.transposed()
+            } else {
+                heatmap.clone()
+            };
+            heatmap.spec = spec.clone();
+            println!(
+                "{} cells are covered by every optimal placement, {} by none",
+                heatmap.forced(),
+                heatmap.never()
+            );
+            failed |= !write_result(
+                &out_dir.join(format!("{name}.heatmap.svg")),
+                &options,
+                |path| std::fs::write(path, heatmap.to_svg()),
+            );
+        }
+        summary.add(&name, &solution, usage.as_ref());
+
+        if !options.no_files {
+            let run = Run {
+                solution: &solution,
+                complete: *complete,
+                time,
+                source,
+                config: run_config.clone(),
+            };
+            let path = out_dir.join(run_log::FILE_NAME);
+            if let Err(err) = run.append(&path) {
+                eprintln!("failed to write {}: {err}", path.display());
+                failed = true;
+            }
+        }
+    }
+
+    if !sweep {
+        failed |= !write_result(&out_dir.join("summary.csv"), &options, |path| {
+            std::fs::write(path, summary.to_csv())
+        });
+        failed |= !write_result(&out_dir.join("summary.json"), &options, |path| {
+            std::fs::write(path, format!("{}\n", summary.to_json()))
+        });
+        if let Some(chart) = summary.to_chart() {
+            failed |= !write_result(&out_dir.join("summary.svg"), &options, |path| {
+                std::fs::write(path, chart.to_svg())
+            });
+        }
+    }
+
+    if failed {
+        std::process::exit(cli::EXIT_ERROR);
+    }
+    if solved.values().any(|solved| !solved.complete) {
+        std::process::exit(cli::EXIT_STOPPED);
+    }
+    if solved.values().any(|solved| solved.heuristic) {
+        std::process::exit(cli::EXIT_HEURISTIC);
+    }
+}
+
+/// The result of a canonical board, shared with its transposed board
+struct Solved {
+    solution: Solution,
+    usage: Option<PieceUsage>,
+    heatmap: Option<Heatmap>,
+    certificate: Option<Certificate>,
+    profile: Option<Profile>,
+    /// whether the search ran until it was done
+    complete: bool,
+    /// found by a heuristic strategy, which may miss better solutions
+    heuristic: bool,
+    /// how long solving the board took
+    time: Option<Duration>,
+    source: Provenance,
+}
+
+impl Solved {
+    fn new(solution: Solution) -> Self {
+        Self {
+            solution,
+            usage: None,
+            heatmap: None,
+            certificate: None,
+            profile: None,
+            complete: true,
+            heuristic: false,
+            time: None,
+            source: Provenance::Search,
+        }
+    }
+}
+
+/// the settings of `solve` for the run log, with the defaults of those that
+/// weren't set
+fn run_config(options: &cli::Options) -> Json {
+    let mut config = options
+        .effective_settings()
+        .map(|(key, value, _)| (key.to_owned(), value.into()))
+        .collect::<Vec<_>>();
+    config.extend([
+        ("prove".to_owned(), options.prove.into()),
+        (
+            "exact-no-monotone-prune".to_owned(),
+            options.exact_no_monotone_prune.into(),
+        ),
+        ("all-optima".to_owned(), options.all_optima.into()),
+        ("aggressive".to_owned(), options.aggressive.into()),
+        ("exact-fill".to_owned(), options.exact_fill.into()),
+        ("exact-states".to_owned(), options.exact_states.into()),
+        ("objective".to_owned(), options.objective.name().into()),
+    ]);
+    Json::Object(config)
+}
+
+/// Writes a result file with `write` unless `--no-files` is set, but only
+/// replaces an existing one with `--force`. Skipped and replaced files are
+/// reported. Returns false if writing failed.
+fn write_result(
+    path: &Path,
+    options: &cli::Options,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> bool {
+    if options.no_files {
+        return true;
+    }
+    if path.exists() {
+        if !options.force {
+            eprintln!(
+                "skipped {}, it already exists (pass --force to overwrite it)",
+                path.display()
+            );
+            return true;
+        }
+        eprintln!("overwriting {}", path.display());
+    }
+    match write(path) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("failed to write {}: {err}", path.display());
+            false
+        }
+    }
+}
+
+/// The strategy of `options` for `spec`, where auto becomes brute-force or lns
+/// depending on the estimated size of the search
+fn board_strategy(spec: &BoardSpec, options: &cli::Options) -> Strategy {
+    if options.strategy != Strategy::Auto {
+        return options.strategy;
+    }
+    // the others only maximize the diameter
+    if options.objective != Objective::Diameter {
+        return Strategy::Tabu;
+    }
+    // they need the complete search of brute-force
+    if options.prove
+        || options.exact_no_monotone_prune
+        || options.all_optima
+        || options.checkpoint_every.is_some()
+        || options.root_files
+        || options.timeout.is_some()
+        || options.trace
+    {
+        return Strategy::BruteForce;
+    }
+
+    // a fixed seed, so the same board always gets the same strategy
+    let estimate = Estimate::new(spec, cli::DRY_RUN_PROBES, &mut Rng::new(0));
+    let strategy = if estimate.nodes <= cli::AUTO_NODE_LIMIT {
+        Strategy::BruteForce
+    } else {
+        Strategy::Lns
+    };
+    if options.verbosity >= Verbosity::Verbose {
+        eprintln!(
+            "[{spec}] ~{:.1e} nodes, solving with {}",
+            estimate.nodes,
+            strategy.name()
+        );
+    }
+    strategy
+}
+
+/// `strategy` with the settings of `options` and `config` for its searches
+fn heuristic_strategy(
+    strategy: Strategy,
+    options: &cli::Options,
+    config: &SearchConfig,
+) -> Box<dyn SearchStrategy> {
+    let budget = options.budget.unwrap_or(cli::DEFAULT_BUDGET);
+    let rng = options.seed.map_or_else(Rng::from_time, Rng::new);
+    match strategy {
+        Strategy::Auto | Strategy::BruteForce => Box::new(BruteForce(config.clone())),
+        Strategy::Lns => {
+            let mut lns = Lns::new(budget, rng);
+            lns.verbose = options.progress;
+            lns.config = SearchConfig {
+                progress: None,
+                ..config.clone()
+            };
+            Box::new(lns)
+        }
+        Strategy::Tabu => {
+            let mut tabu = Tabu::new(budget, rng);
+            tabu.verbose = options.progress;
+            tabu.objective = options.objective;
+            Box::new(tabu)
+        }
+        Strategy::Greedy => Box::new(Greedy),
+    }
+}
+
+fn print_usage(usage: &PieceUsage) {
+    println!(
+        "{} optimal placements with {:.2} pieces on average",
+        usage.solutions,
+        usage.mean_pieces()
+    );
+    for &kind in PentonimoKind::VARIANTS {
+        let count = usage.kinds[kind as usize];
+        if count == 0 {
+            continue;
+        }
+        let variants = usage.variants[kind as usize]
+            .iter()
+            .take(Pentonimo::new(kind).variants().count())
+            .map(|count| count.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("  {kind} {count:6}  variants {variants}");
+    }
+}
+
+/// A tiling of all free cells of `spec` with its pieces instead of the one with
+/// the largest diameter
+fn exact_fill(spec: &BoardSpec) -> Solution {
+    let pieces = Tiling::new(spec)
+        .map(|mut tiling| tiling.first())
+        .unwrap_or_else(|err| {
+            eprintln!("warning: {spec}: {err}");
+            None
+        })
+        .unwrap_or_else(|| {
+            eprintln!("warning: {spec} can't be filled exactly");
+            Vec::new()
+        });
+
+    let mut map = spec.initial_map();
+    for &piece in &pieces {
+        map |= piece;
+    }
+    let (diameter, _) = BfsScratch::new(spec.shape).objective(&map, &spec.ends);
+
+    Solution {
+        spec: spec.clone(),
+        diameter,
+        pieces,
+        proved_optimal: false,
+    }
+}
+
+/// The checkpoint of an earlier, interrupted search for `spec` at `path`
+fn load_checkpoint(path: &Path, spec: &BoardSpec) -> Option<Checkpoint> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let checkpoint = text
+        .parse::<Checkpoint>()
+        .unwrap_or_else(|err| exit_with(&format!("invalid checkpoint {}: {err}", path.display())));
+    if checkpoint.best.spec != *spec {
+        exit_with(&format!(
+            "checkpoint {} belongs to {}, not {spec}",
+            path.display(),
+            checkpoint.best.spec
+        ));
+    }
+    eprintln!(
+        "resuming {spec} from {} ({}/{} roots done)",
+        path.display(),
+        checkpoint.done.iter().filter(|&&done| done).count(),
+        checkpoint.roots
+    );
+    Some(checkpoint)
+}
+
+fn verify(files: &[PathBuf]) {
+    let mut failed = false;
+
+    for file in files {
+        let result = std::fs::read_to_string(file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                if text.starts_with("certificate") {
+                    text.parse::<Certificate>()?.verify()
+                } else {
+                    text.parse::<Solution>()?.verify()
+                }
+            });
+
+        match result {
+            Ok(()) => println!("{}: ok", file.display()),
+            Err(err) => {
+                failed = true;
+                println!("{}: {err}", file.display());
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Unites the finished roots of all checkpoints and keeps the best solution.
+/// Running the search again with the merged checkpoint only searches the roots
+/// none of the runs has finished. The tables of searched states only live in
+/// the memory of their runs and aren't merged, the finished roots are what is
+/// left of them.
+fn merge(files: &[PathBuf], output: Option<&Path>) {
+    let mut merged: Option<Checkpoint> = None;
+    let mut solutions = Vec::new();
+
+    // directories stand for their files, like the root files of `--root-files`
+    let mut expanded = Vec::new();
+    for file in files {
+        match std::fs::read_dir(file) {
+            Ok(entries) => {
+                let mut entries = entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap_or_else(|err| {
+                        exit_with(&format!("failed to read {}: {err}", file.display()))
+                    });
+                entries.sort();
+                expanded.extend(entries);
+            }
+            Err(_) => expanded.push(file.clone()),
+        }
+    }
+
+    for file in &expanded {
+        let text = std::fs::read_to_string(file)
+            .unwrap_or_else(|err| exit_with(&format!("failed to read {}: {err}", file.display())));
+        let result = match text.parse::<Checkpoint>() {
+            Ok(checkpoint) => match &mut merged {
+                Some(merged) => merged.merge(&checkpoint),
+                None => {
+                    merged = Some(checkpoint);
+                    Ok(())
+                }
+            },
+            Err(_) => text
+                .parse::<Solution>()
+                .map(|solution| solutions.push(solution)),
+        };
+        if let Err(err) = result {
+            exit_with(&format!("{}: {err}", file.display()));
+        }
+    }
+
+    let Some(mut merged) = merged else {
+        exit_with("merge requires at least one checkpoint");
+    };
+    for solution in &solutions {
+        merged
+            .merge_solution(solution)
+            .unwrap_or_else(|err| exit_with(&err));
+    }
+
+    eprintln!(
+        "{}: {}/{} roots done, best diameter {}",
+        merged.best.spec,
+        merged.done.iter().filter(|&&done| done).count(),
+        merged.roots,
+        merged.best.diameter
+    );
+
+    match output {
+        Some(path) => std::fs::write(path, merged.to_string())
+            .unwrap_or_else(|err| exit_with(&format!("failed to write {}: {err}", path.display()))),
+        None => print!("{merged}"),
+    }
+}
+
+/// Prints a tiling of every preset or checks the number of its tilings
+fn tile(names: &[String], count: bool) {
+    let presets = if names.is_empty() {
+        PRESETS.iter().collect()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                Preset::find(name).unwrap_or_else(|| exit_with(&format!("unknown preset {name:?}")))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut failed = false;
+    for preset in presets {
+        if count {
+            let solutions = preset.count().unwrap_or_else(|err| exit_with(&err));
+            let ok = if solutions == preset.solutions {
+                "ok"
+            } else {
+                failed = true;
+                "MISMATCH"
+            };
+            println!(
+                "{}: {solutions} tilings, expected {} ({ok})",
+                preset.name, preset.solutions
+            );
+        } else {
+            let spec = preset.spec();
+            let pieces = Tiling::new(&spec)
+                .unwrap_or_else(|err| exit_with(&err))
+                .first()
+                .unwrap_or_else(|| exit_with(&format!("{} has no tiling", preset.name)));
+            println!("{}:", preset.name);
+            print_grid(spec.shape, &piece_grid(&spec, &pieces), None);
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn analyze(file: &Path, svg: Option<&Path>, style: Option<PathStyle>) {
+    let bytes = std::fs::read(file)
+        .unwrap_or_else(|err| exit_with(&format!("failed to read {}: {err}", file.display())));
+    let spec =
+        read_board(&bytes).unwrap_or_else(|err| exit_with(&format!("{}: {err}", file.display())));
+    let map = spec.initial_map();
+    let analysis = Analysis::new(&map);
+
+    let (mx, my) = spec.shape;
+    let mut grid = piece_grid(&spec, &[]);
+    for &Point(x, y) in &analysis.dead_ends {
+        grid[(y * mx + x) as usize] = PrintValue::DeadEnd;
+    }
+    let Point(cx, cy) = analysis.center;
+    if analysis.free_cells > 0 {
+        grid[(cy * mx + cx) as usize] = PrintValue::Center;
+    }
+    if let Some(style) = style.filter(|_| analysis.free_cells > 0) {
+        let path = shortest_path(&map, analysis.diameter_path, style);
+        for (i, point) in path.iter().enumerate() {
+            grid[(point.1 * mx + point.0) as usize] = PrintValue::Path(i);
+        }
+    }
+
+    _ = ConsolePrinter(None).print((mx, my), analysis.diameter, &grid);
+    let GridPath(Point(sx, sy), Point(ex, ey)) = analysis.diameter_path;
+    println!("free cells   {}", analysis.free_cells);
+    println!("components   {}", analysis.components);
+    println!("dead ends    {}", analysis.dead_ends.len());
+    println!(
+        "diameter     {} ({sx},{sy} -> {ex},{ey})",
+        analysis.diameter
+    );
+    if analysis.diameter_pairs.len() > 1 {
+        let pairs = analysis
+            .diameter_pairs
+            .iter()
+            .map(|GridPath(Point(sx, sy), Point(ex, ey))| format!("{sx},{sy} -> {ex},{ey}"))
+            .collect::<Vec<_>>();
+        println!("  {} pairs: {}", pairs.len(), pairs.join("; "));
+    }
+    println!("radius       {} (center {cx},{cy})", analysis.radius);
+    println!(
+        "distances    mean {:.2}, median {}, {} connected pairs",
+        analysis.mean_distance(),
+        analysis.median_distance(),
+        analysis.pairs()
+    );
+    for (distance, &count) in analysis.histogram.iter().enumerate().skip(1) {
+        println!("  {distance:4} {count:6}");
+    }
+
+    if let Some(path) = svg {
+        SvgPrinter::new(path.to_owned(), None)
+            .print((mx, my), analysis.diameter, &grid)
+            .unwrap_or_else(|err| exit_with(&format!("failed to write {}: {err}", path.display())));
+    }
+}
+
+/// A board from a PBM or PGM image, a spec file or an ascii drawing
+fn read_board(bytes: &[u8]) -> Result<BoardSpec, String> {
+    if let [b'P', b'1'..=b'6', ..] = bytes {
+        return parse_netpbm(bytes);
+    }
+
+    let text = std::str::from_utf8(bytes).map_err(|_| "board is neither an image nor text")?;
+    match spec::parse_specs(text) {
+        Ok(specs) if !specs.is_empty() => Ok(specs[0].clone()),
+        _ => {
+            let rows = text
+                .lines()
+                .map(str::trim)
+                .filter(|row| !row.is_empty())
+                .collect::<Vec<_>>();
+            spec::parse_ascii_board(&rows)
+        }
+    }
+}
+
+fn what_if(file: &Path) {
+    let solution = read_solution(file);
+    let what_if = WhatIf::new(&solution);
+    let describe = |piece: PositionedPentonimo| {
+        let (x, y) = piece.position();
+        format!("{} {} {x} {y}", piece.pentonimo().kind(), piece.variant())
+    };
+
+    println!("diameter {}", what_if.diameter);
+    println!("without                diameter  change");
+    for (piece, diameter) in what_if.removed {
+        println!(
+            "  {:20} {diameter:8}  {:+6}",
+            describe(piece),
+            diameter as i64 - what_if.diameter as i64
+        );
+    }
+    println!("with one more          diameter  change");
+    for (kind, best) in what_if.added {
+        match best {
+            Some((piece, diameter)) => println!(
+                "  {:20} {diameter:8}  {:+6}",
+                describe(piece),
+                diameter as i64 - what_if.diameter as i64
+            ),
+            None => println!("  {kind:20} doesn't fit"),
+        }
+    }
+}
+
+fn read_solution(file: &Path) -> Solution {
+    std::fs::read_to_string(file)
+        .map_err(|err| format!("failed to read {}: {err}", file.display()))
+        .and_then(|text| text.parse::<Solution>())
+        .unwrap_or_else(|err| exit_with(&err))
+}
+
+fn maze(file: &Path, format: MazeFormat, output: Option<&Path>) {
+    let solution = read_solution(file);
+    let maze = Maze::new(&solution);
+    let text = match format {
+        MazeFormat::Text => maze.to_text(),
+        MazeFormat::Json => maze.to_json(),
+        MazeFormat::Svg => maze.to_svg(),
+    };
+
+    match output {
+        Some(path) => std::fs::write(path, text)
+            .unwrap_or_else(|err| exit_with(&format!("failed to write {}: {err}", path.display()))),
+        None => print!("{text}"),
+    }
+}
+
+/// Renders a saved solution again, to the console or to an SVG or PNG `output`,
+/// with the [`Bottlenecks`] between the ends of its path highlighted if
+/// `bottlenecks` is set
+fn show(
+    file: &Path,
+    format: ShowFormat,
+    output: Option<&Path>,
+    scale: Option<u32>,
+    bottlenecks: bool,
+    palette: Palette,
+    options: &cli::Options,
+) {
+    let solution = read_solution(file);
+    solution
+        .verify()
+        .unwrap_or_else(|err| exit_with(&format!("{}: {err}", file.display())));
+    let style = (!options.no_path).then_some(options.path_style);
+    let letters = options.letters;
+    let mut grid = build_print_map(&solution, style);
+    let (shape, max) = (solution.spec.shape, solution.diameter);
+
+    let dead_ends = options
+        .dead_ends
+        .then(|| mark_dead_ends(&solution, &mut grid));
+    let bottlenecks = bottlenecks.then(|| {
+        let map = solution.map();
+        let (_, ends) = BfsScratch::new(shape).objective(&map, &solution.spec.ends);
+        let bottlenecks = Bottlenecks::new(&map, ends);
+        for &Point(x, y) in &bottlenecks.cells {
+            grid[(y * shape.0 + x) as usize] = PrintValue::Bottleneck;
+        }
+        bottlenecks
+    });
+
+    let result = match (format, output) {
+        (ShowFormat::Svg, Some(path)) => SvgPrinter {
+            scale: scale.unwrap_or(100),
+            palette,
+            letters,
+            ..SvgPrinter::new(path.to_owned(), Some(&solution))
+        }
+        .print(shape, max, &grid),
+        (ShowFormat::Png, Some(path)) => PngPrinter {
+            path: path.to_owned(),
+            scale: scale.unwrap_or(PngPrinter::DEFAULT_SCALE),
+            palette,
+        }
+        .print(shape, max, &grid),
+        (ShowFormat::AnimatedSvg, Some(path)) => AnimatedSvgPrinter {
+            path: path.to_owned(),
+            solution: &solution,
+            style,
+            scale: scale.unwrap_or(100),
+            palette,
+        }
+        .print(shape, max, &grid),
+        (ShowFormat::Json, output) => JsonPrinter {
+            path: output.map(Path::to_owned),
+            solution: &solution,
+            style,
+        }
+        .print(shape, max, &grid),
+        _ => ConsolePrinter(palette.console.then_some(&palette)).print(shape, max, &grid),
+    };
+    if let (Err(err), Some(path)) = (result, output) {
+        exit_with(&format!("failed to write {}: {err}", path.display()));
+    }
+
+    if let Some(dead_ends) = dead_ends {
+        print_dead_ends(&dead_ends);
+    }
+    if let Some(bottlenecks) = bottlenecks {
+        let GridPath(Point(sx, sy), Point(ex, ey)) = bottlenecks.ends;
+        let cells = bottlenecks
+            .cells
+            .iter()
+            .map(|Point(x, y)| format!("{x},{y}"))
+            .collect::<Vec<_>>();
+        println!(
+            "bottlenecks of {sx},{sy} -> {ex},{ey}: width {}, {} cells: {}",
+            bottlenecks.width,
+            cells.len(),
+            cells.join(" ")
+        );
+    }
+}
+
+/// The [`DeadEnds`] of a solution, marked on the cells of its grid that
+/// aren't part of the path
+fn mark_dead_ends(solution: &Solution, grid: &mut [PrintValue]) -> DeadEnds {
+    let dead_ends = DeadEnds::new(&solution.map());
+    let mx = solution.spec.shape.0;
+    for &Point(x, y) in &dead_ends.cells {
+        let cell = &mut grid[(y * mx + x) as usize];
+        if *cell == PrintValue::Nothing {
+            *cell = PrintValue::DeadEnd;
+        }
+    }
+    dead_ends
+}
+
+fn print_dead_ends(dead_ends: &DeadEnds) {
+    println!(
+        "dead ends {}, corridors {:.2} cells long on average, at most {}",
+        dead_ends.cells.len(),
+        dead_ends.mean(),
+        dead_ends.longest()
+    );
+}
+
+/// The result files in `dir`, one for every board. Of the `.txt`, `.json` and
+/// `.svg` files of a result only the first one is picked.
+fn result_files(dir: &Path) -> Vec<PathBuf> {
+    let entries = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| exit_with(&format!("failed to read {}: {err}", dir.display())));
+    let mut results = BTreeMap::<String, Vec<PathBuf>>::new();
+    for entry in entries {
+        let path = entry
+            .unwrap_or_else(|err| exit_with(&format!("failed to read {}: {err}", dir.display())))
+            .path();
+        let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+            continue;
+        };
+        let stem = stem.to_string_lossy().into_owned();
+        let extension = extension.to_string_lossy();
+        // the cache and the summary aren't results of single boards, and files
+        // like `5_4.stats.json` and `5_4.heatmap.svg` are about a result
+        if matches!(extension.as_ref(), "txt" | "json" | "svg")
+            && !matches!(stem.as_str(), "cache" | "summary")
+            && !stem.contains('.')
+        {
+            results.entry(stem).or_default().push(path);
+        }
+    }
+
+    results
+        .into_values()
+        .map(|mut paths| {
+            paths.sort_by_key(|path| {
+                ["txt", "json", "svg"]
+                    .iter()
+                    .position(|&extension| path.extension().unwrap() == extension)
+            });
+            paths.swap_remove(0)
+        })
+        .collect()
+}
+
+/// the solution in a result file of [`result_files`]
+fn read_result(path: &Path) -> Result<Solution, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        Solution::from_json(&text.parse()?)
+    } else {
+        text.parse()
+    }
+}
+
+/// Prints the [`Stats`] of the solutions in `dir`
+fn stats(dir: &Path, recompute: bool) {
+    let mut stats = Stats::default();
+    let mut invalid = 0;
+    for path in &result_files(dir) {
+        let solution = read_result(path).and_then(|mut solution| {
+            if recompute {
+                let map = solution.map();
+                let (diameter, _) =
+                    BfsScratch::new(solution.spec.shape).objective(&map, &solution.spec.ends);
+                if diameter != solution.diameter {
+                    eprintln!(
+                        "warning: {} records a diameter of {}, but has a diameter of {diameter}",
+                        path.display(),
+                        solution.diameter
+                    );
+                    solution.diameter = diameter;
+                }
+                solution.verify()?;
+            }
+            Ok(solution)
+        });
+        match solution {
+            Ok(solution) => stats.add(&solution),
+            Err(err) => {
+                eprintln!("warning: skipping {}: {err}", path.display());
+                invalid += 1;
+            }
+        }
+    }
+
+    println!("{} boards in {}", stats.boards(), dir.display());
+    if invalid > 0 {
+        println!("{invalid} files skipped");
+    }
+    if stats.boards() > 0 {
+        print!("\n{}", stats.to_table());
+    }
+}
+
+/// Prints the trace of `solve --trace` in `file` as the folded stacks of
+/// [`trace::folded`] or writes them to `output`
+fn fold_trace(file: &Path, output: Option<&Path>) {
+    let nodes = std::fs::read_to_string(file)
+        .map_err(|err| err.to_string())
+        .and_then(|text| trace::parse_trace(&text))
+        .unwrap_or_else(|err| exit_with(&format!("invalid trace {}: {err}", file.display())));
+    let folded = trace::folded(&nodes);
+    match output {
+        Some(path) => std::fs::write(path, folded)
+            .unwrap_or_else(|err| exit_with(&format!("failed to write {}: {err}", path.display()))),
+        None => print!("{folded}"),
+    }
+}
+
+/// Runs every strategy on `board` for at most `budget` and prints a table of
+/// what they found. The heuristic results aren't polished like in `solve`.
+fn bench_strategies(board: &BoardSpec, budget: Duration, seed: Option<u64>) {
+    let rng = || seed.map_or_else(Rng::from_time, Rng::new);
+    let config = SearchConfig {
+        time_limit: Some(budget),
+        ..Default::default()
+    };
+    let mut lns = Lns::new(budget, rng());
+    lns.config = config.clone();
+    let strategies: [Box<dyn SearchStrategy>; 4] = [
+        Box::new(BruteForce(config)),
+        Box::new(lns),
+        Box::new(Tabu::new(budget, rng())),
+        Box::new(Greedy),
+    ];
+
+    println!(
+        "{:<12} {:>8} {:>12} {:>12} {:>12}",
+        "strategy", "diameter", "time to best", "time", "nodes"
+    );
+    for mut strategy in strategies {
+        let start = Instant::now();
+        let (solution, stats) = strategy.search_with_stats(board);
+        let time = start.elapsed();
+        if let Err(err) = solution.verify() {
+            exit_with(&format!(
+                "{} found an invalid solution: {err}",
+                strategy.name()
+            ));
+        }
+        println!(
+            "{:<12} {:>8} {:>12} {:>12} {:>12}",
+            strategy.name(),
+            solution.diameter,
+            format!("{:.3?}", stats.time_to_best),
+            format!("{time:.3?}"),
+            stats.nodes
+        );
+    }
+}
+
+/// Prints the estimates of [`Sampling`] for `board` and its best construction
+fn sample(board: &BoardSpec, samples: u32, seed: Option<u64>) {
+    let start = Instant::now();
+    let estimate = Sampling::new(samples, seed.map_or_else(Rng::from_time, Rng::new)).run(board);
+    if let Err(err) = estimate.best.verify() {
+        exit_with(&format!("sampling found an invalid solution: {err}"));
+    }
+
+    let (mean, (mean_low, mean_high)) = estimate.mean();
+    let (hit_rate, (hit_low, hit_high)) = estimate.hit_rate();
+    let (maximum, maximum_high) = estimate.maximum();
+    println!(
+        "{board}: {samples} constructions in {:.3?}",
+        start.elapsed()
+    );
+    println!("  mean diameter {mean:.2} ({mean_low:.2} to {mean_high:.2})");
+    println!(
+        "  best diameter {}, reached by {:.1}% ({:.1}% to {:.1}%)",
+        estimate.best.diameter,
+        100. * hit_rate,
+        100. * hit_low,
+        100. * hit_high
+    );
+    println!("  estimated maximum {maximum:.1} (at most {maximum_high:.1})");
+    if let Some(known) = best_known::best_known_for(board) {
+        let optimal = if known.optimal { ", optimal" } else { "" };
+        println!("  best known diameter {}{optimal}", known.diameter);
+    }
+
+    let grid = build_print_map(&estimate.best, Some(PathStyle::FewestTurns));
+    _ = ConsolePrinter(None).print(board.shape, estimate.best.diameter, &grid);
+}
+
+/// Solves `board` with at most 1, 2, ... of its pieces and prints how much
+/// every additional piece adds to the diameter
+fn piece_sweep(board: &BoardSpec, timeout: Option<Duration>, svg: Option<&Path>) {
+    let pieces = board.pieces.iter().map(|&n| n as usize).sum::<usize>();
+    let (mut previous, _) =
+        BfsScratch::new(board.shape).objective(&board.initial_map(), &board.ends);
+    let mut points = vec![(0., previous as f64)];
+    let mut stopped = false;
+
+    println!(
+        "{:>6} {:>8} {:>5} {:>5}",
+        "pieces", "diameter", "gain", "used"
+    );
+    for max in 1..=pieces.min(PentonimoKind::VARIANTS.len()) {
+        let config = SearchConfig {
+            time_limit: timeout,
+            max_pieces: Some(max),
+            ..Default::default()
+        };
+        let result = find_best(board, &config);
+        let gain = result.diameter as i64 - previous as i64;
+        println!(
+            "{max:>6} {:>8} {:>+5} {:>5}{}",
+            result.diameter,
+            gain,
+            result.placed.len(),
+            if result.complete { "" } else { " *" }
+        );
+        stopped |= !result.complete;
+        previous = result.diameter;
+        points.push((max as f64, result.diameter as f64));
+    }
+    if stopped {
+        println!("* stopped by --timeout, the diameter may be larger");
+    }
+
+    if let Some(path) = svg {
+        let mut chart = Chart::new(
+            &format!("diameter of {board} by pieces"),
+            "pieces",
+            "diameter",
+        );
+        chart.add(&board.to_string(), points);
+        std::fs::write(path, chart.to_svg())
+            .unwrap_or_else(|err| exit_with(&format!("failed to write {}: {err}", path.display())));
+    }
+}
+
+/// Distributes `pieces` over `boards` and prints the solution of every board
+fn share(boards: &[BoardSpec], pieces: [u8; KINDS], one_sided: bool, goal: Goal) {
+    let shared = shared_budget::share(boards, pieces, one_sided, goal);
+    for solution in &shared.solutions {
+        println!("{}: {}", solution.spec, solution.diameter);
+        print_grid(
+            solution.spec.shape,
+            &build_print_map(solution, Some(PathStyle::default())),
+            None,
+        );
+    }
+    let goal = match goal {
+        Goal::Sum => "sum",
+        Goal::Min => "smallest",
+    };
+    println!("{goal} of the diameters: {}", shared.total);
+}
+
+#[cfg(feature = "server")]
+fn serve(listen: &str, jobs: &Path, workers: usize) {
+    let queue = server::Queue::open(jobs.to_owned()).unwrap_or_else(|err| exit_with(&err));
+    let listener = TcpListener::bind(listen)
+        .unwrap_or_else(|err| exit_with(&format!("failed to listen on {listen}: {err}")));
+    let config = SearchConfig {
+        threads: Some((backend::default_num_threads() / workers).max(1)),
+        ..Default::default()
+    };
+    println!("listening on {listen}, jobs are kept in {}", jobs.display());
+    if let Err(err) = server::serve(listener, Arc::new(queue), workers, config) {
+        exit_with(&format!("failed to accept connections: {err}"));
+    }
+}
+
+#[cfg(feature = "server")]
+fn coordinate(board: &BoardSpec, listen: &str, shard_size: usize, timeout: Duration, state: &Path) {
+    let coordinator = match std::fs::read_to_string(state) {
+        Ok(text) => {
+            let coordinator = Coordinator::resume(board, shard_size, timeout, &text)
+                .unwrap_or_else(|err| {
+                    exit_with(&format!("invalid state {}: {err}", state.display()))
+                });
+            println!(
+                "resuming {}: {} of {} shards are done, best diameter so far {}",
+                state.display(),
+                coordinator.finished(),
+                coordinator.shards(),
+                coordinator.best().diameter
+            );
+            coordinator
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            if let Some(dir) = state.parent() {
+                std::fs::create_dir_all(dir).unwrap_or_else(|err| {
+                    exit_with(&format!("failed to create {}: {err}", dir.display()))
+                });
+            }
+            Coordinator::new(board, shard_size, timeout)
+        }
+        Err(err) => exit_with(&format!("failed to read {}: {err}", state.display())),
+    };
+    let listener = TcpListener::bind(listen)
+        .unwrap_or_else(|err| exit_with(&format!("failed to listen on {listen}: {err}")));
+    println!(
+        "listening on {listen}, {} has {} shards of up to {shard_size} roots",
+        coordinator.spec(),
+        coordinator.shards()
+    );
+    let best = distributed::coordinate(listener, coordinator, Some(state.to_owned()))
+        .unwrap_or_else(|err| exit_with(&format!("failed to accept workers: {err}")));
+    let best = if board.canonical().1 {
+        best.transposed()
+    } else {
+        best
+    };
+    println!("{board}: {}", best.diameter);
+    print_grid(
+        best.spec.shape,
+        &build_print_map(&best, Some(PathStyle::default())),
+        None,
+    );
+}
+
+#[cfg(feature = "server")]
+fn work(coordinator: &str, name: Option<String>, threads: Option<usize>) {
+    let name = name.unwrap_or_else(|| format!("worker-{}", std::process::id()));
+    let config = SearchConfig {
+        threads,
+        ..Default::default()
+    };
+    let address = coordinator
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .unwrap_or_else(|| exit_with(&format!("invalid coordinator address {coordinator:?}")));
+    match distributed::work(address, &name, &config) {
+        Ok(shards) => println!("{name}: searched {shards} shards"),
+        Err(err) => exit_with(&err),
+    }
+}
 
-    for x in 3..=7 {
-        for y in 3..=7 {
-            if x >= y {
-                let (max, tiles) = find_best((x, y));
-                let grid = build_print_map((x, y), (max, tiles));
-                ConsolePrinter.print((x, y), max, &grid);
-                SvgPrinter(PathBuf::from(format!("results/{x}_{y}.svg"))).print((x, y), max, &grid);
+fn play(board: &BoardSpec, ai_first: bool, playouts: u32) {
+    let mut game = Game::new(board);
+    let mut ai = Ai::new(playouts, Rng::from_time());
+    let ai_player = if ai_first { 0 } else { 1 };
+    let mut lines = std::io::stdin().lines();
+
+    println!("enter moves as `KIND VARIANT X Y`, e.g. `L 0 2 3`");
+    loop {
+        print_grid(board.shape, &piece_grid(board, &game.history), None);
+
+        if game.is_over() {
+            if game.player() == ai_player {
+                println!("no moves left for the computer, you win!");
+            } else {
+                println!("no moves left for you, the computer wins");
+            }
+            return;
+        }
+
+        if game.player() == ai_player {
+            let piece = ai.choose(&game).unwrap();
+            let (x, y) = piece.position();
+            println!(
+                "the computer plays {} {} {x} {y}",
+                piece.pentonimo().kind(),
+                piece.variant()
+            );
+            game.play(piece).unwrap();
+            continue;
+        }
+
+        loop {
+            print!("your move: ");
+            _ = std::io::stdout().flush();
+            let Some(Ok(line)) = lines.next() else {
+                println!();
+                return;
+            };
+            match parse_piece(&line).and_then(|piece| game.play(piece)) {
+                Ok(()) => break,
+                Err(err) => println!("{err}"),
             }
         }
     }
 }
 
+fn exit_with(err: &str) -> ! {
+    eprintln!("error: {err}");
+    std::process::exit(cli::EXIT_ERROR)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PrintValue {
     Pentonimo(PentonimoKind),
+    Hole,
     Nothing,
     Path(usize),
+    /// a cell of smallest eccentricity
+    Center,
+    /// a cell with a single free neighbour
+    DeadEnd,
+    /// a cell of a smallest set of cells that separates the ends of the path
+    Bottleneck,
 }
 
 impl Display for PrintValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            PrintValue::Pentonimo(kind) => {
-                let color = match kind {
-                    PentonimoKind::F => 31,
-                    PentonimoKind::L => 32,
-                    PentonimoKind::N => 33,
-                    PentonimoKind::P => 34,
-                    PentonimoKind::T => 35,
-                    PentonimoKind::U => 36,
-                    PentonimoKind::V => 91,
-                    PentonimoKind::W => 92,
-                    PentonimoKind::I => 93,
-                    PentonimoKind::X => 94,
-                    PentonimoKind::Y => 95,
-                    PentonimoKind::Z => 96,
-                };
-
-                write!(f, "\x1b[{color}mxx\x1b[m")
-            }
+            PrintValue::Pentonimo(kind) => write!(f, "\x1b[{}mxx\x1b[m", ansi_color(*kind)),
+            PrintValue::Hole => write!(f, "##"),
             PrintValue::Nothing => write!(f, ".."),
             PrintValue::Path(n) => write!(f, "\x1b[90m{n:2}\x1b[m"),
+            PrintValue::Center => write!(f, "()"),
+            PrintValue::DeadEnd => write!(f, "<>"),
+            PrintValue::Bottleneck => write!(f, "\x1b[1;31m!!\x1b[m"),
         }
     }
 }
 
-fn build_print_map(
-    (mx, my): (u16, u16),
-    (max, tiles): (u16, Vec<PositionedPentonimo>),
-) -> Vec<PrintValue> {
-    let mut map = TileMap::new((mx, my));
-    for &tile in &tiles {
-        assert!(map.can_place(tile));
-        map |= tile;
+/// the ANSI color of the cells of a kind in the console. The 16 basic colors
+/// run out before the smaller pieces, which get colors of the 256 color palette.
+fn ansi_color(kind: PentonimoKind) -> &'static str {
+    match kind {
+        PentonimoKind::F => "31",
+        PentonimoKind::L => "32",
+        PentonimoKind::N => "33",
+        PentonimoKind::P => "34",
+        PentonimoKind::T => "35",
+        PentonimoKind::U => "36",
+        PentonimoKind::V => "91",
+        PentonimoKind::W => "92",
+        PentonimoKind::I => "93",
+        PentonimoKind::X => "94",
+        PentonimoKind::Y => "95",
+        PentonimoKind::Z => "96",
+        PentonimoKind::I4 => "38;5;208",
+        PentonimoKind::L4 => "38;5;130",
+        PentonimoKind::O4 => "38;5;141",
+        PentonimoKind::S4 => "38;5;71",
+        PentonimoKind::T4 => "38;5;168",
+        PentonimoKind::I3 => "38;5;180",
+        PentonimoKind::L3 => "38;5;67",
+        PentonimoKind::I2 => "38;5;250",
+        PentonimoKind::C1 => "38;5;160",
+        PentonimoKind::C2 => "38;5;28",
+        PentonimoKind::C3 => "38;5;25",
+        PentonimoKind::C4 => "38;5;178",
+        PentonimoKind::C5 => "38;5;90",
     }
+}
 
-    let path = {
-        let mut scratch = BfsScratch::new((mx, my));
-        let (new_max, path) = scratch.graph_diameter(&map);
-        assert_eq!(new_max, max);
-        path
+/// the grid of a solution, with the longest shortest path picked by `style` if
+/// it is set
+fn build_print_map(solution: &Solution, style: Option<PathStyle>) -> Vec<PrintValue> {
+    let mx = solution.spec.shape.0;
+    // asserts that the pieces don't overlap each other or the holes
+    let mut grid = piece_grid(&solution.spec, &solution.pieces);
+    let Some(style) = style else {
+        return grid;
     };
 
+    for (i, point) in longest_path(solution, style).iter().enumerate() {
+        let index = mx as usize * point.1 as usize + point.0 as usize;
+        assert_eq!(grid[index], PrintValue::Nothing);
+        grid[index] = PrintValue::Path(i);
+    }
+
+    grid
+}
+
+/// the cells of the longest shortest path of a solution picked by `style`,
+/// none if the diameter is 0
+fn longest_path(solution: &Solution, style: PathStyle) -> Vec<Point> {
+    if solution.diameter == 0 {
+        return Vec::new();
+    }
+    let map = solution.map();
+    let mut scratch = BfsScratch::new(solution.spec.shape);
+    let (max, ends) = scratch.objective(&map, &solution.spec.ends);
+    assert_eq!(max, solution.diameter);
+    shortest_path(&map, ends, style)
+}
+
+/// the holes and pieces of a board
+fn piece_grid(spec: &BoardSpec, pieces: &[PositionedPentonimo]) -> Vec<PrintValue> {
+    let (mx, my) = spec.shape;
     let mut grid = vec![PrintValue::Nothing; mx as usize * my as usize];
 
+    for &(x, y) in &spec.holes {
+        grid[mx as usize * y as usize + x as usize] = PrintValue::Hole;
+    }
+
     for x in 0..mx {
         for y in 0..my {
-            for tile in &tiles {
+            for tile in pieces {
                 if tile.get(x, y) {
                     let index = mx as usize * y as usize + x as usize;
                     assert_eq!(grid[index], PrintValue::Nothing);
@@ -92,113 +1793,635 @@ fn build_print_map(
         }
     }
 
-    for (i, point) in dijkstra(&map, path).iter().enumerate() {
-        let index = mx as usize * point.1 as usize + point.0 as usize;
-        assert_eq!(grid[index], PrintValue::Nothing);
-        grid[index] = PrintValue::Path(i);
-    }
-
     grid
 }
 
 trait Printer {
-    fn print(&self, shape: (u16, u16), max: u16, grid: &[PrintValue]);
+    fn print(&self, shape: (u32, u32), max: u32, grid: &[PrintValue]) -> io::Result<()>;
 }
 
-struct ConsolePrinter;
-impl Printer for ConsolePrinter {
-    fn print(&self, (mx, my): (u16, u16), max: u16, grid: &[PrintValue]) {
+/// Prints the grid in the colors of the palette, if there is one, and in the
+/// colors of the terminal otherwise
+struct ConsolePrinter<'a>(Option<&'a Palette>);
+
+impl Printer for ConsolePrinter<'_> {
+    fn print(&self, (mx, my): (u32, u32), max: u32, grid: &[PrintValue]) -> io::Result<()> {
         println!("({mx},{my}): {max}");
-        for y in 0..my {
-            for x in 0..mx {
-                print!("{} ", grid[mx as usize * y as usize + x as usize]);
+        print_grid((mx, my), grid, self.0);
+        if let Some(legend) = legend(mx, grid, self.0) {
+            println!("{legend}");
+        }
+        Ok(())
+    }
+}
+
+/// Prints the grid with the ends of its path highlighted
+fn print_grid((mx, my): (u32, u32), grid: &[PrintValue], palette: Option<&Palette>) {
+    let last = path_ends(mx, grid).map(|[_, (last, _)]| last);
+    for y in 0..my {
+        for x in 0..mx {
+            match grid[mx as usize * y as usize + x as usize] {
+                PrintValue::Path(n) if n == 0 || Some(n) == last => {
+                    print!("\x1b[1;7m{n:2}\x1b[m ")
+                }
+                value @ (PrintValue::Pentonimo(_) | PrintValue::Path(_)) if palette.is_some() => {
+                    let color = palette.unwrap().ansi(value);
+                    match value {
+                        PrintValue::Path(n) => print!("{color}{n:2}\x1b[m "),
+                        _ => print!("{color}xx\x1b[m "),
+                    }
+                }
+                value => print!("{value} "),
             }
-            println!();
         }
+        println!();
     }
 }
 
-struct SvgPrinter(PathBuf);
+/// the numbers and positions of the first and the last cell of the path in a
+/// grid
+fn path_ends(mx: u32, grid: &[PrintValue]) -> Option<[(usize, (u32, u32)); 2]> {
+    let position = |index: usize| (index as u32 % mx, index as u32 / mx);
+    let path = grid
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| match value {
+            PrintValue::Path(n) => Some((*n, position(index))),
+            _ => None,
+        });
+    Some([path.clone().min()?, path.max()?])
+}
 
-impl Printer for SvgPrinter {
-    fn print(&self, shape: (u16, u16), max: u16, grid: &[PrintValue]) {
-        let mut file = File::create(&self.0).unwrap();
+/// The kinds of the pieces in their colors, the other symbols of the grid and
+/// the ends of the path, unless the grid is empty
+fn legend(mx: u32, grid: &[PrintValue], palette: Option<&Palette>) -> Option<String> {
+    let mut entries = PentonimoKind::VARIANTS
+        .iter()
+        .filter(|&&kind| grid.contains(&PrintValue::Pentonimo(kind)))
+        .map(|&kind| {
+            let color = match palette {
+                Some(palette) => palette.ansi(PrintValue::Pentonimo(kind)),
+                None => format!("\x1b[{}m", ansi_color(kind)),
+            };
+            format!("{color}{kind}\x1b[m")
+        })
+        .collect::<Vec<_>>();
+    for (value, name) in [
+        (PrintValue::Hole, "hole"),
+        (PrintValue::Center, "center"),
+        (PrintValue::DeadEnd, "dead end"),
+        (PrintValue::Bottleneck, "bottleneck"),
+    ] {
+        if grid.contains(&value) {
+            entries.push(format!("{value} {name}"));
+        }
+    }
+    if let Some([(_, (x0, y0)), (_, (x1, y1))]) = path_ends(mx, grid) {
+        entries.push(format!("path from {x0},{y0} to {x1},{y1}"));
+    }
+    (!entries.is_empty()).then(|| entries.join("  "))
+}
 
-        let scale = 100;
+/// The colors of pictures, from `--theme` and the entries of a `--palette`
+/// file
+#[derive(Debug, Clone)]
+struct Palette {
+    kinds: [String; KINDS],
+    /// empty cells, `none` for transparent ones
+    background: String,
+    hole: String,
+    path: String,
+    center: String,
+    dead_end: String,
+    bottleneck: String,
+    /// the lines between cells and the text
+    lines: String,
+    /// whether the console shows these colors instead of its own, for
+    /// palettes from files
+    console: bool,
+}
 
-        let mut write = || {
-            writeln!(
-                file,
-                r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">"#,
-                shape.0 * scale,
-                shape.1 * scale
-            )?;
+impl Palette {
+    fn new(theme: Theme) -> Self {
+        let kinds = std::array::from_fn(|i| {
+            match PentonimoKind::VARIANTS[i] {
+                // PentonimoKind::F => "#ed7b24",
+                // PentonimoKind::L => "#d479ed",
+                // PentonimoKind::N => "#007fff",
+                // PentonimoKind::P => "#57f26e",
+                // PentonimoKind::T => "#3252c7",
+                // PentonimoKind::U => "#640eb0",
+                // PentonimoKind::V => "#85fdff",
+                // PentonimoKind::W => "#1fb585",
+                // PentonimoKind::I => "#ff1745",
+                // PentonimoKind::X => "#ff85de",
+                // PentonimoKind::Y => "#089c08",
+                // PentonimoKind::Z => "#ffd417",
+                PentonimoKind::F => "#ed1515",
+                PentonimoKind::L => "#11d116",
+                PentonimoKind::N => "#f67400",
+                PentonimoKind::P => "#1d99f3",
+                PentonimoKind::T => "#9b59b6",
+                PentonimoKind::U => "#1abc9c",
+                PentonimoKind::V => "#c0392b",
+                PentonimoKind::W => "#1cdc9a",
+                PentonimoKind::I => "#fdbc4b",
+                PentonimoKind::X => "#3daee9",
+                PentonimoKind::Y => "#8e44ad",
+                PentonimoKind::Z => "#16a085",
+                PentonimoKind::I4 => "#ff8700",
+                PentonimoKind::L4 => "#af5f00",
+                PentonimoKind::O4 => "#af87ff",
+                PentonimoKind::S4 => "#5faf5f",
+                PentonimoKind::T4 => "#d75f87",
+                PentonimoKind::I3 => "#d7af87",
+                PentonimoKind::L3 => "#5f87af",
+                PentonimoKind::I2 => "#bcbcbc",
+                PentonimoKind::C1 => "#d70000",
+                PentonimoKind::C2 => "#008700",
+                PentonimoKind::C3 => "#005faf",
+                PentonimoKind::C4 => "#d7af00",
+                PentonimoKind::C5 => "#870087",
+            }
+            .to_owned()
+        });
+        let [background, hole, path, center, dead_end, bottleneck, lines] = match theme {
+            Theme::Light => ["none", "#333", "#bbb", "#fdbc4b", "#ddd", "#da4453", "#000"],
+            Theme::Dark => ["#222", "#000", "#555", "#fdbc4b", "#444", "#ed1515", "#ddd"],
+        }
+        .map(str::to_owned);
+        Palette {
+            kinds,
+            background,
+            hole,
+            path,
+            center,
+            dead_end,
+            bottleneck,
+            lines,
+            console: false,
+        }
+    }
+
+    /// The palette of `theme` with the colors of a JSON file like
+    /// `{"kinds": {"F": "#ed1515"}, "path": "#bbb"}`. Its other fields are
+    /// `background`, `hole`, `center`, `dead_end`, `bottleneck` and `lines`.
+    fn load(path: &Path, theme: Theme) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?
+            .parse::<Json>()
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        let Json::Object(fields) = json else {
+            return Err(format!("{}: the palette isn't an object", path.display()));
+        };
+
+        let mut palette = Palette {
+            console: true,
+            ..Palette::new(theme)
+        };
+        for (key, value) in &fields {
+            let color = |value: &Json| match value {
+                Json::String(color)
+                    if is_color(color) || (key == "background" && color == "none") =>
+                {
+                    Ok(color.clone())
+                }
+                _ => Err(format!(
+                    "{}: invalid color {value} of {key:?}",
+                    path.display()
+                )),
+            };
+            match key.as_str() {
+                "kinds" => {
+                    let Json::Object(kinds) = value else {
+                        return Err(format!("{}: the kinds aren't an object", path.display()));
+                    };
+                    for (kind, value) in kinds {
+                        let kind = kind
+                            .parse::<PentonimoKind>()
+                            .map_err(|_| format!("{}: unknown kind {kind:?}", path.display()))?;
+                        palette.kinds[kind as usize] = color(value)?;
+                    }
+                }
+                "background" => palette.background = color(value)?,
+                "hole" => palette.hole = color(value)?,
+                "path" => palette.path = color(value)?,
+                "center" => palette.center = color(value)?,
+                "dead_end" => palette.dead_end = color(value)?,
+                "bottleneck" => palette.bottleneck = color(value)?,
+                "lines" => palette.lines = color(value)?,
+                _ => return Err(format!("{}: unknown palette entry {key:?}", path.display())),
+            }
+        }
+        Ok(palette)
+    }
+
+    /// the color of a cell in SVG and PNG pictures
+    fn fill(&self, value: PrintValue) -> &str {
+        match value {
+            PrintValue::Pentonimo(kind) => &self.kinds[kind as usize],
+            PrintValue::Hole => &self.hole,
+            PrintValue::Nothing => &self.background,
+            PrintValue::Path(_) => &self.path,
+            PrintValue::Center => &self.center,
+            PrintValue::DeadEnd => &self.dead_end,
+            PrintValue::Bottleneck => &self.bottleneck,
+        }
+    }
+
+    /// the escape sequence of the color of a cell in the console
+    fn ansi(&self, value: PrintValue) -> String {
+        let [r, g, b] = rgb(self.fill(value));
+        format!("\x1b[38;2;{r};{g};{b}m")
+    }
+}
+
+/// the palette of a `--palette` file or of `theme`
+fn load_palette(file: Option<&Path>, theme: Theme) -> Palette {
+    match file {
+        Some(file) => Palette::load(file, theme).unwrap_or_else(|err| exit_with(&err)),
+        None => Palette::new(theme),
+    }
+}
 
+/// `#rgb` or `#rrggbb`
+fn is_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// `#rgb` or `#rrggbb` as bytes, `none` as white
+fn rgb(color: &str) -> [u8; 3] {
+    let Some(hex) = color.strip_prefix('#') else {
+        return [255; 3];
+    };
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).unwrap();
+    match hex.len() {
+        3 => [0, 1, 2].map(|i| digit(i) * 17),
+        _ => [0, 2, 4].map(|i| digit(i) * 16 + digit(i + 1)),
+    }
+}
+
+/// Writes the grid to a file, with the solution it shows in a `<desc>` that
+/// can be read again
+struct SvgPrinter<'a> {
+    path: PathBuf,
+    solution: Option<&'a Solution>,
+    /// the size of a cell
+    scale: u32,
+    palette: Palette,
+    letters: Letters,
+}
+
+impl<'a> SvgPrinter<'a> {
+    fn new(path: PathBuf, solution: Option<&'a Solution>) -> Self {
+        SvgPrinter {
+            path,
+            solution,
+            scale: 100,
+            palette: Palette::new(Theme::Light),
+            letters: Letters::None,
+        }
+    }
+}
+
+impl Printer for SvgPrinter<'_> {
+    fn print(&self, shape: (u32, u32), _max: u32, grid: &[PrintValue]) -> io::Result<()> {
+        // one write per cell, so they have to be buffered
+        let mut file = BufWriter::new(File::create(&self.path)?);
+
+        let scale = self.scale;
+        let ink = &self.palette.lines;
+
+        writeln!(
+            file,
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">"#,
+            shape.0 * scale,
+            shape.1 * scale
+        )?;
+        if let Some(solution) = self.solution {
+            writeln!(file, "{}", solution.svg_desc())?;
+        }
+
+        // the path is a layer of its own, so viewers can hide it
+        let has_path = grid
+            .iter()
+            .any(|value| matches!(value, PrintValue::Path(_)));
+        for path_layer in [false, true] {
+            if path_layer {
+                if !has_path {
+                    break;
+                }
+                writeln!(
+                    file,
+                    r#"<g id="path" inkscape:groupmode="layer" inkscape:label="path">"#
+                )?;
+            } else {
+                writeln!(file, r#"<g id="board">"#)?;
+            }
             for y in 0..shape.1 {
                 for x in 0..shape.0 {
                     let index = shape.0 as usize * y as usize + x as usize;
+                    if matches!(grid[index], PrintValue::Path(_)) != path_layer {
+                        continue;
+                    }
 
-                    let color = match grid[index] {
-                        PrintValue::Pentonimo(kind) => match kind {
-                            // PentonimoKind::F => "#ed7b24",
-                            // PentonimoKind::L => "#d479ed",
-                            // PentonimoKind::N => "#007fff",
-                            // PentonimoKind::P => "#57f26e",
-                            // PentonimoKind::T => "#3252c7",
-                            // PentonimoKind::U => "#640eb0",
-                            // PentonimoKind::V => "#85fdff",
-                            // PentonimoKind::W => "#1fb585",
-                            // PentonimoKind::I => "#ff1745",
-                            // PentonimoKind::X => "#ff85de",
-                            // PentonimoKind::Y => "#089c08",
-                            // PentonimoKind::Z => "#ffd417",
-                            PentonimoKind::F => "#ed1515",
-                            PentonimoKind::L => "#11d116",
-                            PentonimoKind::N => "#f67400",
-                            PentonimoKind::P => "#1d99f3",
-                            PentonimoKind::T => "#9b59b6",
-                            PentonimoKind::U => "#1abc9c",
-                            PentonimoKind::V => "#c0392b",
-                            PentonimoKind::W => "#1cdc9a",
-                            PentonimoKind::I => "#fdbc4b",
-                            PentonimoKind::X => "#3daee9",
-                            PentonimoKind::Y => "#8e44ad",
-                            PentonimoKind::Z => "#16a085",
-                        },
-                        PrintValue::Nothing => "none",
-                        PrintValue::Path(n) => {
-                            writeln!(
-                                file,
-                                r##"<g>
-                                    <rect x="{x}" y="{y}" width="{scale}" height="{scale}" fill="#bbb" stroke="black" stroke-width="{sw}" />
-                                    <text x="{tx}" y="{ty}" font-size="{fw}" text-anchor="middle">{n}</text>
-                                </g>"##,
-                                x = scale * x,
-                                y = scale * y,
-                                tx = (scale * x) as f32 + scale as f32 / 2.,
-                                ty = (scale * y) as f32 + scale as f32 / 1.5,
-                                sw = scale as f32 / 200.,
-                                fw = scale as f32 / 2.
-                            )?;
-                            continue;
-                        }
-                    };
+                    let color = self.palette.fill(grid[index]);
+                    if let PrintValue::Path(n) = grid[index] {
+                        writeln!(
+                            file,
+                            r##"<g>
+                                <rect x="{x}" y="{y}" width="{scale}" height="{scale}" fill="{color}" stroke="{ink}" stroke-width="{sw}" />
+                                <text x="{tx}" y="{ty}" font-size="{fw}" text-anchor="middle" fill="{ink}">{n}</text>
+                            </g>"##,
+                            x = scale * x,
+                            y = scale * y,
+                            tx = (scale * x) as f32 + scale as f32 / 2.,
+                            ty = (scale * y) as f32 + scale as f32 / 1.5,
+                            sw = scale as f32 / 200.,
+                            fw = scale as f32 / 2.
+                        )?;
+                        continue;
+                    }
 
                     write!(
                         file,
-                        r#"<rect x="{x}" y="{y}" width="{scale}" height="{scale}" fill="{color}" stroke="black" stroke-width="{sw}" />"#,
+                        r#"<rect x="{x}" y="{y}" width="{scale}" height="{scale}" fill="{color}" stroke="{ink}" stroke-width="{sw}" />"#,
                         x = scale * x,
                         y = scale * y,
                         sw = scale as f32 / 200.,
                     )?;
                 }
             }
+            writeln!(file, "</g>")?;
+        }
+
+        let lettered = lettered_cells(shape, grid, self.letters);
+        if !lettered.is_empty() {
+            writeln!(file, r#"<g id="letters">"#)?;
+            for index in lettered {
+                let PrintValue::Pentonimo(kind) = grid[index] else {
+                    unreachable!()
+                };
+                let (x, y) = (index as u32 % shape.0, index as u32 / shape.0);
+                writeln!(
+                    file,
+                    r#"<text x="{tx}" y="{ty}" font-size="{fw}" text-anchor="middle" fill="{ink}">{kind}</text>"#,
+                    tx = (scale * x) as f32 + scale as f32 / 2.,
+                    ty = (scale * y) as f32 + scale as f32 / 1.5,
+                    fw = scale as f32 / 2.
+                )?;
+            }
+            writeln!(file, "</g>")?;
+        }
 
-            writeln!(file, "</svg>")?;
+        writeln!(file, "</svg>")?;
+        file.flush()
+    }
+}
 
-            std::io::Result::Ok(())
+/// The indices of the piece cells that show their kind. With [`Letters::One`]
+/// it's the cell closest to the middle of every area of cells of one kind, so
+/// touching pieces of the same kind get a single letter.
+fn lettered_cells((mx, my): (u32, u32), grid: &[PrintValue], letters: Letters) -> Vec<usize> {
+    let is_piece = |index: usize| matches!(grid[index], PrintValue::Pentonimo(_));
+    match letters {
+        Letters::None => Vec::new(),
+        Letters::All => (0..grid.len()).filter(|&index| is_piece(index)).collect(),
+        Letters::One => {
+            let mut seen = vec![false; grid.len()];
+            let mut cells = Vec::new();
+            for start in (0..grid.len()).filter(|&index| is_piece(index)) {
+                if seen[start] {
+                    continue;
+                }
+                seen[start] = true;
+                let mut area = vec![start];
+                let mut i = 0;
+                while let Some(&index) = area.get(i) {
+                    i += 1;
+                    let (x, y) = (index as u32 % mx, index as u32 / mx);
+                    let neighbours = [
+                        (x > 0).then(|| index - 1),
+                        (x + 1 < mx).then(|| index + 1),
+                        (y > 0).then(|| index - mx as usize),
+                        (y + 1 < my).then(|| index + mx as usize),
+                    ];
+                    for next in neighbours.into_iter().flatten() {
+                        if !seen[next] && grid[next] == grid[start] {
+                            seen[next] = true;
+                            area.push(next);
+                        }
+                    }
+                }
+
+                let position =
+                    |index: usize| ((index as u32 % mx) as f32, (index as u32 / mx) as f32);
+                let n = area.len() as f32;
+                let (cx, cy) = area.iter().fold((0., 0.), |(sx, sy), &index| {
+                    let (x, y) = position(index);
+                    (sx + x / n, sy + y / n)
+                });
+                let distance = |index: usize| {
+                    let (x, y) = position(index);
+                    (x - cx).powi(2) + (y - cy).powi(2)
+                };
+                cells.extend(
+                    area.into_iter()
+                        .min_by(|&a, &b| distance(a).total_cmp(&distance(b))),
+                );
+            }
+            cells
+        }
+    }
+}
+
+/// Writes an SVG in which the pieces fade in one after the other in the order
+/// they were placed, and then the cells of the path light up from one end to
+/// the other. Like [`SvgPrinter`] it has the solution in its `<desc>`.
+struct AnimatedSvgPrinter<'a> {
+    path: PathBuf,
+    solution: &'a Solution,
+    /// the path that is drawn, if any
+    style: Option<PathStyle>,
+    /// the size of a cell
+    scale: u32,
+    palette: Palette,
+}
+
+impl AnimatedSvgPrinter<'_> {
+    /// seconds between two pieces
+    const PIECE_STEP: f32 = 0.6;
+    /// seconds a piece takes to fade in
+    const FADE: f32 = 0.4;
+    /// seconds between two cells of the path
+    const PATH_STEP: f32 = 0.08;
+}
+
+impl Printer for AnimatedSvgPrinter<'_> {
+    fn print(&self, shape: (u32, u32), _max: u32, grid: &[PrintValue]) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(&self.path)?);
+
+        let scale = self.scale;
+        let ink = &self.palette.lines;
+        let sw = scale as f32 / 200.;
+        let rect = |(x, y): (u32, u32), color: &str| {
+            format!(
+                r#"<rect x="{}" y="{}" width="{scale}" height="{scale}" fill="{color}" stroke="{ink}" stroke-width="{sw}" />"#,
+                scale * x,
+                scale * y
+            )
         };
+        // hidden until `begin`, then fading in and staying
+        let fade_in = |begin: f32, duration: f32| {
+            format!(
+                r#"<animate attributeName="opacity" from="0" to="1" begin="{begin:.2}s" dur="{duration:.2}s" fill="freeze" />"#
+            )
+        };
+
+        writeln!(
+            file,
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            shape.0 * scale,
+            shape.1 * scale
+        )?;
+        writeln!(file, "{}", self.solution.svg_desc())?;
+
+        // the empty board, with the cells of the pieces and the path left empty
+        writeln!(file, r#"<g id="board">"#)?;
+        for y in 0..shape.1 {
+            for x in 0..shape.0 {
+                let value = match grid[(shape.0 * y + x) as usize] {
+                    PrintValue::Pentonimo(_) | PrintValue::Path(_) => PrintValue::Nothing,
+                    value => value,
+                };
+                write!(file, "{}", rect((x, y), self.palette.fill(value)))?;
+            }
+        }
+        writeln!(file, "</g>")?;
+
+        writeln!(file, r#"<g id="pieces">"#)?;
+        for (i, piece) in self.solution.pieces.iter().enumerate() {
+            let color = self
+                .palette
+                .fill(PrintValue::Pentonimo(piece.pentonimo().kind()));
+            write!(
+                file,
+                r#"<g opacity="0">{}"#,
+                fade_in(i as f32 * Self::PIECE_STEP, Self::FADE)
+            )?;
+            for y in 0..shape.1 {
+                for x in 0..shape.0 {
+                    if piece.get(x, y) {
+                        write!(file, "{}", rect((x, y), color))?;
+                    }
+                }
+            }
+            writeln!(file, "</g>")?;
+        }
+        writeln!(file, "</g>")?;
+
+        if let Some(style) = self.style {
+            let start = self.solution.pieces.len() as f32 * Self::PIECE_STEP;
+            writeln!(file, r#"<g id="path">"#)?;
+            for (n, &Point(x, y)) in longest_path(self.solution, style).iter().enumerate() {
+                writeln!(
+                    file,
+                    r#"<g opacity="0">{}{}<text x="{tx}" y="{ty}" font-size="{fw}" text-anchor="middle" fill="{ink}">{n}</text></g>"#,
+                    fade_in(start + n as f32 * Self::PATH_STEP, Self::PATH_STEP),
+                    rect((x, y), &self.palette.path),
+                    tx = (scale * x) as f32 + scale as f32 / 2.,
+                    ty = (scale * y) as f32 + scale as f32 / 1.5,
+                    fw = scale as f32 / 2.
+                )?;
+            }
+            writeln!(file, "</g>")?;
+        }
+
+        writeln!(file, "</svg>")?;
+        file.flush()
+    }
+}
+
+/// Writes the solution as JSON, see `schema/v1/solution.schema.json`, to a file
+/// or to stdout without one. Other programs get the cells of the path in
+/// order, instead of reading them from the grid.
+struct JsonPrinter<'a> {
+    path: Option<PathBuf>,
+    solution: &'a Solution,
+    /// the path that is written, if any
+    style: Option<PathStyle>,
+}
+
+impl Printer for JsonPrinter<'_> {
+    fn print(&self, _shape: (u32, u32), _max: u32, _grid: &[PrintValue]) -> io::Result<()> {
+        let Json::Object(mut fields) = self.solution.to_json() else {
+            unreachable!("solutions are objects");
+        };
+        if let Some(style) = self.style {
+            let cells = longest_path(self.solution, style);
+            if !cells.is_empty() {
+                let cells = cells.iter().map(|&Point(x, y)| vec![x, y].into());
+                fields.push(("path".to_owned(), Json::Array(cells.collect())));
+            }
+        }
+        let text = format!("{}\n", Json::Object(fields));
+        match &self.path {
+            Some(path) => std::fs::write(path, text),
+            None => io::stdout().write_all(text.as_bytes()),
+        }
+    }
+}
+
+/// Writes the grid to a PNG image with `scale` pixels per cell. The cells of
+/// the path are colored, but without their numbers.
+struct PngPrinter {
+    path: PathBuf,
+    scale: u32,
+    palette: Palette,
+}
+
+impl PngPrinter {
+    /// pixels per cell without `--scale`
+    const DEFAULT_SCALE: u32 = 24;
+}
+
+impl Printer for PngPrinter {
+    fn print(&self, (mx, my): (u32, u32), _max: u32, grid: &[PrintValue]) -> io::Result<()> {
+        let scale = self.scale;
+        let (width, height) = (mx * scale + 1, my * scale + 1);
+
+        let mut palette = vec![rgb(&self.palette.lines)];
+        let mut index = |color: &str| {
+            let color = rgb(color);
+            match palette.iter().position(|&known| known == color) {
+                Some(i) => i as u8,
+                None => {
+                    palette.push(color);
+                    palette.len() as u8 - 1
+                }
+            }
+        };
+        let colors: Vec<u8> = grid
+            .iter()
+            .map(|&value| index(self.palette.fill(value)))
+            .collect();
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                // lines between the cells, unless they are too small for them
+                let line = scale >= 4 && (x % scale == 0 || y % scale == 0);
+                let (cx, cy) = ((x / scale).min(mx - 1), (y / scale).min(my - 1));
+                pixels.push(if line {
+                    0
+                } else {
+                    colors[mx as usize * cy as usize + cx as usize]
+                });
+            }
+        }
 
-        write().unwrap();
+        std::fs::write(&self.path, png::encode(width, height, &palette, &pixels))
     }
 }
@@ -0,0 +1,149 @@
+//! Turns a solution into a maze: the pieces and holes become walls and the
+//! entrance and exit are the endpoints of the longest shortest path, so solving
+//! the maze takes as long as possible.
+
+use std::fmt::Write;
+
+use crate::{
+    backend::default_num_threads,
+    pathfinding::{BfsScratch, Path, Point},
+    solution::Solution,
+};
+
+pub struct Maze {
+    pub shape: (u32, u32),
+    /// row major
+    pub walls: Vec<bool>,
+    pub entrance: (u32, u32),
+    pub exit: (u32, u32),
+}
+
+impl Maze {
+    pub fn new(solution: &Solution) -> Self {
+        let map = solution.map();
+        let shape = solution.spec.shape;
+        let (_, Path(Point(ex, ey), Point(xx, xy))) = BfsScratch::new(shape)
+            .with_threads(default_num_threads())
+            .objective(&map, &solution.spec.ends);
+
+        let mut walls = Vec::with_capacity(shape.0 as usize * shape.1 as usize);
+        for y in 0..shape.1 {
+            for x in 0..shape.0 {
+                walls.push(map.get(x, y));
+            }
+        }
+
+        Self {
+            shape,
+            walls,
+            entrance: (ex, ey),
+            exit: (xx, xy),
+        }
+    }
+
+    fn cell(&self, x: u32, y: u32) -> char {
+        if (x, y) == self.entrance {
+            'S'
+        } else if (x, y) == self.exit {
+            'E'
+        } else if self.walls[(y * self.shape.0 + x) as usize] {
+            '#'
+        } else {
+            '.'
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.shape.1).map(|y| (0..self.shape.0).map(|x| self.cell(x, y)).collect())
+    }
+
+    /// one line per row, `#` for walls, `S` for the entrance and `E` for the exit
+    pub fn to_text(&self) -> String {
+        self.rows().map(|row| row + "\n").collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let rows = self
+            .rows()
+            .map(|row| format!("\"{row}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{{\"width\": {}, \"height\": {}, \"entrance\": [{}, {}], \"exit\": [{}, {}], \"rows\": [{rows}]}}\n",
+            self.shape.0, self.shape.1, self.entrance.0, self.entrance.1, self.exit.0, self.exit.1
+        )
+    }
+
+    /// a printable maze surrounded by a wall, which is open next to the
+    /// entrance and exit if they are at the border
+    pub fn to_svg(&self) -> String {
+        let scale = 100;
+        let (w, h) = (self.shape.0 as i64, self.shape.1 as i64);
+        let opening = |(x, y): (u32, u32)| {
+            let (x, y) = (x as i64, y as i64);
+            if x == 0 {
+                Some((-1, y))
+            } else if x == w - 1 {
+                Some((w, y))
+            } else if y == 0 {
+                Some((x, -1))
+            } else if y == h - 1 {
+                Some((x, h))
+            } else {
+                None
+            }
+        };
+        let openings = [opening(self.entrance), opening(self.exit)];
+
+        let mut svg = String::new();
+        _ = writeln!(
+            svg,
+            r#"<svg viewBox="-{scale} -{scale} {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            (w + 2) * scale,
+            (h + 2) * scale
+        );
+
+        for y in -1..=h {
+            for x in -1..=w {
+                let fill = if x < 0 || y < 0 || x == w || y == h {
+                    if openings.contains(&Some((x, y))) {
+                        continue;
+                    }
+                    "black"
+                } else {
+                    match self.cell(x as u32, y as u32) {
+                        '#' => "black",
+                        'S' => "#11d116",
+                        'E' => "#ed1515",
+                        _ => continue,
+                    }
+                };
+                _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{scale}" height="{scale}" fill="{fill}" />"#,
+                    x * scale,
+                    y * scale
+                );
+            }
+        }
+
+        _ = writeln!(svg, "</svg>");
+        svg
+    }
+}
+
+#[test]
+fn text() {
+    let solution = Solution {
+        spec: crate::spec::BoardSpec::new((3, 2)),
+        diameter: 4,
+        pieces: vec![],
+        proved_optimal: false,
+    };
+    let maze = Maze::new(&solution);
+    let text = maze.to_text();
+    assert_eq!(text.lines().count(), 2);
+    assert_eq!(text.matches('S').count(), 1);
+    assert_eq!(text.matches('E').count(), 1);
+    assert!(maze.to_json().contains("\"width\": 3"));
+}
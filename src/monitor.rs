@@ -0,0 +1,391 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::backend::Instant;
+
+/// whether another thread asked to stop a search with this flag
+#[inline]
+pub fn is_cancelled(cancel: Option<&Arc<AtomicBool>>) -> bool {
+    cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+}
+
+/// What to do when the estimated memory usage of a search exceeds its cap
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryPolicy {
+    /// forget all visited states and keep searching. The result stays correct,
+    /// but states may be searched again
+    #[default]
+    Evict,
+    /// stop the search and report the best result found so far
+    Abort,
+}
+
+/// The size of the table of searched states when the monitor checks it
+#[derive(Debug, Clone, Copy)]
+pub struct MemoUsage {
+    pub states: usize,
+    /// states the table holds before it has to grow
+    pub capacity: usize,
+    /// estimated memory of the table
+    pub bytes: u64,
+}
+
+impl MemoUsage {
+    /// the share of the capacity that is used, from 0 to 1
+    pub fn load_factor(&self) -> f64 {
+        if self.capacity == 0 {
+            0.
+        } else {
+            self.states as f64 / self.capacity as f64
+        }
+    }
+}
+
+/// Shared between all jobs of a search to report progress and watch memory usage.
+pub struct Monitor {
+    label: String,
+    start: Instant,
+    progress: Option<Duration>,
+    /// report the memo table in this interval
+    memo_stats: Option<Duration>,
+    memory_cap: Option<u64>,
+    policy: MemoryPolicy,
+    time_limit: Option<Duration>,
+    /// stops the search when another thread sets it
+    cancel: Option<Arc<AtomicBool>>,
+    /// milliseconds since `start` of the last check
+    last_check: AtomicU64,
+    pub roots_total: usize,
+    pub roots_done: AtomicUsize,
+    /// roots finished by this run and the nanoseconds they took together, for
+    /// the estimated time left
+    roots_timed: AtomicUsize,
+    root_nanos: AtomicU64,
+    /// roots searched at the same time
+    threads: usize,
+    /// the largest diameter found so far
+    pub best: AtomicU32,
+    /// milliseconds since `start` until `best` was found
+    best_at: AtomicU64,
+    /// An upper bound of the diameter every unfinished root can still reach,
+    /// 0 for finished roots. Only covers the states the search visits, so it
+    /// bounds the optimum only if the search is exhaustive.
+    pub root_bounds: Vec<AtomicU32>,
+    pub nodes: AtomicU64,
+    pub buffer_bytes: AtomicU64,
+    pub evictions: AtomicU64,
+    stop: AtomicBool,
+}
+
+/// How often the memory usage is checked, even without progress output
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+impl Monitor {
+    pub fn new(
+        label: String,
+        roots_total: usize,
+        progress: Option<Duration>,
+        memo_stats: Option<Duration>,
+        memory_cap: Option<u64>,
+        policy: MemoryPolicy,
+        time_limit: Option<Duration>,
+    ) -> Self {
+        Self {
+            label,
+            start: Instant::now(),
+            progress,
+            memo_stats,
+            memory_cap,
+            policy,
+            time_limit,
+            cancel: None,
+            last_check: AtomicU64::new(0),
+            roots_total,
+            roots_done: AtomicUsize::new(0),
+            roots_timed: AtomicUsize::new(0),
+            root_nanos: AtomicU64::new(0),
+            threads: 1,
+            best: AtomicU32::new(0),
+            best_at: AtomicU64::new(0),
+            root_bounds: (0..roots_total).map(|_| AtomicU32::new(0)).collect(),
+            nodes: AtomicU64::new(0),
+            buffer_bytes: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    /// stops the search as soon as the jobs see that `cancel` was set
+    pub fn with_cancel(mut self, cancel: Option<Arc<AtomicBool>>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Checked before every child of the depth first search, so a cancelled
+    /// search stops right away instead of at the next check
+    /// estimates the time left for `threads` searching roots at the same time
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    #[inline]
+    pub fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || is_cancelled(self.cancel.as_ref())
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Raises the best diameter to `diameter` and returns the previous one
+    pub fn improve(&self, diameter: u32) -> u32 {
+        let best = self.best.fetch_max(diameter, Ordering::Relaxed);
+        if diameter > best {
+            let at = self.start.elapsed().as_millis() as u64;
+            self.best_at.fetch_max(at, Ordering::Relaxed);
+        }
+        best
+    }
+
+    /// how long it took to find the best diameter
+    pub fn time_to_best(&self) -> Duration {
+        Duration::from_millis(self.best_at.load(Ordering::Relaxed))
+    }
+
+    /// prints a message about the search to stderr
+    pub fn log(&self, message: std::fmt::Arguments) {
+        eprintln!("[{}] {message}", self.label);
+    }
+
+    /// marks a root as finished after its search took `time`
+    pub fn finish_root(&self, index: usize, time: Duration) {
+        self.root_bounds[index].store(0, Ordering::Relaxed);
+        self.roots_done.fetch_add(1, Ordering::Relaxed);
+        self.root_nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+        self.roots_timed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The time left if every open root takes as long as the mean of the
+    /// finished ones. Later roots are usually quicker, as they start from a
+    /// larger best diameter, so the estimate is high at first and improves as
+    /// roots finish. Unknown until the first root is done.
+    pub fn time_left(&self) -> Option<Duration> {
+        let timed = self.roots_timed.load(Ordering::Relaxed) as u64;
+        if timed == 0 {
+            return None;
+        }
+        let mean = self.root_nanos.load(Ordering::Relaxed) / timed;
+        let open = self.roots_total - self.roots_done.load(Ordering::Relaxed);
+        Some(Duration::from_nanos(
+            mean * open.div_ceil(self.threads) as u64,
+        ))
+    }
+
+    /// The diameter of the result lies between these bounds
+    pub fn bounds(&self) -> (u32, u32) {
+        let best = self.best.load(Ordering::Relaxed);
+        let open = self
+            .root_bounds
+            .iter()
+            .map(|bound| bound.load(Ordering::Relaxed))
+            .max()
+            .unwrap_or(0);
+        (best, best.max(open))
+    }
+
+    /// Called regularly by the jobs with the current size of the stored
+    /// states. Returns true if the states have to be evicted.
+    pub fn check(&self, memo: MemoUsage) -> bool {
+        if self.progress.is_none()
+            && self.memo_stats.is_none()
+            && self.memory_cap.is_none()
+            && self.time_limit.is_none()
+            && self.cancel.is_none()
+        {
+            return false;
+        }
+
+        let now = self.start.elapsed().as_millis() as u64;
+        let last = self.last_check.load(Ordering::Relaxed);
+        if now < last + CHECK_INTERVAL.as_millis() as u64
+            || self
+                .last_check
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            // some other thread is doing the check
+            return false;
+        }
+
+        if self
+            .time_limit
+            .is_some_and(|limit| self.start.elapsed() >= limit)
+            || is_cancelled(self.cancel.as_ref())
+        {
+            self.stop();
+        }
+
+        let memory = memo.bytes + self.buffer_bytes.load(Ordering::Relaxed);
+        let states = memo.states;
+        // whether an interval ended since the last check
+        let due = |interval: Duration| {
+            let interval = interval.as_millis() as u64;
+            now / interval != last / interval
+        };
+
+        if let Some(interval) = self.progress {
+            if due(interval) {
+                let (best, bound) = self.bounds();
+                let eta = match self.time_left() {
+                    Some(left) => {
+                        let elapsed = self.start.elapsed().as_secs_f64();
+                        let done = elapsed / (elapsed + left.as_secs_f64()).max(f64::EPSILON);
+                        format!(
+                            "{:.0}% done, about {} left",
+                            100. * done,
+                            format_duration(left)
+                        )
+                    }
+                    None => "time left unknown".to_owned(),
+                };
+                eprintln!(
+                    "[{}] diameter {best}..={bound}  roots {}/{}  nodes {}  states {} ({})  {eta}",
+                    self.label,
+                    self.roots_done.load(Ordering::Relaxed),
+                    self.roots_total,
+                    self.nodes.load(Ordering::Relaxed),
+                    states,
+                    format_bytes(memory),
+                );
+            }
+        }
+
+        if self.memo_stats.is_some_and(due) {
+            let cap = match self.memory_cap {
+                Some(cap) => format!(
+                    "  {:.0}% of the memory cap",
+                    100. * memory as f64 / cap as f64
+                ),
+                None => String::new(),
+            };
+            eprintln!(
+                "[{}] memo: {states} of {} states ({:.0}% full)  {} ({} with buffers){cap}  evictions {}",
+                self.label,
+                memo.capacity,
+                100. * memo.load_factor(),
+                format_bytes(memo.bytes),
+                format_bytes(memory),
+                self.evictions.load(Ordering::Relaxed),
+            );
+        }
+
+        match self.memory_cap {
+            Some(cap) if memory > cap => match self.policy {
+                MemoryPolicy::Evict => {
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                    eprintln!(
+                        "[{}] memory cap of {} reached, evicting {states} states",
+                        self.label,
+                        format_bytes(cap)
+                    );
+                    true
+                }
+                MemoryPolicy::Abort => {
+                    eprintln!(
+                        "[{}] memory cap of {} reached, aborting the search",
+                        self.label,
+                        format_bytes(cap)
+                    );
+                    self.stop();
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// durations like `2h 05m`, `3m 12s` or `40s`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {:02}s", seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// parses sizes like `512M` or `4G`
+pub fn parse_bytes(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size {s:?}"))?;
+    let factor = match unit
+        .to_ascii_uppercase()
+        .trim_end_matches("IB")
+        .trim_end_matches('B')
+    {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("invalid size unit in {s:?}")),
+    };
+
+    number
+        .checked_mul(factor)
+        .ok_or_else(|| format!("size {s:?} is too large"))
+}
+
+#[test]
+fn time_left_follows_the_finished_roots() {
+    let monitor = Monitor::new(
+        "test".to_owned(),
+        10,
+        None,
+        None,
+        None,
+        MemoryPolicy::Evict,
+        None,
+    )
+    .with_threads(4);
+    assert_eq!(monitor.time_left(), None);
+
+    monitor.finish_root(0, Duration::from_secs(4));
+    monitor.finish_root(1, Duration::from_secs(2));
+    // 8 open roots on 4 threads take two rounds of the mean of 3s
+    assert_eq!(monitor.time_left(), Some(Duration::from_secs(6)));
+    assert_eq!(format_duration(Duration::from_secs(7512)), "2h 05m");
+    assert_eq!(format_duration(Duration::from_secs(192)), "3m 12s");
+}
@@ -0,0 +1,146 @@
+//! Import of boards from black and white (PBM) or grayscale (PGM) images.
+//! Every pixel is a cell, dark pixels are holes.
+
+use crate::spec::BoardSpec;
+
+/// Parses the plain (`P1`, `P2`) and binary (`P4`, `P5`) formats
+pub fn parse_netpbm(bytes: &[u8]) -> Result<BoardSpec, String> {
+    let mut header = Header { bytes, pos: 0 };
+    let magic = header.token().ok_or("empty image")?;
+    let kind = match magic {
+        b"P1" | b"P2" | b"P4" | b"P5" => magic[1],
+        _ => return Err("not a PBM or PGM image".to_owned()),
+    };
+
+    let width = header.number()?;
+    let height = header.number()?;
+    let max = if matches!(kind, b'2' | b'5') {
+        header.number()?.max(1)
+    } else {
+        1
+    };
+    let pixels = width as usize * height as usize;
+
+    // true for dark pixels
+    let dark: Vec<bool> = match kind {
+        b'1' => (0..pixels)
+            .map(|_| match header.skip_whitespace_and_comments() {
+                Some(b'0') => Ok(false),
+                Some(b'1') => Ok(true),
+                _ => Err("invalid or missing pixel".to_owned()),
+            })
+            .collect::<Result<_, _>>()?,
+        b'2' => (0..pixels)
+            .map(|_| header.number().map(|value| value * 2 < max))
+            .collect::<Result<_, _>>()?,
+        b'4' => {
+            let data = header.data()?;
+            let row_bytes = (width as usize).div_ceil(8);
+            if data.len() < row_bytes * height as usize {
+                return Err("image data is truncated".to_owned());
+            }
+            (0..pixels)
+                .map(|i| {
+                    let (x, y) = (i % width as usize, i / width as usize);
+                    data[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+                })
+                .collect()
+        }
+        _ => {
+            let data = header.data()?;
+            let sample = if max > 255 { 2 } else { 1 };
+            if data.len() < sample * pixels {
+                return Err("image data is truncated".to_owned());
+            }
+            (0..pixels)
+                .map(|i| {
+                    let value = match sample {
+                        1 => data[i] as u32,
+                        _ => u16::from_be_bytes([data[2 * i], data[2 * i + 1]]) as u32,
+                    };
+                    value * 2 < max
+                })
+                .collect()
+        }
+    };
+
+    let mut spec = BoardSpec::new((width, height));
+    for (i, &dark) in dark.iter().enumerate() {
+        if dark {
+            spec.holes.push((i as u32 % width, i as u32 / width));
+        }
+    }
+    Ok(spec)
+}
+
+struct Header<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    /// returns the next byte that is neither whitespace nor part of a comment
+    /// and advances past it
+    fn skip_whitespace_and_comments(&mut self) -> Option<u8> {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            self.pos += 1;
+            match byte {
+                b'#' => {
+                    while self.bytes.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => {}
+                byte => return Some(byte),
+            }
+        }
+        None
+    }
+
+    fn token(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace_and_comments()?;
+        let start = self.pos - 1;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Some(&self.bytes[start..self.pos])
+    }
+
+    fn number(&mut self) -> Result<u32, String> {
+        let token = self.token().ok_or("image header is truncated")?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| format!("invalid number {:?}", String::from_utf8_lossy(token)))
+    }
+
+    /// the binary data, which follows the header after a single whitespace
+    fn data(&self) -> Result<&'a [u8], String> {
+        self.bytes
+            .get(self.pos + 1..)
+            .ok_or_else(|| "image data is missing".to_owned())
+    }
+}
+
+#[test]
+fn formats() {
+    let expected = [(1, 0), (0, 1)];
+    for image in [
+        &b"P1\n# comment\n3 2\n0 1 0\n1 0 0\n"[..],
+        b"P1 3 2 010100",
+        b"P2\n3 2\n255\n255 0 200\n3 255 128\n",
+        b"P4\n3 2\n\x40\x80",
+        b"P5 3 2 255\n\xff\x00\xc8\x03\xff\x80",
+    ] {
+        let spec = parse_netpbm(image).unwrap();
+        assert_eq!(spec.shape, (3, 2));
+        assert_eq!(spec.holes, expected, "{}", String::from_utf8_lossy(image));
+    }
+
+    assert!(parse_netpbm(b"P6 1 1 255 abc").is_err());
+    assert!(parse_netpbm(b"P4 9 2\n\x00").is_err());
+}
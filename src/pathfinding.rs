@@ -1,19 +1,52 @@
+//! Distances on boards: diameters, the lengths of paths between fixed ends and
+//! the shortest paths that are shown.
+
 use std::{collections::BinaryHeap, fmt::Debug};
 
 use rustc_hash::FxHashMap;
 
+#[cfg(feature = "parallel")]
+use crate::frontier::FrontierPool;
+#[cfg(feature = "row-major")]
+use crate::row_map::{RowMap, RowScratch};
 use crate::tile_map::TileMap;
 
+/// The buffers of breadth first searches on boards of one shape, so repeated
+/// searches don't allocate
 pub struct BfsScratch {
-    shape: (u16, u16),
+    shape: (u32, u32),
+    /// threads expanding large frontiers, see [`BfsScratch::with_threads`]
+    threads: usize,
+    /// started by the first frontier with [`PARALLEL_FRONTIER`] cells
+    #[cfg(feature = "parallel")]
+    pool: Option<FrontierPool>,
+    /// whether the pool took over the current search
+    #[cfg(feature = "parallel")]
+    pooled: bool,
     visited: Vec<bool>,
     candidates_1: Vec<Point>,
     candidates_2: Vec<Point>,
+    /// boards wider than a tile are searched on a row major copy
+    #[cfg(feature = "row-major")]
+    rows: Option<(RowMap, RowScratch)>,
 }
 
+/// Frontiers with this many cells are expanded on the threads of
+/// [`BfsScratch::with_threads`], every one with a part of at least half of
+/// them. Handing a part to a waiting thread takes about a microsecond, as long
+/// as expanding 50 cells.
+pub const PARALLEL_FRONTIER: usize = 512;
+
+#[inline]
+fn index_for_point(shape: (u32, u32), p: Point) -> usize {
+    p.0 as usize + p.1 as usize * shape.0 as usize
+}
+
+/// a cell as x, y
 #[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Point(pub u16, pub u16);
+pub struct Point(pub u32, pub u32);
 
+/// the ends of a shortest path
 #[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Path(pub Point, pub Point);
 
@@ -30,20 +63,39 @@ impl Debug for Path {
 }
 
 impl BfsScratch {
-    pub fn new(shape: (u16, u16)) -> Self {
+    pub fn new(shape: (u32, u32)) -> Self {
         let max_width = shape.0.max(shape.1);
         // 1 4 8 12 16
         let max_candidates = (max_width as usize / 2 * 4).max(1);
         Self {
             shape,
+            threads: 1,
+            #[cfg(feature = "parallel")]
+            pool: None,
+            #[cfg(feature = "parallel")]
+            pooled: false,
             visited: vec![false; shape.0 as usize * shape.1 as usize],
             candidates_1: Vec::with_capacity(max_candidates),
             candidates_2: Vec::with_capacity(max_candidates),
+            #[cfg(feature = "row-major")]
+            rows: (shape.0 > 8).then(|| (RowMap::new(shape), RowScratch::default())),
         }
     }
 
+    /// Expands frontiers of at least [`PARALLEL_FRONTIER`] cells on up to
+    /// `threads` threads, which boards from about 250x250 cells on have. The
+    /// threads start with the first such frontier and wait for the next one
+    /// until the scratch is dropped. Smaller frontiers, like all of those of
+    /// mazes, stay on the calling thread, and so does everything without the
+    /// `parallel` feature. The searches find the cells in the same order either
+    /// way.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
     // bfs search to find eccentricity
-    pub fn eccentricity(&mut self, tile_map: &TileMap, x: u16, y: u16) -> (u16, Point) {
+    pub fn eccentricity(&mut self, tile_map: &TileMap, x: u32, y: u32) -> (u32, Point) {
         debug_assert_eq!(self.shape, tile_map.shape);
 
         let start = Point(x, y);
@@ -52,63 +104,125 @@ impl BfsScratch {
             return (0, start);
         }
 
-        self.candidates_1.clear();
-        self.candidates_1.push(start);
+        self.start(start);
+        let mut prev = start;
 
-        #[inline]
-        fn index_for_point(shape: (u16, u16), p: Point) -> usize {
-            p.0 as usize + p.1 as usize * shape.0 as usize
+        for i in 0u32.. {
+            if self.candidates_1.is_empty() {
+                return (i, prev);
+            }
+
+            self.grow(tile_map);
+            if let Some(&last) = self.candidates_1.last() {
+                prev = last;
+            }
         }
 
-        self.visited.fill(false);
-        self.visited[index_for_point(self.shape, start)] = true;
+        unreachable!("eccentricity > u32::MAX")
+    }
 
-        let mut prev = start;
+    /// The steps from `start` to every cell by row major index, None for cells
+    /// it can't reach. Like [`BfsScratch::eccentricity`] this expands large
+    /// frontiers on several threads.
+    pub fn distances(&mut self, tile_map: &TileMap, start: Point) -> Vec<Option<u32>> {
+        debug_assert_eq!(self.shape, tile_map.shape);
+        let mut distances = vec![None; self.visited.len()];
+        if tile_map.get(start.0, start.1) {
+            return distances;
+        }
 
-        for i in 0u16.. {
+        self.start(start);
+        for i in 0u32.. {
             if self.candidates_1.is_empty() {
-                return (i, prev);
+                return distances;
             }
+            for &p in &self.candidates_1 {
+                distances[index_for_point(self.shape, p)] = Some(i);
+            }
+            self.grow(tile_map);
+        }
 
-            self.candidates_2.clear();
+        unreachable!("distance > u32::MAX")
+    }
 
-            for &candidate in &self.candidates_1 {
-                self.visited[index_for_point(self.shape, candidate)] = true;
+    /// makes `start` the only visited cell and the frontier
+    fn start(&mut self, start: Point) {
+        self.visited.fill(false);
+        self.visited[index_for_point(self.shape, start)] = true;
+        self.candidates_1.clear();
+        self.candidates_1.push(start);
+        #[cfg(feature = "parallel")]
+        {
+            self.pooled = false;
+        }
+    }
 
-                for (dx, dy) in OffsetIterator::default() {
-                    let x = candidate.0 as i32 + dx;
-                    let y = candidate.1 as i32 + dy;
+    /// Replaces the frontier with the cells next to it that weren't visited,
+    /// in the order the frontier finds them
+    fn grow(&mut self, tile_map: &TileMap) {
+        self.candidates_2.clear();
+
+        #[cfg(feature = "parallel")]
+        if self.threads > 1 && (self.pooled || self.candidates_1.len() >= PARALLEL_FRONTIER) {
+            let (shape, threads) = (self.shape, self.threads);
+            let pool = self
+                .pool
+                .get_or_insert_with(|| FrontierPool::new(shape, threads));
+            if !self.pooled {
+                pool.take_over(tile_map, &self.visited, &self.candidates_1);
+                self.pooled = true;
+            }
+            let parts = self.candidates_1.len() / (PARALLEL_FRONTIER / 2);
+            pool.grow(&mut self.candidates_1, &mut self.candidates_2, parts);
+            return;
+        }
 
-                    if x < 0 || x >= tile_map.shape.0 as i32 {
-                        continue;
-                    }
-                    if y < 0 || y >= tile_map.shape.1 as i32 {
-                        continue;
-                    }
+        for &candidate in &self.candidates_1 {
+            for (dx, dy) in OffsetIterator::default() {
+                let x = candidate.0 as i32 + dx;
+                let y = candidate.1 as i32 + dy;
 
-                    let p = Point(x as u16, y as u16);
+                if x < 0 || x >= tile_map.shape.0 as i32 {
+                    continue;
+                }
+                if y < 0 || y >= tile_map.shape.1 as i32 {
+                    continue;
+                }
 
-                    if !tile_map.get(p.0, p.1) && !self.visited[index_for_point(self.shape, p)] {
-                        self.candidates_2.push(p);
-                        prev = p;
-                    }
+                let p = Point(x as u32, y as u32);
+
+                // mark cells when they are discovered, so they don't enter the next
+                // frontier multiple times
+                let index = index_for_point(self.shape, p);
+                if !tile_map.get(p.0, p.1) && !self.visited[index] {
+                    self.visited[index] = true;
+                    self.candidates_2.push(p);
                 }
             }
-
-            std::mem::swap(&mut self.candidates_1, &mut self.candidates_2);
         }
 
-        unreachable!("eccentricity > u16::MAX")
+        std::mem::swap(&mut self.candidates_1, &mut self.candidates_2);
     }
 
-    pub fn graph_diameter(&mut self, tile_map: &TileMap) -> (u16, Path) {
+    pub fn graph_diameter(&mut self, tile_map: &TileMap) -> (u32, Path) {
         debug_assert_eq!(self.shape, tile_map.shape);
 
         let mut maximum = None;
         let mut max_coords = None;
 
-        for y in 0..tile_map.shape.0 {
-            for x in 0..tile_map.shape.1 {
+        #[cfg(feature = "row-major")]
+        if let Some((rows, _)) = &mut self.rows {
+            rows.copy_from(tile_map);
+        }
+
+        for y in 0..tile_map.shape.1 {
+            for x in 0..tile_map.shape.0 {
+                #[cfg(feature = "row-major")]
+                let (e, end) = match &mut self.rows {
+                    Some((rows, scratch)) => rows.eccentricity(scratch, x, y),
+                    None => self.eccentricity(tile_map, x, y),
+                };
+                #[cfg(not(feature = "row-major"))]
                 let (e, end) = self.eccentricity(tile_map, x, y);
 
                 if maximum.is_none() || e > maximum.unwrap() {
@@ -120,6 +234,100 @@ impl BfsScratch {
 
         (maximum.unwrap(), max_coords.unwrap())
     }
+
+    /// The number of cells on a shortest path between the ends of `path`
+    /// including both, or 0 if there is none. Stops as soon as the goal is found.
+    pub fn path_length(&mut self, tile_map: &TileMap, Path(start, goal): Path) -> u32 {
+        debug_assert_eq!(self.shape, tile_map.shape);
+        let w = self.shape.0;
+        if tile_map.get(start.0, start.1) || tile_map.get(goal.0, goal.1) {
+            return 0;
+        }
+
+        self.visited.fill(false);
+        self.visited[(start.0 + start.1 * w) as usize] = true;
+        self.candidates_1.clear();
+        self.candidates_1.push(start);
+
+        for cells in 1.. {
+            if self.candidates_1.is_empty() {
+                return 0;
+            }
+            if self.candidates_1.contains(&goal) {
+                return cells;
+            }
+            self.candidates_2.clear();
+            for &candidate in &self.candidates_1 {
+                for next in neighbours(tile_map, candidate) {
+                    let index = (next.0 + next.1 * w) as usize;
+                    if !self.visited[index] {
+                        self.visited[index] = true;
+                        self.candidates_2.push(next);
+                    }
+                }
+            }
+            std::mem::swap(&mut self.candidates_1, &mut self.candidates_2);
+        }
+
+        unreachable!("path longer than u32::MAX")
+    }
+
+    /// What searches maximize: the diameter of a board without fixed `ends`,
+    /// and the number of cells between the closest two of its ends otherwise,
+    /// which is 0 if they are disconnected. Also returns the ends of the path.
+    pub fn objective(&mut self, tile_map: &TileMap, ends: &[Point]) -> (u32, Path) {
+        if ends.is_empty() {
+            return self.graph_diameter(tile_map);
+        }
+
+        let mut closest = None;
+        for (i, &start) in ends.iter().enumerate() {
+            for &end in &ends[i + 1..] {
+                let path = Path(start, end);
+                let cells = self.path_length(tile_map, path);
+                if closest.is_none_or(|(closest, _)| cells < closest) {
+                    closest = Some((cells, path));
+                }
+            }
+        }
+        closest.expect("a path needs at least two ends")
+    }
+
+    /// The number of free cells of the largest connected area. No shortest path
+    /// can visit more cells than that, however the pieces are placed.
+    pub fn largest_component(&mut self, tile_map: &TileMap) -> u32 {
+        debug_assert_eq!(self.shape, tile_map.shape);
+        let (w, h) = self.shape;
+
+        self.visited.fill(false);
+        let mut largest = 0;
+        for y in 0..h {
+            for x in 0..w {
+                let index = (x + y * w) as usize;
+                if self.visited[index] || tile_map.get(x, y) {
+                    continue;
+                }
+
+                // depth first, so the stack may grow beyond the BFS capacity
+                self.candidates_1.clear();
+                self.candidates_1.push(Point(x, y));
+                self.visited[index] = true;
+                let mut size = 0;
+                while let Some(p) = self.candidates_1.pop() {
+                    size += 1;
+                    for next in neighbours(tile_map, p) {
+                        let index = (next.0 + next.1 * w) as usize;
+                        if !self.visited[index] {
+                            self.visited[index] = true;
+                            self.candidates_1.push(next);
+                        }
+                    }
+                }
+                largest = largest.max(size);
+            }
+        }
+        largest
+    }
 }
 
 #[derive(Default)]
@@ -165,17 +373,60 @@ impl PartialEq for Vertex {
 impl Eq for Vertex {}
 impl PartialOrd for Vertex {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cost.cmp(&other.cost))
+        Some(self.cmp(other))
     }
 }
+// reversed, so the `BinaryHeap` pops the cheapest vertex first
 impl Ord for Vertex {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cost
-            .cmp(&other.cost)
+        other
+            .cost
+            .cmp(&self.cost)
             .then_with(|| self.position.cmp(&other.position))
     }
 }
 
+/// the free cells next to `p`
+pub fn neighbours(map: &TileMap, p: Point) -> impl Iterator<Item = Point> + '_ {
+    OffsetIterator::default().filter_map(move |(dx, dy)| {
+        let x = p.0.checked_add_signed(dx)?;
+        let y = p.1.checked_add_signed(dy)?;
+        (x < map.shape.0 && y < map.shape.1 && !map.get(x, y)).then_some(Point(x, y))
+    })
+}
+
+/// The diameter in cells like [`BfsScratch::graph_diameter`] with every pair of
+/// cells that realizes it instead of a single one. Every pair is listed once,
+/// with the cell that comes first in row major order as its start. Runs a
+/// breadth first search from every free cell.
+pub fn diameter_pairs(map: &TileMap) -> (u32, Vec<Path>) {
+    let width = map.shape.0;
+    let mut scratch = BfsScratch::new(map.shape);
+    let mut diameter = 0;
+    let mut pairs = Vec::new();
+    for y in 0..map.shape.1 {
+        for x in 0..width {
+            let start = Point(x, y);
+            let first = (y * width + x) as usize;
+            let distances = scratch.distances(map, start);
+            for (i, distance) in distances.into_iter().enumerate().skip(first) {
+                let Some(distance) = distance else {
+                    continue;
+                };
+                let end = Point(i as u32 % width, i as u32 / width);
+                if distance + 1 > diameter {
+                    diameter = distance + 1;
+                    pairs.clear();
+                }
+                if distance + 1 == diameter {
+                    pairs.push(Path(start, end));
+                }
+            }
+        }
+    }
+    (diameter, pairs)
+}
+
 // https://doc.rust-lang.org/std/collections/binary_heap/index.html
 pub fn dijkstra(map: &TileMap, Path(start, goal): Path) -> Vec<Point> {
     let mut queue = BinaryHeap::new();
@@ -209,11 +460,11 @@ pub fn dijkstra(map: &TileMap, Path(start, goal): Path) -> Vec<Point> {
                 && vx < map.shape.0 as i32
                 && vy >= 0
                 && vy < map.shape.1 as i32
-                && !map.get(vx as u16, vy as u16)
+                && !map.get(vx as u32, vy as u32)
             {
                 let next = Vertex {
                     cost: cost + 1,
-                    position: Point(vx as u16, vy as u16),
+                    position: Point(vx as u32, vy as u32),
                 };
 
                 // If so, add it to the frontier and continue
@@ -241,3 +492,246 @@ pub fn dijkstra(map: &TileMap, Path(start, goal): Path) -> Vec<Point> {
 
     path
 }
+
+/// Which of the shortest paths between two cells to pick
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// the one [`dijkstra`] finds
+    Any,
+    /// the one with the fewest changes of direction, which draws the cleanest
+    /// pictures
+    #[default]
+    FewestTurns,
+    MostTurns,
+    /// the one with the most blocked cells and board edges next to it
+    HugWalls,
+}
+
+/// A shortest path from the start to the goal of `path` that is the best one
+/// for `style`, including both ends.
+pub fn shortest_path(map: &TileMap, path: Path, style: PathStyle) -> Vec<Point> {
+    if style == PathStyle::Any {
+        return dijkstra(map, path);
+    }
+
+    let Path(start, goal) = path;
+    let width = map.shape.0 as usize;
+    let index = |p: Point| p.0 as usize + p.1 as usize * width;
+    let mut scratch = BfsScratch::new(map.shape);
+    let from_start = scratch.distances(map, start);
+    let from_goal = scratch.distances(map, goal);
+    let length = from_start[index(goal)].expect("the goal can't be reached");
+    // cells that lie on some shortest path, with their distance from the start
+    let on_path = |p: Point| {
+        let (a, b) = (from_start[index(p)]?, from_goal[index(p)]?);
+        (a + b == length).then_some(a)
+    };
+    let walls = |p: Point| 4 - neighbours(map, p).count() as i64;
+
+    // The cheapest way to reach every cell in every direction it can be
+    // entered from, with the cell and direction before it. The secondary costs
+    // are minimized, so the ones to maximize are negative.
+    let mut best = FxHashMap::<(Point, usize), (i64, Option<(Point, usize)>)>::default();
+    let start_cost = match style {
+        PathStyle::HugWalls => -walls(start),
+        _ => 0,
+    };
+    // the direction of the start is a fifth one that every turn is free from
+    best.insert((start, 4), (start_cost, None));
+    let mut layer = vec![(start, 4)];
+
+    for distance in 0..length {
+        let mut next = Vec::new();
+        for &(p, dir) in &layer {
+            let (cost, _) = best[&(p, dir)];
+            for n in neighbours(map, p) {
+                if on_path(n) != Some(distance + 1) {
+                    continue;
+                }
+                let d = if n.1 == p.1 {
+                    (n.0 > p.0) as usize
+                } else {
+                    2 + (n.1 > p.1) as usize
+                };
+                let turn = (dir != 4 && dir != d) as i64;
+                let cost = cost
+                    + match style {
+                        PathStyle::Any | PathStyle::FewestTurns => turn,
+                        PathStyle::MostTurns => -turn,
+                        PathStyle::HugWalls => -walls(n),
+                    };
+                match best.get(&(n, d)) {
+                    Some(&(known, _)) if known <= cost => {}
+                    known => {
+                        if known.is_none() {
+                            next.push((n, d));
+                        }
+                        best.insert((n, d), (cost, Some((p, dir))));
+                    }
+                }
+            }
+        }
+        layer = next;
+    }
+
+    let mut state = *layer
+        .iter()
+        .filter(|(p, _)| *p == goal)
+        .min_by_key(|state| best[state].0)
+        .unwrap();
+    let mut points = vec![state.0];
+    while let Some(previous) = best[&state].1 {
+        points.push(previous.0);
+        state = previous;
+    }
+    points.reverse();
+    points
+}
+
+/// number of changes of direction along a path
+pub fn turns(path: &[Point]) -> usize {
+    path.windows(3)
+        .filter(|w| (w[0].0 == w[1].0) != (w[1].0 == w[2].0))
+        .count()
+}
+
+#[test]
+fn path_styles_pick_among_shortest_paths() {
+    let map = TileMap::new((5, 5));
+    let path = Path(Point(0, 0), Point(4, 4));
+
+    for style in [
+        PathStyle::Any,
+        PathStyle::FewestTurns,
+        PathStyle::MostTurns,
+        PathStyle::HugWalls,
+    ] {
+        let points = shortest_path(&map, path, style);
+        assert_eq!(points.len(), 9, "{style:?}");
+        assert_eq!((points[0], points[8]), (path.0, path.1));
+        assert!(points
+            .windows(2)
+            .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+        match style {
+            PathStyle::FewestTurns => assert_eq!(turns(&points), 1),
+            PathStyle::MostTurns => assert_eq!(turns(&points), 7),
+            // along the edges of the board
+            PathStyle::HugWalls => {
+                assert!(points
+                    .iter()
+                    .all(|p| p.0 == 0 || p.0 == 4 || p.1 == 0 || p.1 == 4))
+            }
+            PathStyle::Any => {}
+        }
+    }
+}
+
+#[test]
+fn fewest_turns_never_turns_more_than_any_other_path() {
+    crate::arbitrary::check(200, |map: &TileMap| {
+        let (diameter, path) = BfsScratch::new(map.shape).graph_diameter(map);
+        if diameter == 0 {
+            return true;
+        }
+        let fewest = shortest_path(map, path, PathStyle::FewestTurns);
+        shortest_path(map, path, PathStyle::Any).len() == diameter as usize
+            && fewest.len() == diameter as usize
+            && [PathStyle::Any, PathStyle::MostTurns, PathStyle::HugWalls]
+                .into_iter()
+                .all(|style| turns(&fewest) <= turns(&shortest_path(map, path, style)))
+    });
+}
+
+#[test]
+fn path_lengths_match_distances() {
+    crate::arbitrary::check(200, |map: &TileMap| {
+        let mut scratch = BfsScratch::new(map.shape);
+        let (w, h) = map.shape;
+        let start = Point(0, 0);
+        let from_start = scratch.distances(map, start);
+        (0..h)
+            .flat_map(|y| (0..w).map(move |x| Point(x, y)))
+            .all(|goal| {
+                let expected = match from_start[(goal.0 + goal.1 * w) as usize] {
+                    Some(distance) if !map.get(0, 0) => distance + 1,
+                    _ => 0,
+                };
+                scratch.path_length(map, Path(start, goal)) == expected
+            })
+    });
+}
+
+#[test]
+fn objectives_of_fixed_ends() {
+    let mut map = TileMap::new((5, 1));
+    let ends = [Point(0, 0), Point(2, 0), Point(4, 0)];
+    let mut scratch = BfsScratch::new(map.shape);
+    assert_eq!(scratch.objective(&map, &[]).0, 5);
+    // the middle end is 3 cells away from both others
+    assert_eq!(scratch.objective(&map, &ends), (3, Path(ends[0], ends[1])));
+    map.block(3, 0);
+    assert_eq!(scratch.objective(&map, &ends).0, 0);
+}
+
+#[test]
+fn diameter_pairs_match_graph_diameter() {
+    crate::arbitrary::check(200, |map: &TileMap| {
+        let (diameter, path) = BfsScratch::new(map.shape).graph_diameter(map);
+        let (pairs_diameter, pairs) = diameter_pairs(map);
+        let Path(start, end) = path;
+        let normalized = if (start.1, start.0) <= (end.1, end.0) {
+            Path(start, end)
+        } else {
+            Path(end, start)
+        };
+        let mut scratch = BfsScratch::new(map.shape);
+        pairs_diameter == diameter
+            && (diameter == 0 || pairs.contains(&normalized))
+            && pairs.iter().all(|&Path(start, end)| {
+                scratch.distances(map, start)[(end.1 * map.shape.0 + end.0) as usize]
+                    == Some(diameter - 1)
+            })
+    });
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_frontiers_match_sequential() {
+    let shape = (700, 700);
+    let mut map = TileMap::new(shape);
+    let mut rng = crate::rng::Rng::new(0);
+    for _ in 0..40_000 {
+        map.block(rng.below(700) as u32, rng.below(700) as u32);
+    }
+
+    let mut sequential = BfsScratch::new(shape);
+    let mut parallel = BfsScratch::new(shape).with_threads(4);
+    // the same threads search from every start
+    for (x, y) in [(0, 0), (350, 350), (699, 3)] {
+        assert_eq!(
+            sequential.eccentricity(&map, x, y),
+            parallel.eccentricity(&map, x, y)
+        );
+        assert!(parallel.pooled);
+        assert_eq!(
+            sequential.distances(&map, Point(x, y)),
+            parallel.distances(&map, Point(x, y))
+        );
+    }
+}
+
+#[test]
+fn diameter_of_non_square_boards() {
+    // a U-shaped corridor whose ends are far from the top left corner
+    let mut wide = TileMap::new((8, 3));
+    let mut tall = TileMap::new((3, 8));
+    for i in 1..8 {
+        wide.block(i, 1);
+        tall.block(1, i);
+    }
+    for map in [wide, tall] {
+        let (diameter, _) = BfsScratch::new(map.shape).graph_diameter(&map);
+        // the diameter counts the cells on the longest path, not its edges
+        assert_eq!(diameter, 17, "{:?}", map.shape);
+    }
+}
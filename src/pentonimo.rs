@@ -1,8 +1,16 @@
-use std::{fmt::Display, ops::Range};
+//! The twelve pentominoes, the smaller polyominoes and custom pieces, their
+//! orientations and their positions on a board.
+
+use std::{fmt::Display, ops::Range, sync::OnceLock};
 
 use crate::tile::{Rotate, Tile};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::VariantArray)]
+/// the pieces, named by the letters they look like. Pieces with fewer than
+/// five cells have their number of cells after the letter, see [`Family`].
+/// The shapes of `C1` to `C5` are set by [`define_custom`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum::VariantArray, strum::EnumString, strum::Display,
+)]
 #[repr(u8)]
 pub enum PentonimoKind {
     F,
@@ -17,24 +25,175 @@ pub enum PentonimoKind {
     X,
     Y,
     Z,
+    I4,
+    L4,
+    O4,
+    S4,
+    T4,
+    I3,
+    L3,
+    I2,
+    C1,
+    C2,
+    C3,
+    C4,
+    C5,
+}
+
+/// number of kinds of pieces, the length of the piece counts of a board
+pub const KINDS: usize = 25;
+
+/// the first of the custom kinds, which are the last ones
+const FIRST_CUSTOM: usize = PentonimoKind::C1 as usize;
+
+/// one of every pentomino and none of the smaller pieces, the pieces of a board
+/// unless it sets others
+pub const PENTOMINOES: [u8; KINDS] = {
+    let mut pieces = [0; KINDS];
+    let mut kind = 0;
+    while kind < 12 {
+        pieces[kind] = 1;
+        kind += 1;
+    }
+    pieces
+};
+
+/// The pieces of the same number of cells, and the custom pieces of any size.
+/// Pieces of several families can be placed on the same board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Family {
+    Domino,
+    Triomino,
+    Tetromino,
+    Pentomino,
+    Custom,
+}
+
+impl PentonimoKind {
+    pub const fn family(self) -> Family {
+        match self as u8 {
+            0..12 => Family::Pentomino,
+            12..17 => Family::Tetromino,
+            17..19 => Family::Triomino,
+            19 => Family::Domino,
+            _ => Family::Custom,
+        }
+    }
+
+    /// the number of cells the pieces of this kind cover, 0 for custom kinds
+    /// without a shape
+    pub fn cells(self) -> u8 {
+        match self.family() {
+            Family::Domino => 2,
+            Family::Triomino => 3,
+            Family::Tetromino => 4,
+            Family::Pentomino => 5,
+            Family::Custom => custom_shape(self).map_or(0, |shape| shape.tile.0.count_ones() as u8),
+        }
+    }
+
+    /// whether pieces of this kind can be made, which custom kinds only can
+    /// once [`define_custom`] gave them a shape
+    pub fn is_defined(self) -> bool {
+        self.family() != Family::Custom || custom_shape(self).is_some()
+    }
+}
+
+/// A custom kind with its distinct orientations
+#[derive(Debug)]
+struct CustomShape {
+    tile: Tile,
+    bounds: PentonimoBounds,
+    variants: Vec<Pentonimo>,
+}
+
+/// the shapes of the custom kinds, set once by [`define_custom`]
+static CUSTOM: OnceLock<Vec<Option<CustomShape>>> = OnceLock::new();
+
+fn custom_shape(kind: PentonimoKind) -> Option<&'static CustomShape> {
+    CUSTOM.get()?.get(kind as usize - FIRST_CUSTOM)?.as_ref()
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// Gives custom kinds the shapes of tiles whose cells are all in the top left
+/// 5x5 cells. The shapes can only be set once for the whole process, before
+/// the first piece of a custom kind is made.
+pub fn define_custom(shapes: &[(PentonimoKind, Tile)]) -> Result<(), String> {
+    let mut custom: Vec<Option<CustomShape>> = (FIRST_CUSTOM..KINDS).map(|_| None).collect();
+    for &(kind, tile) in shapes {
+        if kind.family() != Family::Custom {
+            return Err(format!("{kind} isn't a custom kind"));
+        }
+        if tile.is_empty() || tile.0 & !pentonimo_area() != 0 {
+            return Err(format!(
+                "{kind} has to cover some of the top left 5x5 cells"
+            ));
+        }
+        let slot = &mut custom[kind as usize - FIRST_CUSTOM];
+        if slot.is_some() {
+            return Err(format!("{kind} is defined twice"));
+        }
+        let cells = (0..5).flat_map(|y| (0..5).map(move |x| (x, y)));
+        let (xs, ys): (Vec<u8>, Vec<u8>) = cells.filter(|&(x, y)| tile.get(x, y)).unzip();
+        let bounds = PentonimoBounds::new(
+            0..xs.iter().max().unwrap() + 1,
+            0..ys.iter().max().unwrap() + 1,
+        );
+        let generator = Pentonimo { kind, tile, bounds }.normalize();
+
+        // every rotation of the piece and of its mirror image, without those
+        // that have the same cells as an earlier one
+        let flipped = generator.flip_y();
+        let mut variants = Vec::<Pentonimo>::new();
+        for base in [generator, flipped] {
+            for variant in [
+                base,
+                base.rotate(Rotate::Right),
+                base.rotate(Rotate::Left),
+                base.rotate(Rotate::Full),
+            ] {
+                if variants
+                    .iter()
+                    .all(|v| v.normalize() != variant.normalize())
+                {
+                    variants.push(variant);
+                }
+            }
+        }
+        *slot = Some(CustomShape {
+            tile: generator.tile,
+            bounds: generator.bounds,
+            variants,
+        });
+    }
+    CUSTOM
+        .set(custom)
+        .map_err(|_| "the custom pieces are already defined".to_owned())
+}
+
+/// the top left 5x5 cells of a tile, where the generators of pieces are
+const fn pentonimo_area() -> u64 {
+    0x1f * 0x0000_0001_0101_0101
+}
+
+/// A piece in one of its orientations, see [`Pentonimo::variants`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Pentonimo {
     kind: PentonimoKind,
     tile: Tile,
     bounds: PentonimoBounds,
 }
 
-#[derive(Clone, Copy)]
+/// A piece placed on a board, with the top left corner of its bounding box at
+/// its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PositionedPentonimo {
     // normalized pentonimo
     pentonimo: Pentonimo,
-    position: (u16, u16),
+    position: (u32, u32),
 }
 
 impl PositionedPentonimo {
-    pub fn position(&self) -> (u16, u16) {
+    pub fn position(&self) -> (u32, u32) {
         self.position
     }
 
@@ -46,11 +205,76 @@ impl PositionedPentonimo {
         self.pentonimo
     }
 
-    pub fn get(&self, x: u16, y: u16) -> bool {
-        let dx = (8 + x - self.position.0) % 8;
-        let dy = (8 + y - self.position.1) % 8;
+    /// The cells of this pentonimo in the 2x2 tiles from the tile its position
+    /// is in, as [`Pentonimo::shift_split`] by the position within that tile.
+    /// The pentonimo starts at the corner of its tile, so the parts are just
+    /// the tile shifted a whole tile apart without any branches.
+    #[inline]
+    pub fn parts(&self) -> [Tile; 4] {
+        let (x, y) = (self.position.0 % 8, self.position.1 % 8);
+        let tile = self.pentonimo.tile.0;
+        // the cells that stay in their row of the tile and the ones shifted
+        // into the same row of the next, per byte
+        let stay = 0x0101_0101_0101_0101 * (0xff >> x << x);
+        let left = (tile << x) & stay;
+        let right = (tile >> (8 - x)) & (0x0101_0101_0101_0101 * ((1 << x) - 1));
+        let (left, right) = ((left as u128) << (8 * y), (right as u128) << (8 * y));
+        [
+            left as u64,
+            right as u64,
+            (left >> 64) as u64,
+            (right >> 64) as u64,
+        ]
+        .map(Tile)
+    }
+
+    /// index of this pentonimo's orientation in `Pentonimo::variants`
+    pub fn variant(&self) -> usize {
+        Pentonimo::new(self.pentonimo.kind)
+            .variants()
+            .position(|variant| variant.normalize() == self.pentonimo)
+            .expect("positioned pentonimo is not a variant of its kind")
+    }
 
-        self.pentonimo.tile().get(dx as u8, dy as u8)
+    /// mirrors this pentonimo along the board diagonal
+    pub fn transpose(self) -> Self {
+        Self {
+            pentonimo: self.pentonimo.transpose(),
+            position: (self.position.1, self.position.0),
+        }
+    }
+
+    /// mirrors this pentonimo left to right on a board `width` cells wide
+    pub fn flip_x(self, width: u32) -> Self {
+        Self {
+            pentonimo: self.pentonimo.flip_x().normalize(),
+            position: (
+                width - self.position.0 - self.shape().0 as u32,
+                self.position.1,
+            ),
+        }
+    }
+
+    /// mirrors this pentonimo top to bottom on a board `height` cells tall
+    pub fn flip_y(self, height: u32) -> Self {
+        Self {
+            pentonimo: self.pentonimo.flip_y().normalize(),
+            position: (
+                self.position.0,
+                height - self.position.1 - self.shape().1 as u32,
+            ),
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        let (Some(dx), Some(dy)) = (
+            x.checked_sub(self.position.0),
+            y.checked_sub(self.position.1),
+        ) else {
+            return false;
+        };
+
+        dx < 8 && dy < 8 && self.pentonimo.tile().get(dx as u8, dy as u8)
     }
 }
 
@@ -68,7 +292,24 @@ impl Pentonimo {
         )
     }
 
-    pub fn position(self, x: u16, y: u16) -> PositionedPentonimo {
+    pub fn variant(kind: PentonimoKind, index: usize) -> Option<Self> {
+        Self::new(kind).variants().nth(index)
+    }
+
+    /// Whether this orientation is a rotation of the one [`Pentonimo::new`]
+    /// makes, not a mirror image, which one-sided sets of pieces can't place.
+    /// Every orientation of a kind that is its own mirror image is a rotation.
+    pub fn is_rotation(self) -> bool {
+        let generator = Self::new(self.kind);
+        let own = self.normalize();
+        [Rotate::Right, Rotate::Left, Rotate::Full]
+            .map(|rotate| generator.rotate(rotate))
+            .into_iter()
+            .chain([generator])
+            .any(|rotation| rotation.normalize() == own)
+    }
+
+    pub fn position(self, x: u32, y: u32) -> PositionedPentonimo {
         PositionedPentonimo {
             pentonimo: self.normalize(),
             position: (x, y),
@@ -106,6 +347,18 @@ impl Pentonimo {
                     .into_iter(),
                 )
             };
+            (FLIP_ROTATE_HALF, $self: ident) => {{
+                let flipped = $self.flip_y();
+                VariantIterator::Mirror(
+                    [
+                        $self,
+                        $self.rotate(Rotate::Right),
+                        flipped,
+                        flipped.rotate(Rotate::Right),
+                    ]
+                    .into_iter(),
+                )
+            }};
             (ROTATE_HALF, $self: ident) => {
                 VariantIterator::HalfRotational([$self, $self.rotate(Rotate::Right)].into_iter())
             };
@@ -123,7 +376,26 @@ impl Pentonimo {
             PentonimoKind::I => permutations!(ROTATE_HALF, self),
             PentonimoKind::X => VariantIterator::Rotational([self].into_iter()),
             PentonimoKind::Y => permutations!(FLIP_ROTATE, self),
-            PentonimoKind::Z => permutations!(ROTATE, self),
+            PentonimoKind::Z => permutations!(FLIP_ROTATE_HALF, self),
+            PentonimoKind::I4 => permutations!(ROTATE_HALF, self),
+            PentonimoKind::L4 => permutations!(FLIP_ROTATE, self),
+            PentonimoKind::O4 => VariantIterator::Rotational([self].into_iter()),
+            PentonimoKind::S4 => permutations!(FLIP_ROTATE_HALF, self),
+            PentonimoKind::T4 => permutations!(ROTATE, self),
+            PentonimoKind::I3 => permutations!(ROTATE_HALF, self),
+            PentonimoKind::L3 => permutations!(ROTATE, self),
+            PentonimoKind::I2 => permutations!(ROTATE_HALF, self),
+            PentonimoKind::C1
+            | PentonimoKind::C2
+            | PentonimoKind::C3
+            | PentonimoKind::C4
+            | PentonimoKind::C5 => VariantIterator::Custom(
+                custom_shape(self.kind)
+                    .expect("custom pieces are only made once they are defined")
+                    .variants
+                    .iter()
+                    .copied(),
+            ),
         }
     }
 }
@@ -133,6 +405,8 @@ pub enum VariantIterator {
     HalfRotational(std::array::IntoIter<Pentonimo, 2>),
     Mirror(std::array::IntoIter<Pentonimo, 4>),
     Asymetric(std::array::IntoIter<Pentonimo, 8>),
+    /// the orientations of a custom kind, see [`define_custom`]
+    Custom(std::iter::Copied<std::slice::Iter<'static, Pentonimo>>),
 }
 
 impl Iterator for VariantIterator {
@@ -144,6 +418,7 @@ impl Iterator for VariantIterator {
             VariantIterator::HalfRotational(inner) => inner.next(),
             VariantIterator::Mirror(inner) => inner.next(),
             VariantIterator::Asymetric(inner) => inner.next(),
+            VariantIterator::Custom(inner) => inner.next(),
         }
     }
 }
@@ -167,31 +442,54 @@ fn number_of_variants() {
             PentonimoKind::X => 1,
             PentonimoKind::Y => 8,
             PentonimoKind::Z => 4,
+            PentonimoKind::I4 => 2,
+            PentonimoKind::L4 => 8,
+            PentonimoKind::O4 => 1,
+            PentonimoKind::S4 => 4,
+            PentonimoKind::T4 => 4,
+            PentonimoKind::I3 => 2,
+            PentonimoKind::L3 => 4,
+            PentonimoKind::I2 => 2,
+            PentonimoKind::C1
+            | PentonimoKind::C2
+            | PentonimoKind::C3
+            | PentonimoKind::C4
+            | PentonimoKind::C5 => unreachable!("custom kinds have no fixed shape"),
         }
     }
 
-    assert_eq!(
+    assert_eq!(PentonimoKind::VARIANTS.len(), KINDS);
+    let polyominoes = || {
         PentonimoKind::VARIANTS
             .iter()
-            .copied()
-            .map(expected_count)
-            .sum::<usize>(),
-        63
+            .filter(|kind| kind.family() != Family::Custom)
+    };
+    assert_eq!(
+        polyominoes().copied().map(expected_count).sum::<usize>(),
+        63 + 19 + 6 + 2
     );
 
-    assert!(PentonimoKind::VARIANTS
-        .iter()
+    assert!(polyominoes()
         .all(|&kind| Pentonimo::new(dbg!(kind)).variants().count() == expected_count(kind)));
 
-    let variants = PentonimoKind::VARIANTS
-        .iter()
+    let variants = polyominoes()
         .flat_map(|&kind| Pentonimo::new(dbg!(kind)).variants())
         .collect::<HashSet<Pentonimo>>();
 
-    assert_eq!(variants.len(), 63);
+    assert_eq!(variants.len(), 63 + 19 + 6 + 2);
+    assert!(variants
+        .iter()
+        .all(|variant| variant.tile().0.count_ones() == variant.kind().cells() as u32));
+
+    // variants at other offsets in their tile can still be the same shape
+    let shapes = variants
+        .into_iter()
+        .map(Pentonimo::normalize)
+        .collect::<HashSet<Pentonimo>>();
+    assert_eq!(shapes.len(), 63 + 19 + 6 + 2);
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct PentonimoBounds {
     start_x: u8,
     end_x: u8,
@@ -217,10 +515,6 @@ impl PentonimoBounds {
     pub fn contains(&self, x: u8, y: u8) -> bool {
         self.range_x().contains(&x) && self.range_y().contains(&y)
     }
-    fn checked_add(coordinate: u8, d: i8) -> Option<u8> {
-        let res = coordinate as i16 + d as i16;
-        (0..8).contains(&res).then_some(res as u8)
-    }
     fn rotate_point(x: u8, y: u8, rotate: Rotate) -> (u8, u8) {
         debug_assert!(x <= 8);
         debug_assert!(y <= 8);
@@ -239,8 +533,8 @@ impl PentonimoBounds {
 
     fn shift_x(self, d: i8) -> Self {
         Self {
-            start_x: (self.start_x as i8 + d).clamp(0, 7) as u8,
-            end_x: (self.end_x as i8 + d).clamp(0, 7) as u8,
+            start_x: (self.start_x as i8 + d).clamp(0, 8) as u8,
+            end_x: (self.end_x as i8 + d).clamp(0, 8) as u8,
             start_y: self.start_y,
             end_y: self.end_y,
         }
@@ -249,8 +543,8 @@ impl PentonimoBounds {
         Self {
             start_x: self.start_x,
             end_x: self.end_x,
-            start_y: (self.start_y as i8 + d).clamp(0, 7) as u8,
-            end_y: (self.end_y as i8 + d).clamp(0, 7) as u8,
+            start_y: (self.start_y as i8 + d).clamp(0, 8) as u8,
+            end_y: (self.end_y as i8 + d).clamp(0, 8) as u8,
         }
     }
 
@@ -273,6 +567,14 @@ impl PentonimoBounds {
     fn flip_xy(self) -> Self {
         self.flip_x().flip_y()
     }
+    fn transpose(self) -> Self {
+        Self {
+            start_x: self.start_y,
+            end_x: self.end_y,
+            start_y: self.start_x,
+            end_y: self.end_x,
+        }
+    }
 
     fn rotate(self, rotate: Rotate) -> Self {
         let (x1, y1) = Self::rotate_point(self.start_x, self.start_y, rotate);
@@ -350,6 +652,14 @@ impl Pentonimo {
         }
     }
     #[inline]
+    pub fn transpose(self) -> Self {
+        Self {
+            kind: self.kind,
+            tile: self.tile.transpose(),
+            bounds: self.bounds.transpose(),
+        }
+    }
+    #[inline]
     pub fn normalize(self) -> Self {
         Self {
             kind: self.kind,
@@ -435,7 +745,7 @@ const fn pentonimo(positions: [&[u8; 5]; 5]) -> Tile {
 
 impl PentonimoKind {
     #[rustfmt::skip]
-    const fn generator_tile(self) -> (Tile, PentonimoBounds) {
+    fn generator_tile(self) -> (Tile, PentonimoBounds) {
         match self {
             PentonimoKind::F => const { (
                 pentonimo([
@@ -557,6 +867,194 @@ impl PentonimoKind {
                 ]),
                 PentonimoBounds::new(0..3, 0..3),
             )},
+            PentonimoKind::I4 => const { (
+                pentonimo([
+                    b"x....",
+                    b"x....",
+                    b"x....",
+                    b"x....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..1, 0..4),
+            )},
+            PentonimoKind::L4 => const { (
+                pentonimo([
+                    b"x....",
+                    b"x....",
+                    b"xx...",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..2, 0..3),
+            )},
+            PentonimoKind::O4 => const { (
+                pentonimo([
+                    b"xx...",
+                    b"xx...",
+                    b".....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..2, 0..2),
+            )},
+            PentonimoKind::S4 => const { (
+                pentonimo([
+                    b".xx..",
+                    b"xx...",
+                    b".....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..3, 0..2),
+            )},
+            PentonimoKind::T4 => const { (
+                pentonimo([
+                    b"xxx..",
+                    b".x...",
+                    b".....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..3, 0..2),
+            )},
+            PentonimoKind::I3 => const { (
+                pentonimo([
+                    b"x....",
+                    b"x....",
+                    b"x....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..1, 0..3),
+            )},
+            PentonimoKind::L3 => const { (
+                pentonimo([
+                    b"x....",
+                    b"xx...",
+                    b".....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..2, 0..2),
+            )},
+            PentonimoKind::I2 => const { (
+                pentonimo([
+                    b"x....",
+                    b"x....",
+                    b".....",
+                    b".....",
+                    b".....",
+                ]),
+                PentonimoBounds::new(0..1, 0..2),
+            )},
+            PentonimoKind::C1
+            | PentonimoKind::C2
+            | PentonimoKind::C3
+            | PentonimoKind::C4
+            | PentonimoKind::C5 => {
+                let shape = custom_shape(self)
+                    .unwrap_or_else(|| panic!("custom piece {self} is not defined"));
+                (shape.tile, shape.bounds)
+            }
+        }
+    }
+}
+
+#[test]
+fn transpose_is_a_variant() {
+    use strum::VariantArray;
+
+    for &kind in PentonimoKind::VARIANTS
+        .iter()
+        .filter(|kind| kind.is_defined())
+    {
+        for variant in Pentonimo::new(kind).variants() {
+            let positioned = variant.position(2, 1);
+            let transposed = positioned.transpose();
+            transposed.variant();
+
+            for x in 0..8 {
+                for y in 0..8 {
+                    assert_eq!(positioned.get(x, y), transposed.get(y, x));
+                }
+            }
         }
     }
 }
+
+#[test]
+fn transform_invariants() {
+    use crate::arbitrary::check;
+
+    fn bounding_box(tile: Tile) -> PentonimoBounds {
+        let cells = (0..8)
+            .flat_map(|x| (0..8).map(move |y| (x, y)))
+            .filter(|&(x, y)| tile.get(x, y));
+        let (xs, ys): (Vec<u8>, Vec<u8>) = cells.unzip();
+        PentonimoBounds {
+            start_x: *xs.iter().min().unwrap(),
+            end_x: xs.iter().max().unwrap() + 1,
+            start_y: *ys.iter().min().unwrap(),
+            end_y: ys.iter().max().unwrap() + 1,
+        }
+    }
+
+    check(1000, |&p: &Pentonimo| {
+        let rotated = (0..4).fold(p, |p, _| p.rotate(Rotate::Right));
+        rotated == p
+            && p.rotate(Rotate::Left).rotate(Rotate::Right) == p
+            && p.rotate(Rotate::Full) == p.flip_xy()
+            && p.flip_x().flip_x() == p
+            && p.flip_y().flip_y() == p
+            && p.transpose().transpose() == p
+            && p.normalize().normalize() == p.normalize()
+            && [
+                p.rotate(Rotate::Right),
+                p.flip_x(),
+                p.transpose(),
+                p.normalize(),
+            ]
+            .iter()
+            .all(|q| {
+                q.bounds == bounding_box(q.tile) && q.tile.0.count_ones() == p.kind.cells() as u32
+            })
+    });
+}
+
+#[test]
+fn parts_match_shift_split() {
+    use strum::VariantArray;
+
+    let kinds = PentonimoKind::VARIANTS
+        .iter()
+        .filter(|kind| kind.family() != Family::Custom);
+    for &kind in kinds {
+        for variant in Pentonimo::new(kind).variants() {
+            for (x, y) in (0..16).flat_map(|y| (0..16).map(move |x| (x, y))) {
+                let piece = variant.position(x, y);
+                let split = piece.pentonimo().shift_split((x % 8) as i8, (y % 8) as i8);
+                assert_eq!(piece.parts(), split, "{kind:?} at {x},{y}");
+            }
+        }
+    }
+}
+
+#[test]
+fn one_sided_orientations() {
+    use strum::VariantArray;
+
+    // the chiral pieces lose their mirror images, the others keep all
+    let rotations = |kind| {
+        Pentonimo::new(kind)
+            .variants()
+            .filter(|v| v.is_rotation())
+            .count()
+    };
+    let pentominoes = PentonimoKind::VARIANTS
+        .iter()
+        .filter(|kind| kind.family() == Family::Pentomino);
+    let counts = pentominoes.map(|&kind| rotations(kind)).collect::<Vec<_>>();
+    assert_eq!(counts, [4, 4, 4, 4, 4, 4, 4, 4, 2, 1, 4, 2]);
+    assert!(!Pentonimo::new(PentonimoKind::F).flip_x().is_rotation());
+    assert!(Pentonimo::new(PentonimoKind::T).flip_x().is_rotation());
+}
@@ -0,0 +1,190 @@
+//! A small encoder of paletted PNG images, enough for pictures of boards.
+//! Rows are filtered with their predecessor and the differences are run-length
+//! encoded with fixed Huffman codes, so the large plain cells compress well.
+
+/// Encodes `pixels`, row by row indices into `palette`, as a PNG image
+pub fn encode(width: u32, height: u32, palette: &[[u8; 3]], pixels: &[u8]) -> Vec<u8> {
+    assert!(!palette.is_empty() && palette.len() <= 256);
+    assert_eq!(pixels.len(), width as usize * height as usize);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bit palette indices, deflate, adaptive filters, not interlaced
+    header.extend([8, 3, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"PLTE", palette.as_flattened());
+
+    // every row uses the up filter, the first one has zeros above it
+    let mut filtered = Vec::with_capacity(pixels.len() + height as usize);
+    let mut above = vec![0; width as usize];
+    for row in pixels.chunks(width.max(1) as usize) {
+        filtered.push(2);
+        filtered.extend(row.iter().zip(&above).map(|(&x, &up)| x.wrapping_sub(up)));
+        above.copy_from_slice(row);
+    }
+    chunk(&mut png, b"IDAT", &zlib(&filtered));
+    chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// A zlib stream of a single deflate block with fixed codes, in which runs of
+/// a byte are copies at distance 1
+fn zlib(bytes: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter {
+        bytes: vec![0x78, 0x01],
+        bits: 0,
+        len: 0,
+    };
+    // last block, fixed Huffman codes
+    out.write(1, 1);
+    out.write(1, 2);
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = if i == 0 {
+            0
+        } else {
+            bytes[i..]
+                .iter()
+                .take(258)
+                .take_while(|&&byte| byte == bytes[i - 1])
+                .count()
+        };
+        if run >= 3 {
+            out.length(run);
+            // distance code 0 is a distance of 1
+            out.code(0, 5);
+            i += run;
+        } else {
+            out.literal(bytes[i] as u16);
+            i += 1;
+        }
+    }
+    out.literal(256);
+
+    let mut zlib = out.finish();
+    zlib.extend(adler32(bytes).to_be_bytes());
+    zlib
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    /// the lowest `len` bits of `value`, least significant first
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= value << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    /// a Huffman code, most significant bit first
+    fn code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    /// a literal byte or the end of the block
+    fn literal(&mut self, value: u16) {
+        let value = value as u32;
+        match value {
+            0..=143 => self.code(0x30 + value, 8),
+            144..=255 => self.code(0x190 + value - 144, 9),
+            256..=279 => self.code(value - 256, 7),
+            _ => self.code(0xc0 + value - 280, 8),
+        }
+    }
+
+    /// the length of a copy, 3 to 258
+    fn length(&mut self, length: usize) {
+        const BASES: [u16; 29] = [
+            3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99,
+            115, 131, 163, 195, 227, 258,
+        ];
+        const EXTRA: [u32; 29] = [
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+        ];
+        let i = BASES.partition_point(|&base| base as usize <= length) - 1;
+        self.literal(257 + i as u16);
+        self.write((length - BASES[i] as usize) as u32, EXTRA[i]);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+#[test]
+fn checksums_match_known_values() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+}
+
+#[test]
+fn images_have_a_valid_layout() {
+    let (width, height) = (300, 200);
+    let pixels: Vec<u8> = (0..width * height)
+        .map(|i| (i % width / 50) as u8)
+        .collect();
+    let png = encode(
+        width,
+        height,
+        &[[0; 3], [255; 3], [255, 0, 0], [0, 0, 255], [9; 3], [7; 3]],
+        &pixels,
+    );
+
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(png[16..20], width.to_be_bytes());
+    assert_eq!(png[20..24], height.to_be_bytes());
+    assert!(png.ends_with(b"IEND\xaeB`\x82"));
+    // runs of colors and repeated rows are cheap
+    assert!(png.len() < pixels.len() / 20, "{} bytes", png.len());
+}
@@ -0,0 +1,98 @@
+use std::{fmt::Display, ops::AddAssign, time::Duration};
+
+use crate::{backend::Instant, json::Json};
+
+/// Time spent in the different parts of a search, summed over all threads.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Profile {
+    pub diameter: Duration,
+    pub placements: Duration,
+    pub memo: Duration,
+    pub results: Duration,
+    pub bound: Duration,
+    pub nodes: u64,
+    /// subtrees skipped because their bound can't beat the best result
+    pub pruned: u64,
+    /// states skipped because a searched state dominates them
+    pub dominated: u64,
+    pub wall: Duration,
+}
+
+impl AddAssign for Profile {
+    fn add_assign(&mut self, rhs: Self) {
+        self.diameter += rhs.diameter;
+        self.placements += rhs.placements;
+        self.memo += rhs.memo;
+        self.results += rhs.results;
+        self.bound += rhs.bound;
+        self.nodes += rhs.nodes;
+        self.pruned += rhs.pruned;
+        self.dominated += rhs.dominated;
+        self.wall += rhs.wall;
+    }
+}
+
+impl Profile {
+    /// as described by `schema/v1/stats.schema.json`, times in seconds
+    pub fn to_json(&self) -> Json {
+        let seconds = |time: Duration| time.as_secs_f64().into();
+        Json::versioned([
+            ("wall", seconds(self.wall)),
+            ("diameter", seconds(self.diameter)),
+            ("placements", seconds(self.placements)),
+            ("memo", seconds(self.memo)),
+            ("results", seconds(self.results)),
+            ("bound", seconds(self.bound)),
+            ("nodes", self.nodes.into()),
+            ("pruned", self.pruned.into()),
+            ("dominated", self.dominated.into()),
+        ])
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let measured = self.diameter + self.placements + self.memo + self.results + self.bound;
+
+        writeln!(
+            f,
+            "  {:<12} {:>12}",
+            "wall time",
+            format!("{:.3?}", self.wall)
+        )?;
+        for (name, time) in [
+            ("diameter", self.diameter),
+            ("placements", self.placements),
+            ("memo", self.memo),
+            ("results", self.results),
+            ("bound", self.bound),
+        ] {
+            let share = if measured.is_zero() {
+                0.
+            } else {
+                100. * time.as_secs_f64() / measured.as_secs_f64()
+            };
+            writeln!(f, "  {name:<12} {:>12} {share:5.1}%", format!("{time:.3?}"))?;
+        }
+        writeln!(f, "  {:<12} {:>12}", "nodes", self.nodes)?;
+        writeln!(f, "  {:<12} {:>12}", "pruned", self.pruned)?;
+        write!(f, "  {:<12} {:>12}", "dominated", self.dominated)
+    }
+}
+
+/// Measures a section if profiling is enabled, and does nothing otherwise.
+pub struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    #[inline]
+    pub fn start(enabled: bool) -> Self {
+        Self(enabled.then(Instant::now))
+    }
+
+    #[inline]
+    pub fn stop(self, total: &mut Duration) {
+        if let Some(start) = self.0 {
+            *total += start.elapsed();
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Results of complete searches kept across runs, so boards that were solved
+//! before aren't searched again. A board whose transposed, mirrored or rotated
+//! board is in the cache is answered by mapping the cached solution back.
+//!
+//! `cache.txt` in the result directory holds the text of every cached
+//! [`Solution`], separated by empty lines. New results are appended.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{solution::Solution, spec::BoardSpec};
+
+/// name of the cache in the result directory
+pub const FILE_NAME: &str = "cache.txt";
+
+/// Where the result of a board comes from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Provenance {
+    #[default]
+    Search,
+    Cache,
+    /// the cached solution of the transposed board
+    TransposedCache,
+    /// the cached solution of a mirrored or rotated board
+    MirroredCache,
+}
+
+impl Provenance {
+    /// where the result of the transposed board comes from
+    pub fn transposed(self) -> Self {
+        match self {
+            Provenance::Search => Provenance::Search,
+            Provenance::Cache => Provenance::TransposedCache,
+            Provenance::TransposedCache => Provenance::Cache,
+            Provenance::MirroredCache => Provenance::MirroredCache,
+        }
+    }
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Provenance::Search => "search",
+            Provenance::Cache => "cache",
+            Provenance::TransposedCache => "transposed cache",
+            Provenance::MirroredCache => "mirrored cache",
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    /// by the [`key`] of their specs
+    solutions: HashMap<BoardSpec, Solution>,
+}
+
+impl ResultCache {
+    /// Reads the cache at `path`, which is empty if there is no such file.
+    /// Every cached solution is verified, so a cache written by an older build
+    /// with a different objective is rejected instead of being trusted.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.to_string()),
+        };
+        let mut cache = Self::default();
+        for (i, entry) in text.split("\n\n").enumerate() {
+            if entry.trim().is_empty() {
+                continue;
+            }
+            let with_entry = |err: String| format!("entry {}: {err}", i + 1);
+            let solution = entry.parse::<Solution>().map_err(with_entry)?;
+            solution.verify().map_err(with_entry)?;
+            cache.insert(solution);
+        }
+        Ok(cache)
+    }
+
+    /// The solution of `spec` with the spec itself, and where it comes from.
+    /// The board itself and its transposed board are looked up first. One-sided
+    /// boards only come from rotated ones, mirrored pieces don't fit them.
+    pub fn get(&self, spec: &BoardSpec) -> Option<(Solution, Provenance)> {
+        let flips = [(false, false), (true, false), (false, true), (true, true)];
+        let (mut solution, provenance) = flips
+            .into_iter()
+            .flat_map(|flip| [false, true].map(|transpose| (transpose, flip)))
+            .filter(|&(transpose, (flip_x, flip_y))| {
+                !spec.one_sided || !(transpose ^ flip_x ^ flip_y)
+            })
+            .find_map(|(transpose, (flip_x, flip_y))| {
+                let image = match transpose {
+                    true => spec.transposed(),
+                    false => spec.clone(),
+                };
+                let cached = self.solutions.get(&key(&image.flipped(flip_x, flip_y)))?;
+                let solution = cached.flipped(flip_x, flip_y);
+                Some(match (transpose, flip_x || flip_y) {
+                    (false, false) => (solution, Provenance::Cache),
+                    (true, false) => (solution.transposed(), Provenance::TransposedCache),
+                    (true, true) => (solution.transposed(), Provenance::MirroredCache),
+                    (false, true) => (solution, Provenance::MirroredCache),
+                })
+            })?;
+        solution.spec = spec.clone();
+        Some((solution, provenance))
+    }
+
+    /// Adds `solution` if its board isn't cached yet, and returns whether it was added
+    pub fn insert(&mut self, mut solution: Solution) -> bool {
+        solution.spec = key(&solution.spec);
+        if self.get(&solution.spec).is_some() {
+            return false;
+        }
+        self.solutions.insert(solution.spec.clone(), solution);
+        true
+    }
+
+    /// Adds `solution` and appends it to the cache at `path` if its board isn't cached yet
+    pub fn append(&mut self, path: &Path, solution: &Solution) -> io::Result<()> {
+        if !self.insert(solution.clone()) {
+            return Ok(());
+        }
+        let solution = &self.solutions[&key(&solution.spec)];
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // a single write, so concurrent runs don't interleave their entries
+        file.write_all(format!("{solution}\n").as_bytes())
+    }
+}
+
+/// `spec` without its name and with its holes sorted, so equal boards have equal keys
+fn key(spec: &BoardSpec) -> BoardSpec {
+    let mut key = BoardSpec {
+        name: None,
+        ..spec.clone()
+    };
+    key.holes.sort_unstable();
+    key.holes.dedup();
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Provenance, ResultCache};
+    use crate::{
+        brute_force::{find_best, SearchConfig},
+        solution::Solution,
+        spec::BoardSpec,
+    };
+
+    #[test]
+    fn transposed_boards_come_from_the_cache() {
+        let spec = "5x4 holes=1,1".parse::<BoardSpec>().unwrap();
+        let result = find_best(&spec, &SearchConfig::default());
+        let mut cache = ResultCache::default();
+        assert!(cache.insert(Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        }));
+
+        let (solution, provenance) = cache.get(&spec).unwrap();
+        assert_eq!(provenance, Provenance::Cache);
+        assert_eq!(solution.spec, spec);
+
+        let transposed = spec.transposed();
+        let (solution, provenance) = cache.get(&transposed).unwrap();
+        assert_eq!(provenance, Provenance::TransposedCache);
+        assert_eq!(solution.spec, transposed);
+        assert_eq!(solution.diameter, result.diameter);
+        solution.verify().unwrap();
+
+        // a board is only cached once, in either direction
+        assert!(!cache.insert(solution));
+        assert!(cache.get(&BoardSpec::new((5, 4))).is_none());
+    }
+
+    #[test]
+    fn mirrored_and_rotated_boards_come_from_the_cache() {
+        let spec = "5x4 holes=1,1;0,3 free=4,0 ends=0,0;4,1"
+            .parse::<BoardSpec>()
+            .unwrap();
+        let result = find_best(&spec, &SearchConfig::default());
+        let mut cache = ResultCache::default();
+        assert!(cache.insert(Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        }));
+
+        for (flip_x, flip_y) in [(true, false), (false, true), (true, true)] {
+            for image in [
+                spec.flipped(flip_x, flip_y),
+                spec.flipped(flip_x, flip_y).transposed(),
+            ] {
+                let (solution, provenance) = cache.get(&image).unwrap();
+                assert_eq!(provenance, Provenance::MirroredCache, "{image}");
+                assert_eq!(solution.spec, image);
+                assert_eq!(solution.diameter, result.diameter);
+                solution
+                    .verify()
+                    .unwrap_or_else(|err| panic!("{image}: {err}"));
+                assert!(!cache.insert(solution));
+            }
+        }
+
+        let other = "5x4 holes=1,1;0,2 free=4,0 ends=0,0;4,1"
+            .parse::<BoardSpec>()
+            .unwrap();
+        assert!(cache.get(&other).is_none());
+    }
+
+    #[test]
+    fn one_sided_boards_only_come_from_rotations() {
+        let spec = "5x4 holes=1,1 pieces=one-sided,*=0,F=1,N=1"
+            .parse::<BoardSpec>()
+            .unwrap();
+        let result = find_best(&spec, &SearchConfig::default());
+        let mut cache = ResultCache::default();
+        assert!(cache.insert(Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        }));
+
+        // turned by 180 degrees
+        let (solution, provenance) = cache.get(&spec.flipped(true, true)).unwrap();
+        assert_eq!(provenance, Provenance::MirroredCache);
+        solution.verify().unwrap();
+        for image in [
+            spec.flipped(true, false),
+            spec.flipped(false, true),
+            spec.transposed(),
+        ] {
+            assert!(cache.get(&image).is_none(), "{image}");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Small, fast and reproducible random numbers for randomized search and
+//! testing. Not suitable for anything security related.
+
+use crate::backend::{SystemTime, UNIX_EPOCH};
+
+/// splitmix64
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// seeded from the current time
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in `0..n`
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
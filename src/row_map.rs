@@ -0,0 +1,227 @@
+//! A row major copy of a board for breadth first searches: every row is
+//! packed into as many `u64` as its width needs, instead of the 8×8 tiles of
+//! [`TileMap`]. Shifting a whole row by a cell is a single shift per word,
+//! which makes growing a BFS frontier by one step cheap on wide boards.
+//!
+//! With the `row-major` feature [`BfsScratch`](crate::pathfinding::BfsScratch)
+//! searches boards wider than a tile on this layout. It keeps a [`RowMap`] and
+//! a [`RowScratch`] of its own, which every diameter copies the board into.
+//! Pieces are only ever placed on the [`TileMap`].
+
+use crate::{pathfinding::Point, tile_map::TileMap};
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct RowMap {
+    pub shape: (u32, u32),
+    words_per_row: usize,
+    /// row after row, bits beyond the width are blocked
+    pub words: Vec<u64>,
+}
+
+impl RowMap {
+    pub fn new(shape: (u32, u32)) -> Self {
+        let words_per_row = shape.0.div_ceil(64).max(1) as usize;
+        let mut map = Self {
+            shape,
+            words_per_row,
+            words: vec![0; words_per_row * shape.1 as usize],
+        };
+        map.clear();
+        map
+    }
+
+    /// frees every cell of the board
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+        if !self.shape.0.is_multiple_of(64) {
+            let padding = !0 << (self.shape.0 % 64);
+            for row in self.words.chunks_exact_mut(self.words_per_row) {
+                row[self.words_per_row - 1] |= padding;
+            }
+        }
+    }
+
+    /// Copies the blocked cells of `map`, which has the shape of this map,
+    /// without allocating
+    pub fn copy_from(&mut self, map: &TileMap) {
+        debug_assert_eq!(self.shape, map.shape);
+        self.clear();
+        let tiles_per_row = map.tile_shape().0;
+        for (y, row) in self.words.chunks_exact_mut(self.words_per_row).enumerate() {
+            let tiles = &map.tiles()[y / 8 * tiles_per_row..][..tiles_per_row];
+            for (tx, tile) in tiles.iter().enumerate() {
+                // a row of a tile is a byte, and 8 tiles fill a word
+                let cells = tile.0 >> (8 * (y % 8)) & 0xff;
+                row[tx / 8] |= cells << (8 * (tx % 8));
+            }
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> (usize, u64) {
+        (
+            y as usize * self.words_per_row + x as usize / 64,
+            1 << (x % 64),
+        )
+    }
+
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        let (index, bit) = self.index(x, y);
+        self.words[index] & bit != 0
+    }
+
+    /// Length and end of the longest shortest path from a cell, like
+    /// [`BfsScratch::eccentricity`](crate::pathfinding::BfsScratch::eccentricity).
+    /// The search grows the whole frontier by one step at a time. Of the cells
+    /// farthest away the one with the largest row major index is returned.
+    pub fn eccentricity(&self, scratch: &mut RowScratch, x: u32, y: u32) -> (u32, Point) {
+        let start = Point(x, y);
+        if self.get(x, y) {
+            return (0, start);
+        }
+
+        let n = self.words_per_row;
+        let RowScratch {
+            visited,
+            frontier,
+            next,
+        } = scratch;
+        visited.clone_from(&self.words);
+        for buffer in [&mut *frontier, &mut *next] {
+            buffer.clear();
+            buffer.resize(self.words.len(), 0);
+        }
+        let (index, bit) = self.index(x, y);
+        frontier[index] = bit;
+        visited[index] |= bit;
+        // rows that can contain frontier cells
+        let (mut top, mut bottom) = (y as usize, y as usize);
+        let rows = self.shape.1 as usize;
+
+        for i in 1u32.. {
+            let (from, to) = (top.saturating_sub(1), (bottom + 1).min(rows - 1));
+            let mut found = None;
+            for row in from..=to {
+                for k in 0..n {
+                    let word = row * n + k;
+                    let here = frontier[word];
+                    let mut grown = here << 1 | here >> 1;
+                    if k > 0 {
+                        grown |= frontier[word - 1] >> 63;
+                    }
+                    if k + 1 < n {
+                        grown |= frontier[word + 1] << 63;
+                    }
+                    if row > 0 {
+                        grown |= frontier[word - n];
+                    }
+                    if row + 1 < rows {
+                        grown |= frontier[word + n];
+                    }
+                    grown &= !visited[word];
+                    next[word] = grown;
+                    if grown != 0 {
+                        visited[word] |= grown;
+                        found = Some(found.map_or((row, row), |(first, _)| (first, row)));
+                    }
+                }
+            }
+
+            let Some((first, last)) = found else {
+                // the previous frontier is the farthest one
+                let word = (from..=to)
+                    .flat_map(|row| (0..n).map(move |k| row * n + k))
+                    .rfind(|&word| frontier[word] != 0)
+                    .unwrap();
+                let x = (word % n) as u32 * 64 + 63 - frontier[word].leading_zeros();
+                return (i, Point(x, (word / n) as u32));
+            };
+
+            for row in from..=to {
+                frontier[row * n..(row + 1) * n].fill(0);
+            }
+            std::mem::swap(frontier, next);
+            (top, bottom) = (first, last);
+        }
+
+        unreachable!("eccentricity > u32::MAX")
+    }
+}
+
+/// The buffers of [`RowMap::eccentricity`], so repeated searches don't
+/// allocate
+#[derive(Debug, Default)]
+pub struct RowScratch {
+    visited: Vec<u64>,
+    frontier: Vec<u64>,
+    next: Vec<u64>,
+}
+
+impl From<&TileMap> for RowMap {
+    fn from(map: &TileMap) -> Self {
+        let mut rows = RowMap::new(map.shape);
+        rows.copy_from(map);
+        rows
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RowMap, RowScratch};
+    use crate::{
+        arbitrary::check,
+        pathfinding::{BfsScratch, Point},
+        tile_map::TileMap,
+    };
+
+    #[test]
+    fn copies_match_tile_maps() {
+        check(1000, |map: &TileMap| {
+            let rows = RowMap::from(map);
+            (0..map.shape.1).all(|y| (0..map.shape.0).all(|x| rows.get(x, y) == map.get(x, y)))
+        });
+
+        // cells at the ends of the words of a row
+        let mut map = TileMap::new((150, 9));
+        for (x, y) in [(0, 0), (63, 1), (64, 2), (127, 7), (128, 8), (149, 8)] {
+            map.block(x, y);
+        }
+        let rows = RowMap::from(&map);
+        for (x, y) in (0..9).flat_map(|y| (0..150).map(move |x| (x, y))) {
+            assert_eq!(rows.get(x, y), map.get(x, y), "{x},{y}");
+        }
+    }
+
+    #[test]
+    fn eccentricity_matches_bfs() {
+        check(100, |map: &TileMap| {
+            let rows = RowMap::from(map);
+            let mut buffers = RowScratch::default();
+            let mut scratch = BfsScratch::new(map.shape);
+            (0..map.shape.1).all(|y| {
+                (0..map.shape.0).all(|x| {
+                    let (length, Point(ex, ey)) = rows.eccentricity(&mut buffers, x, y);
+                    let (expected, _) = scratch.eccentricity(map, x, y);
+                    let distance =
+                        scratch.distances(map, Point(x, y))[(ey * map.shape.0 + ex) as usize];
+                    length == expected && (length == 0 || distance == Some(length - 1))
+                })
+            })
+        });
+    }
+
+    #[test]
+    fn wide_rows_span_words() {
+        let shape = (150, 3);
+        let mut map = TileMap::new(shape);
+        for y in 0..2 {
+            map.block(100, y);
+        }
+        let rows = RowMap::from(&map);
+        assert_eq!(
+            rows.eccentricity(&mut RowScratch::default(), 0, 0),
+            (154, Point(149, 0))
+        );
+    }
+}
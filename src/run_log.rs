@@ -0,0 +1,60 @@
+//! The append-only log of every board `solve` has solved, so results collected
+//! over many runs can be traced back to the build and the settings that found
+//! them. Every line of `runs.jsonl` is an object described by
+//! `schema/v1/run.schema.json`.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{json::Json, result_cache::Provenance, solution::Solution};
+
+/// name of the log in the result directory
+pub const FILE_NAME: &str = "runs.jsonl";
+
+/// the commit the binary was built from, `unknown` outside of a git checkout
+pub const COMMIT: &str = env!("PENTONIMO_COMMIT");
+
+/// One solved board
+pub struct Run<'a> {
+    pub solution: &'a Solution,
+    /// whether the search ran until it was done
+    pub complete: bool,
+    pub time: Duration,
+    /// whether the board was searched or taken from the result cache
+    pub source: Provenance,
+    /// the settings of the run by name
+    pub config: Json,
+}
+
+impl Run<'_> {
+    pub fn to_json(&self) -> Json {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Json::versioned([
+            ("timestamp", timestamp.into()),
+            ("pentonimo", env!("CARGO_PKG_VERSION").into()),
+            ("commit", COMMIT.into()),
+            ("config", self.config.clone()),
+            ("spec", self.solution.spec.to_string().into()),
+            ("diameter", self.solution.diameter.into()),
+            ("pieces", self.solution.pieces.len().into()),
+            ("proved_optimal", self.solution.proved_optimal.into()),
+            ("complete", self.complete.into()),
+            ("source", self.source.to_string().into()),
+            ("seconds", self.time.as_secs_f64().into()),
+        ])
+    }
+
+    /// appends the run as a line to the log, which is created if needed
+    pub fn append(&self, path: &Path) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // a single write, so concurrent runs don't interleave their lines
+        file.write_all(format!("{}\n", self.to_json()).as_bytes())
+    }
+}
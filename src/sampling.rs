@@ -0,0 +1,198 @@
+//! Estimates the largest diameter of a board by sampling instead of searching:
+//! many randomized greedy constructions are run and the distribution of their
+//! diameters gives confidence intervals for how well they do and an estimate of
+//! the largest diameter they can reach. This is a quick sanity check before an
+//! exact search of a big board, not a bound, as greedy constructions may never
+//! find the placements of the optimum.
+
+use crate::{
+    pathfinding::BfsScratch, pentonimo::PositionedPentonimo, rng::Rng, solution::Solution,
+    spec::BoardSpec,
+};
+
+/// quantile of the normal distribution of two-sided 95% intervals
+const Z_95: f64 = 1.96;
+
+pub struct Sampling {
+    /// constructions to run
+    pub samples: u32,
+    /// random placements compared at every step of a construction
+    pub candidates: usize,
+    pub rng: Rng,
+}
+
+impl Sampling {
+    pub fn new(samples: u32, rng: Rng) -> Self {
+        Self {
+            samples,
+            candidates: 8,
+            rng,
+        }
+    }
+
+    pub fn run(&mut self, spec: &BoardSpec) -> SampleEstimate {
+        let all = spec.placements();
+        let mut scratch = BfsScratch::new(spec.shape);
+        let mut diameters = Vec::with_capacity(self.samples as usize);
+        let mut best = Solution {
+            spec: spec.clone(),
+            diameter: scratch.objective(&spec.initial_map(), &spec.ends).0,
+            pieces: Vec::new(),
+            proved_optimal: false,
+        };
+
+        for _ in 0..self.samples {
+            let (diameter, pieces) = self.construct(spec, &all, &mut scratch);
+            diameters.push(diameter);
+            if diameter > best.diameter {
+                best.diameter = diameter;
+                best.pieces = pieces;
+            }
+        }
+        diameters.sort_unstable();
+
+        SampleEstimate { diameters, best }
+    }
+
+    /// Places pieces until none fits, every time the best of a few random
+    /// ones, and returns the largest diameter on the way with its pieces
+    fn construct(
+        &mut self,
+        spec: &BoardSpec,
+        all: &[PositionedPentonimo],
+        scratch: &mut BfsScratch,
+    ) -> (u32, Vec<PositionedPentonimo>) {
+        let mut map = spec.initial_map();
+        let mut available = spec.candidates();
+        let mut pieces = Vec::new();
+        let mut fits = Vec::new();
+        let mut best = (scratch.objective(&map, &spec.ends).0, Vec::new());
+
+        loop {
+            map.can_place_batch(all, &mut fits);
+            let children = all
+                .iter()
+                .zip(&fits)
+                .filter(|&(piece, &fits)| fits && available.get(piece.pentonimo().kind() as u8) > 0)
+                .map(|(piece, _)| piece)
+                .collect::<Vec<_>>();
+            if children.is_empty() {
+                break;
+            }
+
+            let mut chosen: Option<(u32, PositionedPentonimo)> = None;
+            for _ in 0..self.candidates.min(children.len()) {
+                let piece = *children[self.rng.below(children.len() as u64) as usize];
+                let (diameter, _) = scratch.objective(&(map.clone() | piece), &spec.ends);
+                if chosen.is_none_or(|(score, _)| diameter > score) {
+                    chosen = Some((diameter, piece));
+                }
+            }
+
+            let (diameter, piece) = chosen.unwrap();
+            map |= piece;
+            available.decrement(piece.pentonimo().kind() as u8);
+            pieces.push(piece);
+            if diameter > best.0 {
+                best = (diameter, pieces.clone());
+            }
+        }
+        best
+    }
+}
+
+/// The diameters of the constructions of [`Sampling`]. All intervals are 95%
+/// confidence intervals.
+pub struct SampleEstimate {
+    /// the diameter of every construction, sorted
+    pub diameters: Vec<u32>,
+    /// the best construction
+    pub best: Solution,
+}
+
+impl SampleEstimate {
+    /// the mean diameter of a construction and its interval
+    pub fn mean(&self) -> (f64, (f64, f64)) {
+        let n = self.diameters.len() as f64;
+        let mean = self.diameters.iter().map(|&d| d as f64).sum::<f64>() / n;
+        let variance = self
+            .diameters
+            .iter()
+            .map(|&d| (d as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.).max(1.);
+        let half = Z_95 * (variance / n).sqrt();
+        (mean, (mean - half, mean + half))
+    }
+
+    /// How often a construction reaches the best diameter and the Wilson score
+    /// interval of that probability
+    pub fn hit_rate(&self) -> (f64, (f64, f64)) {
+        let n = self.diameters.len() as f64;
+        let hits = self
+            .diameters
+            .iter()
+            .filter(|&&d| d == self.best.diameter)
+            .count() as f64;
+        let p = hits / n;
+        let z2 = Z_95 * Z_95;
+        let center = (p + z2 / (2. * n)) / (1. + z2 / n);
+        let half = Z_95 * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt() / (1. + z2 / n);
+        (p, ((center - half).max(0.), (center + half).min(1.)))
+    }
+
+    /// The largest diameter constructions reach, estimated from the two
+    /// largest ones by Robson and Whitlock as `2 x(n) - x(n-1)`, and the upper
+    /// end of its one-sided interval `x(n) + 19 (x(n) - x(n-1))`. Constructions
+    /// that often reach the best diameter make both equal to it.
+    pub fn maximum(&self) -> (f64, f64) {
+        let [.., second, first] = self.diameters[..] else {
+            let best = self.best.diameter as f64;
+            return (best, best);
+        };
+        let gap = (first - second) as f64;
+        (first as f64 + gap, first as f64 + 19. * gap)
+    }
+}
+
+#[test]
+fn estimates_bracket_the_sampled_diameters() {
+    use crate::brute_force::{find_best, SearchConfig};
+
+    let spec = BoardSpec::new((5, 4));
+    let estimate = Sampling::new(200, Rng::new(3)).run(&spec);
+    assert_eq!(estimate.diameters.len(), 200);
+    assert_eq!(estimate.best.diameter, *estimate.diameters.last().unwrap());
+    estimate.best.verify().unwrap();
+    let optimum = find_best(&spec, &SearchConfig::default()).diameter;
+    assert!(estimate.best.diameter <= optimum);
+
+    let (mean, (low, high)) = estimate.mean();
+    assert!(low <= mean && mean <= high);
+    assert!(mean <= estimate.best.diameter as f64);
+    let (p, (low, high)) = estimate.hit_rate();
+    assert!(0. < low && low <= p && p <= high && high <= 1.);
+    let (maximum, upper) = estimate.maximum();
+    assert!(estimate.best.diameter as f64 <= maximum && maximum <= upper);
+}
+
+#[test]
+fn intervals_of_known_samples() {
+    let estimate = SampleEstimate {
+        diameters: vec![4, 6, 8, 10],
+        best: Solution {
+            spec: BoardSpec::new((3, 3)),
+            diameter: 10,
+            pieces: Vec::new(),
+            proved_optimal: false,
+        },
+    };
+    let (mean, (low, high)) = estimate.mean();
+    assert_eq!(mean, 7.);
+    // the sample variance is 20 / 3
+    assert!((high - low - 2. * 1.96 * (20. / 3. / 4f64).sqrt()).abs() < 1e-9);
+    let (p, (low, high)) = estimate.hit_rate();
+    assert_eq!(p, 0.25);
+    assert!((low - 0.0456).abs() < 1e-3 && (high - 0.6994).abs() < 1e-3);
+    assert_eq!(estimate.maximum(), (12., 48.));
+}
@@ -0,0 +1,590 @@
+//! `pentonimo serve`: a queue of boards to solve, fed over TCP. Worker threads
+//! take the queued boards one after the other while clients keep submitting
+//! more. Every request is a line and gets a line of JSON back:
+//!
+//! ```text
+//! submit 7x5 holes=0,0   {"id": 1, "state": "queued"}
+//! list                   {"jobs": [{"id": 1, "spec": "7x5 holes=0,0", "state": "running", ...}]}
+//! cancel 1               {"id": 1, "state": "cancelled"}
+//! result 1               {"id": 1, ..., "state": "done", "complete": true, "solution": {...}}
+//! ```
+//!
+//! where the solution is described by `schema/v1/solution.schema.json`. Failed
+//! requests get `{"error": "..."}`.
+//!
+//! The queue survives restarts: `queue.jsonl` in the job directory logs every
+//! submitted, cancelled and finished job and `<id>.txt` holds the solution of
+//! every finished one. Jobs that were running when the server stopped are
+//! queued again.
+//!
+//! Workers take the queued job with the smallest estimated search first, so
+//! quick boards aren't stuck behind slow ones. Boards are checked like those of
+//! `solve` before they are queued, and ones that can't be searched are
+//! rejected.
+//!
+//! Connections are tasks on a tokio runtime, so idle clients cost no threads.
+//! Requests are answered on its blocking threads, since checking and
+//! estimating large boards takes a while, and the searches run on `workers`
+//! threads of their own.
+
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::{self, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{
+    analysis::{board_issues, BoardIssue},
+    brute_force::{find_best, SearchConfig},
+    estimate::priority,
+    json::Json,
+    solution::Solution,
+    spec::BoardSpec,
+};
+
+/// name of the log of the queue in the job directory
+pub const LOG_NAME: &str = "queue.jsonl";
+
+#[derive(Debug, Clone)]
+pub enum Status {
+    Queued,
+    Running,
+    Done {
+        solution: Solution,
+        /// false if the search was stopped before it was done
+        complete: bool,
+    },
+    Cancelled,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Queued => "queued",
+            Status::Running => "running",
+            Status::Done { .. } => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub spec: BoardSpec,
+    pub status: Status,
+    /// estimated nodes of the search, see [`priority`]
+    pub priority: f64,
+    /// stops the search of a running job
+    cancel: Arc<AtomicBool>,
+}
+
+/// The jobs of a server, shared by its connections and workers
+pub struct Queue {
+    dir: PathBuf,
+    state: Mutex<State>,
+    /// signalled when a job is queued or the queue shuts down
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    jobs: BTreeMap<u64, Job>,
+    next_id: u64,
+    shutdown: bool,
+}
+
+impl Queue {
+    /// Opens the queue kept in `dir`, which is created if needed
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+
+        let mut state = State {
+            next_id: 1,
+            ..Default::default()
+        };
+        let log = dir.join(LOG_NAME);
+        let text = match std::fs::read_to_string(&log) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(format!("failed to read {}: {err}", log.display())),
+        };
+        for (i, line) in text.lines().enumerate() {
+            let at = |err: String| format!("{}:{}: {err}", log.display(), i + 1);
+            let entry = line.parse::<Json>().map_err(at)?;
+            state.replay(&entry, &dir).map_err(at)?;
+        }
+
+        Ok(Self {
+            dir,
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        })
+    }
+
+    /// appends an entry to the log, so the change survives restarts
+    fn log(&self, entry: Json) -> Result<(), String> {
+        let path = self.dir.join(LOG_NAME);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(format!("{entry}\n").as_bytes()))
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))
+    }
+
+    /// Queues a board. Boards that can't be searched are rejected, see
+    /// [`check`].
+    pub fn submit(&self, spec: BoardSpec) -> Result<u64, String> {
+        check(&spec)?;
+        // before locking, estimating large boards takes a while
+        let priority = priority(&spec);
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        self.log(Json::Object(vec![
+            ("submit".to_owned(), id.into()),
+            ("spec".to_owned(), spec.to_string().into()),
+        ]))?;
+        state.next_id += 1;
+        state.jobs.insert(id, Job::new(id, spec, priority));
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Removes a queued job or stops a running one
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let job = state
+            .jobs
+            .get(&id)
+            .ok_or_else(|| format!("unknown job {id}"))?;
+        match job.status {
+            Status::Queued | Status::Running => {}
+            _ => return Err(format!("job {id} is {} already", job.status.name())),
+        }
+        self.log(Json::Object(vec![("cancel".to_owned(), id.into())]))?;
+        let job = state.jobs.get_mut(&id).unwrap();
+        job.cancel.store(true, Ordering::Relaxed);
+        job.status = Status::Cancelled;
+        Ok(())
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        self.state.lock().unwrap().jobs.values().cloned().collect()
+    }
+
+    pub fn job(&self, id: u64) -> Option<Job> {
+        self.state.lock().unwrap().jobs.get(&id).cloned()
+    }
+
+    /// Waits for the queued job with the smallest priority, the first submitted
+    /// one of equal ones, and marks it as running. None once the queue shuts
+    /// down.
+    pub fn next(&self) -> Option<Job> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.shutdown {
+                return None;
+            }
+            if let Some(job) = state
+                .jobs
+                .values_mut()
+                .filter(|job| matches!(job.status, Status::Queued))
+                .min_by(|a, b| a.priority.total_cmp(&b.priority))
+            {
+                job.status = Status::Running;
+                return Some(job.clone());
+            }
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    /// Stores the result of a running job. Results of cancelled jobs are dropped.
+    pub fn finish(&self, id: u64, solution: Solution, complete: bool) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let Some(job) = state.jobs.get_mut(&id) else {
+            return Err(format!("unknown job {id}"));
+        };
+        if !matches!(job.status, Status::Running) {
+            return Ok(());
+        }
+        let path = self.dir.join(format!("{id}.txt"));
+        std::fs::write(&path, solution.to_string())
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+        self.log(Json::Object(vec![
+            ("done".to_owned(), id.into()),
+            ("complete".to_owned(), complete.into()),
+        ]))?;
+        job.status = Status::Done { solution, complete };
+        Ok(())
+    }
+
+    /// wakes up all workers waiting for jobs, which then return
+    pub fn shutdown(&self) {
+        self.state.lock().unwrap().shutdown = true;
+        self.changed.notify_all();
+    }
+}
+
+impl Job {
+    fn new(id: u64, spec: BoardSpec, priority: f64) -> Self {
+        Self {
+            id,
+            spec,
+            status: Status::Queued,
+            priority,
+            cancel: Default::default(),
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("id".to_owned(), self.id.into()),
+            ("spec".to_owned(), self.spec.to_string().into()),
+            ("state".to_owned(), self.status.name().into()),
+            ("estimated_nodes".to_owned(), self.priority.round().into()),
+        ];
+        if let Status::Done { solution, complete } = &self.status {
+            fields.push(("complete".to_owned(), (*complete).into()));
+            fields.push(("solution".to_owned(), solution.to_json()));
+        }
+        Json::Object(fields)
+    }
+}
+
+impl State {
+    /// applies an entry of the log
+    fn replay(&mut self, entry: &Json, dir: &std::path::Path) -> Result<(), String> {
+        let id = |key| match entry.get(key) {
+            Some(&Json::Number(id)) => Ok(id as u64),
+            _ => Err(format!("invalid {key} entry")),
+        };
+        if entry.get("submit").is_some() {
+            let id = id("submit")?;
+            let Some(Json::String(spec)) = entry.get("spec") else {
+                return Err("submitted job without a spec".to_owned());
+            };
+            let spec = spec.parse::<BoardSpec>()?;
+            // the custom pieces of the server can differ from the last run
+            check(&spec)?;
+            let priority = priority(&spec);
+            self.jobs.insert(id, Job::new(id, spec, priority));
+            self.next_id = self.next_id.max(id + 1);
+            return Ok(());
+        }
+
+        let (id, status) = if entry.get("cancel").is_some() {
+            (id("cancel")?, Status::Cancelled)
+        } else if entry.get("done").is_some() {
+            let id = id("done")?;
+            let path = dir.join(format!("{id}.txt"));
+            let solution = std::fs::read_to_string(&path)
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?
+                .parse()?;
+            let complete = entry.get("complete") == Some(&Json::Bool(true));
+            (id, Status::Done { solution, complete })
+        } else {
+            return Err("unknown entry".to_owned());
+        };
+        self.jobs
+            .get_mut(&id)
+            .ok_or_else(|| format!("unknown job {id}"))?
+            .status = status;
+        Ok(())
+    }
+}
+
+/// Whether a board can be searched, which are the ones `solve` doesn't skip.
+/// Searching or estimating boards with pieces of custom kinds that weren't
+/// given a shape panics.
+fn check(spec: &BoardSpec) -> Result<(), String> {
+    if let Some(kind) = spec.undefined_piece() {
+        return Err(format!(
+            "{spec}: custom piece {kind} isn't defined on this server"
+        ));
+    }
+    let errors = board_issues(spec)
+        .into_iter()
+        .filter(BoardIssue::is_error)
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{spec}: {}", errors.join(", ")))
+    }
+}
+
+/// Searches the jobs of `queue` until it shuts down
+pub fn work(queue: &Queue, config: &SearchConfig) {
+    while let Some(job) = queue.next() {
+        let config = SearchConfig {
+            cancel: Some(job.cancel.clone()),
+            ..config.clone()
+        };
+        let result = find_best(&job.spec, &config);
+        let solution = Solution {
+            spec: job.spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: result.certificate.is_some(),
+        };
+        if let Err(err) = queue.finish(job.id, solution, result.complete) {
+            eprintln!("job {}: {err}", job.id);
+        }
+    }
+}
+
+/// The answer to a request line
+pub fn handle(queue: &Queue, request: &str) -> Json {
+    let error = |err: String| Json::Object(vec![("error".to_owned(), err.into())]);
+    let state = |job: Job| {
+        Json::Object(vec![
+            ("id".to_owned(), job.id.into()),
+            ("state".to_owned(), job.status.name().into()),
+        ])
+    };
+    let parse_id = |id: &str| {
+        id.trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid job id {id:?}"))
+    };
+
+    let (command, argument) = request
+        .trim()
+        .split_once(' ')
+        .unwrap_or((request.trim(), ""));
+    let answer = match command {
+        "submit" => argument
+            .parse::<BoardSpec>()
+            .and_then(|spec| queue.submit(spec))
+            .map(|id| state(queue.job(id).unwrap())),
+        "list" => Ok(Json::Object(vec![(
+            "jobs".to_owned(),
+            Json::Array(
+                queue
+                    .jobs()
+                    .into_iter()
+                    .map(|job| {
+                        let Json::Object(mut fields) = job.to_json() else {
+                            unreachable!("jobs are objects")
+                        };
+                        // the solutions are fetched one by one
+                        fields.retain(|(key, _)| key != "solution");
+                        Json::Object(fields)
+                    })
+                    .collect(),
+            ),
+        )])),
+        "cancel" => parse_id(argument)
+            .and_then(|id| queue.cancel(id).map(|_| id))
+            .map(|id| state(queue.job(id).unwrap())),
+        "result" => parse_id(argument).and_then(|id| {
+            queue
+                .job(id)
+                .map(|job| job.to_json())
+                .ok_or_else(|| format!("unknown job {id}"))
+        }),
+        _ => Err(format!(
+            "unknown request {command:?}, expected submit, list, cancel or result"
+        )),
+    };
+    answer.unwrap_or_else(error)
+}
+
+/// answers the requests of a client until it disconnects
+async fn serve_client(queue: Arc<Queue>, stream: TcpStream) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let queue = queue.clone();
+        let answer = tokio::task::spawn_blocking(move || handle(&queue, &line))
+            .await
+            .map_err(io::Error::other)?;
+        writer.write_all(format!("{answer}\n").as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Accepts clients on `listener` while `workers` threads search the queued
+/// jobs with `config`. Only returns if accepting clients fails.
+pub fn serve(
+    listener: TcpListener,
+    queue: Arc<Queue>,
+    workers: usize,
+    config: SearchConfig,
+) -> io::Result<()> {
+    for _ in 0..workers {
+        let queue = queue.clone();
+        let config = config.clone();
+        std::thread::spawn(move || work(&queue, &config));
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_io()
+        .build()?;
+    let result = runtime.block_on(async {
+        listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve_client(queue, stream).await {
+                    eprintln!("connection to {peer} failed: {err}");
+                }
+            });
+        }
+    });
+    queue.shutdown();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        sync::Arc,
+    };
+
+    use super::{handle, Queue};
+    use crate::{
+        brute_force::{find_best, SearchConfig},
+        json::Json,
+        solution::Solution,
+    };
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("pentonimo-{name}-{}", std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn quick_boards_come_first() {
+        let dir = temp_dir("priority");
+        let queue = Queue::open(dir.clone()).unwrap();
+        for spec in ["6x6", "3x4", "5x5", "4x3"] {
+            queue.submit(spec.parse().unwrap()).unwrap();
+        }
+        let order = (0..4).map(|_| queue.next().unwrap().id).collect::<Vec<_>>();
+        assert_eq!(order, [2, 4, 3, 1]);
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn queue_survives_restarts() {
+        let dir = temp_dir("queue");
+        let queue = Queue::open(dir.clone()).unwrap();
+        for spec in ["4x4", "5x3", "5x4"] {
+            handle(&queue, &format!("submit {spec}"));
+        }
+        assert_eq!(
+            handle(&queue, "cancel 2").get("state"),
+            Some(&Json::from("cancelled"))
+        );
+        assert!(handle(&queue, "cancel 2").get("error").is_some());
+
+        // the first job is finished, then the server stops during the last one
+        let job = queue.next().unwrap();
+        let result = find_best(&job.spec, &SearchConfig::default());
+        let solution = Solution {
+            spec: job.spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        queue.finish(job.id, solution, result.complete).unwrap();
+        assert_eq!(queue.next().unwrap().id, 3);
+        drop(queue);
+
+        let queue = Queue::open(dir.clone()).unwrap();
+        let states = queue
+            .jobs()
+            .iter()
+            .map(|job| job.status.name())
+            .collect::<Vec<_>>();
+        assert_eq!(states, ["done", "cancelled", "queued"]);
+        let answer = handle(&queue, "result 1");
+        assert_eq!(answer.get("complete"), Some(&Json::Bool(true)));
+        assert_eq!(
+            answer
+                .get("solution")
+                .and_then(|solution| solution.get("diameter")),
+            Some(&Json::from(result.diameter))
+        );
+        assert_eq!(queue.submit("4x4".parse().unwrap()).unwrap(), 4);
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bad_boards_are_rejected() {
+        let dir = temp_dir("rejected");
+        let queue = Queue::open(dir.clone()).unwrap();
+        for spec in [
+            "2x2",
+            "2x1 holes=0,0;1,0",
+            "5x5 holes=2,0;2,1;2,2;2,3;2,4 ends=0,0;4,4",
+        ] {
+            let answer = handle(&queue, &format!("submit {spec}"));
+            assert!(answer.get("error").is_some(), "{spec}: {answer}");
+        }
+        assert!(queue.jobs().is_empty());
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn clients_talk_json_lines() {
+        let dir = temp_dir("serve");
+        let queue = Arc::new(Queue::open(dir.clone()).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server_queue = queue.clone();
+        std::thread::spawn(move || {
+            super::serve(listener, server_queue, 1, SearchConfig::default()).unwrap()
+        });
+
+        let stream = TcpStream::connect(address).unwrap();
+        let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+        let mut request = |line: &str| {
+            writeln!(&stream, "{line}").unwrap();
+            lines.next().unwrap().unwrap().parse::<Json>().unwrap()
+        };
+
+        assert_eq!(request("submit 4x4").get("id"), Some(&Json::from(1u32)));
+        assert!(request("submit 4x").get("error").is_some());
+        assert!(request("frobnicate").get("error").is_some());
+        let result = loop {
+            let result = request("result 1");
+            if result.get("state") == Some(&Json::from("done")) {
+                break result;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(result.get("complete"), Some(&Json::Bool(true)));
+        let Some(Json::Array(jobs)) = request("list").get("jobs").cloned() else {
+            panic!("list has jobs");
+        };
+        assert_eq!(jobs.len(), 1);
+        assert!(jobs[0].get("solution").is_none());
+
+        queue.shutdown();
+        _ = std::fs::remove_dir_all(dir);
+    }
+}
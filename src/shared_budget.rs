@@ -0,0 +1,240 @@
+//! Several boards that share one set of pieces: every piece is placed on at
+//! most one of them, and the sum or the smallest of their diameters is
+//! maximized.
+//!
+//! Every board is searched exhaustively once for its best placement with every
+//! set of pieces, which only works for small boards. The boards are then
+//! combined one after the other, keeping the best total for every set of
+//! pieces used so far. Both totals only grow with the diameter of every board,
+//! so the best total of the boards so far is all that matters for the next.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    candidates::Candidates,
+    pathfinding::BfsScratch,
+    pentonimo::{PositionedPentonimo, KINDS},
+    solution::Solution,
+    spec::BoardSpec,
+    tile_map::TileMap,
+};
+
+/// What is maximized over all boards
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    #[default]
+    Sum,
+    /// the smallest diameter, so every board gets a fair share
+    Min,
+}
+
+impl Goal {
+    fn combine(self, total: u32, diameter: u32) -> u32 {
+        match self {
+            Goal::Sum => total + diameter,
+            Goal::Min => total.min(diameter),
+        }
+    }
+
+    /// the total of no boards
+    fn empty(self) -> u32 {
+        match self {
+            Goal::Sum => 0,
+            Goal::Min => u32::MAX,
+        }
+    }
+}
+
+pub struct Shared {
+    pub total: u32,
+    /// the solution of every board, in the order they were given. Their specs
+    /// allow all shared pieces.
+    pub solutions: Vec<Solution>,
+}
+
+/// Distributes `pieces` over `specs` so that `goal` is as large as possible,
+/// only rotated if they are `one_sided`. The pieces of the specs are ignored.
+pub fn share(specs: &[BoardSpec], pieces: [u8; KINDS], one_sided: bool, goal: Goal) -> Shared {
+    let budget = Candidates::new(pieces);
+    let none = Candidates::new([0; KINDS]);
+    let specs = specs
+        .iter()
+        .map(|spec| BoardSpec {
+            pieces,
+            one_sided,
+            ..spec.clone()
+        })
+        .collect::<Vec<_>>();
+
+    let mut boards = Vec::with_capacity(specs.len());
+    // for every board, the best total up to it for every set of pieces used
+    // up to it, with the pieces used by the boards before and by itself
+    let mut stages = Vec::<FxHashMap<Candidates, (u32, Candidates, Candidates)>>::new();
+    let mut totals = FxHashMap::from_iter([(none, goal.empty())]);
+    for spec in &specs {
+        let best = best_by_pieces(spec);
+        let mut stage = FxHashMap::default();
+        for (&before, &total) in &totals {
+            for (&used, &(diameter, _)) in &best {
+                let Some(sum) = add_within(before, used, budget) else {
+                    continue;
+                };
+                let total = goal.combine(total, diameter);
+                if stage.get(&sum).is_none_or(|&(known, _, _)| total > known) {
+                    stage.insert(sum, (total, before, used));
+                }
+            }
+        }
+        totals = stage
+            .iter()
+            .map(|(&used, &(total, _, _))| (used, total))
+            .collect();
+        stages.push(stage);
+        boards.push(best);
+    }
+
+    let (mut used, total) = totals
+        .into_iter()
+        .max_by_key(|&(used, total)| (total, std::cmp::Reverse(used_count(used))))
+        .expect("there is always a way to place no pieces");
+    let mut solutions = Vec::with_capacity(specs.len());
+    for ((spec, stage), best) in specs.iter().zip(&stages).zip(&boards).rev() {
+        let (_, before, board_used) = stage[&used];
+        let (diameter, placed) = &best[&board_used];
+        solutions.push(Solution {
+            spec: spec.clone(),
+            diameter: *diameter,
+            pieces: placed.clone(),
+            proved_optimal: false,
+        });
+        used = before;
+    }
+    solutions.reverse();
+
+    Shared { total, solutions }
+}
+
+/// The best placement on `spec` for every set of its pieces, by the pieces
+/// it uses, including the empty board
+pub fn best_by_pieces(spec: &BoardSpec) -> FxHashMap<Candidates, (u32, Vec<PositionedPentonimo>)> {
+    let mut enumeration = Enumeration {
+        spec,
+        all: spec.placements(),
+        scratch: BfsScratch::new(spec.shape),
+        path: Vec::new(),
+        best: FxHashMap::default(),
+    };
+    enumeration.visit(spec.initial_map(), Candidates::new([0; KINDS]), 0);
+    enumeration.best
+}
+
+/// Every set of pieces that fits on a board, each once: pieces are only placed
+/// in the order of the placements.
+struct Enumeration<'a> {
+    spec: &'a BoardSpec,
+    all: Vec<PositionedPentonimo>,
+    scratch: BfsScratch,
+    path: Vec<PositionedPentonimo>,
+    best: FxHashMap<Candidates, (u32, Vec<PositionedPentonimo>)>,
+}
+
+impl Enumeration<'_> {
+    fn visit(&mut self, map: TileMap, used: Candidates, from: usize) {
+        let (diameter, _) = self.scratch.objective(&map, &self.spec.ends);
+        if diameter == 0 && !self.spec.ends.is_empty() {
+            return;
+        }
+        if self
+            .best
+            .get(&used)
+            .is_none_or(|&(known, _)| diameter > known)
+        {
+            self.best.insert(used, (diameter, self.path.clone()));
+        }
+
+        for i in from..self.all.len() {
+            let piece = self.all[i];
+            let kind = piece.pentonimo().kind() as u8;
+            if used.get(kind) >= self.spec.pieces[kind as usize] || !map.can_place(piece) {
+                continue;
+            }
+            let mut used = used;
+            used.increment(kind);
+            self.path.push(piece);
+            self.visit(map.clone() | piece, used, i + 1);
+            self.path.pop();
+        }
+    }
+}
+
+/// the pieces of `a` and `b` together, if they are at most `budget`
+fn add_within(a: Candidates, b: Candidates, budget: Candidates) -> Option<Candidates> {
+    let mut sum = a;
+    for kind in 0..KINDS as u8 {
+        let count = a.get(kind) + b.get(kind);
+        if count > budget.get(kind) {
+            return None;
+        }
+        sum.set(kind, count);
+    }
+    Some(sum)
+}
+
+fn used_count(used: Candidates) -> u32 {
+    used.into_iter().map(u32::from).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{best_by_pieces, share, Goal};
+    use crate::{
+        brute_force::{find_best, SearchConfig},
+        pentonimo::{KINDS, PENTOMINOES},
+        spec::BoardSpec,
+    };
+
+    #[test]
+    fn single_boards_match_the_search() {
+        for shape in [(4, 4), (5, 3), (5, 4)] {
+            let spec = BoardSpec::new(shape);
+            let result = find_best(&spec, &SearchConfig::default());
+            let best = best_by_pieces(&spec)
+                .into_values()
+                .filter(|(_, placed)| !placed.is_empty())
+                .map(|(diameter, _)| diameter)
+                .max();
+            assert_eq!(best, Some(result.diameter), "{spec}");
+        }
+    }
+
+    #[test]
+    fn boards_share_the_pieces() {
+        let specs = [BoardSpec::new((5, 3)), BoardSpec::new((4, 4))];
+        let pieces = PENTOMINOES;
+        for goal in [Goal::Sum, Goal::Min] {
+            let shared = share(&specs, pieces, false, goal);
+            let mut used = [0; KINDS];
+            for solution in &shared.solutions {
+                solution.verify().unwrap();
+                for piece in &solution.pieces {
+                    used[piece.pentonimo().kind() as usize] += 1;
+                }
+            }
+            assert!(used.iter().zip(pieces).all(|(&used, max)| used <= max));
+
+            let diameters = shared.solutions.iter().map(|solution| solution.diameter);
+            let total = match goal {
+                Goal::Sum => diameters.sum(),
+                Goal::Min => diameters.min().unwrap(),
+            };
+            assert_eq!(shared.total, total);
+        }
+
+        // the best boards on their own are an upper bound
+        let alone = specs
+            .iter()
+            .map(|spec| find_best(spec, &SearchConfig::default()).diameter)
+            .sum::<u32>();
+        assert!(share(&specs, pieces, false, Goal::Sum).total <= alone);
+    }
+}
@@ -0,0 +1,195 @@
+//! The loops over [`Tile`]s of a [`TileMap`](crate::tile_map::TileMap) that
+//! compare or intersect several tiles at once: with AVX2 or SSE2 on x86_64,
+//! with NEON on aarch64 and one tile at a time on other targets. AVX2 is used
+//! when the build enables it, which `.cargo/config.toml` does with
+//! `-Ctarget-cpu=native` on machines that have it.
+
+use crate::tile::Tile;
+
+/// the number of tiles at the start of `a` and `b` that are equal in both
+#[inline]
+pub fn equal_prefix(a: &[Tile], b: &[Tile]) -> usize {
+    let len = a.len().min(b.len());
+    let start = arch::equal_prefix(&a[..len], &b[..len]);
+    start
+        + a[start..len]
+            .iter()
+            .zip(&b[start..len])
+            .take_while(|(a, b)| a == b)
+            .count()
+}
+
+/// whether no cell is blocked in both tiles of any of the pairs
+#[inline]
+pub fn disjoint(a: &[Tile; 4], b: &[Tile; 4]) -> bool {
+    arch::disjoint(a, b)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+mod arch {
+    use std::arch::x86_64::*;
+
+    use crate::tile::Tile;
+
+    /// the equal tiles before the first chunk of 4 that differs, at most the
+    /// tiles of whole chunks
+    #[inline]
+    pub fn equal_prefix(a: &[Tile], b: &[Tile]) -> usize {
+        let mut start = 0;
+        while start + 4 <= a.len() {
+            // SAFETY: both slices have 4 tiles from `start` on, which is all
+            // that unaligned loads need, and avx2 is enabled for the build
+            let equal = unsafe {
+                let x = _mm256_loadu_si256(a.as_ptr().add(start).cast());
+                let y = _mm256_loadu_si256(b.as_ptr().add(start).cast());
+                _mm256_movemask_epi8(_mm256_cmpeq_epi8(x, y)) as u32
+            };
+            if equal != u32::MAX {
+                // one bit per byte, so 8 per tile
+                return start + (!equal).trailing_zeros() as usize / 8;
+            }
+            start += 4;
+        }
+        start
+    }
+
+    #[inline]
+    pub fn disjoint(a: &[Tile; 4], b: &[Tile; 4]) -> bool {
+        // SAFETY: 4 tiles are 256 bits and avx2 is enabled for the build
+        unsafe {
+            let x = _mm256_loadu_si256(a.as_ptr().cast());
+            let y = _mm256_loadu_si256(b.as_ptr().cast());
+            _mm256_testz_si256(x, y) == 1
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+mod arch {
+    use std::arch::x86_64::*;
+
+    use crate::tile::Tile;
+
+    /// like the avx2 version with chunks of 2 tiles, sse2 is part of every x86_64
+    #[inline]
+    pub fn equal_prefix(a: &[Tile], b: &[Tile]) -> usize {
+        let mut start = 0;
+        while start + 2 <= a.len() {
+            // SAFETY: both slices have 2 tiles from `start` on
+            let equal = unsafe {
+                let x = _mm_loadu_si128(a.as_ptr().add(start).cast());
+                let y = _mm_loadu_si128(b.as_ptr().add(start).cast());
+                _mm_movemask_epi8(_mm_cmpeq_epi8(x, y)) as u32
+            };
+            if equal != 0xffff {
+                return start + (!equal).trailing_zeros() as usize / 8;
+            }
+            start += 2;
+        }
+        start
+    }
+
+    #[inline]
+    pub fn disjoint(a: &[Tile; 4], b: &[Tile; 4]) -> bool {
+        // SAFETY: 4 tiles are two times 128 bits
+        unsafe {
+            let low = _mm_and_si128(
+                _mm_loadu_si128(a.as_ptr().cast()),
+                _mm_loadu_si128(b.as_ptr().cast()),
+            );
+            let high = _mm_and_si128(
+                _mm_loadu_si128(a.as_ptr().add(2).cast()),
+                _mm_loadu_si128(b.as_ptr().add(2).cast()),
+            );
+            let zero = _mm_cmpeq_epi8(_mm_or_si128(low, high), _mm_setzero_si128());
+            _mm_movemask_epi8(zero) == 0xffff
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    use std::arch::aarch64::*;
+
+    use crate::tile::Tile;
+
+    /// like the x86_64 versions with chunks of 2 tiles, neon is part of every aarch64
+    #[inline]
+    pub fn equal_prefix(a: &[Tile], b: &[Tile]) -> usize {
+        let mut start = 0;
+        while start + 2 <= a.len() {
+            // SAFETY: both slices have 2 tiles from `start` on
+            let equal = unsafe {
+                let x = vld1q_u64(a.as_ptr().add(start).cast());
+                let y = vld1q_u64(b.as_ptr().add(start).cast());
+                vceqq_u64(x, y)
+            };
+            // SAFETY: neon is enabled on every aarch64 target
+            if unsafe { vminvq_u32(vreinterpretq_u32_u64(equal)) } != u32::MAX {
+                let first = unsafe { vgetq_lane_u64::<0>(equal) };
+                return start + (first == u64::MAX) as usize;
+            }
+            start += 2;
+        }
+        start
+    }
+
+    #[inline]
+    pub fn disjoint(a: &[Tile; 4], b: &[Tile; 4]) -> bool {
+        // SAFETY: 4 tiles are two times 128 bits
+        unsafe {
+            let low = vandq_u64(vld1q_u64(a.as_ptr().cast()), vld1q_u64(b.as_ptr().cast()));
+            let high = vandq_u64(
+                vld1q_u64(a.as_ptr().add(2).cast()),
+                vld1q_u64(b.as_ptr().add(2).cast()),
+            );
+            vmaxvq_u32(vreinterpretq_u32_u64(vorrq_u64(low, high))) == 0
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod arch {
+    use crate::tile::Tile;
+
+    /// leaves all tiles to the loop of [`super::equal_prefix`]
+    #[inline]
+    pub fn equal_prefix(_: &[Tile], _: &[Tile]) -> usize {
+        0
+    }
+
+    #[inline]
+    pub fn disjoint(a: &[Tile; 4], b: &[Tile; 4]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (*a & *b).is_empty())
+    }
+}
+
+#[test]
+fn matches_tile_by_tile() {
+    let mut rng = crate::rng::Rng::new(7);
+    for _ in 0..1000 {
+        let len = rng.below(12) as usize;
+        let a = (0..len)
+            .map(|_| Tile(rng.next_u64() & rng.next_u64()))
+            .collect::<Vec<_>>();
+        let mut b = a.clone();
+        if len > 0 && rng.below(4) > 0 {
+            // flip a single cell, so only one byte of one tile differs
+            let index = rng.below(len as u64) as usize;
+            b[index].0 ^= 1 << rng.below(64);
+        }
+        let expected = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+        assert_eq!(equal_prefix(&a, &b), expected, "{a:?} {b:?}");
+
+        let quad = |rng: &mut crate::rng::Rng| {
+            [(); 4].map(|_| Tile(rng.next_u64() & rng.next_u64() & rng.next_u64()))
+        };
+        let (a, mut b) = (quad(&mut rng), quad(&mut rng));
+        if rng.below(2) == 0 {
+            // random tiles mostly overlap somewhere
+            b = [0, 1, 2, 3].map(|i| Tile(b[i].0 & !a[i].0 | (rng.below(8) == 0) as u64));
+        }
+        let expected = a.iter().zip(&b).all(|(a, b)| a.0 & b.0 == 0);
+        assert_eq!(disjoint(&a, &b), expected, "{a:?} {b:?}");
+    }
+}
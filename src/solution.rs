@@ -0,0 +1,362 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    json::Json,
+    pathfinding::BfsScratch,
+    pentonimo::{Pentonimo, PentonimoKind, PositionedPentonimo, KINDS},
+    spec::BoardSpec,
+    tile_map::TileMap,
+};
+
+/// The best placement found for a board, in a plain text format:
+///
+/// ```text
+/// spec 5x5
+/// diameter 14
+/// piece I 1 0 0
+/// ```
+///
+/// where every `piece` line is the kind, the index of its variant and its position.
+/// A `proved-optimal` line marks solutions found by an exhaustive search.
+/// The SVG pictures of solutions contain this text in their [`SVG_DESC_ID`]
+/// `<desc>`, so they can be parsed as well.
+/// id of the `<desc>` element of SVG pictures that contains the solution
+pub const SVG_DESC_ID: &str = "pentonimo-solution";
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub spec: BoardSpec,
+    pub diameter: u32,
+    pub pieces: Vec<PositionedPentonimo>,
+    /// no placement on this board has a larger diameter
+    pub proved_optimal: bool,
+}
+
+impl Solution {
+    /// Checks that the pieces can legally be placed on the board and that the
+    /// resulting map has the claimed diameter.
+    pub fn verify(&self) -> Result<(), String> {
+        let spec = &self.spec;
+        let mut map = spec.initial_map();
+        let free = spec.free_map();
+        let mut used = [0u8; KINDS];
+
+        for piece in &self.pieces {
+            let kind = piece.pentonimo().kind();
+            let (x, y) = piece.position();
+            let (w, h) = piece.pentonimo().shape();
+
+            used[kind as usize] += 1;
+            if used[kind as usize] > spec.pieces[kind as usize] {
+                return Err(format!(
+                    "uses {} pieces of kind {kind}, but only {} are available",
+                    used[kind as usize], spec.pieces[kind as usize]
+                ));
+            }
+            if x + w as u32 > spec.shape.0 || y + h as u32 > spec.shape.1 {
+                return Err(format!("piece {kind} at {x},{y} is outside of the board"));
+            }
+            if spec.one_sided && !piece.pentonimo().is_rotation() {
+                return Err(format!(
+                    "piece {kind} at {x},{y} is mirrored, but the pieces are one-sided"
+                ));
+            }
+            if !map.can_place(*piece) {
+                return Err(format!("piece {kind} at {x},{y} overlaps a blocked cell"));
+            }
+            if !free.can_place(*piece) {
+                return Err(format!(
+                    "piece {kind} at {x},{y} covers a cell that has to stay free"
+                ));
+            }
+            if !spec.allows(*piece) {
+                return Err(format!(
+                    "piece {kind} at {x},{y} covers a cell outside of the region of {kind}"
+                ));
+            }
+            map |= *piece;
+        }
+
+        let (diameter, _) = BfsScratch::new(spec.shape).objective(&map, &spec.ends);
+        if diameter == 0 && !spec.ends.is_empty() {
+            return Err("the pieces disconnect the ends".to_owned());
+        }
+        if diameter != self.diameter {
+            return Err(format!(
+                "claims a diameter of {}, but has a diameter of {diameter}",
+                self.diameter
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// the board with all pieces placed
+    pub fn map(&self) -> TileMap {
+        let mut map = self.spec.initial_map();
+        for &piece in &self.pieces {
+            map |= piece;
+        }
+        map
+    }
+
+    /// as described by `schema/v1/solution.schema.json`
+    pub fn to_json(&self) -> Json {
+        let holes = self
+            .spec
+            .holes
+            .iter()
+            .map(|&(x, y)| vec![x, y].into())
+            .collect();
+        let free = self
+            .spec
+            .free
+            .iter()
+            .map(|&(x, y)| vec![x, y].into())
+            .collect::<Vec<_>>();
+        let pieces = self
+            .pieces
+            .iter()
+            .map(|piece| {
+                let (x, y) = piece.position();
+                Json::Object(vec![
+                    (
+                        "kind".to_owned(),
+                        piece.pentonimo().kind().to_string().into(),
+                    ),
+                    ("variant".to_owned(), piece.variant().into()),
+                    ("x".to_owned(), x.into()),
+                    ("y".to_owned(), y.into()),
+                ])
+            })
+            .collect();
+        let mut fields = vec![
+            ("spec", self.spec.to_string().into()),
+            ("width", self.spec.shape.0.into()),
+            ("height", self.spec.shape.1.into()),
+            ("holes", Json::Array(holes)),
+            ("diameter", self.diameter.into()),
+            ("proved_optimal", self.proved_optimal.into()),
+            ("pieces", Json::Array(pieces)),
+        ];
+        if !free.is_empty() {
+            fields.insert(4, ("free", Json::Array(free)));
+        }
+        Json::versioned(fields)
+    }
+
+    /// reads the output of [`Solution::to_json`]
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let spec = match json.get("spec") {
+            Some(Json::String(spec)) => spec.parse::<BoardSpec>()?,
+            _ => return Err("solution is missing its spec".to_owned()),
+        };
+        let diameter = match json.get("diameter") {
+            Some(&Json::Number(diameter)) => diameter as u32,
+            _ => return Err("solution is missing its diameter".to_owned()),
+        };
+        let pieces = match json.get("pieces") {
+            Some(Json::Array(pieces)) => pieces
+                .iter()
+                .map(|piece| {
+                    let field = |key| match piece.get(key) {
+                        Some(Json::String(kind)) => Ok(kind.clone()),
+                        Some(Json::Number(n)) => Ok(n.to_string()),
+                        _ => Err(format!("piece is missing its {key}")),
+                    };
+                    parse_piece(&format!(
+                        "{} {} {} {}",
+                        field("kind")?,
+                        field("variant")?,
+                        field("x")?,
+                        field("y")?
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err("solution is missing its pieces".to_owned()),
+        };
+
+        Ok(Solution {
+            spec,
+            diameter,
+            pieces,
+            proved_optimal: json.get("proved_optimal") == Some(&Json::Bool(true)),
+        })
+    }
+
+    /// the `<desc>` element of the SVG picture of the solution
+    pub fn svg_desc(&self) -> String {
+        let text = self
+            .to_string()
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        format!("<desc id=\"{SVG_DESC_ID}\">\n{text}</desc>")
+    }
+
+    /// the solution in the `<desc>` element written by [`Solution::svg_desc`]
+    fn from_svg(svg: &str) -> Result<Self, String> {
+        let start = format!("<desc id=\"{SVG_DESC_ID}\">");
+        let text = svg
+            .split_once(&start)
+            .and_then(|(_, rest)| rest.split_once("</desc>"))
+            .ok_or("the picture has no solution")?
+            .0;
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+            .parse()
+    }
+
+    /// the same solution mirrored along the board diagonal
+    pub fn transposed(&self) -> Self {
+        Self {
+            spec: self.spec.transposed(),
+            diameter: self.diameter,
+            pieces: self.pieces.iter().map(|piece| piece.transpose()).collect(),
+            proved_optimal: self.proved_optimal,
+        }
+    }
+
+    /// the same solution mirrored left to right and/or top to bottom
+    pub fn flipped(&self, flip_x: bool, flip_y: bool) -> Self {
+        let (w, h) = self.spec.shape;
+        Self {
+            spec: self.spec.flipped(flip_x, flip_y),
+            diameter: self.diameter,
+            pieces: (self.pieces.iter())
+                .map(|&piece| match (flip_x, flip_y) {
+                    (false, false) => piece,
+                    (true, false) => piece.flip_x(w),
+                    (false, true) => piece.flip_y(h),
+                    (true, true) => piece.flip_x(w).flip_y(h),
+                })
+                .collect(),
+            proved_optimal: self.proved_optimal,
+        }
+    }
+}
+
+impl Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "spec {}", self.spec)?;
+        writeln!(f, "diameter {}", self.diameter)?;
+        if self.proved_optimal {
+            writeln!(f, "proved-optimal")?;
+        }
+        for piece in &self.pieces {
+            let (x, y) = piece.position();
+            writeln!(
+                f,
+                "piece {} {} {x} {y}",
+                piece.pentonimo().kind(),
+                piece.variant()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Solution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim_start().starts_with('<') {
+            return Self::from_svg(s);
+        }
+
+        let mut spec = None;
+        let mut diameter = None;
+        let mut pieces = Vec::new();
+        let mut proved_optimal = false;
+
+        for (i, line) in s.lines().enumerate() {
+            let with_line = |err: String| format!("line {}: {err}", i + 1);
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "spec" => spec = Some(value.parse::<BoardSpec>().map_err(with_line)?),
+                "diameter" => {
+                    diameter = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| with_line(format!("invalid diameter {value:?}")))?,
+                    )
+                }
+                "piece" => pieces.push(parse_piece(value).map_err(with_line)?),
+                "proved-optimal" => proved_optimal = true,
+                _ => return Err(with_line(format!("unknown entry {key:?}"))),
+            }
+        }
+
+        Ok(Solution {
+            spec: spec.ok_or("solution is missing its spec")?,
+            diameter: diameter.ok_or("solution is missing its diameter")?,
+            pieces,
+            proved_optimal,
+        })
+    }
+}
+
+/// parses `KIND VARIANT X Y`
+pub fn parse_piece(s: &str) -> Result<PositionedPentonimo, String> {
+    let words = s.split_whitespace().collect::<Vec<_>>();
+    let [kind, variant, x, y] = words[..] else {
+        return Err(format!("expected `KIND VARIANT X Y`, got {s:?}"));
+    };
+
+    let kind =
+        PentonimoKind::from_str(kind).map_err(|_| format!("unknown pentonimo kind {kind:?}"))?;
+    if !kind.is_defined() {
+        return Err(format!("custom piece {kind} isn't defined"));
+    }
+    let number = |n: &str| n.parse().map_err(|_| format!("invalid number {n:?}"));
+    let variant = Pentonimo::variant(kind, number(variant)? as usize)
+        .ok_or_else(|| format!("{kind} has no variant {variant}"))?;
+
+    Ok(variant.position(number(x)?, number(y)?))
+}
+
+#[test]
+fn json_roundtrips() {
+    let mut spec = BoardSpec::new((6, 5));
+    spec.holes.push((5, 4));
+    let solution = Solution {
+        pieces: vec![
+            parse_piece("I 1 0 0").unwrap(),
+            parse_piece("X 0 2 2").unwrap(),
+        ],
+        diameter: 3,
+        spec,
+        proved_optimal: true,
+    };
+    let json = solution.to_json().to_string().parse::<Json>().unwrap();
+    assert_eq!(
+        Solution::from_json(&json).unwrap().to_string(),
+        solution.to_string()
+    );
+    assert!(Solution::from_json(&Json::Object(Vec::new())).is_err());
+}
+
+#[test]
+fn svg_descriptions_roundtrip() {
+    let mut spec = BoardSpec::new((5, 4));
+    spec.holes.push((4, 3));
+    let solution = Solution {
+        pieces: vec![parse_piece("I 1 0 0").unwrap()],
+        diameter: 0,
+        spec,
+        proved_optimal: true,
+    };
+    let svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">\n{}\n<rect /></svg>\n",
+        solution.svg_desc()
+    );
+    let parsed = svg.parse::<Solution>().unwrap();
+    assert_eq!(parsed.to_string(), solution.to_string());
+    assert!("<svg></svg>".parse::<Solution>().is_err());
+}
@@ -0,0 +1,749 @@
+use std::{fmt::Display, str::FromStr};
+
+use strum::VariantArray;
+
+use crate::{
+    brute_force::placements,
+    candidates::Candidates,
+    pathfinding::{BfsScratch, Point},
+    pentonimo::{Family, PentonimoKind, PositionedPentonimo, KINDS, PENTOMINOES},
+    tile_map::TileMap,
+};
+
+/// A board to solve: its dimensions, cells that are blocked from the start,
+/// cells that no piece may cover and how many pieces of every kind may be placed.
+///
+/// Specs are written one per line, e.g. `7x5 holes=0,0;6,4 pieces=F=2,X=0`.
+/// `free=border;3,2` keeps the outer ring of the board and the cell 3,2 free.
+/// `ends=0,0;6,4` fixes the ends of the path, so the distance between them is
+/// maximized instead of the diameter. With more ends, like the doors of a
+/// maze, the distance between the closest two of them is maximized.
+/// `region-X=2..4,1..3` only places Xs on the cells from 2,1 to 4,3 and
+/// `region-I=border` only Is along the edges, for boards in a certain style.
+/// `pieces=one-sided-pentominoes` never places mirror images of the pieces.
+/// Lists of cells take single cells, inclusive ranges and `border`.
+/// Boards with many holes can also be drawn as ascii art (see [`parse_specs`]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoardSpec {
+    pub name: Option<String>,
+    pub shape: (u32, u32),
+    pub holes: Vec<(u32, u32)>,
+    /// cells that have to stay free, sorted by row
+    pub free: Vec<(u32, u32)>,
+    /// the cells the path has to connect, which stay free and connected.
+    /// Either none or at least two.
+    pub ends: Vec<Point>,
+    pub pieces: [u8; KINDS],
+    /// whether pieces are only placed rotated and never mirrored, see
+    /// [`Pentonimo::is_rotation`](crate::pentonimo::Pentonimo::is_rotation).
+    /// Mirror images of the board are different problems then.
+    pub one_sided: bool,
+    /// kinds whose pieces may only cover the cells of their region, sorted by
+    /// kind, with the cells sorted by row
+    pub regions: Vec<(PentonimoKind, Vec<(u32, u32)>)>,
+}
+
+impl BoardSpec {
+    pub fn new(shape: (u32, u32)) -> Self {
+        Self {
+            name: None,
+            shape,
+            holes: Vec::new(),
+            free: Vec::new(),
+            ends: Vec::new(),
+            pieces: PENTOMINOES,
+            one_sided: false,
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn initial_map(&self) -> TileMap {
+        let mut map = TileMap::new(self.shape);
+        for &(x, y) in &self.holes {
+            map.block(x, y);
+        }
+        map
+    }
+
+    pub fn candidates(&self) -> Candidates {
+        Candidates::new(self.pieces)
+    }
+
+    /// a custom kind of the pieces that has no shape, see
+    /// [`define_custom`](crate::pentonimo::define_custom)
+    pub fn undefined_piece(&self) -> Option<PentonimoKind> {
+        PentonimoKind::VARIANTS
+            .iter()
+            .copied()
+            .find(|&kind| self.pieces[kind as usize] > 0 && !kind.is_defined())
+    }
+
+    /// the number of cells all pieces together cover
+    pub fn piece_cells(&self) -> usize {
+        PentonimoKind::VARIANTS
+            .iter()
+            .map(|&kind| kind.cells() as usize * self.pieces[kind as usize] as usize)
+            .sum()
+    }
+
+    /// a map with only the cells that have to stay free and the ends blocked,
+    /// so pieces that can be placed on it leave them free
+    pub fn free_map(&self) -> TileMap {
+        let mut map = TileMap::new(self.shape);
+        for &(x, y) in &self.free {
+            map.block(x, y);
+        }
+        for &Point(x, y) in &self.ends {
+            map.block(x, y);
+        }
+        map
+    }
+
+    /// whether `piece` only covers cells of the region of its kind, if it has
+    /// one, and isn't a mirror image of its kind on a one-sided board
+    pub fn allows(&self, piece: PositionedPentonimo) -> bool {
+        if self.one_sided && !piece.pentonimo().is_rotation() {
+            return false;
+        }
+        let kind = piece.pentonimo().kind();
+        let Some((_, region)) = self.regions.iter().find(|(other, _)| *other == kind) else {
+            return true;
+        };
+        let (x, y) = piece.position();
+        let (w, h) = piece.pentonimo().shape();
+        (y..y + h as u32)
+            .flat_map(|y| (x..x + w as u32).map(move |x| (x, y)))
+            .all(|(x, y)| !piece.get(x, y) || region.contains(&(x, y)))
+    }
+
+    /// Every position of every piece on the empty board that leaves the cells
+    /// that have to stay free free, stays in the region of its kind, isn't
+    /// mirrored on one-sided boards and doesn't disconnect the ends. Pieces only ever take cells away, so a
+    /// piece that disconnects the ends on its own does so with any other
+    /// pieces.
+    pub fn placements(&self) -> Vec<PositionedPentonimo> {
+        let map = self.initial_map();
+        // a single test of every placement for both kinds of cells
+        let mut blocked = map.clone();
+        blocked |= &self.free_map();
+        let mut all = placements(&blocked, self.candidates());
+        if !self.regions.is_empty() || self.one_sided {
+            all.retain(|&piece| self.allows(piece));
+        }
+        if !self.ends.is_empty() {
+            let mut scratch = BfsScratch::new(self.shape);
+            all.retain(|&piece| scratch.objective(&(map.clone() | piece), &self.ends).0 > 0);
+        }
+        all
+    }
+
+    /// name used for the result files of this board
+    pub fn file_stem(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{}_{}", self.shape.0, self.shape.1),
+        }
+    }
+
+    /// the same board mirrored along its diagonal
+    pub fn transposed(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            shape: (self.shape.1, self.shape.0),
+            holes: self.holes.iter().map(|&(x, y)| (y, x)).collect(),
+            free: sorted(self.free.iter().map(|&(x, y)| (y, x)).collect()),
+            ends: self.ends.iter().map(|&Point(x, y)| Point(y, x)).collect(),
+            pieces: self.pieces,
+            one_sided: self.one_sided,
+            regions: (self.regions.iter())
+                .map(|(kind, cells)| (*kind, sorted(cells.iter().map(|&(x, y)| (y, x)).collect())))
+                .collect(),
+        }
+    }
+
+    /// the same board mirrored left to right and/or top to bottom
+    pub fn flipped(&self, flip_x: bool, flip_y: bool) -> Self {
+        let (w, h) = self.shape;
+        let flip = |x: u32, y: u32| {
+            (
+                if flip_x { w - 1 - x } else { x },
+                if flip_y { h - 1 - y } else { y },
+            )
+        };
+        Self {
+            name: self.name.clone(),
+            shape: self.shape,
+            holes: self.holes.iter().map(|&(x, y)| flip(x, y)).collect(),
+            free: sorted(self.free.iter().map(|&(x, y)| flip(x, y)).collect()),
+            ends: (self.ends.iter())
+                .map(|&Point(x, y)| {
+                    let (x, y) = flip(x, y);
+                    Point(x, y)
+                })
+                .collect(),
+            pieces: self.pieces,
+            one_sided: self.one_sided,
+            regions: (self.regions.iter())
+                .map(|(kind, cells)| {
+                    (
+                        *kind,
+                        sorted(cells.iter().map(|&(x, y)| flip(x, y)).collect()),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Unnamed representative of all specs describing the same problem up to
+    /// transposition, which is wider than it is tall. Also returns whether this
+    /// spec has to be transposed to get there. One-sided specs are never
+    /// transposed, which would mirror their pieces.
+    pub fn canonical(&self) -> (Self, bool) {
+        let transposed = self.shape.0 < self.shape.1 && !self.one_sided;
+        let mut canonical = if transposed {
+            self.transposed()
+        } else {
+            self.clone()
+        };
+        canonical.name = None;
+        canonical.holes.sort_unstable();
+        canonical.holes.dedup();
+        (canonical, transposed)
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "name" => {
+                if value.is_empty() || value.contains(['/', '\\']) {
+                    return Err(format!("invalid name {value:?}"));
+                }
+                self.name = Some(value.to_owned());
+            }
+            "holes" => {
+                for hole in value.split(';').filter(|hole| !hole.is_empty()) {
+                    let (x, y) = parse_pair(hole, ',')?;
+                    self.holes.push((x, y));
+                }
+            }
+            "free" => {
+                self.free.extend(parse_cells(value, self.shape)?);
+                self.free = sorted(std::mem::take(&mut self.free));
+            }
+            "ends" => {
+                for cell in value.split(';').filter(|cell| !cell.is_empty()) {
+                    let (x, y) = parse_pair(cell, ',')?;
+                    self.ends.push(Point(x, y));
+                }
+            }
+            "pieces" => (self.pieces, self.one_sided) = parse_pieces(value)?,
+            _ if key.starts_with("region-") => {
+                let name = &key["region-".len()..];
+                let kind = PentonimoKind::from_str(name)
+                    .map_err(|_| format!("unknown pentonimo kind {name:?}"))?;
+                let mut cells = parse_cells(value, self.shape)?;
+                match self
+                    .regions
+                    .binary_search_by_key(&(kind as usize), |&(kind, _)| kind as usize)
+                {
+                    Ok(i) => self.regions[i].1.append(&mut cells),
+                    Err(i) => self.regions.insert(i, (kind, cells)),
+                }
+                for (_, cells) in &mut self.regions {
+                    *cells = sorted(std::mem::take(cells));
+                }
+            }
+            _ => return Err(format!("unknown option {key:?}")),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (w, h) = self.shape;
+        if w == 0 || h == 0 {
+            return Err(format!("board {w}x{h} is empty"));
+        }
+        if let Some(&(x, y)) = self.holes.iter().find(|&&(x, y)| x >= w || y >= h) {
+            return Err(format!("hole {x},{y} is outside of the {w}x{h} board"));
+        }
+        if let Some(&(x, y)) = self.free.iter().find(|&&(x, y)| x >= w || y >= h) {
+            return Err(format!("free cell {x},{y} is outside of the {w}x{h} board"));
+        }
+        if let Some(&(x, y)) = self.free.iter().find(|cell| self.holes.contains(cell)) {
+            return Err(format!("cell {x},{y} can't be both a hole and free"));
+        }
+        for (kind, cells) in &self.regions {
+            if let Some(&(x, y)) = cells.iter().find(|&&(x, y)| x >= w || y >= h) {
+                return Err(format!(
+                    "cell {x},{y} of the region of {kind} is outside of the {w}x{h} board"
+                ));
+            }
+        }
+        if self.ends.len() == 1 {
+            return Err("a path needs at least two ends".to_owned());
+        }
+        for (i, &Point(x, y)) in self.ends.iter().enumerate() {
+            if x >= w || y >= h {
+                return Err(format!("end {x},{y} is outside of the {w}x{h} board"));
+            }
+            if self.holes.contains(&(x, y)) {
+                return Err(format!("end {x},{y} is a hole"));
+            }
+            if self.ends[..i].contains(&Point(x, y)) {
+                return Err(format!("end {x},{y} is given twice"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for BoardSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.shape.0, self.shape.1)?;
+        if let Some(name) = &self.name {
+            write!(f, " name={name}")?;
+        }
+        if !self.holes.is_empty() {
+            write!(f, " holes=")?;
+            for (i, (x, y)) in self.holes.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ";")?;
+                }
+                write!(f, "{x},{y}")?;
+            }
+        }
+        if !self.free.is_empty() {
+            write!(f, " free={}", format_cells(&self.free, self.shape))?;
+        }
+        if !self.ends.is_empty() {
+            let ends = self
+                .ends
+                .iter()
+                .map(|Point(x, y)| format!("{x},{y}"))
+                .collect::<Vec<_>>();
+            write!(f, " ends={}", ends.join(";"))?;
+        }
+        if self.pieces != PENTOMINOES || self.one_sided {
+            // every pentomino and the smaller pieces the board has
+            write!(f, " pieces=")?;
+            if self.one_sided {
+                write!(f, "one-sided,")?;
+            }
+            for (i, kind) in PentonimoKind::VARIANTS.iter().enumerate() {
+                if kind.family() != Family::Pentomino && self.pieces[i] == 0 {
+                    continue;
+                }
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{kind}={}", self.pieces[i])?;
+            }
+        }
+        for (kind, cells) in &self.regions {
+            write!(f, " region-{kind}={}", format_cells(cells, self.shape))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BoardSpec {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let size = words.next().ok_or("empty board spec")?;
+        let mut spec = BoardSpec::new(parse_pair(size, 'x')?);
+
+        for word in words {
+            let (key, value) = word
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got {word:?}"))?;
+            spec.set_option(key, value)?;
+        }
+
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+/// the cells at the edge of a board
+fn border((w, h): (u32, u32)) -> Vec<(u32, u32)> {
+    (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .filter(|&(x, y)| x == 0 || y == 0 || x + 1 == w || y + 1 == h)
+        .collect()
+}
+
+/// Parses a list of cells like `border;3,2;0..2,4`, with inclusive ranges
+/// of coordinates and `border` for the edge of a board of `shape`
+fn parse_cells(value: &str, shape: (u32, u32)) -> Result<Vec<(u32, u32)>, String> {
+    let mut cells = Vec::new();
+    for cell in value.split(';').filter(|cell| !cell.is_empty()) {
+        if cell == "border" {
+            cells.extend(border(shape));
+            continue;
+        }
+        let (x, y) = cell
+            .split_once(',')
+            .ok_or_else(|| format!("expected a cell like 3,2 or 0..2,4, got {cell:?}"))?;
+        let coordinate = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid number {n:?}"))
+        };
+        let range = |n: &str| match n.split_once("..") {
+            Some((a, b)) => Ok((coordinate(a)?, coordinate(b)?)),
+            None => coordinate(n).map(|n| (n, n)),
+        };
+        let ((x0, x1), (y0, y1)) = (range(x)?, range(y)?);
+        if x0 > x1 || y0 > y1 {
+            return Err(format!("empty range of cells {cell:?}"));
+        }
+        cells.extend((y0..=y1).flat_map(|y| (x0..=x1).map(move |x| (x, y))));
+    }
+    Ok(cells)
+}
+
+/// A list of cells sorted by row for [`parse_cells`], with `border` if it has
+/// all cells of the edge
+fn format_cells(cells: &[(u32, u32)], shape: (u32, u32)) -> String {
+    let border = border(shape);
+    let whole_border = border.iter().all(|cell| cells.contains(cell));
+    let mut parts = Vec::new();
+    if whole_border {
+        parts.push("border".to_owned());
+    }
+    for &(x, y) in cells {
+        if !whole_border || !border.contains(&(x, y)) {
+            parts.push(format!("{x},{y}"));
+        }
+    }
+    parts.join(";")
+}
+
+/// cells sorted by row without duplicates
+fn sorted(mut cells: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    cells.sort_unstable_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+    cells
+}
+
+fn parse_pair(s: &str, separator: char) -> Result<(u32, u32), String> {
+    let (a, b) = s
+        .split_once(separator)
+        .ok_or_else(|| format!("expected two numbers separated by {separator:?}, got {s:?}"))?;
+    let parse = |n: &str| {
+        n.trim()
+            .parse()
+            .map_err(|_| format!("invalid number {n:?}"))
+    };
+    Ok((parse(a)?, parse(b)?))
+}
+
+/// names of the piece sets of [`piece_set`]
+const PIECE_SETS: [&str; 5] = [
+    "pentominoes",
+    "pentomino-double",
+    "tetrominoes",
+    "triominoes",
+    "polyominoes",
+];
+
+/// The counts of a named piece set, whose pieces can be placed in all their
+/// orientations. See [`parse_pieces`] for one-sided sets.
+pub fn piece_set(name: &str) -> Result<[u8; KINDS], String> {
+    let families = |families: &[Family], count: u8| {
+        let mut pieces = [0; KINDS];
+        for (i, kind) in PentonimoKind::VARIANTS.iter().enumerate() {
+            if families.contains(&kind.family()) {
+                pieces[i] = count;
+            }
+        }
+        pieces
+    };
+    match name {
+        "pentominoes" => Ok(PENTOMINOES),
+        "pentomino-double" => Ok(families(&[Family::Pentomino], 2)),
+        "tetrominoes" => Ok(families(&[Family::Tetromino], 1)),
+        "triominoes" => Ok(families(&[Family::Triomino], 1)),
+        "polyominoes" => Ok(families(
+            &[
+                Family::Domino,
+                Family::Triomino,
+                Family::Tetromino,
+                Family::Pentomino,
+            ],
+            1,
+        )),
+        _ => Err(format!(
+            "unknown piece set {name:?}, expected KIND=COUNT or one of {}, \
+             optionally after one-sided-",
+            PIECE_SETS.join(", ")
+        )),
+    }
+}
+
+/// parses a list of piece counts like `F=2,X=0,O4=1`, and whether the pieces
+/// are one-sided. Kinds that are not mentioned keep their default count, one
+/// of every pentomino and none of the smaller pieces. `*=COUNT` sets the count
+/// of every pentomino, so `*=0,F=1,N=1` only has the F and the N. The name of a
+/// [`piece_set`] replaces all counts, so `tetrominoes,I2=1` has the tetrominoes
+/// and a domino.
+///
+/// `one-sided` places the pieces only rotated, never mirrored, and so do sets
+/// like `one-sided-pentominoes`. The counts are still by kind: a one-sided set
+/// has a single piece of every kind in the orientations of [`Pentonimo::new`],
+/// not a second one for the mirror image like the 18 one-sided pentominoes.
+///
+/// [`Pentonimo::new`]: crate::pentonimo::Pentonimo::new
+pub fn parse_pieces(s: &str) -> Result<([u8; KINDS], bool), String> {
+    let mut pieces = PENTOMINOES;
+    let mut one_sided = false;
+    for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+        let Some((kind, count)) = entry.split_once('=') else {
+            let entry = entry.trim();
+            if entry == "one-sided" {
+                one_sided = true;
+                continue;
+            }
+            let set = entry.strip_prefix("one-sided-");
+            one_sided = set.is_some();
+            pieces = piece_set(set.unwrap_or(entry))?;
+            continue;
+        };
+        let count = count
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|&count| count < 32)
+            .ok_or_else(|| format!("invalid count for {}: {count:?}", kind.trim()))?;
+        if kind.trim() == "*" {
+            for (i, kind) in PentonimoKind::VARIANTS.iter().enumerate() {
+                if kind.family() == Family::Pentomino {
+                    pieces[i] = count;
+                }
+            }
+            continue;
+        }
+        let kind = PentonimoKind::from_str(kind.trim())
+            .map_err(|_| format!("unknown pentonimo kind {kind:?}"))?;
+        pieces[kind as usize] = count;
+    }
+    Ok((pieces, one_sided))
+}
+
+/// Parses an ascii drawing of a board: `.` is a free cell, `#` a hole and `o`
+/// a cell that has to stay free. All rows have to be of the same width.
+pub fn parse_ascii_board(rows: &[&str]) -> Result<BoardSpec, String> {
+    let width = rows.first().map_or(0, |row| row.trim_end().chars().count());
+    let mut spec = BoardSpec::new((width as u32, rows.len() as u32));
+
+    for (y, row) in rows.iter().enumerate() {
+        let row = row.trim_end();
+        if row.chars().count() != width {
+            return Err(format!("row {y} of board is not {width} cells wide"));
+        }
+        for (x, c) in row.chars().enumerate() {
+            match c {
+                '.' => {}
+                '#' => spec.holes.push((x as u32, y as u32)),
+                'o' => spec.free.push((x as u32, y as u32)),
+                _ => return Err(format!("invalid cell {c:?} in row {y} of board")),
+            }
+        }
+    }
+
+    spec.validate()?;
+    Ok(spec)
+}
+
+/// Parses a batch file of board specs.
+///
+/// Every non-empty line that is not a `#` comment is a spec (see [`BoardSpec`]).
+/// A line starting with `board` followed by options begins an ascii drawn board
+/// (see [`parse_ascii_board`]), which ends at the next empty line:
+///
+/// ```text
+/// 7x5 pieces=X=0
+/// board name=ring
+/// .......
+/// ..###..
+/// .......
+/// ```
+pub fn parse_specs(input: &str) -> Result<Vec<BoardSpec>, String> {
+    let mut specs = Vec::new();
+    let mut lines = input.lines().enumerate().peekable();
+
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let with_line = |err: String| format!("line {}: {err}", i + 1);
+
+        if let Some(options) = line.strip_prefix("board") {
+            let mut rows = Vec::new();
+            while let Some((_, row)) = lines.next_if(|(_, row)| !row.trim().is_empty()) {
+                rows.push(row.trim());
+            }
+
+            let mut spec = parse_ascii_board(&rows).map_err(with_line)?;
+            for word in options.split_whitespace() {
+                let (key, value) = word
+                    .split_once('=')
+                    .ok_or_else(|| with_line(format!("expected key=value, got {word:?}")))?;
+                if key == "holes" || key == "free" {
+                    return Err(with_line(format!("{key} of ascii boards are drawn")));
+                }
+                spec.set_option(key, value).map_err(with_line)?;
+            }
+            specs.push(spec);
+        } else {
+            specs.push(line.parse().map_err(with_line)?);
+        }
+    }
+
+    Ok(specs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_pieces, parse_specs, BoardSpec};
+    use crate::pentonimo::{PentonimoKind, PENTOMINOES};
+
+    #[test]
+    fn roundtrip() {
+        let specs = parse_specs(
+            "# comment\n\
+             7x5\n\
+             6x4 name=notched holes=0,0;5,3 pieces=F=2,X=0\n\
+             board pieces=I=0\n\
+             ..#\n\
+             o..\n\
+             \n\
+             5x4 free=2,1;border;1,1 ends=0,0;4,3;4,0\n\
+             4x4 pieces=*=0,O4=2,I2=1\n",
+        )
+        .unwrap();
+
+        assert_eq!(specs.len(), 5);
+        assert_eq!(specs[0], BoardSpec::new((7, 5)));
+        assert_eq!(specs[1].holes, vec![(0, 0), (5, 3)]);
+        assert_eq!(specs[1].pieces[0], 2);
+        assert_eq!(specs[2].shape, (3, 2));
+        assert_eq!(specs[2].holes, vec![(2, 0)]);
+        assert_eq!(specs[2].free, vec![(0, 1)]);
+        assert_eq!(specs[3].free.len(), 16);
+        assert_eq!(
+            specs[3].to_string(),
+            "5x4 free=border;1,1;2,1 ends=0,0;4,3;4,0"
+        );
+        assert_eq!(specs[4].piece_cells(), 2 * 4 + 2);
+
+        for spec in specs {
+            assert_eq!(spec.to_string().parse::<BoardSpec>().unwrap(), spec);
+        }
+
+        assert!("3x3 holes=3,0".parse::<BoardSpec>().is_err());
+        assert!("3x3 free=3,0".parse::<BoardSpec>().is_err());
+        assert!("3x3 holes=1,1 free=1,1".parse::<BoardSpec>().is_err());
+        assert!("3x3 holes=1,1 ends=0,0;1,1".parse::<BoardSpec>().is_err());
+        assert!("3x3 ends=0,0".parse::<BoardSpec>().is_err());
+        assert!("3x3 ends=0,0;1,0;0,0".parse::<BoardSpec>().is_err());
+    }
+
+    #[test]
+    fn regions_restrict_placements() {
+        let spec = "7x5 region-X=2..4,1..3 region-I=border"
+            .parse::<BoardSpec>()
+            .unwrap();
+        assert_eq!(spec.regions[0], (PentonimoKind::I, super::border((7, 5))));
+        assert_eq!(spec.regions[1].1.len(), 9);
+        assert_eq!(spec.to_string().parse::<BoardSpec>().unwrap(), spec);
+        assert_eq!(spec.transposed().transposed(), spec);
+
+        let count = |kind| {
+            (spec.placements().iter())
+                .filter(|piece| piece.pentonimo().kind() == kind)
+                .count()
+        };
+        // the X fits the 3x3 center once, the I the top and bottom row three
+        // times and the left and right column once
+        assert_eq!(count(PentonimoKind::X), 1);
+        assert_eq!(count(PentonimoKind::I), 8);
+        // kinds without a region go anywhere
+        assert_eq!(count(PentonimoKind::F), 8 * 5 * 3);
+
+        assert!("7x5 region-Q=0,0".parse::<BoardSpec>().is_err());
+        assert!("7x5 region-X=5..7,0".parse::<BoardSpec>().is_err());
+        assert!("7x5 region-X=3..2,0".parse::<BoardSpec>().is_err());
+    }
+
+    #[test]
+    fn piece_counts() {
+        assert_eq!(parse_pieces("").unwrap(), (PENTOMINOES, false));
+        // F, L, N, P, T, U, V, W, I, X, Y, Z, I4, L4, O4, S4, T4, I3, L3, I2, C1, C2, C3, C4, C5
+        assert_eq!(
+            parse_pieces("*=2").unwrap().0,
+            [2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("*=0,F=1,N=2").unwrap().0,
+            [1, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("T=0, X=3").unwrap().0,
+            [1, 1, 1, 1, 0, 1, 1, 1, 1, 3, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("*=0,O4=2,I2=1,C3=4").unwrap().0,
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 1, 0, 0, 4, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("tetrominoes,I2=1").unwrap().0,
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 1, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("X=0,pentomino-double").unwrap().0,
+            parse_pieces("*=2").unwrap().0
+        );
+        assert_eq!(
+            parse_pieces("polyominoes").unwrap().0,
+            [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            parse_pieces("one-sided-pentominoes").unwrap(),
+            (PENTOMINOES, true)
+        );
+        assert_eq!(
+            parse_pieces("one-sided,tetrominoes").unwrap(),
+            parse_pieces("tetrominoes").unwrap()
+        );
+        assert!(parse_pieces("one-sided-tetrominoes,I2=1").unwrap().1);
+        assert!(parse_pieces("one-sided-hexominoes").is_err());
+        assert!(parse_pieces("F").is_err());
+        assert!(parse_pieces("Q=1").is_err());
+        assert!(parse_pieces("*=32").is_err());
+    }
+
+    #[test]
+    fn one_sided_specs() {
+        let spec = "5x7 pieces=one-sided-pentominoes"
+            .parse::<BoardSpec>()
+            .unwrap();
+        assert!(spec.one_sided);
+        assert_eq!(spec.to_string().parse::<BoardSpec>().unwrap(), spec);
+        // a transposed board would need the mirror images of the pieces
+        assert_eq!(spec.canonical(), (spec.clone(), false));
+
+        let placements = spec.placements();
+        assert!(placements
+            .iter()
+            .all(|piece| piece.pentonimo().is_rotation()));
+        let two_sided = BoardSpec::new(spec.shape).placements();
+        let mirrored = two_sided
+            .iter()
+            .filter(|piece| !piece.pentonimo().is_rotation());
+        assert_eq!(placements.len() + mirrored.count(), two_sided.len());
+    }
+}
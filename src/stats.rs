@@ -0,0 +1,135 @@
+//! Tables of the solutions of many runs, as printed by `pentonimo stats`.
+//! Every board counts once with its best solution, also if runs solved it
+//! transposed.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::solution::Solution;
+
+struct Board {
+    shape: (u32, u32),
+    diameter: u32,
+    pieces: usize,
+    proved_optimal: bool,
+    /// how many solutions of the board were added
+    solutions: usize,
+}
+
+#[derive(Default)]
+pub struct Stats {
+    /// by the spec of the board with width >= height, unless it's one-sided
+    boards: BTreeMap<String, Board>,
+}
+
+impl Stats {
+    pub fn add(&mut self, solution: &Solution) {
+        let (w, h) = solution.spec.shape;
+        let spec = if w < h && !solution.spec.one_sided {
+            solution.spec.transposed()
+        } else {
+            solution.spec.clone()
+        };
+        let board = self.boards.entry(spec.to_string()).or_insert(Board {
+            shape: spec.shape,
+            diameter: solution.diameter,
+            pieces: solution.pieces.len(),
+            proved_optimal: false,
+            solutions: 0,
+        });
+        board.solutions += 1;
+        board.proved_optimal |= solution.proved_optimal;
+        if solution.diameter > board.diameter {
+            board.diameter = solution.diameter;
+            board.pieces = solution.pieces.len();
+        }
+    }
+
+    pub fn boards(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// The boards of every shape and of every number of placed pieces
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+
+        let by_shape = group(self.boards.values(), |board| board.shape);
+        writeln!(table, "shape  boards solutions proved  best  mean diameter").unwrap();
+        for ((w, h), boards) in by_shape {
+            let shape = format!("{w}x{h}");
+            write_row(&mut table, &shape, &boards);
+        }
+
+        let by_pieces = group(self.boards.values(), |board| board.pieces);
+        writeln!(
+            table,
+            "\npieces boards solutions proved  best  mean diameter"
+        )
+        .unwrap();
+        for (pieces, boards) in by_pieces {
+            write_row(&mut table, &pieces.to_string(), &boards);
+        }
+
+        table
+    }
+}
+
+fn group<'a, K: Ord>(
+    boards: impl Iterator<Item = &'a Board>,
+    key: impl Fn(&Board) -> K,
+) -> BTreeMap<K, Vec<&'a Board>> {
+    let mut groups = BTreeMap::<K, Vec<_>>::new();
+    for board in boards {
+        groups.entry(key(board)).or_default().push(board);
+    }
+    groups
+}
+
+fn write_row(table: &mut String, key: &str, boards: &[&Board]) {
+    let solutions = boards.iter().map(|board| board.solutions).sum::<usize>();
+    let proved = boards.iter().filter(|board| board.proved_optimal).count();
+    let best = boards.iter().map(|board| board.diameter).max().unwrap_or(0);
+    let mean = boards
+        .iter()
+        .map(|board| board.diameter as f64)
+        .sum::<f64>()
+        / boards.len() as f64;
+    writeln!(
+        table,
+        "{key:6} {:6} {solutions:9} {proved:6} {best:5} {mean:14.2}",
+        boards.len()
+    )
+    .unwrap();
+}
+
+#[test]
+fn boards_count_once_with_their_best_solution() {
+    let solution = |text: &str| text.parse::<Solution>().unwrap();
+    let mut stats = Stats::default();
+    stats.add(&solution("spec 4x3\ndiameter 5\npiece I 1 0 0"));
+    stats.add(&solution(
+        "spec 3x4\ndiameter 7\nproved-optimal\npiece P 0 0 0",
+    ));
+    stats.add(&solution("spec 3x3\ndiameter 4\npiece P 0 0 0"));
+    stats.add(&solution("spec 4x3 holes=0,0\ndiameter 6"));
+    assert_eq!(stats.boards(), 3);
+
+    let table = stats.to_table();
+    let rows = table.lines().collect::<Vec<_>>();
+    assert_eq!(
+        rows[1].split_whitespace().collect::<Vec<_>>(),
+        ["3x3", "1", "1", "0", "4", "4.00"]
+    );
+    assert_eq!(
+        rows[2].split_whitespace().collect::<Vec<_>>(),
+        ["4x3", "2", "3", "1", "7", "6.50"]
+    );
+    // the board without holes counts with the piece of its best solution
+    assert_eq!(
+        rows[5].split_whitespace().collect::<Vec<_>>(),
+        ["0", "1", "1", "0", "6", "6.00"]
+    );
+    assert_eq!(
+        rows[6].split_whitespace().collect::<Vec<_>>(),
+        ["1", "2", "3", "1", "7", "5.50"]
+    );
+}
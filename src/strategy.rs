@@ -0,0 +1,160 @@
+//! Different ways to search for a placement with a large diameter, so they can
+//! be used and compared interchangeably.
+
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{
+    analysis::DeadEnds,
+    brute_force::{find_best, SearchConfig},
+    pathfinding::{BfsScratch, Point},
+    pentonimo::PositionedPentonimo,
+    solution::Solution,
+    spec::BoardSpec,
+    tile_map::TileMap,
+};
+
+pub trait SearchStrategy {
+    fn name(&self) -> &'static str;
+    /// the best solution the strategy finds for `spec` and how it got there
+    fn search_with_stats(&mut self, spec: &BoardSpec) -> (Solution, SearchStats);
+    /// the best solution the strategy finds for `spec`
+    fn search(&mut self, spec: &BoardSpec) -> Solution {
+        self.search_with_stats(spec).0
+    }
+    /// Lets another thread stop the searches: once `cancel` is set, they
+    /// return the best solution found so far right away
+    fn set_cancel(&mut self, cancel: Arc<AtomicBool>);
+}
+
+/// What the local searches maximize. The brute-force search and lns only
+/// maximize the diameter, as their bounds only hold for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    #[default]
+    Diameter,
+    /// the number of dead ends, for boards that look like mazes
+    DeadEnds,
+}
+
+impl Objective {
+    pub fn name(self) -> &'static str {
+        match self {
+            Objective::Diameter => "diameter",
+            Objective::DeadEnds => "dead-ends",
+        }
+    }
+
+    /// The score of a board and its diameter. Scores are compared as tuples,
+    /// so boards with equal objectives are compared by their diameters.
+    pub fn score(self, scratch: &mut BfsScratch, map: &TileMap, ends: &[Point]) -> (u32, u32) {
+        let (diameter, _) = scratch.objective(map, ends);
+        match self {
+            Objective::Diameter => (diameter, diameter),
+            Objective::DeadEnds => (DeadEnds::new(map).cells.len() as u32, diameter),
+        }
+    }
+}
+
+/// What a search did, to compare strategies
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchStats {
+    /// how long it took to find the returned solution
+    pub time_to_best: Duration,
+    /// nodes of the depth first searches or boards evaluated by local searches
+    pub nodes: u64,
+}
+
+/// The depth first search of `find_best`
+pub struct BruteForce(pub SearchConfig);
+
+impl SearchStrategy for BruteForce {
+    fn name(&self) -> &'static str {
+        "brute-force"
+    }
+
+    fn search_with_stats(&mut self, spec: &BoardSpec) -> (Solution, SearchStats) {
+        let result = find_best(spec, &self.0);
+        let stats = SearchStats {
+            time_to_best: result.time_to_best,
+            nodes: result.nodes,
+        };
+        let solution = Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: result.certificate.is_some(),
+        };
+        (solution, stats)
+    }
+
+    fn set_cancel(&mut self, cancel: Arc<AtomicBool>) {
+        self.0.cancel = Some(cancel);
+    }
+}
+
+/// The board left for the other pieces once `fixed` are placed: their cells
+/// become holes and they are no longer available.
+pub fn reduced_spec(spec: &BoardSpec, fixed: &[PositionedPentonimo]) -> BoardSpec {
+    let mut reduced = spec.clone();
+    for piece in fixed {
+        reduced.pieces[piece.pentonimo().kind() as usize] -= 1;
+        for y in 0..spec.shape.1 {
+            for x in 0..spec.shape.0 {
+                if piece.get(x, y) {
+                    reduced.holes.push((x, y));
+                }
+            }
+        }
+    }
+    reduced
+}
+
+/// the board with `pieces` placed, if none of them overlap
+pub fn placed_map(spec: &BoardSpec, pieces: &[PositionedPentonimo]) -> Option<TileMap> {
+    let mut map = spec.initial_map();
+    for &piece in pieces {
+        if !map.can_place(piece) {
+            return None;
+        }
+        map |= piece;
+    }
+    Some(map)
+}
+
+#[test]
+fn cancelled_searches_stop_right_away() {
+    use crate::{lns::Lns, rng::Rng, tabu::Tabu};
+    use std::{sync::atomic::Ordering, time::Instant};
+
+    let hour = Duration::from_secs(3600);
+    let strategies: [Box<dyn SearchStrategy>; 3] = [
+        Box::new(BruteForce(SearchConfig {
+            threads: Some(2),
+            best_known: false,
+            ..Default::default()
+        })),
+        Box::new(Lns::new(hour, Rng::new(0))),
+        Box::new(Tabu::new(hour, Rng::new(0))),
+    ];
+    let spec = BoardSpec::new((8, 8));
+    for mut strategy in strategies {
+        let cancel = Arc::new(AtomicBool::new(false));
+        strategy.set_cancel(cancel.clone());
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            cancel.store(true, Ordering::Relaxed);
+        });
+        let start = Instant::now();
+        let solution = strategy.search(&spec);
+        canceller.join().unwrap();
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "{}",
+            strategy.name()
+        );
+        solution.verify().unwrap();
+    }
+}
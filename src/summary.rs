@@ -0,0 +1,133 @@
+//! The overview of a batch of boards, written as `summary.csv`,
+//! `summary.json` and the chart `summary.svg`.
+
+use std::collections::BTreeMap;
+
+use strum::VariantArray;
+
+use crate::{
+    analysis::PieceUsage,
+    chart::Chart,
+    json::Json,
+    pentonimo::{PentonimoKind, KINDS},
+    solution::Solution,
+};
+
+struct Row {
+    name: String,
+    shape: (u32, u32),
+    holes: usize,
+    diameter: u32,
+    pieces: usize,
+    proved_optimal: bool,
+    /// optimal placements, mean pieces and pieces of every kind
+    usage: Option<(usize, f64, [u64; KINDS])>,
+}
+
+pub struct Summary {
+    /// whether the rows have piece usages
+    all_optima: bool,
+    rows: Vec<Row>,
+}
+
+impl Summary {
+    pub fn new(all_optima: bool) -> Self {
+        Self {
+            all_optima,
+            rows: Vec::new(),
+        }
+    }
+
+    /// adds the solution of a board with the name of its result files
+    pub fn add(&mut self, name: &str, solution: &Solution, usage: Option<&PieceUsage>) {
+        self.rows.push(Row {
+            name: name.to_owned(),
+            shape: solution.spec.shape,
+            holes: solution.spec.holes.len(),
+            diameter: solution.diameter,
+            pieces: solution.pieces.len(),
+            proved_optimal: solution.proved_optimal,
+            usage: usage.map(|usage| (usage.solutions, usage.mean_pieces(), usage.kinds)),
+        });
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("name,width,height,holes,diameter,pieces");
+        if self.all_optima {
+            csv += ",optima,mean_pieces";
+            for kind in PentonimoKind::VARIANTS {
+                csv += &format!(",{kind}");
+            }
+        }
+        csv.push('\n');
+
+        for row in &self.rows {
+            csv += &format!(
+                "{},{},{},{},{},{}",
+                row.name, row.shape.0, row.shape.1, row.holes, row.diameter, row.pieces
+            );
+            if let Some((optima, mean_pieces, kinds)) = row.usage {
+                csv += &format!(",{optima},{mean_pieces:.2}");
+                for count in kinds {
+                    csv += &format!(",{count}");
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// The diameter by the area of the boards, with a line for every length of
+    /// the shorter side. None if there are less than two sizes to compare.
+    pub fn to_chart(&self) -> Option<Chart> {
+        let mut lines = BTreeMap::<u32, Vec<(f64, f64)>>::new();
+        for row in &self.rows {
+            let (w, h) = row.shape;
+            let area = (w * h) as usize - row.holes;
+            let point = (area as f64, row.diameter as f64);
+            let line = lines.entry(w.min(h)).or_default();
+            if !line.contains(&point) {
+                line.push(point);
+            }
+        }
+        if lines.values().map(Vec::len).sum::<usize>() < 2 {
+            return None;
+        }
+
+        let mut chart = Chart::new("diameter by board size", "free cells", "diameter");
+        for (side, mut points) in lines {
+            points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            chart.add(&format!("{side} wide"), points);
+        }
+        Some(chart)
+    }
+
+    /// as described by `schema/v1/summary.schema.json`
+    pub fn to_json(&self) -> Json {
+        let boards = self.rows.iter().map(|row| {
+            let mut fields = vec![
+                ("name".to_owned(), row.name.as_str().into()),
+                ("width".to_owned(), row.shape.0.into()),
+                ("height".to_owned(), row.shape.1.into()),
+                ("holes".to_owned(), row.holes.into()),
+                ("diameter".to_owned(), row.diameter.into()),
+                ("pieces".to_owned(), row.pieces.into()),
+                ("proved_optimal".to_owned(), row.proved_optimal.into()),
+            ];
+            if let Some((optima, mean_pieces, kinds)) = row.usage {
+                let kinds = PentonimoKind::VARIANTS
+                    .iter()
+                    .zip(kinds)
+                    .map(|(kind, count)| (kind.to_string(), count.into()))
+                    .collect();
+                fields.extend([
+                    ("optima".to_owned(), optima.into()),
+                    ("mean_pieces".to_owned(), mean_pieces.into()),
+                    ("kinds".to_owned(), Json::Object(kinds)),
+                ]);
+            }
+            Json::Object(fields)
+        });
+        Json::versioned([("boards", Json::Array(boards.collect()))])
+    }
+}
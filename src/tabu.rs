@@ -0,0 +1,205 @@
+//! Tabu search: a local search over complete placements that always takes the
+//! best of a sample of moves, but doesn't return to recently visited boards.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{
+    backend::Instant,
+    brute_force::{board_symmetries, Symmetry},
+    monitor::is_cancelled,
+    pathfinding::BfsScratch,
+    pentonimo::PositionedPentonimo,
+    rng::Rng,
+    solution::Solution,
+    spec::BoardSpec,
+    strategy::{placed_map, Objective, SearchStats, SearchStrategy},
+    tile_map::TileMap,
+};
+
+pub struct Tabu {
+    /// time for the whole search
+    pub budget: Duration,
+    /// number of visited boards that can't be visited again
+    pub tenure: usize,
+    /// moves evaluated in every iteration
+    pub samples: usize,
+    /// print every improvement to stderr
+    pub verbose: bool,
+    pub objective: Objective,
+    pub rng: Rng,
+    /// stops the search before the budget is used up once it is set
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Tabu {
+    pub fn new(budget: Duration, rng: Rng) -> Self {
+        Self {
+            budget,
+            tenure: 64,
+            samples: 32,
+            verbose: false,
+            objective: Objective::Diameter,
+            rng,
+            cancel: None,
+        }
+    }
+}
+
+/// A change of one piece of a placement
+#[derive(Debug, Clone, Copy)]
+enum Move {
+    Add(PositionedPentonimo),
+    Remove(usize),
+    /// relocates the piece if the new one has the same kind
+    Replace(usize, PositionedPentonimo),
+}
+
+impl Move {
+    fn apply(self, pieces: &mut Vec<PositionedPentonimo>) {
+        match self {
+            Move::Add(piece) => pieces.push(piece),
+            Move::Remove(i) => {
+                pieces.swap_remove(i);
+            }
+            Move::Replace(i, piece) => pieces[i] = piece,
+        }
+    }
+}
+
+/// the hash of the board, the same for all boards mirroring it
+fn board_hash(map: &TileMap, symmetries: &[Symmetry]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    map.canonical(symmetries).hash(&mut hasher);
+    hasher.finish()
+}
+
+impl SearchStrategy for Tabu {
+    fn name(&self) -> &'static str {
+        "tabu"
+    }
+
+    fn search_with_stats(&mut self, spec: &BoardSpec) -> (Solution, SearchStats) {
+        let start = Instant::now();
+        let initial = spec.initial_map();
+        let all = spec.placements();
+        let symmetries = board_symmetries(spec);
+        let mut scratch = BfsScratch::new(spec.shape);
+
+        let mut tabu = HashSet::new();
+        let mut recent = VecDeque::new();
+        let mut pieces = Vec::new();
+        let mut best_score = self.objective.score(&mut scratch, &initial, &spec.ends);
+        let mut best = Solution {
+            spec: spec.clone(),
+            diameter: best_score.1,
+            pieces: Vec::new(),
+            proved_optimal: false,
+        };
+
+        let mut stats = SearchStats::default();
+        let mut iterations = 0;
+        while start.elapsed() < self.budget
+            && !all.is_empty()
+            && !is_cancelled(self.cancel.as_ref())
+        {
+            iterations += 1;
+            let mut chosen: Option<((u32, u32), u64, Vec<PositionedPentonimo>)> = None;
+
+            for _ in 0..self.samples {
+                let piece = all[self.rng.below(all.len() as u64) as usize];
+                let mv = match self.rng.below(3) {
+                    _ if pieces.is_empty() => Move::Add(piece),
+                    0 => Move::Add(piece),
+                    1 => Move::Remove(self.rng.below(pieces.len() as u64) as usize),
+                    _ => Move::Replace(self.rng.below(pieces.len() as u64) as usize, piece),
+                };
+
+                let mut next = pieces.clone();
+                mv.apply(&mut next);
+                let kind = piece.pentonimo().kind() as usize;
+                let used = next
+                    .iter()
+                    .filter(|placed| placed.pentonimo().kind() as usize == kind)
+                    .count();
+                if used > spec.pieces[kind] as usize {
+                    continue;
+                }
+                let Some(map) = placed_map(spec, &next) else {
+                    continue;
+                };
+
+                let score = self.objective.score(&mut scratch, &map, &spec.ends);
+                stats.nodes += 1;
+                let hash = board_hash(&map, &symmetries);
+                // tabu boards are only revisited if they beat the best one
+                if tabu.contains(&hash) && score <= best_score {
+                    continue;
+                }
+                if chosen.as_ref().is_none_or(|(chosen, ..)| score > *chosen) {
+                    chosen = Some((score, hash, next));
+                }
+            }
+
+            let Some((score, hash, next)) = chosen else {
+                continue;
+            };
+            pieces = next;
+            if tabu.insert(hash) {
+                recent.push_back(hash);
+            }
+            while recent.len() > self.tenure {
+                tabu.remove(&recent.pop_front().unwrap());
+            }
+
+            if score > best_score {
+                if self.verbose {
+                    eprintln!(
+                        "[{spec}] tabu iteration {iterations}: {} {}",
+                        self.objective.name(),
+                        score.0
+                    );
+                }
+                best_score = score;
+                best.diameter = score.1;
+                best.pieces = pieces.clone();
+                stats.time_to_best = start.elapsed();
+            }
+        }
+
+        (best, stats)
+    }
+
+    fn set_cancel(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+}
+
+#[test]
+fn finds_valid_solutions() {
+    let spec = BoardSpec::new((5, 4));
+    let mut tabu = Tabu::new(Duration::from_millis(200), Rng::new(0));
+    let solution = tabu.search(&spec);
+    solution.verify().unwrap();
+    assert!(solution.diameter >= 10);
+}
+
+#[test]
+fn maximizes_dead_ends() {
+    use crate::analysis::DeadEnds;
+
+    let spec = BoardSpec::new((6, 5));
+    let dead_ends = |objective| {
+        let mut tabu = Tabu::new(Duration::from_millis(300), Rng::new(1));
+        tabu.objective = objective;
+        let solution = tabu.search(&spec);
+        solution.verify().unwrap();
+        DeadEnds::new(&solution.map()).cells.len()
+    };
+    assert!(dead_ends(Objective::DeadEnds) >= 4);
+    assert!(dead_ends(Objective::DeadEnds) >= dead_ends(Objective::Diameter));
+}
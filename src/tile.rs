@@ -1,9 +1,15 @@
+//! Bitboards of 8x8 cells, the building blocks of [`TileMap`](crate::tile_map::TileMap)
+//! and of the pieces.
+
 use std::{
     fmt::Display,
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not},
 };
 
+/// 8x8 cells, where the bit `8 * y + x` is set if the cell x, y is blocked
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+// laid out as a `u64`, so `crate::simd` can load slices of tiles into vectors
+#[repr(transparent)]
 pub struct Tile(pub u64);
 
 impl Tile {
@@ -21,6 +27,11 @@ impl Tile {
         self.0 & (1 << index) != 0
     }
     #[inline]
+    pub fn set(&mut self, x: u8, y: u8) {
+        let index = 8 * y + x;
+        self.0 |= 1 << index;
+    }
+    #[inline]
     pub fn is_empty(&self) -> bool {
         self.0 == 0
     }
@@ -217,7 +228,8 @@ impl Display for Tile {
 #[cfg(test)]
 mod test {
 
-    use super::Tile;
+    use super::{Rotate, Tile};
+    use crate::arbitrary::check;
 
     #[test]
     fn shift() {
@@ -230,4 +242,19 @@ mod test {
             assert_ne!(full.shift_y(-i).0, 0);
         }
     }
+
+    #[test]
+    fn transform_invariants() {
+        check(1000, |&tile: &Tile| {
+            let rotated = (0..4).fold(tile, |tile, _| tile.rotate(Rotate::Right));
+            rotated == tile
+                && tile.rotate(Rotate::Left).rotate(Rotate::Right) == tile
+                && tile.rotate(Rotate::Full).rotate(Rotate::Full) == tile
+                && tile.flip_x().flip_x() == tile
+                && tile.flip_y().flip_y() == tile
+                && tile.flip_xy() == tile.flip_x().flip_y()
+                && tile.transpose().transpose() == tile
+                && (0..8).all(|x| (0..8).all(|y| tile.get(x, y) == tile.transpose().get(y, x)))
+        });
+    }
 }
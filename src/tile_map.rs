@@ -1,24 +1,70 @@
+//! Boards of any size, made of [`Tile`]s.
+
 use std::{
+    borrow::Cow,
+    cmp::Ordering,
     fmt::Display,
+    hash::{Hash, Hasher},
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
 };
 
 use smallvec::SmallVec;
 
-use crate::{pentonimo::PositionedPentonimo, tile::Tile};
+use crate::{pentonimo::PositionedPentonimo, simd, tile::Tile};
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+/// A board as row major [`Tile`]s. Cells covered by holes or placed pieces
+/// are blocked, and so are the cells of the last tiles outside of `shape`.
+#[derive(Debug, Clone)]
 pub struct TileMap {
-    pub tiles: SmallVec<[Tile; 1]>,
-    pub shape: (u16, u16),
+    tiles: SmallVec<[Tile; 1]>,
+    pub shape: (u32, u32),
+    /// cells of the tiles that are not blocked, kept up to date by all
+    /// operations on the map
+    free: u32,
+    /// the xor of the [`tile_hash`] of every tile, kept up to date like `free`,
+    /// so hashing a map doesn't have to read all of its tiles
+    hash: u128,
+}
+
+/// Two independent 64 bit hashes of a tile at an index of the tiles, which
+/// are combined by xor into the hash of a map. Tiles can then be replaced by
+/// removing their old hash and adding the new one.
+#[inline]
+fn tile_hash(index: usize, tile: Tile) -> u128 {
+    // the splitmix64 finalizer, which maps different tiles to different hashes
+    let mix = |mut z: u64| {
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    let index = index as u64;
+    let low = mix(tile.0 ^ index.wrapping_mul(0x9e3779b97f4a7c15));
+    let high = mix(tile.0.rotate_left(32) ^ index.wrapping_mul(0xd1b54a32d192ed03) ^ 1);
+    (high as u128) << 64 | low as u128
+}
+
+impl Hash for TileMap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.shape.hash(state);
+        self.hash.hash(state);
+    }
 }
 
+impl PartialEq for TileMap {
+    fn eq(&self, other: &Self) -> bool {
+        // maps with different hashes differ, which is checked first
+        self.hash == other.hash && self.shape == other.shape && self.tiles == other.tiles
+    }
+}
+
+impl Eq for TileMap {}
+
 impl Display for TileMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let tile_shape = self.tile_shape();
 
         let (x_max, y_max) = if f.alternate() {
-            (8 * tile_shape.1 as u16, 8 * tile_shape.0 as u16)
+            (8 * tile_shape.0 as u32, 8 * tile_shape.1 as u32)
         } else {
             (self.shape.0, self.shape.1)
         };
@@ -46,20 +92,15 @@ impl Display for TileMap {
     }
 }
 
-#[inline]
-fn div_ceil(a: u16, b: u16) -> u16 {
-    (a + b - 1) / b
-}
-
 impl TileMap {
     pub fn tile_shape(&self) -> (usize, usize) {
         (
-            div_ceil(self.shape.0, 8) as usize,
-            div_ceil(self.shape.1, 8) as usize,
+            self.shape.0.div_ceil(8) as usize,
+            self.shape.1.div_ceil(8) as usize,
         )
     }
-    pub fn new(shape: (u16, u16)) -> Self {
-        let tile_shape = (div_ceil(shape.0, 8) as usize, div_ceil(shape.1, 8) as usize);
+    pub fn new(shape: (u32, u32)) -> Self {
+        let tile_shape = (shape.0.div_ceil(8) as usize, shape.1.div_ceil(8) as usize);
 
         let mut tiles = vec![Tile(0); tile_shape.0 * tile_shape.1];
 
@@ -70,7 +111,7 @@ impl TileMap {
             // iterate over bottom-most row and fill leftover cells
             let block_bottom = Tile::fill_bottom(8 - remainder.1 as u8);
             for x in 0..tile_shape.0 {
-                let index = (tile_shape.1 - 1) * tile_shape.0 as usize + x;
+                let index = (tile_shape.1 - 1) * tile_shape.0 + x;
                 tiles[index] |= block_bottom;
             }
         }
@@ -79,19 +120,105 @@ impl TileMap {
             // iterate over right-most column and fill leftover cells
             let block_right = Tile::fill_right(8 - remainder.0 as u8);
             for y in 0..tile_shape.1 {
-                let index = y * tile_shape.0 as usize + tile_shape.0 - 1;
+                let index = y * tile_shape.0 + tile_shape.0 - 1;
                 tiles[index] |= block_right;
             }
         }
 
+        let free = shape.0 * shape.1;
+        let hash = full_hash(&tiles);
         Self {
             tiles: tiles.into(),
             shape,
+            free,
+            hash,
         }
     }
 
+    /// the tiles of 8x8 cells, row by row, with the cells outside the board blocked
+    #[inline]
+    pub fn tiles(&self) -> &SmallVec<[Tile; 1]> {
+        &self.tiles
+    }
+
+    /// replaces a tile, keeping the free cells and the hash up to date
+    #[inline]
+    fn set_tile(&mut self, index: usize, tile: Tile) {
+        let old = self.tiles[index];
+        self.free = self.free + old.0.count_ones() - tile.0.count_ones();
+        self.hash ^= tile_hash(index, old) ^ tile_hash(index, tile);
+        self.tiles[index] = tile;
+    }
+
     #[inline]
     pub fn can_place(&self, rhs: PositionedPentonimo) -> bool {
+        let (x, y) = rhs.position();
+        let parts = rhs.parts();
+
+        let width = self.tile_shape().0;
+        let index = (x / 8) as usize + (y / 8) as usize * width;
+        // parts that are empty can lie outside the board, any tile does for them
+        let last = self.tiles.len() - 1;
+        let tiles = [0, 1, width, width + 1].map(|offset| self.tiles[(index + offset).min(last)]);
+        simd::disjoint(&tiles, &parts)
+    }
+
+    /// Whether each of `pieces` can be placed, as [`TileMap::can_place`], into
+    /// `fits`, which is cleared first so it can be reused between calls.
+    pub fn can_place_batch(&self, pieces: &[PositionedPentonimo], fits: &mut Vec<bool>) {
+        fits.clear();
+        fits.extend(pieces.iter().map(|&piece| self.can_place(piece)));
+    }
+
+    #[inline]
+    pub fn get_tile(&self, tx: u32, ty: u32) -> Tile {
+        let tile_index = tx as usize + (ty as usize) * self.tile_shape().0;
+        self.tiles[tile_index]
+    }
+
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        let (tx, ty) = (x / 8, y / 8);
+        let (x, y) = (x % 8, y % 8);
+
+        self.get_tile(tx, ty).get(x as u8, y as u8)
+    }
+
+    /// the number of cells of the board that are not blocked
+    #[inline]
+    pub fn free_cells(&self) -> u32 {
+        self.free
+    }
+
+    /// counts the free cells and hashes the tiles again after changing whole tiles
+    fn recount(&mut self) {
+        self.free = self.tiles.iter().map(|tile| 64 - tile.0.count_ones()).sum();
+        self.hash = full_hash(&self.tiles);
+    }
+
+    /// marks a single cell as blocked, e.g. for holes in the board
+    #[inline]
+    pub fn block(&mut self, x: u32, y: u32) {
+        let tile_index = (x / 8) as usize + (y / 8) as usize * self.tile_shape().0;
+        let (x, y) = ((x % 8) as u8, (y % 8) as u8);
+        let mut tile = self.tiles[tile_index];
+        tile.set(x, y);
+        self.set_tile(tile_index, tile);
+    }
+}
+
+/// the hash of a map with these tiles, see [`tile_hash`]
+fn full_hash(tiles: &[Tile]) -> u128 {
+    tiles
+        .iter()
+        .enumerate()
+        .fold(0, |hash, (index, &tile)| hash ^ tile_hash(index, tile))
+}
+
+impl TileMap {
+    /// Frees the cells of a placed pentonimo again. Cells blocked from the start
+    /// stay blocked as long as the pentonimo didn't overlap them.
+    pub fn remove(&mut self, rhs: PositionedPentonimo) {
         let (x, y) = rhs.position();
         let parts = rhs.pentonimo().shift_split((x % 8) as i8, (y % 8) as i8);
 
@@ -101,28 +228,94 @@ impl TileMap {
             if !part.is_empty() {
                 let dx = i % 2;
                 let dy = i / 2;
+                let tile_index = tx as usize + dx + (ty as usize + dy) * self.tile_shape().0;
 
-                if !(self.get_tile(tx + dx as u16, ty + dy as u16) & *part).is_empty() {
-                    return false;
-                }
+                self.set_tile(tile_index, self.tiles[tile_index] & !*part);
             }
         }
+    }
+}
 
-        true
+impl TileMap {
+    /// The map transposed (only for square maps), then mirrored along x and
+    /// then along y, as given by the flags.
+    pub fn transformed(&self, (transpose, flip_x, flip_y): (bool, bool, bool)) -> Self {
+        let (w, h) = self.shape;
+        debug_assert!(!transpose || w == h, "only square maps can be transposed");
+
+        if let [mut tile] = self.tiles[..] {
+            if transpose {
+                tile = tile.transpose();
+            }
+            if flip_x {
+                // the padding ends up on the left, move it back to the right
+                tile = tile.flip_x().shift_x(w as i8 - 8) | Tile::fill_right(8 - w as u8);
+            }
+            if flip_y {
+                tile = tile.flip_y().shift_y(h as i8 - 8) | Tile::fill_bottom(8 - h as u8);
+            }
+            return Self {
+                tiles: SmallVec::from_elem(tile, 1),
+                shape: self.shape,
+                free: self.free,
+                hash: tile_hash(0, tile),
+            };
+        }
+
+        let mut map = Self::new(self.shape);
+        for y in 0..h {
+            for x in 0..w {
+                if self.get(x, y) {
+                    let (mut tx, mut ty) = if transpose { (y, x) } else { (x, y) };
+                    if flip_x {
+                        tx = w - 1 - tx;
+                    }
+                    if flip_y {
+                        ty = h - 1 - ty;
+                    }
+                    map.block(tx, ty);
+                }
+            }
+        }
+        map
     }
 
-    #[inline]
-    pub fn get_tile(&self, tx: u16, ty: u16) -> Tile {
-        let tile_index = tx as usize + (ty as usize) * self.tile_shape().0;
-        self.tiles[tile_index]
+    /// The smallest of the images of the map under `symmetries`, compared by
+    /// their tiles. Borrows the map if none is smaller.
+    pub fn canonical(&self, symmetries: &[(bool, bool, bool)]) -> Cow<'_, Self> {
+        let mut canonical = Cow::Borrowed(self);
+        for &symmetry in symmetries {
+            let map = self.transformed(symmetry);
+            if map.cmp_tiles(&canonical).is_lt() {
+                canonical = Cow::Owned(map);
+            }
+        }
+        canonical
     }
+}
 
-    #[inline]
-    pub fn get(&self, x: u16, y: u16) -> bool {
-        let (tx, ty) = (x / 8, y / 8);
-        let (x, y) = (x % 8, y % 8);
+impl TileMap {
+    /// Compares the tiles of maps of the same shape in order, as their
+    /// canonical form does. Equal tiles are skipped several at a time with
+    /// [`simd::equal_prefix`].
+    pub fn cmp_tiles(&self, other: &TileMap) -> Ordering {
+        debug_assert_eq!(self.shape, other.shape);
+        let start = simd::equal_prefix(&self.tiles, &other.tiles);
+        self.tiles[start..]
+            .iter()
+            .map(|tile| tile.0)
+            .cmp(other.tiles[start..].iter().map(|tile| tile.0))
+    }
+}
 
-        self.get_tile(tx, ty).get(x as u8, y as u8)
+impl BitOrAssign<&TileMap> for TileMap {
+    /// blocks the cells blocked in either map
+    fn bitor_assign(&mut self, rhs: &TileMap) {
+        debug_assert_eq!(self.shape, rhs.shape);
+        for (tile, other) in self.tiles.iter_mut().zip(&rhs.tiles) {
+            *tile |= *other;
+        }
+        self.recount();
     }
 }
 
@@ -140,7 +333,7 @@ impl BitOrAssign<PositionedPentonimo> for TileMap {
                 let dy = i / 2;
                 let tile_index = tx as usize + dx + (ty as usize + dy) * self.tile_shape().0;
 
-                self.tiles[tile_index] |= *part;
+                self.set_tile(tile_index, self.tiles[tile_index] | *part);
             }
         }
     }
@@ -163,6 +356,7 @@ impl BitAndAssign<PositionedPentonimo> for TileMap {
                 self.tiles[tile_index] &= *part;
             }
         }
+        self.recount();
     }
 }
 
@@ -185,3 +379,182 @@ impl BitOr<PositionedPentonimo> for TileMap {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use strum::VariantArray;
+
+    use super::{full_hash, TileMap};
+    use crate::{
+        arbitrary::check,
+        pathfinding::BfsScratch,
+        pentonimo::{Pentonimo, PentonimoKind, PositionedPentonimo},
+    };
+
+    #[test]
+    fn can_place_matches_cells() {
+        check(1000, |(map, piece): &(TileMap, PositionedPentonimo)| {
+            let cells = (0..map.shape.0).flat_map(|x| (0..map.shape.1).map(move |y| (x, y)));
+
+            let overlaps = cells.clone().any(|(x, y)| map.get(x, y) && piece.get(x, y));
+            if map.can_place(*piece) == overlaps {
+                return false;
+            }
+
+            let placed = map.clone() | *piece;
+            cells
+                .into_iter()
+                .all(|(x, y)| placed.get(x, y) == (map.get(x, y) || piece.get(x, y)))
+        });
+    }
+
+    #[test]
+    fn remove_undoes_place() {
+        check(1000, |(map, piece): &(TileMap, PositionedPentonimo)| {
+            if !map.can_place(*piece) {
+                return true;
+            }
+            let mut placed = map.clone() | *piece;
+            placed.remove(*piece);
+            placed == *map
+        });
+    }
+
+    #[test]
+    fn free_cells_stay_counted() {
+        check(1000, |(map, piece): &(TileMap, PositionedPentonimo)| {
+            let counted = |map: &TileMap| {
+                let cells = (0..map.shape.0).flat_map(|x| (0..map.shape.1).map(move |y| (x, y)));
+                map.free_cells() == cells.filter(|&(x, y)| !map.get(x, y)).count() as u32
+            };
+
+            let mut placed = map.clone() | *piece;
+            // masking with a piece clears the padding, so count all tile cells
+            let blocked = placed.clone() & *piece;
+            let popcount = blocked
+                .tiles
+                .iter()
+                .map(|tile| 64 - tile.0.count_ones())
+                .sum();
+            let counts = counted(&placed) && blocked.free_cells() == popcount;
+            placed.block(0, 0);
+            placed.block(0, 0);
+            let transformed = placed.transformed((false, true, false));
+            let mut union = map.clone();
+            union |= &placed;
+            counts && counted(&placed) && counted(&transformed) && counted(&union)
+        });
+    }
+
+    #[test]
+    fn hashes_stay_up_to_date() {
+        check(1000, |(map, piece): &(TileMap, PositionedPentonimo)| {
+            let fresh = |map: &TileMap| map.hash == full_hash(&map.tiles);
+
+            let mut placed = map.clone() | *piece;
+            let blocked = placed.clone() & *piece;
+            let mut removed = placed.clone();
+            removed.remove(*piece);
+            placed.block(0, 0);
+            let transformed = placed.transformed((false, true, true));
+            let mut union = map.clone();
+            union |= &transformed;
+            fresh(map)
+                && [&placed, &blocked, &removed, &transformed, &union]
+                    .into_iter()
+                    .all(fresh)
+        });
+    }
+
+    #[test]
+    fn transformed_matches_cells() {
+        check(1000, |map: &TileMap| {
+            let (w, h) = map.shape;
+            (0..8)
+                .map(|i| (i & 1 != 0, i & 2 != 0, i & 4 != 0))
+                .filter(|&(transpose, _, _)| !transpose || w == h)
+                .all(|symmetry| {
+                    let (transpose, flip_x, flip_y) = symmetry;
+                    let transformed = map.transformed(symmetry);
+                    transformed.tiles.len() == map.tiles.len()
+                        && (0..h).all(|y| {
+                            (0..w).all(|x| {
+                                let (mut tx, mut ty) = if transpose { (y, x) } else { (x, y) };
+                                if flip_x {
+                                    tx = w - 1 - tx;
+                                }
+                                if flip_y {
+                                    ty = h - 1 - ty;
+                                }
+                                transformed.get(tx, ty) == map.get(x, y)
+                            })
+                        })
+                        // the padding stays blocked
+                        && transformed.free_cells() == map.free_cells()
+                })
+        });
+    }
+
+    #[test]
+    fn bulk_operations_match_cells() {
+        check(1000, |map: &TileMap| {
+            let (w, h) = map.shape;
+            let flipped = map.transformed((false, true, true));
+            let mut other = TileMap::new(map.shape);
+            for (x, y) in (0..h).flat_map(|y| (0..w).map(move |x| (x, y))) {
+                if flipped.get(x, y) {
+                    other.block(x, y);
+                }
+            }
+            let cells = (0..h).flat_map(|y| (0..w).map(move |x| (x, y)));
+
+            let mut or = map.clone();
+            or |= &other;
+            let words = |map: &TileMap| map.tiles.iter().map(|tile| tile.0).collect::<Vec<_>>();
+
+            cells
+                .clone()
+                .all(|(x, y)| or.get(x, y) == (map.get(x, y) || other.get(x, y)))
+                && or.free_cells() == cells.filter(|&(x, y)| !or.get(x, y)).count() as u32
+                && map.cmp_tiles(&other) == words(map).cmp(&words(&other))
+                && map.cmp_tiles(map).is_eq()
+        });
+    }
+
+    #[test]
+    fn place_across_tiles() {
+        let shape = (20, 13);
+
+        for &kind in PentonimoKind::VARIANTS
+            .iter()
+            .filter(|kind| kind.is_defined())
+        {
+            for variant in Pentonimo::new(kind).variants() {
+                let (w, h) = variant.shape();
+                for x in 0..=shape.0 - w as u32 {
+                    for y in 0..=shape.1 - h as u32 {
+                        let positioned = variant.position(x, y);
+                        let mut map = TileMap::new(shape);
+                        assert!(map.can_place(positioned));
+                        map |= positioned;
+                        assert!(!map.can_place(positioned));
+
+                        for cx in 0..shape.0 {
+                            for cy in 0..shape.1 {
+                                assert_eq!(map.get(cx, cy), positioned.get(cx, cy));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diameter_of_large_map() {
+        let shape = (40, 24);
+        let (diameter, _) = BfsScratch::new(shape).graph_diameter(&TileMap::new(shape));
+        // the diameter counts the cells on the longest path, not its edges
+        assert_eq!(diameter, shape.0 + shape.1 - 1);
+    }
+}
@@ -0,0 +1,213 @@
+//! Covering every free cell of a board with pentonimos, solved as an exact
+//! cover problem.
+
+use crate::{
+    exact_cover::ExactCover,
+    pentonimo::{PositionedPentonimo, KINDS},
+    spec::BoardSpec,
+};
+
+/// A classic board with a known number of tilings
+pub struct Preset {
+    pub name: &'static str,
+    pub shape: (u32, u32),
+    pub holes: &'static [(u32, u32)],
+    /// number of tilings that are distinct up to rotation and reflection
+    pub solutions: u64,
+    /// number of rotations and reflections mapping the board onto itself
+    pub symmetries: u64,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "6x10",
+        shape: (10, 6),
+        holes: &[],
+        solutions: 2339,
+        symmetries: 4,
+    },
+    Preset {
+        name: "5x12",
+        shape: (12, 5),
+        holes: &[],
+        solutions: 1010,
+        symmetries: 4,
+    },
+    Preset {
+        name: "4x15",
+        shape: (15, 4),
+        holes: &[],
+        solutions: 368,
+        symmetries: 4,
+    },
+    Preset {
+        name: "3x20",
+        shape: (20, 3),
+        holes: &[],
+        solutions: 2,
+        symmetries: 4,
+    },
+    // the chessboard with its 2x2 center removed, Dana Scott's puzzle from 1958
+    Preset {
+        name: "8x8-center",
+        shape: (8, 8),
+        holes: &[(3, 3), (4, 3), (3, 4), (4, 4)],
+        solutions: 65,
+        symmetries: 8,
+    },
+];
+
+impl Preset {
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn spec(&self) -> BoardSpec {
+        let mut spec = BoardSpec::new(self.shape);
+        spec.name = Some(self.name.to_owned());
+        spec.holes = self.holes.to_vec();
+        spec
+    }
+
+    /// Counts all tilings of the board and reduces them by its symmetries.
+    /// None of the presets has a tiling that is symmetric itself, so every
+    /// distinct tiling is counted `symmetries` times.
+    pub fn count(&self) -> Result<u64, String> {
+        Ok(Tiling::new(&self.spec())?.count() / self.symmetries)
+    }
+}
+
+/// The exact cover problem of tiling a board: every free cell has to be covered
+/// and no piece used more often than the spec allows.
+pub struct Tiling {
+    cover: ExactCover,
+    /// the placement of every row of `cover`
+    placements: Vec<PositionedPentonimo>,
+}
+
+impl Tiling {
+    pub fn new(spec: &BoardSpec) -> Result<Self, String> {
+        let map = spec.initial_map();
+        let (w, h) = spec.shape;
+
+        // one column per free cell
+        let mut cells = vec![None; w as usize * h as usize];
+        let mut free = 0;
+        for y in 0..h {
+            for x in 0..w {
+                if !map.get(x, y) {
+                    cells[(y * w + x) as usize] = Some(free);
+                    free += 1;
+                }
+            }
+        }
+
+        let area = spec.piece_cells();
+        if area < free {
+            return Err(format!(
+                "{free} free cells can't be covered by pieces of {area} cells"
+            ));
+        }
+
+        // Pieces that are available once get a column, which is primary if all
+        // pieces have to be used. Pieces available more often are limited by a
+        // group instead, so their copies aren't told apart.
+        let unique = spec.pieces.iter().filter(|&&count| count == 1).count();
+        let (primary, secondary) = if area == free {
+            (free + unique, 0)
+        } else {
+            (free, unique)
+        };
+        let mut cover = ExactCover::new(primary, secondary);
+
+        let mut pieces = [None; KINDS];
+        let mut groups = [None; KINDS];
+        let mut column = free;
+        for (kind, &count) in spec.pieces.iter().enumerate() {
+            match count {
+                0 => {}
+                1 => {
+                    pieces[kind] = Some(column);
+                    column += 1;
+                }
+                _ => groups[kind] = Some(cover.add_group(count as usize)),
+            }
+        }
+
+        let placements = spec.placements();
+        let mut columns = Vec::with_capacity(6);
+        for placement in &placements {
+            let kind = placement.pentonimo().kind() as usize;
+            columns.clear();
+            columns.extend(pieces[kind]);
+            for y in 0..h {
+                for x in 0..w {
+                    if placement.get(x, y) {
+                        columns.extend(cells[(y * w + x) as usize]);
+                    }
+                }
+            }
+            cover.add_row_in_group(&columns, groups[kind]);
+        }
+
+        Ok(Self { cover, placements })
+    }
+
+    /// the number of tilings, counting rotated and reflected ones separately
+    pub fn count(&mut self) -> u64 {
+        self.cover.count()
+    }
+
+    pub fn first(&mut self) -> Option<Vec<PositionedPentonimo>> {
+        let rows = self.cover.first()?;
+        Some(rows.into_iter().map(|row| self.placements[row]).collect())
+    }
+}
+
+#[test]
+fn preset_3x20() {
+    let preset = Preset::find("3x20").unwrap();
+    assert_eq!(preset.count(), Ok(preset.solutions));
+}
+
+/// takes several seconds even in release mode
+#[test]
+#[ignore]
+fn all_presets() {
+    for preset in PRESETS {
+        assert_eq!(preset.count(), Ok(preset.solutions), "{}", preset.name);
+    }
+}
+
+#[test]
+fn first_tiling_covers_the_board() {
+    let spec = Preset::find("6x10").unwrap().spec();
+    let pieces = Tiling::new(&spec).unwrap().first().unwrap();
+    assert_eq!(pieces.len(), 12);
+
+    let mut map = spec.initial_map();
+    for piece in pieces {
+        assert!(map.can_place(piece));
+        map |= piece;
+    }
+    for x in 0..spec.shape.0 {
+        for y in 0..spec.shape.1 {
+            assert!(map.get(x, y));
+        }
+    }
+}
+
+#[test]
+fn repeated_pieces() {
+    // 2x5 can be covered by two I in one way and by two L in two ways
+    let mut spec = BoardSpec::new((5, 2));
+    spec.pieces = [0; KINDS];
+    spec.pieces[crate::pentonimo::PentonimoKind::I as usize] = 2;
+    assert_eq!(Tiling::new(&spec).unwrap().count(), 1);
+
+    spec.pieces[crate::pentonimo::PentonimoKind::L as usize] = 3;
+    assert_eq!(Tiling::new(&spec).unwrap().count(), 1 + 2);
+
+    spec.pieces = [0; KINDS];
+    assert!(Tiling::new(&spec).is_err());
+}
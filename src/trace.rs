@@ -0,0 +1,260 @@
+//! A bounded trace of a brute-force search, to see where it spends its
+//! effort. The nodes down to a depth are recorded until a limit is reached,
+//! each with the totals of its subtree. `solve --trace` writes them as lines
+//! described by `schema/v1/trace.schema.json`, and [`folded`] turns them into
+//! the folded stacks read by flamegraph and treemap tools.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{json::Json, pentonimo::PositionedPentonimo, solution::parse_piece};
+
+/// Why the search of a node ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum End {
+    /// all children were searched or skipped
+    Searched,
+    /// the piece made the diameter smaller
+    Decreased,
+    /// the piece disconnected the ends
+    Disconnected,
+    /// a searched state dominates it
+    Dominated,
+    /// the search was stopped
+    Stopped,
+}
+
+/// A searched state, the placement of `piece` on its parent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceNode {
+    pub id: usize,
+    /// `None` for the roots
+    pub parent: Option<usize>,
+    pub root: usize,
+    /// pieces placed, 1 for the roots
+    pub depth: usize,
+    pub piece: PositionedPentonimo,
+    pub diameter: u32,
+    /// nodes visited in the subtree, including this one
+    pub nodes: u64,
+    /// children skipped in the subtree because their bound can't beat the best result
+    pub pruned: u64,
+    /// children skipped in the subtree because they were searched before
+    pub known: u64,
+    /// nodes of the subtree skipped because a searched state dominates them
+    pub dominated: u64,
+    pub end: End,
+}
+
+impl TraceNode {
+    /// a line of the trace, as described by `schema/v1/trace.schema.json`
+    pub fn to_json(&self) -> Json {
+        let (x, y) = self.piece.position();
+        let piece = format!(
+            "{} {} {x} {y}",
+            self.piece.pentonimo().kind(),
+            self.piece.variant()
+        );
+        let mut fields = vec![("id", self.id.into())];
+        if let Some(parent) = self.parent {
+            fields.push(("parent", parent.into()));
+        }
+        fields.extend([
+            ("root", self.root.into()),
+            ("depth", self.depth.into()),
+            ("piece", piece.into()),
+            ("diameter", self.diameter.into()),
+            ("nodes", self.nodes.into()),
+            ("pruned", self.pruned.into()),
+            ("known", self.known.into()),
+            ("dominated", self.dominated.into()),
+            ("end", self.end.to_string().into()),
+        ]);
+        Json::versioned(fields)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let number = |key| match json.get(key) {
+            Some(&Json::Number(n)) => Ok(n as u64),
+            _ => Err(format!("missing {key}")),
+        };
+        let string = |key| match json.get(key) {
+            Some(Json::String(s)) => Ok(s.as_str()),
+            _ => Err(format!("missing {key}")),
+        };
+        Ok(TraceNode {
+            id: number("id")? as usize,
+            parent: number("parent").ok().map(|parent| parent as usize),
+            root: number("root")? as usize,
+            depth: number("depth")? as usize,
+            piece: parse_piece(string("piece")?)?,
+            diameter: number("diameter")? as u32,
+            nodes: number("nodes")?,
+            pruned: number("pruned")?,
+            known: number("known")?,
+            dominated: number("dominated")?,
+            end: string("end")?
+                .parse()
+                .map_err(|_| format!("unknown end {:?}", string("end").unwrap()))?,
+        })
+    }
+}
+
+/// The trace of a search, shared by its jobs
+#[derive(Debug)]
+pub struct Trace {
+    /// record nodes with at most this many pieces
+    pub max_depth: usize,
+    /// record at most this many nodes
+    pub limit: usize,
+    next: AtomicUsize,
+    nodes: Mutex<Vec<TraceNode>>,
+}
+
+impl Trace {
+    pub fn new(max_depth: usize, limit: usize) -> Self {
+        Self {
+            max_depth,
+            limit,
+            next: AtomicUsize::new(0),
+            nodes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The id of a new node at `depth`, unless it's too deep or the limit is reached
+    pub fn reserve(&self, depth: usize) -> Option<usize> {
+        if depth > self.max_depth || self.next.load(Ordering::Relaxed) >= self.limit {
+            return None;
+        }
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+        (id < self.limit).then_some(id)
+    }
+
+    pub fn record(&self, node: TraceNode) {
+        self.nodes.lock().unwrap().push(node);
+    }
+
+    /// the recorded nodes by id, every parent before its children
+    pub fn nodes(&self) -> Vec<TraceNode> {
+        let mut nodes = self.nodes.lock().unwrap().clone();
+        nodes.sort_by_key(|node| node.id);
+        nodes
+    }
+
+    /// the recorded nodes as JSON lines
+    pub fn to_jsonl(&self) -> String {
+        self.nodes()
+            .iter()
+            .map(|node| format!("{}\n", node.to_json()))
+            .collect()
+    }
+}
+
+/// reads the lines written by [`Trace::to_jsonl`]
+pub fn parse_trace(text: &str) -> Result<Vec<TraceNode>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.parse::<Json>()
+                .and_then(|json| TraceNode::from_json(&json))
+                .map_err(|err| format!("line {}: {err}", i + 1))
+        })
+        .collect()
+}
+
+/// One line per node with the pieces from its root down to it, separated by
+/// `;`, and the nodes of its subtree that weren't recorded as the count.
+/// Flamegraphs of it show the share of every subtree in the search.
+pub fn folded(nodes: &[TraceNode]) -> String {
+    let by_id = nodes
+        .iter()
+        .map(|node| (node.id, node))
+        .collect::<HashMap<_, _>>();
+    let mut recorded_children = HashMap::<usize, u64>::new();
+    for node in nodes {
+        if let Some(parent) = node.parent {
+            *recorded_children.entry(parent).or_default() += node.nodes;
+        }
+    }
+
+    let frame = |node: &TraceNode| {
+        let (x, y) = node.piece.position();
+        let mut frame = format!(
+            "{} {} {x},{y} d={}",
+            node.piece.pentonimo().kind(),
+            node.piece.variant(),
+            node.diameter
+        );
+        if node.end != End::Searched {
+            write!(frame, " [{}]", node.end).unwrap();
+        }
+        frame
+    };
+
+    let mut folded = String::new();
+    for node in nodes {
+        let mut frames = vec![frame(node)];
+        let mut parent = node.parent;
+        while let Some(id) = parent {
+            let Some(&ancestor) = by_id.get(&id) else {
+                break;
+            };
+            frames.push(frame(ancestor));
+            parent = ancestor.parent;
+        }
+        frames.push(format!("root {}", node.root));
+        frames.reverse();
+
+        let own = node
+            .nodes
+            .saturating_sub(recorded_children.get(&node.id).copied().unwrap_or(0));
+        if own > 0 {
+            writeln!(folded, "{} {own}", frames.join(";")).unwrap();
+        }
+    }
+    folded
+}
+
+#[test]
+fn traces_roundtrip_and_fold() {
+    let node = |id, parent, piece, nodes, end| TraceNode {
+        id,
+        parent,
+        root: 2,
+        depth: if parent.is_some() { 2 } else { 1 },
+        piece: parse_piece(piece).unwrap(),
+        diameter: 7,
+        nodes,
+        pruned: 1,
+        known: 2,
+        dominated: 0,
+        end,
+    };
+    let trace = Trace::new(2, 3);
+    let ids = [0, 1, 2].map(|depth| trace.reserve(depth));
+    assert_eq!(ids, [Some(0), Some(1), Some(2)]);
+    assert_eq!(trace.reserve(3), None);
+    assert_eq!(trace.reserve(1), None);
+
+    trace.record(node(1, Some(0), "L 3 2 0", 4, End::Searched));
+    trace.record(node(0, None, "I 0 0 0", 10, End::Searched));
+    trace.record(node(2, Some(0), "X 0 1 1", 1, End::Decreased));
+    let nodes = parse_trace(&trace.to_jsonl()).unwrap();
+    assert_eq!(nodes, trace.nodes());
+    assert_eq!(nodes[0].parent, None);
+
+    assert_eq!(
+        folded(&nodes),
+        "root 2;I 0 0,0 d=7 5\n\
+         root 2;I 0 0,0 d=7;L 3 2,0 d=7 4\n\
+         root 2;I 0 0,0 d=7;X 0 1,1 d=7 [decreased] 1\n"
+    );
+}
@@ -0,0 +1,233 @@
+//! `pentonimo view`, a viewer of the solutions in a results directory for the
+//! terminal: the list of boards next to the picked one, whose pieces can be
+//! inspected one by one.
+
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use pentonimo::{
+    pathfinding::{BfsScratch, PathStyle},
+    solution::Solution,
+};
+
+use crate::{ansi_color, build_print_map, exit_with, read_result, result_files};
+
+/// columns of the list of boards
+const LIST_WIDTH: usize = 16;
+
+struct Viewer {
+    /// the results sorted by their shapes
+    results: Vec<(PathBuf, Solution)>,
+    /// the index of the shown result
+    selected: usize,
+    /// the index of the inspected piece of the shown result
+    piece: Option<usize>,
+    /// the path of `--path`, none with `--no-path`
+    style: Option<PathStyle>,
+    show_path: bool,
+}
+
+/// Shows the solutions in `dir` until the user leaves
+pub fn view(dir: &Path, style: Option<PathStyle>) {
+    let mut results = Vec::new();
+    for path in result_files(dir) {
+        match read_result(&path) {
+            Ok(solution) => results.push((path, solution)),
+            Err(err) => eprintln!("warning: skipping {}: {err}", path.display()),
+        }
+    }
+    if results.is_empty() {
+        exit_with(&format!("no solutions in {}", dir.display()));
+    }
+    results.sort_by(|(a, x), (b, y)| (x.spec.shape, a).cmp(&(y.spec.shape, b)));
+
+    let mut viewer = Viewer {
+        results,
+        selected: 0,
+        piece: None,
+        style,
+        show_path: style.is_some(),
+    };
+    if let Err(err) = viewer.run() {
+        exit_with(&format!("view failed: {err}"));
+    }
+}
+
+impl Viewer {
+    fn run(&mut self) -> io::Result<()> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let result = self.event_loop(&mut out);
+        // the terminal is restored after errors as well
+        queue!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        out.flush()?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop(&mut self, out: &mut impl Write) -> io::Result<()> {
+        loop {
+            self.draw(out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let pieces = self.solution().pieces.len();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                // raw mode doesn't turn it into a signal
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.select((self.selected + 1).min(self.results.len() - 1))
+                }
+                KeyCode::Right | KeyCode::Char('l') if pieces > 0 => {
+                    self.piece = Some(self.piece.map_or(0, |i| (i + 1).min(pieces - 1)))
+                }
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.piece = self.piece.and_then(|i| i.checked_sub(1))
+                }
+                KeyCode::Char('p') => self.show_path = !self.show_path && self.style.is_some(),
+                _ => {}
+            }
+        }
+    }
+
+    fn solution(&self) -> &Solution {
+        &self.results[self.selected].1
+    }
+
+    fn select(&mut self, index: usize) {
+        if index != self.selected {
+            self.selected = index;
+            self.piece = None;
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (_, rows) = terminal::size()?;
+        let rows = rows as usize;
+
+        // the list scrolls to keep the shown result visible
+        let first = (self.selected + 1).saturating_sub(rows);
+        let list = self.results[first..]
+            .iter()
+            .enumerate()
+            .take(rows)
+            .map(|(i, (path, solution))| {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let proved = if solution.proved_optimal { "*" } else { " " };
+                let mut entry = format!("{stem} {}{proved}", solution.diameter);
+                if entry.len() > LIST_WIDTH {
+                    entry = format!(
+                        "{stem:.*} {}{proved}",
+                        LIST_WIDTH.saturating_sub(5),
+                        solution.diameter
+                    );
+                }
+                let entry = format!("{entry:>LIST_WIDTH$}");
+                if first + i == self.selected {
+                    format!("\x1b[7m{entry}\x1b[m")
+                } else {
+                    entry
+                }
+            })
+            .collect::<Vec<_>>();
+        let details = self.details();
+
+        queue!(out, terminal::Clear(ClearType::All))?;
+        for row in 0..rows {
+            let left = list.get(row).cloned().unwrap_or(" ".repeat(LIST_WIDTH));
+            let right = details.get(row).map_or("", String::as_str);
+            queue!(
+                out,
+                cursor::MoveTo(0, row as u16),
+                Print(format!("{left} | {right}"))
+            )?;
+        }
+        out.flush()
+    }
+
+    /// the lines right of the list: the shown result, its board and its
+    /// inspected piece
+    fn details(&self) -> Vec<String> {
+        let (path, solution) = &self.results[self.selected];
+        let (mx, my) = solution.spec.shape;
+        let proved = if solution.proved_optimal {
+            ", proved optimal"
+        } else {
+            ""
+        };
+        let mut lines = vec![
+            format!("{}: diameter {}{proved}", solution.spec, solution.diameter),
+            path.display().to_string(),
+            String::new(),
+        ];
+
+        let style = self.style.filter(|_| self.show_path);
+        let grid = build_print_map(solution, style);
+        let piece = self.piece.map(|i| solution.pieces[i]);
+        for y in 0..my {
+            let mut line = String::new();
+            for x in 0..mx {
+                match piece {
+                    Some(piece) if piece.get(x, y) => {
+                        let kind = piece.pentonimo().kind();
+                        let name = kind.to_string();
+                        line += &format!("\x1b[7;{}m{name:<2}\x1b[m ", ansi_color(kind));
+                    }
+                    _ => line += &format!("{} ", grid[(y * mx + x) as usize]),
+                }
+            }
+            lines.push(line);
+        }
+        lines.push(String::new());
+
+        match self.piece {
+            Some(i) => {
+                let piece = solution.pieces[i];
+                let (x, y) = piece.position();
+                let cells = (0..my)
+                    .flat_map(|y| (0..mx).map(move |x| (x, y)))
+                    .filter(|&(x, y)| piece.get(x, y))
+                    .map(|(x, y)| format!("{x},{y}"))
+                    .collect::<Vec<_>>();
+                let mut map = solution.spec.initial_map();
+                for (j, &other) in solution.pieces.iter().enumerate() {
+                    if j != i {
+                        map |= other;
+                    }
+                }
+                let (without, _) =
+                    BfsScratch::new(solution.spec.shape).objective(&map, &solution.spec.ends);
+                lines.push(format!(
+                    "piece {}/{}: {} variant {} at {x},{y}",
+                    i + 1,
+                    solution.pieces.len(),
+                    piece.pentonimo().kind(),
+                    piece.variant()
+                ));
+                lines.push(format!("cells {}", cells.join(" ")));
+                lines.push(format!("diameter {without} without it"));
+            }
+            None => lines.push(format!("{} pieces", solution.pieces.len())),
+        }
+        lines.push(String::new());
+        lines.push("up/down: board  left/right: piece  p: path  q: quit".to_owned());
+        lines
+    }
+}
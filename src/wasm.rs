@@ -0,0 +1,226 @@
+//! Bindings for JavaScript, so boards can be solved and drawn in a browser.
+//!
+//! The `wasm` feature exports [`TileMap`], [`Pentonimo`] and the functions
+//! [`solve`] and [`render`] with `wasm-bindgen`. Without the default `parallel`
+//! feature the solver runs on the calling thread, which is the only one a page
+//! has, so the bindings are built with
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/pentonimo.wasm
+//! ```
+//!
+//! Solutions are passed as the JSON of `schema/v1/solution.schema.json` and
+//! errors are thrown as strings.
+
+use std::time::Duration;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    brute_force::{find_best, SearchConfig},
+    json::Json,
+    maze::Maze,
+    pathfinding::BfsScratch,
+    pentonimo::{self, PentonimoKind},
+    solution::Solution,
+    tile_map,
+};
+
+/// A board, see [`tile_map::TileMap`]
+#[wasm_bindgen]
+pub struct TileMap(tile_map::TileMap);
+
+#[wasm_bindgen]
+impl TileMap {
+    /// an empty board of `width` by `height` cells
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32) -> Result<TileMap, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("a board of {width}x{height} has no cells"));
+        }
+        Ok(Self(tile_map::TileMap::new((width, height))))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.0.shape.0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.0.shape.1
+    }
+
+    /// whether the cell is covered by a hole or a piece
+    pub fn get(&self, x: u32, y: u32) -> Result<bool, String> {
+        self.check(x, y)?;
+        Ok(self.0.get(x, y))
+    }
+
+    /// makes a cell a hole
+    pub fn block(&mut self, x: u32, y: u32) -> Result<(), String> {
+        self.check(x, y)?;
+        self.0.block(x, y);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = freeCells)]
+    pub fn free_cells(&self) -> u32 {
+        self.0.free_cells()
+    }
+
+    /// whether `piece` fits with the top left corner of its bounding box at
+    /// `x`, `y`
+    #[wasm_bindgen(js_name = canPlace)]
+    pub fn can_place(&self, piece: &Pentonimo, x: u32, y: u32) -> bool {
+        let (width, height) = piece.0.shape();
+        // positions come from JavaScript, so they can be anything up to u32::MAX
+        let fits = |start: u32, size: u8, end: u32| {
+            start
+                .checked_add(size as u32)
+                .is_some_and(|stop| stop <= end)
+        };
+        fits(x, width, self.0.shape.0)
+            && fits(y, height, self.0.shape.1)
+            && self.0.can_place(piece.0.position(x, y))
+    }
+
+    /// places `piece` like [`TileMap::can_place`], if it fits
+    pub fn place(&mut self, piece: &Pentonimo, x: u32, y: u32) -> Result<(), String> {
+        if !self.can_place(piece, x, y) {
+            return Err(format!("{} doesn't fit at {x},{y}", piece.0.kind()));
+        }
+        self.0 |= piece.0.position(x, y);
+        Ok(())
+    }
+
+    /// the longest shortest path between two free cells
+    pub fn diameter(&self) -> u32 {
+        BfsScratch::new(self.0.shape).graph_diameter(&self.0).0
+    }
+
+    /// one line per row, `x` for covered cells and `.` for free ones
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_text(&self) -> String {
+        // Display resets the colors at the end of every line
+        self.0.to_string().replace("\x1b[m", "")
+    }
+
+    fn check(&self, x: u32, y: u32) -> Result<(), String> {
+        let (width, height) = self.0.shape;
+        if x >= width || y >= height {
+            return Err(format!("{x},{y} is outside of the {width}x{height} board"));
+        }
+        Ok(())
+    }
+}
+
+/// A piece in one of its orientations, see [`pentonimo::Pentonimo`]
+#[wasm_bindgen]
+pub struct Pentonimo(pentonimo::Pentonimo);
+
+#[wasm_bindgen]
+impl Pentonimo {
+    /// the orientation `variant` of the piece named `kind`, like `X` or `I4`
+    #[wasm_bindgen(constructor)]
+    pub fn new(kind: &str, variant: usize) -> Result<Pentonimo, String> {
+        let kind = parse_kind(kind)?;
+        pentonimo::Pentonimo::variant(kind, variant)
+            .map(Self)
+            .ok_or_else(|| format!("{kind} has no variant {variant}"))
+    }
+
+    /// the number of orientations of the piece named `kind`
+    pub fn variants(kind: &str) -> Result<usize, String> {
+        Ok(pentonimo::Pentonimo::new(parse_kind(kind)?)
+            .variants()
+            .count())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.0.kind().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.0.shape().0 as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.0.shape().1 as u32
+    }
+
+    /// the covered cells relative to the top left corner of the bounding box,
+    /// as x and y one after the other
+    pub fn cells(&self) -> Vec<u32> {
+        let piece = self.0.position(0, 0);
+        let (width, height) = (self.width(), self.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| piece.get(x, y))
+            .flat_map(|(x, y)| [x, y])
+            .collect()
+    }
+}
+
+fn parse_kind(kind: &str) -> Result<PentonimoKind, String> {
+    kind.parse::<PentonimoKind>()
+        .ok()
+        .filter(|kind| kind.is_defined())
+        .ok_or_else(|| format!("unknown pentonimo kind {kind:?}"))
+}
+
+/// Searches the best placement of the board `spec`, written like a line of a
+/// batch file, for at most `time_limit_ms` milliseconds if it is given. Returns
+/// the solution as JSON.
+#[wasm_bindgen]
+pub fn solve(spec: &str, time_limit_ms: Option<u32>) -> Result<String, String> {
+    let spec = spec.parse()?;
+    let config = SearchConfig {
+        time_limit: time_limit_ms.map(|ms| Duration::from_millis(ms.into())),
+        ..SearchConfig::default()
+    };
+    let result = find_best(&spec, &config);
+    let solution = Solution {
+        spec,
+        diameter: result.diameter,
+        pieces: result.placed,
+        proved_optimal: result.certificate.is_some(),
+    };
+    Ok(solution.to_json().to_string())
+}
+
+/// The SVG of the maze of a solution given as JSON, see [`Maze::to_svg`]
+#[wasm_bindgen]
+pub fn render(solution: &str) -> Result<String, String> {
+    let solution = Solution::from_json(&solution.parse::<Json>()?)?;
+    solution.verify()?;
+    Ok(Maze::new(&solution).to_svg())
+}
+
+#[test]
+fn solve_and_render() {
+    let mut map = TileMap::new(5, 4).unwrap();
+    let lying = Pentonimo::new("I", 1).unwrap();
+    assert_eq!(lying.cells(), [0, 0, 1, 0, 2, 0, 3, 0, 4, 0]);
+    assert!(!map.can_place(&Pentonimo::new("I", 0).unwrap(), 0, 0));
+    map.place(&lying, 0, 1).unwrap();
+    assert!(map.place(&lying, 0, 1).is_err());
+    assert!(!map.can_place(&lying, u32::MAX, 0));
+    assert!(map.place(&lying, 0, u32::MAX).is_err());
+    assert_eq!(map.diameter(), 6);
+    assert_eq!(map.to_text().lines().nth(1), Some("xxxxx"));
+    assert!(Pentonimo::new("Q", 0).is_err());
+    assert_eq!(Pentonimo::variants("X"), Ok(1));
+
+    let solution = solve("5x4", None).unwrap();
+    let json = solution.parse::<Json>().unwrap();
+    assert_eq!(json.get("diameter"), Some(&Json::Number(13.0)));
+    assert!(render(&solution).unwrap().starts_with("<svg"));
+    assert!(solve("5x", None).is_err());
+}
@@ -0,0 +1,299 @@
+//! Verified solutions for all boards up to 6x6. The solver has to keep finding
+//! solutions that are at least as good, so pruning changes that lose optimal
+//! solutions are caught.
+
+use pentonimo::{
+    best_known::best_known_for,
+    brute_force::{find_best, SearchConfig},
+    pathfinding::{BfsScratch, Point},
+    solution::Solution,
+};
+
+/// boards that are searched with every setting
+const MAX_QUICK_AREA: u32 = 20;
+
+/// Boards that are only searched with the default settings, which takes
+/// seconds in debug builds. Larger boards take minutes, run them with
+/// `--ignored`.
+const MAX_DEFAULT_AREA: u32 = 30;
+
+fn golden_solutions() -> Vec<Solution> {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut solutions = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            text.parse::<Solution>()
+                .unwrap_or_else(|err| panic!("{}: {err}", path.display()))
+        })
+        .collect::<Vec<_>>();
+    solutions.sort_by_key(|solution| solution.spec.shape);
+    solutions
+}
+
+fn resolve(filter: impl Fn(u32) -> bool, config: &SearchConfig) {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if !filter(w * h) {
+            continue;
+        }
+        let result = find_best(&golden.spec, config);
+        assert_eq!(result.diameter, golden.diameter, "{}", golden.spec);
+
+        let solution = Solution {
+            spec: golden.spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        solution.verify().unwrap();
+    }
+}
+
+#[test]
+fn golden_solutions_verify() {
+    for solution in golden_solutions() {
+        solution
+            .verify()
+            .unwrap_or_else(|err| panic!("{}: {err}", solution.spec));
+    }
+}
+
+#[test]
+fn solver_matches_small_golden_solutions() {
+    resolve(|area| area <= MAX_QUICK_AREA, &SearchConfig::default());
+}
+
+#[test]
+fn exact_states_match_small_golden_solutions() {
+    let config = SearchConfig {
+        exact_states: true,
+        ..Default::default()
+    };
+    resolve(|area| area <= MAX_QUICK_AREA, &config);
+}
+
+#[test]
+fn memo_settings_match_small_golden_solutions() {
+    // odd thread counts need a power of two of shards, too
+    for (threads, memo_shards, memo_capacity) in [(3, None, None), (1, Some(2), Some(0))] {
+        let config = SearchConfig {
+            threads: Some(threads),
+            memo_shards,
+            memo_capacity,
+            ..Default::default()
+        };
+        resolve(|area| area <= MAX_QUICK_AREA, &config);
+    }
+}
+
+#[test]
+fn best_known_diameters_match_golden_solutions() {
+    for golden in golden_solutions() {
+        let known = best_known_for(&golden.spec).map(|bound| bound.diameter);
+        assert_eq!(known, Some(golden.diameter), "{}", golden.spec);
+    }
+    let config = SearchConfig {
+        best_known: true,
+        ..Default::default()
+    };
+    resolve(|area| area <= MAX_QUICK_AREA, &config);
+}
+
+#[test]
+fn solver_matches_mid_size_golden_solutions() {
+    resolve(
+        |area| area > MAX_QUICK_AREA && area <= MAX_DEFAULT_AREA,
+        &SearchConfig::default(),
+    );
+}
+
+#[test]
+#[ignore]
+fn solver_matches_large_golden_solutions() {
+    resolve(|area| area > MAX_DEFAULT_AREA, &SearchConfig::default());
+}
+
+#[test]
+fn all_optima_of_small_boards_verify() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > 16 {
+            continue;
+        }
+        let config = SearchConfig {
+            all_optima: true,
+            ..Default::default()
+        };
+        let result = find_best(&golden.spec, &config);
+        assert!(!result.optima.is_empty(), "{}", golden.spec);
+        for pieces in result.optima {
+            let solution = Solution {
+                spec: golden.spec.clone(),
+                diameter: golden.diameter,
+                pieces,
+                proved_optimal: false,
+            };
+            solution
+                .verify()
+                .unwrap_or_else(|err| panic!("{}: {err}", solution.spec));
+        }
+    }
+}
+
+#[test]
+fn exhaustive_search_proves_small_golden_solutions() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > 16 {
+            continue;
+        }
+        let config = SearchConfig {
+            exhaustive: true,
+            ..Default::default()
+        };
+        let result = find_best(&golden.spec, &config);
+        assert_eq!(result.diameter, golden.diameter, "{}", golden.spec);
+        assert!(result.certificate.is_some());
+    }
+}
+
+#[test]
+fn sampled_hashes_have_no_collisions() {
+    let config = SearchConfig {
+        check_hashes: true,
+        ..Default::default()
+    };
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > MAX_QUICK_AREA {
+            continue;
+        }
+        let check = find_best(&golden.spec, &config).hash_check.unwrap();
+        assert_eq!(check.collisions, 0, "{}", golden.spec);
+    }
+}
+
+#[test]
+fn piece_limits_reach_golden_solutions() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > 16 {
+            continue;
+        }
+        let mut previous = 0;
+        for max in 1..=golden.pieces.len() {
+            let config = SearchConfig {
+                max_pieces: Some(max),
+                ..Default::default()
+            };
+            let result = find_best(&golden.spec, &config);
+            assert!(result.placed.len() <= max, "{}", golden.spec);
+            assert!(result.diameter >= previous, "{}", golden.spec);
+            previous = result.diameter;
+        }
+        assert_eq!(previous, golden.diameter, "{}", golden.spec);
+    }
+}
+
+#[test]
+fn required_free_cells_stay_free() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > MAX_QUICK_AREA || w < 3 || h < 3 {
+            continue;
+        }
+        let mut spec = golden.spec.clone();
+        spec.free = (0..w).map(|x| (x, 0)).collect();
+        let result = find_best(&spec, &SearchConfig::default());
+        assert!(result.diameter <= golden.diameter, "{spec}");
+
+        let mut solution = Solution {
+            spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        solution.verify().unwrap();
+
+        let covers_top = golden
+            .pieces
+            .iter()
+            .any(|piece| (0..w).any(|x| piece.get(x, 0)));
+        solution.pieces = golden.pieces;
+        solution.diameter = golden.diameter;
+        assert_eq!(solution.verify().is_err(), covers_top, "{}", solution.spec);
+    }
+}
+
+#[test]
+fn fixed_ends_maximize_their_distance() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > MAX_QUICK_AREA {
+            continue;
+        }
+        let mut spec = golden.spec.clone();
+        spec.ends = vec![Point(0, 0), Point(w - 1, h - 1)];
+        if spec.placements().is_empty() {
+            // every piece cuts the corners apart
+            continue;
+        }
+        let result = find_best(&spec, &SearchConfig::default());
+        // no path is longer than the diameter of its board
+        assert!(result.diameter <= golden.diameter, "{spec}");
+
+        let solution = Solution {
+            spec: spec.clone(),
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        };
+        solution.verify().unwrap();
+
+        // with a single piece every placement can be tried
+        let config = SearchConfig {
+            max_pieces: Some(1),
+            ..Default::default()
+        };
+        let mut scratch = BfsScratch::new(spec.shape);
+        let best = spec
+            .placements()
+            .into_iter()
+            .map(|piece| {
+                scratch
+                    .objective(&(spec.initial_map() | piece), &spec.ends)
+                    .0
+            })
+            .max()
+            .unwrap();
+        assert_eq!(find_best(&spec, &config).diameter, best, "{spec}");
+    }
+}
+
+#[test]
+fn pieces_never_disconnect_doors() {
+    for golden in golden_solutions() {
+        let (w, h) = golden.spec.shape;
+        if w * h > 16 {
+            continue;
+        }
+        let mut spec = golden.spec.clone();
+        spec.ends = vec![Point(0, 0), Point(w - 1, 0), Point(0, h - 1)];
+        let config = SearchConfig {
+            exhaustive: true,
+            ..Default::default()
+        };
+        let result = find_best(&spec, &config);
+        assert!(result.diameter > 0, "{spec}");
+        Solution {
+            spec,
+            diameter: result.diameter,
+            pieces: result.placed,
+            proved_optimal: false,
+        }
+        .verify()
+        .unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+//! The JSON outputs have to match the published schemas in `schema/`, which
+//! downstream tools rely on.
+
+use std::{path::Path, sync::Arc};
+
+use pentonimo::{
+    analysis::PieceUsage,
+    brute_force::{find_best, SearchConfig},
+    json::{Json, SCHEMA_VERSION},
+    result_cache::Provenance,
+    run_log::Run,
+    solution::Solution,
+    spec::BoardSpec,
+    summary::Summary,
+    trace::Trace,
+};
+
+fn schema(name: &str) -> Json {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(format!("schema/v{SCHEMA_VERSION}/{name}.schema.json"));
+    std::fs::read_to_string(&path)
+        .unwrap()
+        .parse()
+        .unwrap_or_else(|err| panic!("{}: {err}", path.display()))
+}
+
+/// validates the output as it is written, not only the value
+fn check(output: &Json, schema: &Json) {
+    let written = output.to_string().parse::<Json>().unwrap();
+    written
+        .validate(schema)
+        .unwrap_or_else(|err| panic!("{err} in {written}"));
+}
+
+#[test]
+fn outputs_match_schemas() {
+    let mut spec = BoardSpec::new((5, 4));
+    spec.holes.push((2, 1));
+    spec.free.push((0, 0));
+    let config = SearchConfig {
+        profile: true,
+        all_optima: true,
+        ..Default::default()
+    };
+    let result = find_best(&spec, &config);
+    let solution = Solution {
+        spec: spec.clone(),
+        diameter: result.diameter,
+        pieces: result.placed.clone(),
+        proved_optimal: false,
+    };
+    check(&solution.to_json(), &schema("solution"));
+    // show --format json and the JSON result files add the path
+    let Json::Object(mut fields) = solution.to_json() else {
+        panic!("solutions are objects");
+    };
+    fields.push(("path".to_owned(), Json::Array(vec![vec![0u32, 0].into()])));
+    check(&Json::Object(fields), &schema("solution"));
+    check(&result.profile.unwrap().to_json(), &schema("stats"));
+
+    let usage = PieceUsage::new(&result.optima);
+    for all_optima in [false, true] {
+        let mut summary = Summary::new(all_optima);
+        summary.add("5_4", &solution, all_optima.then_some(&usage));
+        summary.add("5_4_2", &solution, all_optima.then_some(&usage));
+        check(&summary.to_json(), &schema("summary"));
+    }
+
+    let run = Run {
+        solution: &solution,
+        complete: result.complete,
+        time: std::time::Duration::from_millis(1500),
+        source: Provenance::TransposedCache,
+        config: Json::Object(vec![
+            ("strategy".to_owned(), "brute-force".into()),
+            ("prove".to_owned(), false.into()),
+            ("objective".to_owned(), "dead-ends".into()),
+        ]),
+    };
+    check(&run.to_json(), &schema("run"));
+
+    // on 5x4 every child of a root is pruned
+    let trace = Arc::new(Trace::new(2, 1000));
+    let config = SearchConfig {
+        trace: Some(trace.clone()),
+        ..Default::default()
+    };
+    find_best(&BoardSpec::new((6, 4)), &config);
+    let nodes = trace.nodes();
+    assert!(nodes.iter().any(|node| node.parent.is_some()));
+    for node in nodes {
+        check(&node.to_json(), &schema("trace"));
+    }
+}
+
+#[test]
+fn schemas_reject_other_versions() {
+    let solution = Solution {
+        spec: BoardSpec::new((3, 3)),
+        diameter: 5,
+        pieces: Vec::new(),
+        proved_optimal: false,
+    };
+    let Json::Object(mut fields) = solution.to_json() else {
+        panic!("solutions are objects");
+    };
+    fields[0].1 = Json::from(SCHEMA_VERSION + 1);
+    assert!(Json::Object(fields).validate(&schema("solution")).is_err());
+}
@@ -0,0 +1,21 @@
+//! Requests of remote clients can't take `pentonimo serve` down. Custom pieces
+//! are defined for the whole process, so this runs apart from the unit tests,
+//! which define them.
+#![cfg(feature = "server")]
+
+use pentonimo::{json::Json, server};
+
+#[test]
+fn undefined_custom_pieces_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("pentonimo-custom-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    let queue = server::Queue::open(dir.clone()).unwrap();
+
+    let answer = server::handle(&queue, "submit 5x5 pieces=C1=1");
+    let Some(Json::String(error)) = answer.get("error") else {
+        panic!("expected an error, got {answer}");
+    };
+    assert!(error.contains("C1"), "{error}");
+    assert!(queue.jobs().is_empty());
+    _ = std::fs::remove_dir_all(dir);
+}